@@ -11,9 +11,12 @@ import {
   TransactionSignature,
 } from '@solana/web3.js';
 import {
+  AccountUpgrade,
   Conversation,
   ConversationEncryptionInfo,
+  ConversationPaddingPolicy,
   Message,
+  MessageIdRecord,
   User,
   UserConversation
 } from './state';
@@ -23,6 +26,7 @@ import {
   InstructionData,
 } from './instruction';
 import BN from 'bn.js';
+import {randomBytes} from 'crypto';
 
 export type SolConversation = {
   index: number;
@@ -66,6 +70,7 @@ export class Service {
     receiverWalletAddress: PublicKey,
     messageType: number,
     content: Uint8Array,
+    messageId: Uint8Array = randomBytes(Message.MESSAGE_ID_SIZE),
   ): Promise<TransactionSignature | void> {
     console.log('Send message to', receiverWalletAddress.toBase58());
 
@@ -106,10 +111,18 @@ export class Service {
     const messagePdaAddress = await this.getMessagePdaAddress(conversationPdaAddress, messageIndex);
     console.log('Message PDA account:', messagePdaAddress.toBase58());
 
+    // Get message-id PDA address - resending with the same message ID does not store the message twice
+    const messageIdPdaAddress = await MessageIdRecord.findPdaAddress(conversationPdaAddress, senderWalletAddress, messageId, this.programId);
+
+    // Get conversation-padding-policy PDA address
+    const paddingPolicyPdaAddress = await ConversationPaddingPolicy.findPdaAddress(conversationPdaAddress, this.programId);
+
     // Send transaction
     const instructionData = new InstructionData(InstantMessagingInstruction.SendMessage, {
       messageType: messageType,
       content,
+      expected_index: messageIndex,
+      message_id: messageId,
     }).encode();
     const instruction = new TransactionInstruction({
       keys: [
@@ -119,9 +132,11 @@ export class Service {
         {pubkey: receiverPdaAddress, isSigner: false, isWritable: true},
         {pubkey: conversationPdaAddress, isSigner: false, isWritable: true},
         {pubkey: messagePdaAddress, isSigner: false, isWritable: true},
+        {pubkey: messageIdPdaAddress, isSigner: false, isWritable: true},
         {pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false},
         {pubkey: SYSVAR_CLOCK_PUBKEY, isSigner: false, isWritable: false},
         {pubkey: SystemProgram.programId, isSigner: false, isWritable: false},
+        {pubkey: paddingPolicyPdaAddress, isSigner: false, isWritable: false},
       ],
       programId: this.programId,
       data: new Instruction({
@@ -175,6 +190,9 @@ export class Service {
     const messagePdaAddress = await this.getMessagePdaAddress(conversationPdaAddress, messageIndex);
     console.log('Message PDA account:', messagePdaAddress.toBase58());
 
+    // Get conversation-padding-policy PDA address
+    const paddingPolicyPdaAddress = await ConversationPaddingPolicy.findPdaAddress(conversationPdaAddress, this.programId);
+
     // Send transaction
     const instructionData = new InstructionData(InstantMessagingInstruction.CreateMessageAccount, {
       conversation_index: conversationIndex,
@@ -192,6 +210,7 @@ export class Service {
         {pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false},
         {pubkey: SYSVAR_CLOCK_PUBKEY, isSigner: false, isWritable: false},
         {pubkey: SystemProgram.programId, isSigner: false, isWritable: false},
+        {pubkey: paddingPolicyPdaAddress, isSigner: false, isWritable: false},
       ],
       programId: this.programId,
       data: new Instruction({
@@ -224,8 +243,18 @@ export class Service {
 
     // Get documents
     for (let i = 0; i < conversationCounter; i++) {
-      const userConversationPdaAddress = await this.getUserConversationPdaAddress(userPdaAddress, i);
-      const conversationPdaAddress = await this.getConversationAddress(userConversationPdaAddress);
+      // Accounts not yet migrated keep the v1 address
+      const userConversation =
+        await this.getUserConversation(await this.getUserConversationPdaAddress(userPdaAddress, i))
+        ?? await this.getUserConversation(await UserConversation.findPdaAddress(userPdaAddress, i, this.programId));
+
+      // Conversation was dropped from the list, e.g. the user left the group, or the user hid it
+      if (userConversation === null || userConversation.hidden) {
+        continue;
+      }
+
+      const conversationPdaAddress = new PublicKey(Buffer.from(userConversation.conversation_address ?? ''));
+
       const messages = await this.getMessages(conversationPdaAddress);
 
       console.log(messages);
@@ -252,7 +281,7 @@ export class Service {
 
     // Get messages
     for (let i = 0; i < messagesCounter; i++) {
-      const messagePdaAddress = await Message.findPdaAddress(conversationPdaAddress, i, this.programId);
+      const messagePdaAddress = await this.findMessageAccountAddress(conversationPdaAddress, i);
       const message = await this.getMessage(messagePdaAddress);
       message.index = i;
       messages.push(message);
@@ -268,7 +297,7 @@ export class Service {
       throw Error('Cannot find the message account');
     }
 
-    const message = Message.decode<Message>(Message.schema, Message, accountInfo.data);
+    const message = Message.decodeAccount(accountInfo.data);
 
     if (!message) {
       throw Error('Problem with message data');
@@ -299,6 +328,9 @@ export class Service {
       return userPdaAddress;
     }
 
+    // User accounts cannot be created while a legacy account is upgraded
+    const upgradePdaAddress = await AccountUpgrade.findPdaAddress(userPdaAddress, this.programId);
+
     // Send transaction
     const instructionData = new InstructionData(InstantMessagingInstruction.CreateUserAccount, {}).encode();
     const instruction = new TransactionInstruction({
@@ -308,6 +340,7 @@ export class Service {
         {pubkey: userWalletAddress, isSigner: false, isWritable: false},
         {pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false},
         {pubkey: SystemProgram.programId, isSigner: false, isWritable: false},
+        {pubkey: upgradePdaAddress, isSigner: false, isWritable: false},
       ],
       programId: this.programId,
       data: new Instruction({
@@ -346,6 +379,9 @@ export class Service {
     const receiverUserConversationPdaAddress = await this.getUserConversationPdaAddress(receiverPdaAddress, receiverConversationCounter);
     console.log('Sender user-conversation PDA account:', receiverUserConversationPdaAddress.toBase58())
 
+    // Conversation accounts cannot be created while a legacy account is upgraded
+    const upgradePdaAddress = await AccountUpgrade.findPdaAddress(conversationPdaAddress, this.programId);
+
     // Send transaction
     const instructionData = new InstructionData(InstantMessagingInstruction.CreateConversationAccount, {}).encode();
     const instruction = new TransactionInstruction({
@@ -366,6 +402,7 @@ export class Service {
         },
         {pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false},
         {pubkey: SystemProgram.programId, isSigner: false, isWritable: false},
+        {pubkey: upgradePdaAddress, isSigner: false, isWritable: false},
       ],
       programId: this.programId,
       data: new Instruction({
@@ -429,23 +466,31 @@ export class Service {
   }
 
   private async getUserConversationPdaAddress(userPdaAddress: PublicKey, conversationIndex: number): Promise<PublicKey> {
-    return await UserConversation.findPdaAddress(userPdaAddress, conversationIndex, this.programId);
+    return await UserConversation.findPdaAddressV2(userPdaAddress, conversationIndex, this.programId);
   }
 
-  private async getConversationAddress(userConversationPdaAddress: PublicKey): Promise<PublicKey> {
+  private async getUserConversation(userConversationPdaAddress: PublicKey): Promise<UserConversation | null> {
     const accountInfo = await this.connection.getAccountInfo(userConversationPdaAddress);
 
     if (accountInfo === null) {
-      throw Error('Cannot find the user account');
+      return null;
     }
 
-    const address = UserConversation.decode<UserConversation>(UserConversation.schema, UserConversation, accountInfo?.data)
-      .conversation_address;
-
-    return new PublicKey(Buffer.from(address ?? ''));
+    return UserConversation.decodeAccount(accountInfo.data);
   }
 
   private async getMessagePdaAddress(conversationPdaAddress: PublicKey, messageIndex: number): Promise<PublicKey> {
+    return await Message.findPdaAddressV2(conversationPdaAddress, messageIndex, this.programId);
+  }
+
+  // Messages not yet migrated keep the v1 address
+  private async findMessageAccountAddress(conversationPdaAddress: PublicKey, messageIndex: number): Promise<PublicKey> {
+    const messagePdaAddress = await this.getMessagePdaAddress(conversationPdaAddress, messageIndex);
+
+    if (await this.connection.getAccountInfo(messagePdaAddress) !== null) {
+      return messagePdaAddress;
+    }
+
     return await Message.findPdaAddress(conversationPdaAddress, messageIndex, this.programId);
   }
 
@@ -456,8 +501,8 @@ export class Service {
       throw Error('Cannot find the user account');
     }
 
-    return User.decode<User>(User.schema, User, accountInfo.data)
-      .conversation_counter;
+    return new BN(User.decodeAccount(accountInfo.data).conversation_counter)
+      .toNumber();
   }
 
   private async getMessageCounter(conversationPdaAddress: PublicKey): Promise<number> {
@@ -467,8 +512,8 @@ export class Service {
       throw Error('Cannot find the conversation account');
     }
 
-    return Conversation.decode<Conversation>(Conversation.schema, Conversation, accountInfo?.data)
-      .message_counter;
+    return new BN(Conversation.decodeAccount(accountInfo.data).message_counter)
+      .toNumber();
   }
 
   private async getConversationEncryptionInfoData(conversationPdaAddress: PublicKey): Promise<EncryptionInfoData> {
@@ -61,7 +61,7 @@ export class InstructionData extends SolanaBorsh {
         {
           kind: 'struct',
           fields: [
-            ['conversation_index', 'u32'],
+            ['conversation_index', 'u64'],
           ],
         },
       ],
@@ -72,7 +72,7 @@ export class InstructionData extends SolanaBorsh {
         {
           kind: 'struct',
           fields: [
-            ['conversation_index', 'u32'],
+            ['conversation_index', 'u64'],
             ['message_type', 'u8'],
             ['content', ['u8']],
           ],
@@ -98,6 +98,8 @@ export class InstructionData extends SolanaBorsh {
           fields: [
             ['message_type', 'u8'],
             ['content', ['u8']],
+            ['expected_index', 'u64'],
+            ['message_id', [16]],
           ],
         },
       ],
@@ -1,23 +1,46 @@
-import {Schema} from 'borsh';
+import {Schema, deserializeUnchecked} from 'borsh';
 import {SolanaBorsh} from '../solanaBorsh';
 import {PublicKey} from '@solana/web3.js';
 import BN from 'bn.js';
 
+// Size of the u32 counter of user and conversation accounts created before the counters were widened
+const LEGACY_COUNTER_SIZE = 4;
+
+// Legacy user and conversation accounts hold a u32 counter without the bump seed
+function widenLegacyCounter(buffer: Buffer, size: number): Buffer {
+  const data = Buffer.alloc(size);
+  buffer.copy(data, 0, 0, LEGACY_COUNTER_SIZE);
+
+  return data;
+}
+
+// Seed of the index in v2 addresses - the u64 index as 8 little-endian bytes
+function indexSeed(index: number | BN): Buffer {
+  return new BN(index).toArrayLike(Buffer, 'le', 8);
+}
+
 // User account
 export type UserType = Omit<User, 'assign' | 'encode'>;
 
 export class User extends SolanaBorsh {
-  conversation_counter = 0;
+  conversation_counter = new BN(0);
+  bump_seed = 0;
 
   static ACCOUNT_ADDRESS_SEED = 'user';
 
+  // Size of accounts created before the bump seed was stored
+  static LEGACY_SIZE = LEGACY_COUNTER_SIZE;
+
+  static SIZE = 9;
+
   static schema: Schema = new Map([
     [
       User,
       {
         kind: 'struct',
         fields: [
-          ['conversation_counter', 'u32'],
+          ['conversation_counter', 'u64'],
+          ['bump_seed', 'u8'],
         ],
       },
     ],
@@ -31,6 +54,14 @@ export class User extends SolanaBorsh {
     }
   }
 
+  static decodeAccount(buffer: Buffer): User {
+    const data = buffer.length < User.SIZE
+      ? widenLegacyCounter(buffer, User.SIZE)
+      : buffer;
+
+    return User.decode<User>(User.schema, User, data);
+  }
+
   static async findPdaAddress(
     userAddress: PublicKey,
     programId: PublicKey,
@@ -51,17 +82,24 @@ export class User extends SolanaBorsh {
 export type ConversationType = Omit<Document, 'assign' | 'encode'>;
 
 export class Conversation extends SolanaBorsh {
-  message_counter = 0;
+  message_counter = new BN(0);
+  bump_seed = 0;
 
   static ACCOUNT_ADDRESS_SEED = 'conversation';
 
+  // Size of accounts created before the bump seed was stored
+  static LEGACY_SIZE = LEGACY_COUNTER_SIZE;
+
+  static SIZE = 9;
+
   static schema: Schema = new Map([
     [
       Conversation,
       {
         kind: 'struct',
         fields: [
-          ['message_counter', 'u32'],
+          ['message_counter', 'u64'],
+          ['bump_seed', 'u8'],
         ],
       },
     ],
@@ -75,6 +113,14 @@ export class Conversation extends SolanaBorsh {
     }
   }
 
+  static decodeAccount(buffer: Buffer): Conversation {
+    const data = buffer.length < Conversation.SIZE
+      ? widenLegacyCounter(buffer, Conversation.SIZE)
+      : buffer;
+
+    return Conversation.decode<Conversation>(Conversation.schema, Conversation, data);
+  }
+
   static async findPdaAddress(
       firstUserPdaAddress: PublicKey,
       secondUserPdaAddress: PublicKey,
@@ -98,12 +144,69 @@ export class Conversation extends SolanaBorsh {
   }
 }
 
+// AccountUpgrade account - exists while a legacy user or conversation account is upgraded
+export class AccountUpgrade extends SolanaBorsh {
+  counter = new BN(0);
+  funder = new Uint8Array(32);
+
+  static ACCOUNT_ADDRESS_SEED = 'account-upgrade';
+
+  static schema: Schema = new Map([
+    [
+      AccountUpgrade,
+      {
+        kind: 'struct',
+        fields: [
+          ['counter', 'u64'],
+          ['funder', [32]],
+        ],
+      },
+    ],
+  ]);
+
+  constructor(properties: { counter: BN, funder: Uint8Array }) {
+    super(AccountUpgrade.schema);
+
+    if (properties) {
+      this.assign(properties);
+    }
+  }
+
+  static async findPdaAddress(
+      accountPdaAddress: PublicKey,
+      programId: PublicKey,
+  ): Promise<PublicKey> {
+    const publicKeyNonce = await PublicKey.findProgramAddress(
+        [
+          accountPdaAddress.toBuffer(),
+          Buffer.from(this.ACCOUNT_ADDRESS_SEED),
+        ],
+        programId,
+    );
+
+    return publicKeyNonce[0];
+  }
+}
+
 // UserConversation account
 export class UserConversation extends SolanaBorsh {
   conversation_address: PublicKey | undefined;
+  archived = false;
+  hidden = false;
+  muted_until = 0;
+  folder_id = 0;
+  bump_seed = 0;
 
   static ACCOUNT_ADDRESS_SEED = 'user-conversation';
 
+  // Static seed of v2 addresses - v2 seeds hold the index as little-endian u32
+  static ACCOUNT_ADDRESS_SEED_V2 = 'user-conversation-v2';
+
+  // Size of accounts created before the per-user flags were added
+  static LEGACY_SIZE = 32;
+
+  static SIZE = 47;
+
   static schema: Schema = new Map([
     [
       UserConversation,
@@ -111,6 +214,11 @@ export class UserConversation extends SolanaBorsh {
         kind: 'struct',
         fields: [
           ['conversation_address', [32]],
+          ['archived', 'u8'],
+          ['hidden', 'u8'],
+          ['muted_until', 'u64'],
+          ['folder_id', 'u32'],
+          ['bump_seed', 'u8'],
         ],
       },
     ],
@@ -124,6 +232,15 @@ export class UserConversation extends SolanaBorsh {
     }
   }
 
+  // Legacy accounts hold the conversation address only - missing fields are read as unset
+  static decodeAccount(buffer: Buffer): UserConversation {
+    const data = buffer.length === UserConversation.LEGACY_SIZE
+      ? Buffer.concat([buffer, Buffer.alloc(UserConversation.SIZE - buffer.length)])
+      : buffer;
+
+    return UserConversation.decode<UserConversation>(UserConversation.schema, UserConversation, data);
+  }
+
   static async findPdaAddress(
       userPdaAddress: PublicKey,
       conversationIndex: number,
@@ -139,6 +256,23 @@ export class UserConversation extends SolanaBorsh {
 
     return publicKeyNonce[0];
   }
+
+  static async findPdaAddressV2(
+      userPdaAddress: PublicKey,
+      conversationIndex: number | BN,
+      programId: PublicKey,
+  ): Promise<PublicKey> {
+    const publicKeyNonce = await PublicKey.findProgramAddress(
+        [
+          userPdaAddress.toBuffer(),
+          indexSeed(conversationIndex),
+          Buffer.from(this.ACCOUNT_ADDRESS_SEED_V2),
+        ],
+        programId,
+    );
+
+    return publicKeyNonce[0];
+  }
 }
 
 // Message account
@@ -147,9 +281,15 @@ export class Message extends SolanaBorsh {
   message_type: number | undefined;
   content: Uint8Array | undefined;
   timestamp: BN | undefined;
+  message_id: Uint8Array | undefined;
 
   static ACCOUNT_ADDRESS_SEED = 'message';
 
+  // Static seed of v2 addresses - v2 seeds hold the index as little-endian u32
+  static ACCOUNT_ADDRESS_SEED_V2 = 'message-v2';
+
+  static MESSAGE_ID_SIZE = 16;
+
   static schema: Schema = new Map([
     [
       Message,
@@ -160,12 +300,13 @@ export class Message extends SolanaBorsh {
           ['message_type', 'u8'],
           ['content', ['u8']],
           ['timestamp', 'u64'],
+          ['message_id', [16]],
         ],
       },
     ],
   ]);
 
-  constructor(properties: ConversationType) {
+  constructor(properties: { sender: Uint8Array, message_type: number, content: Uint8Array, timestamp: BN, message_id: Uint8Array }) {
     super(Message.schema);
 
     if (properties) {
@@ -173,6 +314,16 @@ export class Message extends SolanaBorsh {
     }
   }
 
+  // Messages sent before message IDs end with the timestamp
+  static decodeAccount(buffer: Buffer): Message {
+    const legacySize = 32 + 1 + 4 + buffer.readUInt32LE(33) + 8;
+    const data = buffer.length === legacySize
+      ? Buffer.concat([buffer, Buffer.alloc(Message.MESSAGE_ID_SIZE)])
+      : buffer;
+
+    return Message.decode<Message>(Message.schema, Message, data);
+  }
+
   static async findPdaAddress(
       conversationPdaAddress: PublicKey,
       messageIndex: number,
@@ -188,6 +339,168 @@ export class Message extends SolanaBorsh {
 
     return publicKeyNonce[0];
   }
+
+  static async findPdaAddressV2(
+      conversationPdaAddress: PublicKey,
+      messageIndex: number | BN,
+      programId: PublicKey,
+  ): Promise<PublicKey> {
+    const publicKeyNonce = await PublicKey.findProgramAddress(
+        [
+          conversationPdaAddress.toBuffer(),
+          indexSeed(messageIndex),
+          Buffer.from(this.ACCOUNT_ADDRESS_SEED_V2),
+        ],
+        programId,
+    );
+
+    return publicKeyNonce[0];
+  }
+}
+
+// MessageIdRecord account
+export class MessageIdRecord extends SolanaBorsh {
+  message_index = new BN(0);
+
+  static ACCOUNT_ADDRESS_SEED = 'message-id';
+
+  static SIZE = 8;
+
+  static schema: Schema = new Map([
+    [
+      MessageIdRecord,
+      {
+        kind: 'struct',
+        fields: [
+          ['message_index', 'u64'],
+        ],
+      },
+    ],
+  ]);
+
+  constructor(properties: { message_index: BN }) {
+    super(MessageIdRecord.schema);
+
+    if (properties) {
+      this.assign(properties);
+    }
+  }
+
+  // Message IDs are chosen per sender - the sender wallet is part of the address
+  static async findPdaAddress(
+      conversationPdaAddress: PublicKey,
+      senderWalletAddress: PublicKey,
+      messageId: Uint8Array,
+      programId: PublicKey,
+  ): Promise<PublicKey> {
+    const publicKeyNonce = await PublicKey.findProgramAddress(
+        [
+          conversationPdaAddress.toBuffer(),
+          senderWalletAddress.toBuffer(),
+          Buffer.from(messageId),
+          Buffer.from(this.ACCOUNT_ADDRESS_SEED),
+        ],
+        programId,
+    );
+
+    return publicKeyNonce[0];
+  }
+}
+
+// PagedConversation account
+export class PagedConversation extends SolanaBorsh {
+  page_counter = 0;
+  message_counter = new BN(0);
+  last_page_size = 0;
+
+  static ACCOUNT_ADDRESS_SEED = 'paged-conversation';
+
+  static schema: Schema = new Map([
+    [
+      PagedConversation,
+      {
+        kind: 'struct',
+        fields: [
+          ['page_counter', 'u32'],
+          ['message_counter', 'u64'],
+          ['last_page_size', 'u32'],
+        ],
+      },
+    ],
+  ]);
+
+  constructor(properties: { page_counter: number, message_counter: BN, last_page_size: number }) {
+    super(PagedConversation.schema);
+
+    if (properties) {
+      this.assign(properties);
+    }
+  }
+
+  static async findPdaAddress(
+      conversationPdaAddress: PublicKey,
+      programId: PublicKey,
+  ): Promise<PublicKey> {
+    const publicKeyNonce = await PublicKey.findProgramAddress(
+        [
+          conversationPdaAddress.toBuffer(),
+          Buffer.from(this.ACCOUNT_ADDRESS_SEED),
+        ],
+        programId,
+    );
+
+    return publicKeyNonce[0];
+  }
+}
+
+// MessagePage account
+export class MessagePage extends SolanaBorsh {
+  messages: Message[] = [];
+
+  static ACCOUNT_ADDRESS_SEED = 'message-page';
+
+  static schema: Schema = new Map<any, any>([
+    ...Message.schema,
+    [
+      MessagePage,
+      {
+        kind: 'struct',
+        fields: [
+          ['messages', [Message]],
+        ],
+      },
+    ],
+  ]);
+
+  constructor(properties: { messages: Message[] }) {
+    super(MessagePage.schema);
+
+    if (properties) {
+      this.assign(properties);
+    }
+  }
+
+  // Page accounts are allocated at full size, the rest after the messages is zeroed
+  static decodeUnchecked(buffer: Buffer): MessagePage {
+    return deserializeUnchecked(MessagePage.schema, MessagePage, buffer);
+  }
+
+  static async findPdaAddress(
+      conversationPdaAddress: PublicKey,
+      pageIndex: number,
+      programId: PublicKey,
+  ): Promise<PublicKey> {
+    const publicKeyNonce = await PublicKey.findProgramAddress(
+        [
+          conversationPdaAddress.toBuffer(),
+          new BN(pageIndex).toArrayLike(Buffer, 'le', 4),
+          Buffer.from(this.ACCOUNT_ADDRESS_SEED),
+        ],
+        programId,
+    );
+
+    return publicKeyNonce[0];
+  }
 }
 
 // ConversationEncryptionInfo account
@@ -232,8 +545,159 @@ export class ConversationEncryptionInfo extends SolanaBorsh {
   }
 }
 
+// ConversationPaddingPolicy account
+export class ConversationPaddingPolicy extends SolanaBorsh {
+  scheme: number | undefined;
+
+  static ACCOUNT_ADDRESS_SEED = 'conversation-padding-policy';
+
+  static schema: Schema = new Map([
+    [
+      ConversationPaddingPolicy,
+      {
+        kind: 'struct',
+        fields: [
+          ['scheme', 'u8'],
+        ],
+      },
+    ],
+  ]);
+
+  constructor(properties: { scheme: number }) {
+    super(ConversationPaddingPolicy.schema);
+
+    if (properties) {
+      this.assign(properties);
+    }
+  }
+
+  static async findPdaAddress(
+      conversationPdaAddress: PublicKey,
+      programId: PublicKey,
+  ): Promise<PublicKey> {
+    const publicKeyNonce = await PublicKey.findProgramAddress(
+        [
+          conversationPdaAddress.toBuffer(),
+          Buffer.from(this.ACCOUNT_ADDRESS_SEED),
+        ],
+        programId,
+    );
+
+    return publicKeyNonce[0];
+  }
+}
+
+// ConversationMetadata account - the account is allocated for the longest fields, trailing bytes are zeroed
+export class ConversationMetadata extends SolanaBorsh {
+  creator: Uint8Array = new Uint8Array(32);
+  created_at = 0;
+  title = '';
+  description = '';
+  avatar_uri = '';
+
+  static ACCOUNT_ADDRESS_SEED = 'conversation-metadata';
+
+  static schema: Schema = new Map([
+    [
+      ConversationMetadata,
+      {
+        kind: 'struct',
+        fields: [
+          ['creator', [32]],
+          ['created_at', 'u64'],
+          ['title', 'string'],
+          ['description', 'string'],
+          ['avatar_uri', 'string'],
+        ],
+      },
+    ],
+  ]);
+
+  constructor(properties: { creator: Uint8Array, created_at: number, title: string, description: string, avatar_uri: string }) {
+    super(ConversationMetadata.schema);
+
+    if (properties) {
+      this.assign(properties);
+    }
+  }
+
+  static decodeUnchecked(buffer: Buffer): ConversationMetadata {
+    return deserializeUnchecked(ConversationMetadata.schema, ConversationMetadata, buffer);
+  }
+
+  static async findPdaAddress(
+      conversationPdaAddress: PublicKey,
+      programId: PublicKey,
+  ): Promise<PublicKey> {
+    const publicKeyNonce = await PublicKey.findProgramAddress(
+        [
+          conversationPdaAddress.toBuffer(),
+          Buffer.from(this.ACCOUNT_ADDRESS_SEED),
+        ],
+        programId,
+    );
+
+    return publicKeyNonce[0];
+  }
+}
+
+// PinnedMessages account - the account is allocated for the longest list, trailing bytes are zeroed
+export class PinnedMessages extends SolanaBorsh {
+  message_indexes: BN[] = [];
+
+  static ACCOUNT_ADDRESS_SEED = 'pinned-messages';
+
+  static MAX_PINNED_MESSAGES = 16;
+
+  static schema: Schema = new Map([
+    [
+      PinnedMessages,
+      {
+        kind: 'struct',
+        fields: [
+          ['message_indexes', ['u64']],
+        ],
+      },
+    ],
+  ]);
+
+  constructor(properties: { message_indexes: BN[] }) {
+    super(PinnedMessages.schema);
+
+    if (properties) {
+      this.assign(properties);
+    }
+  }
+
+  static decodeUnchecked(buffer: Buffer): PinnedMessages {
+    return deserializeUnchecked(PinnedMessages.schema, PinnedMessages, buffer);
+  }
+
+  static async findPdaAddress(
+      conversationPdaAddress: PublicKey,
+      programId: PublicKey,
+  ): Promise<PublicKey> {
+    const publicKeyNonce = await PublicKey.findProgramAddress(
+        [
+          conversationPdaAddress.toBuffer(),
+          Buffer.from(this.ACCOUNT_ADDRESS_SEED),
+        ],
+        programId,
+    );
+
+    return publicKeyNonce[0];
+  }
+}
+
+export enum PaddingScheme {
+  NONE = 0,
+  POWER_OF_TWO = 1,
+  BLOCK_256 = 2,
+}
+
 export enum MessageType {
   PLAIN_TEXT = 0,
   RSA_ENCRYPTED = 1,
   ARWEAVE = 2,
+  SEALED_SENDER = 3,
 }
@@ -0,0 +1,69 @@
+//! Off-chain keys of blinded conversations
+//!
+//! Both participants derive the same blinded-conversation address and signing authority from the secret
+//! they share, so the conversation account does not reveal who takes part in it.
+
+use {
+    crate::{
+        encryption::{
+            ed25519_address,
+            ed25519_secret_scalar,
+            CodecError,
+        },
+        state::BlindedConversation,
+    },
+    curve25519_dalek::{
+        edwards::CompressedEdwardsY,
+        scalar::Scalar,
+    },
+    solana_program::pubkey::Pubkey,
+};
+
+/// Get secret shared by two wallets - X25519 over the ed25519 keys of the wallets
+///
+/// `wallet_secret_seed` is the 32-byte ed25519 seed - the first half of a Solana keypair.
+pub fn derive_shared_secret(
+    wallet_secret_seed: &[u8; 32],
+    peer_wallet_address: &Pubkey,
+) -> Result<[u8; 32], CodecError> {
+    let peer_point = CompressedEdwardsY(peer_wallet_address.to_bytes())
+        .decompress()
+        .ok_or_else(|| CodecError::InvalidAddress(peer_wallet_address.to_string()))?
+        .to_montgomery();
+
+    Ok((peer_point * Scalar::from_bytes_mod_order(ed25519_secret_scalar(wallet_secret_seed))).to_bytes())
+}
+
+/// Keys of a blinded conversation, derived the same way by both participants
+#[derive(Clone, Debug, PartialEq)]
+pub struct BlindedConversationKeys {
+    /// Seed of the blinded-conversation account address
+    pub blinded_id: [u8; 32],
+    /// Ed25519 seed of the keypair that signs on behalf of the participants
+    pub authority_seed: [u8; 32],
+}
+
+impl BlindedConversationKeys {
+    /// Derive keys from the own wallet seed and the wallet address of the other participant
+    pub fn derive(
+        wallet_secret_seed: &[u8; 32],
+        peer_wallet_address: &Pubkey,
+    ) -> Result<Self, CodecError> {
+        let shared_secret = derive_shared_secret(wallet_secret_seed, peer_wallet_address)?;
+
+        Ok(Self {
+            blinded_id: BlindedConversation::derive_blinded_id(&shared_secret),
+            authority_seed: BlindedConversation::derive_authority_seed(&shared_secret),
+        })
+    }
+
+    /// Get address of the keypair that signs on behalf of the participants
+    pub fn authority(&self) -> Pubkey {
+        ed25519_address(&self.authority_seed)
+    }
+
+    /// Get address of the blinded-conversation account
+    pub fn conversation_address(&self, program_id: &Pubkey) -> Pubkey {
+        BlindedConversation::find_pda_address(&self.blinded_id, &self.authority(), program_id)
+    }
+}
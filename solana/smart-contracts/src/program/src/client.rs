@@ -0,0 +1,105 @@
+use {
+    crate::{
+        error::InstantMessagingError,
+        id,
+        instruction::send_message,
+        state::{
+            Conversation,
+            Message,
+            MessageIdRecord,
+            User,
+        },
+    },
+    solana_program::{
+        instruction::{
+            Instruction,
+            InstructionError,
+        },
+        pubkey::Pubkey,
+    },
+};
+
+/// Connection to the cluster used by the client helpers
+pub trait ConversationClient {
+    type Error;
+
+    /// Get current message counter of the conversation account
+    fn message_counter(&mut self, conversation_address: &Pubkey) -> Result<u64, Self::Error>;
+
+    /// Get message index stored in the message-id account - `None` if the account does not exist
+    fn message_id_index(&mut self, message_id_address: &Pubkey) -> Result<Option<u64>, Self::Error>;
+
+    /// Sign and process the instruction in a transaction
+    fn process_instruction(&mut self, instruction: Instruction) -> Result<(), Self::Error>;
+
+    /// Get error of the failed instruction, if the transaction failed on one
+    fn instruction_error(error: &Self::Error) -> Option<&InstructionError>;
+}
+
+/// Check if the instruction failed because another message landed first
+pub fn is_message_index_conflict(error: &InstructionError) -> bool {
+    *error == InstructionError::Custom(InstantMessagingError::MessageIndexConflict as u32)
+}
+
+/// Send message with the current message counter as expected index - on a message index conflict
+/// the counter is refreshed and the message is sent again, at most `max_attempts` times in total.
+/// Every attempt carries `message_id`, so an attempt that landed despite a failed response is not stored twice.
+/// Returns the index the message is stored at - read back from the message-id account, so a message ID
+/// that was already sent yields the index of the first send, not the counter of the no-op attempt
+#[allow(clippy::too_many_arguments)]
+pub fn send_message_with_retry<C: ConversationClient>(
+    client: &mut C,
+    funder_address: &Pubkey,
+    sender_wallet_address: &Pubkey,
+    receiver_wallet_address: &Pubkey,
+    message_type: u8,
+    content: &[u8],
+    message_id: [u8; 16],
+    max_attempts: u32,
+) -> Result<u64, C::Error> {
+    let conversation_address = Conversation::find_pda_address(
+        &User::find_pda_address(sender_wallet_address, &id()),
+        &User::find_pda_address(receiver_wallet_address, &id()),
+        &id(),
+    );
+
+    let message_id_address = MessageIdRecord::find_pda_address(
+        &conversation_address,
+        sender_wallet_address,
+        &message_id,
+        &id(),
+    );
+
+    let mut attempt = 1;
+
+    loop {
+        let message_index = client.message_counter(&conversation_address)?;
+
+        let result = client.process_instruction(send_message(
+            funder_address,
+            sender_wallet_address,
+            receiver_wallet_address,
+            message_index,
+            message_type,
+            content.to_vec(),
+            message_id,
+        ));
+
+        match result {
+            Ok(()) if message_id == Message::NO_MESSAGE_ID => return Ok(message_index),
+            Ok(()) => return Ok(client.message_id_index(&message_id_address)?.unwrap_or(message_index)),
+            Err(error) => {
+                let is_conflict = matches!(
+                    C::instruction_error(&error),
+                    Some(instruction_error) if is_message_index_conflict(instruction_error)
+                );
+
+                if !is_conflict || attempt >= max_attempts {
+                    return Err(error);
+                }
+            }
+        }
+
+        attempt += 1;
+    }
+}
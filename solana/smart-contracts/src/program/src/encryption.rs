@@ -0,0 +1,202 @@
+//! Off-chain codec for encrypted conversations
+//!
+//! Mirrors the layouts written by the TypeScript client (`src/client/im`), so Rust consumers can
+//! read and write the same `Message::content` and `ConversationEncryptionInfo::data` bytes.
+
+use {
+    crate::state::MessageType,
+    curve25519_dalek::{
+        constants::ED25519_BASEPOINT_TABLE,
+        scalar::Scalar,
+    },
+    serde::{Deserialize, Serialize},
+    sha2::{
+        Digest,
+        Sha512,
+    },
+    solana_program::pubkey::Pubkey,
+    std::{
+        convert::TryFrom,
+        str::FromStr,
+    },
+    thiserror::Error,
+};
+
+/// Errors that may be returned by the codec
+#[derive(Debug, Error)]
+pub enum CodecError {
+    /// Message type is not known to the codec
+    #[error("Unsupported message type {0}")]
+    UnsupportedMessageType(u8),
+
+    /// Encryption type is not known to the codec
+    #[error("Unsupported encryption type {0}")]
+    UnsupportedEncryptionType(u8),
+
+    /// Textual content is not valid UTF-8
+    #[error("Content is not valid UTF-8")]
+    InvalidUtf8(#[from] std::string::FromUtf8Error),
+
+    /// Encryption info is not valid JSON
+    #[error("Invalid encryption info: {0}")]
+    InvalidEncryptionInfo(#[from] serde_json::Error),
+
+    /// Address is not a base58 encoded public key
+    #[error("Invalid address {0}")]
+    InvalidAddress(String),
+
+    /// Sealed sender envelope cannot be decoded
+    #[error("Invalid sealed sender envelope")]
+    InvalidSealedSenderEnvelope(#[from] std::io::Error),
+
+    /// Padding scheme is not known to the codec
+    #[error("Unsupported padding scheme {0}")]
+    UnsupportedPaddingScheme(u8),
+
+    /// Content is not padded according to the scheme
+    #[error("Invalid padding")]
+    InvalidPadding,
+}
+
+/// Decoded content of a message account
+#[derive(Clone, Debug, PartialEq)]
+pub enum MessageContent {
+    /// UTF-8 text, stored as is
+    PlainText(String),
+    /// RSA ciphertext, stored verbatim - the client does not add any framing
+    RsaEncrypted(Vec<u8>),
+    /// UTF-8 reference to the Arweave transaction holding the content
+    Arweave(String),
+    /// Encrypted `SealedSenderEnvelope`, stored verbatim
+    SealedSender(Vec<u8>),
+}
+
+impl MessageContent {
+    /// Get message type the content is stored with
+    pub fn message_type(&self) -> u8 {
+        match self {
+            MessageContent::PlainText(_) => MessageType::PLAIN_TEXT,
+            MessageContent::RsaEncrypted(_) => MessageType::RSA_ENCRYPTED,
+            MessageContent::Arweave(_) => MessageType::ARWEAVE,
+            MessageContent::SealedSender(_) => MessageType::SEALED_SENDER,
+        }
+    }
+
+    /// Encode content into `Message::content` bytes
+    pub fn encode(&self) -> Vec<u8> {
+        match self {
+            MessageContent::PlainText(text) => text.as_bytes().to_vec(),
+            MessageContent::RsaEncrypted(ciphertext) => ciphertext.clone(),
+            MessageContent::Arweave(reference) => reference.as_bytes().to_vec(),
+            MessageContent::SealedSender(ciphertext) => ciphertext.clone(),
+        }
+    }
+
+    /// Decode `Message::content` bytes stored with the given message type
+    pub fn decode(message_type: u8, content: &[u8]) -> Result<Self, CodecError> {
+        match message_type {
+            MessageType::PLAIN_TEXT => Ok(MessageContent::PlainText(String::from_utf8(content.to_vec())?)),
+            MessageType::RSA_ENCRYPTED => Ok(MessageContent::RsaEncrypted(content.to_vec())),
+            MessageType::ARWEAVE => Ok(MessageContent::Arweave(String::from_utf8(content.to_vec())?)),
+            MessageType::SEALED_SENDER => Ok(MessageContent::SealedSender(content.to_vec())),
+            _ => Err(CodecError::UnsupportedMessageType(message_type)),
+        }
+    }
+}
+
+/// Define encryption types
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(into = "u8", try_from = "u8")]
+pub enum EncryptionType {
+    Rsa = 0,
+}
+
+impl From<EncryptionType> for u8 {
+    fn from(encryption_type: EncryptionType) -> Self {
+        encryption_type as u8
+    }
+}
+
+impl TryFrom<u8> for EncryptionType {
+    type Error = CodecError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(EncryptionType::Rsa),
+            _ => Err(CodecError::UnsupportedEncryptionType(value)),
+        }
+    }
+}
+
+/// Type specific data of the encryption info
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct EncryptionData {
+    /// Base58 encoded wallet addresses of the participants
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub addresses: Option<Vec<String>>,
+}
+
+/// Content of `ConversationEncryptionInfo::data` - JSON, field order matches `JSON.stringify`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EncryptionInfoData {
+    /// Encryption type used within the conversation
+    #[serde(rename = "type")]
+    pub encryption_type: EncryptionType,
+    /// Type specific data
+    pub data: EncryptionData,
+}
+
+impl EncryptionInfoData {
+    /// Create RSA encryption info for a conversation, as the client does on the first message
+    pub fn rsa(sender_wallet_address: &Pubkey, receiver_wallet_address: &Pubkey) -> Self {
+        Self {
+            encryption_type: EncryptionType::Rsa,
+            data: EncryptionData {
+                addresses: Some(vec![
+                    sender_wallet_address.to_string(),
+                    receiver_wallet_address.to_string(),
+                ]),
+            },
+        }
+    }
+
+    /// Get participant wallet addresses
+    pub fn addresses(&self) -> Result<Vec<Pubkey>, CodecError> {
+        self.data
+            .addresses
+            .iter()
+            .flatten()
+            .map(|address| {
+                Pubkey::from_str(address).map_err(|_| CodecError::InvalidAddress(address.clone()))
+            })
+            .collect()
+    }
+
+    /// Encode into `ConversationEncryptionInfo::data` bytes
+    pub fn encode(&self) -> Result<Vec<u8>, CodecError> {
+        Ok(serde_json::to_vec(self)?)
+    }
+
+    /// Decode from `ConversationEncryptionInfo::data` bytes
+    pub fn decode(data: &[u8]) -> Result<Self, CodecError> {
+        Ok(serde_json::from_slice(data)?)
+    }
+}
+
+/// Get clamped ed25519 secret scalar of the given seed - the first half of the SHA-512 digest
+pub(crate) fn ed25519_secret_scalar(seed: &[u8; 32]) -> [u8; 32] {
+    let mut scalar_bytes = [0_u8; 32];
+    scalar_bytes.copy_from_slice(&Sha512::digest(seed)[..32]);
+    scalar_bytes[0] &= 248;
+    scalar_bytes[31] &= 127;
+    scalar_bytes[31] |= 64;
+
+    scalar_bytes
+}
+
+/// Get address of the ed25519 keypair with the given seed - same as `keypair_from_seed` of the SDK
+pub(crate) fn ed25519_address(seed: &[u8; 32]) -> Pubkey {
+    let point = &Scalar::from_bits(ed25519_secret_scalar(seed)) * &ED25519_BASEPOINT_TABLE;
+
+    Pubkey::new_from_array(point.compress().to_bytes())
+}
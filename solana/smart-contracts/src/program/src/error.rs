@@ -4,15 +4,23 @@ use {
 };
 
 /// Errors that may be returned by the program
-#[derive(Debug, Error)]
+#[derive(Clone, Copy, Debug, Eq, Error, PartialEq)]
 pub enum InstantMessagingError {
     /// Incorrect account address derivation
     #[error("Incorrect account address derivation")]
     AddressDerivationMismatch,
+
+    /// Message index is not the current message counter of the conversation - another message landed first
+    #[error("Message index conflict")]
+    MessageIndexConflict,
+
+    /// Conversation or message counter would overflow - legacy accounts hold `u32` counters
+    #[error("Counter overflow")]
+    CounterOverflow,
 }
 
-impl From<DocumentsError> for ProgramError {
-    fn from(e: DocumentsError) -> Self {
+impl From<InstantMessagingError> for ProgramError {
+    fn from(e: InstantMessagingError) -> Self {
         ProgramError::Custom(e as u32)
     }
 }
@@ -21,4 +29,4 @@ impl<T> DecodeError<T> for InstantMessagingError {
     fn type_of() -> &'static str {
         "Instant Messaging Error"
     }
-}
\ No newline at end of file
+}
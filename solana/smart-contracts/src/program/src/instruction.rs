@@ -1,10 +1,12 @@
 use {
     borsh::{BorshDeserialize, BorshSerialize},
     solana_program::{
+        hash::Hash,
         instruction::{
             AccountMeta,
             Instruction,
         },
+        clock::UnixTimestamp,
         pubkey::Pubkey,
         system_program,
         sysvar,
@@ -12,10 +14,27 @@ use {
     crate::{
         id,
         state::{
+            AccountUpgrade,
+            BlindedConversation,
+            Community,
             User,
             Conversation,
             ConversationEncryptionInfo,
+            ConversationHandshake,
+            ConversationMetadata,
+            ConversationPaddingPolicy,
+            PinnedMessages,
+            SenderSequence,
+            Group,
+            GroupBan,
+            GroupMember,
+            Invite,
+            JoinRequest,
             Message,
+            MessageIdRecord,
+            MessagePage,
+            PagedConversation,
+            SealedSenderCredential,
             UserConversation,
         },
     },
@@ -32,6 +51,7 @@ pub enum InstantMessagingInstruction {
     /// 2. `[]` Wallet address of the user (must be a system account)
     /// 3. `[]` Rent sysvar
     /// 4. `[]` System program
+    /// 5. `[]` PDA address of the account-upgrade, derived from the user address (must not exist)
     CreateUserAccount,
 
     /// Create a new conversation account
@@ -46,6 +66,7 @@ pub enum InstantMessagingInstruction {
     /// 5. `[writable]` PDA address of the user-conversation - receiver
     /// 6. `[]` Rent sysvar
     /// 7. `[]` System program
+    /// 8. `[]` PDA address of the account-upgrade, derived from the conversation address (must not exist)
     CreateConversationAccount,
 
     /// Create a new user-conversation account
@@ -58,7 +79,7 @@ pub enum InstantMessagingInstruction {
     /// 3. `[]` Rent sysvar
     /// 4. `[]` System program
     CreateUserConversationAccount {
-        conversation_index: u32,
+        conversation_index: u64,
     },
 
     /// Create a new message account
@@ -74,8 +95,9 @@ pub enum InstantMessagingInstruction {
     /// 6. `[]` Rent sysvar
     /// 7. `[]` Clock sysvar
     /// 8. `[]` System program
+    /// 9. `[]` PDA address of the conversation-padding-policy
     CreateMessageAccount {
-        conversation_index: u32,
+        conversation_index: u64,
         message_type: u8,
         content: Vec<u8>,
     },
@@ -107,148 +129,2793 @@ pub enum InstantMessagingInstruction {
     /// 3. `[writable]` PDA address of the user - receiver
     /// 4. `[writable]` PDA address of the conversation
     /// 5. `[writable]` PDA address of the message
+    /// 6. `[writable]` PDA address of the message-id (ignored without message ID)
+    /// 7. `[]` Rent sysvar
+    /// 8. `[]` Clock sysvar
+    /// 9. `[]` System program
+    /// 10. `[]` PDA address of the conversation-padding-policy
+    ///
+    /// Fails with `MessageIndexConflict` if `expected_index` is not the current message counter of the conversation.
+    /// A message ID that was already sent to the conversation makes the instruction a no-op, all zeros disable the check
+    SendMessage {
+        message_type: u8,
+        content: Vec<u8>,
+        expected_index: u64,
+        message_id: [u8; 16],
+    },
+
+    /// Create a new conversation-handshake account - MLS commit and welcome that start an epoch
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` Funder account (must be a system account)
+    /// 1. `[signer]` Sender account (must be a system account)
+    /// 2. `[writable]` PDA address of the conversation-handshake
+    /// 3. `[]` PDA address of the conversation-handshake of the previous epoch (ignored for epoch 0)
+    /// 4. `[]` PDA address of the user - sender
+    /// 5. `[]` PDA address of the user - receiver
+    /// 6. `[]` PDA address of the conversation
+    /// 7. `[]` Rent sysvar
+    /// 8. `[]` Clock sysvar
+    /// 9. `[]` System program
+    CreateConversationHandshakeAccount {
+        epoch: u64,
+        commit: Vec<u8>,
+        welcome: Vec<u8>,
+    },
+
+    /// Create or replace the sealed-sender-credential of a conversation participant
+    ///
+    /// A credential whose chain is not used up is only replaced with a signature of its current authority.
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` Funder account (must be a system account)
+    /// 1. `[signer]` Sender account (must be a system account)
+    /// 2. `[writable]` PDA address of the sealed-sender-credential of the sender
+    /// 3. `[]` PDA address of the user - sender
+    /// 4. `[]` PDA address of the user - receiver
+    /// 5. `[]` PDA address of the conversation
+    /// 6. `[]` Rent sysvar
+    /// 7. `[]` System program
+    /// 8. `[signer]` Current authority of the credential (optional - required to replace an active chain)
+    SetSealedSenderCredential {
+        authority: Pubkey,
+        chain_hash: Hash,
+    },
+
+    /// Send a message without revealing the sender - the sender identity is encrypted in the content
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` Funder account (must be a system account) - usually a relayer
+    /// 1. `[signer]` Authority account - the ephemeral keypair named by the sealed-sender-credential
+    /// 2. `[writable]` PDA address of the conversation
+    /// 3. `[writable]` PDA address of the sealed-sender-credential of the sender
+    /// 4. `[writable]` PDA address of the message
+    /// 5. `[]` Rent sysvar
+    /// 6. `[]` Clock sysvar
+    /// 7. `[]` System program
+    /// 8. `[]` PDA address of the conversation-padding-policy
+    SendSealedMessage {
+        next_authority: Pubkey,
+        next_chain_hash: Hash,
+        content: Vec<u8>,
+    },
+
+    /// Create a new blinded-conversation account
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` Funder account (must be a system account)
+    /// 1. `[signer]` Authority account - derived from the shared secret
+    /// 2. `[writable]` PDA address of the blinded-conversation
+    /// 3. `[]` Rent sysvar
+    /// 4. `[]` System program
+    CreateBlindedConversationAccount {
+        blinded_id: [u8; 32],
+    },
+
+    /// Send a message to a blinded conversation
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` Funder account (must be a system account)
+    /// 1. `[signer]` Authority account - derived from the shared secret
+    /// 2. `[writable]` PDA address of the blinded-conversation
+    /// 3. `[writable]` PDA address of the message
+    /// 4. `[]` Rent sysvar
+    /// 5. `[]` Clock sysvar
+    /// 6. `[]` System program
+    /// 7. `[]` PDA address of the conversation-padding-policy
+    SendBlindedMessage {
+        message_type: u8,
+        content: Vec<u8>,
+    },
+
+    /// Create the conversation-padding-policy account - a policy without padding can be replaced, any other
+    /// padding scheme is immutable once set
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` Funder account (must be a system account)
+    /// 1. `[signer]` Sender account (must be a system account)
+    /// 2. `[writable]` PDA address of the conversation-padding-policy
+    /// 3. `[]` PDA address of the user - sender
+    /// 4. `[]` PDA address of the user - receiver
+    /// 5. `[]` PDA address of the conversation
+    /// 6. `[]` Rent sysvar
+    /// 7. `[]` System program
+    SetConversationPaddingPolicy {
+        scheme: u8,
+    },
+
+    /// Create a new group or channel account owned by the creator
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` Funder account (must be a system account)
+    /// 1. `[signer]` Creator account (must be a system account)
+    /// 2. `[writable]` PDA address of the group
+    /// 3. `[writable]` PDA address of the group-member - creator
+    /// 4. `[writable]` PDA address of the user - creator
+    /// 5. `[writable]` PDA address of the user-conversation - creator
+    /// 6. `[]` Rent sysvar
+    /// 7. `[]` System program
+    CreateGroupAccount {
+        group_index: u64,
+        kind: u8,
+    },
+
+    /// Add a member to the group
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` Funder account (must be a system account)
+    /// 1. `[signer]` Inviter account (must be a system account)
+    /// 2. `[]` PDA address of the group or community
+    /// 3. `[]` PDA address of the group-member - inviter
+    /// 4. `[signer]` Wallet address of the new member
+    /// 5. `[writable]` PDA address of the group-member - new member
+    /// 6. `[]` PDA address of the group-ban - new member
+    /// 7. `[writable]` PDA address of the user - new member
+    /// 8. `[writable]` PDA address of the user-conversation - new member
+    /// 9. `[]` Rent sysvar
+    /// 10. `[]` System program
+    AddGroupMember {
+        role: u8,
+        permissions: u8,
+    },
+
+    /// Send a message to the group
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` Funder account (must be a system account)
+    /// 1. `[signer]` Sender account (must be a system account)
+    /// 2. `[writable]` PDA address of the group
+    /// 3. `[]` PDA address of the group-member - sender, derived from the community for community groups
+    /// 4. `[writable]` PDA address of the message
+    /// 5. `[]` Rent sysvar
+    /// 6. `[]` Clock sysvar
+    /// 7. `[]` System program
+    /// 8. `[]` PDA address of the conversation-padding-policy, derived from the group
+    SendGroupMessage {
+        message_type: u8,
+        content: Vec<u8>,
+    },
+
+    /// Change role and permissions of a group member
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` Acting member account (must be a system account)
+    /// 1. `[]` PDA address of the group or community
+    /// 2. `[]` PDA address of the group-member - acting member
+    /// 3. `[]` Wallet address of the target member
+    /// 4. `[writable]` PDA address of the group-member - target member
+    SetMemberRole {
+        role: u8,
+        permissions: u8,
+    },
+
+    /// Transfer ownership of the group to another member - the previous owner becomes an admin
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` Owner account (must be a system account)
+    /// 1. `[writable]` PDA address of the group or community
+    /// 2. `[writable]` PDA address of the group-member - owner
+    /// 3. `[]` Wallet address of the new owner
+    /// 4. `[writable]` PDA address of the group-member - new owner
+    TransferGroupOwnership,
+
+    /// Create an invite to the group - only admins can create invites
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` Funder account (must be a system account)
+    /// 1. `[signer]` Creator account (must be a system account)
+    /// 2. `[]` PDA address of the group or community
+    /// 3. `[]` PDA address of the group-member - creator
+    /// 4. `[writable]` PDA address of the invite
+    /// 5. `[]` Rent sysvar
+    /// 6. `[]` Clock sysvar
+    /// 7. `[]` System program
+    CreateInviteAccount {
+        /// Hash of the invite code - see `Invite::hash_code`
+        code_hash: Hash,
+        expires_at: UnixTimestamp,
+        max_uses: u32,
+        /// Role given to the joining members - `GroupRole::MEMBER` if not set
+        role: Option<u8>,
+    },
+
+    /// Join the group with an invite - the invite code is checked against the hash of the invite
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` Funder account (must be a system account)
+    /// 1. `[signer]` Joining member account (must be a system account)
+    /// 2. `[]` PDA address of the group or community
+    /// 3. `[writable]` PDA address of the invite
+    /// 4. `[writable]` PDA address of the group-member - joining member
+    /// 5. `[]` PDA address of the group-ban - joining member
+    /// 6. `[writable]` PDA address of the user - joining member
+    /// 7. `[writable]` PDA address of the user-conversation - joining member
+    /// 8. `[]` Rent sysvar
+    /// 9. `[]` Clock sysvar
+    /// 10. `[]` System program
+    JoinWithInvite {
+        code: [u8; 16],
+    },
+
+    /// Request to join a private group - the requester pays for the request
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` Requester account (must be a system account)
+    /// 1. `[]` PDA address of the group or community
+    /// 2. `[writable]` PDA address of the join-request
+    /// 3. `[]` PDA address of the group-member - requester
+    /// 4. `[]` PDA address of the group-ban - requester
+    /// 5. `[]` Rent sysvar
+    /// 6. `[]` Clock sysvar
+    /// 7. `[]` System program
+    RequestToJoin,
+
+    /// Approve the join request - the requester becomes a member and the request is closed
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` Funder account (must be a system account)
+    /// 1. `[signer]` Admin account (must be a system account)
+    /// 2. `[]` PDA address of the group or community
+    /// 3. `[]` PDA address of the group-member - admin
+    /// 4. `[writable]` Requester account (must be a system account)
+    /// 5. `[writable]` PDA address of the join-request
+    /// 6. `[writable]` PDA address of the group-member - requester
+    /// 7. `[]` PDA address of the group-ban - requester
+    /// 8. `[writable]` PDA address of the user - requester
+    /// 9. `[writable]` PDA address of the user-conversation - requester
+    /// 10. `[]` Rent sysvar
+    /// 11. `[]` System program
+    ApproveJoin,
+
+    /// Deny the join request - the request is closed and refunded to the requester
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` Admin account (must be a system account)
+    /// 1. `[]` PDA address of the group or community
+    /// 2. `[]` PDA address of the group-member - admin
+    /// 3. `[writable]` Requester account (must be a system account)
+    /// 4. `[writable]` PDA address of the join-request
+    DenyJoin,
+
+    /// Remove a member from the group - the group-member account and the user-conversation entry of the group
+    /// are closed, their lamports go back to the removed member
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` Acting member account (must be a system account)
+    /// 1. `[]` PDA address of the group or community
+    /// 2. `[]` PDA address of the group-member - acting member
+    /// 3. `[writable]` Wallet address of the removed member
+    /// 4. `[writable]` PDA address of the group-member - removed member
+    /// 5. `[]` PDA address of the user - removed member
+    /// 6. `[writable]` PDA address of the user-conversation of the group - removed member
+    RemoveMember {
+        conversation_index: u64,
+    },
+
+    /// Ban a wallet from the group - removes the membership as `RemoveMember` does and blocks joining again
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` Funder account (must be a system account)
+    /// 1. `[signer]` Acting member account (must be a system account)
+    /// 2. `[]` PDA address of the group or community
+    /// 3. `[]` PDA address of the group-member - acting member
+    /// 4. `[writable]` Wallet address of the banned wallet
+    /// 5. `[writable]` PDA address of the group-member - banned wallet
+    /// 6. `[writable]` PDA address of the group-ban - banned wallet
+    /// 7. `[]` Rent sysvar
+    /// 8. `[]` Clock sysvar
+    /// 9. `[]` System program
+    /// 10. `[]` PDA address of the user - banned wallet
+    /// 11. `[writable]` PDA address of the user-conversation of the group - banned wallet
+    BanMember {
+        conversation_index: u64,
+    },
+
+    /// Leave the group and drop it from the own user-conversation list
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer, writable]` Member account (must be a system account)
+    /// 1. `[]` PDA address of the group or community
+    /// 2. `[writable]` PDA address of the group-member - member
+    /// 3. `[]` PDA address of the user - member
+    /// 4. `[writable]` PDA address of the user-conversation of the group - member
+    LeaveGroup {
+        conversation_index: u64,
+    },
+
+    /// Subscribe to a channel as a read-only member
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` Funder account (must be a system account)
+    /// 1. `[signer]` Subscriber account (must be a system account)
+    /// 2. `[]` PDA address of the channel
+    /// 3. `[writable]` PDA address of the group-member - subscriber
+    /// 4. `[]` PDA address of the group-ban - subscriber
+    /// 5. `[writable]` PDA address of the user - subscriber
+    /// 6. `[writable]` PDA address of the user-conversation - subscriber
+    /// 7. `[]` Rent sysvar
+    /// 8. `[]` System program
+    Subscribe,
+
+    /// Unsubscribe from a channel - accounts are the same as for `LeaveGroup`
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer, writable]` Subscriber account (must be a system account)
+    /// 1. `[]` PDA address of the channel
+    /// 2. `[writable]` PDA address of the group-member - subscriber
+    /// 3. `[]` PDA address of the user - subscriber
+    /// 4. `[writable]` PDA address of the user-conversation of the channel - subscriber
+    Unsubscribe {
+        conversation_index: u64,
+    },
+
+    /// Create a new community account owned by the creator
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` Funder account (must be a system account)
+    /// 1. `[signer]` Creator account (must be a system account)
+    /// 2. `[writable]` PDA address of the community
+    /// 3. `[writable]` PDA address of the group-member - creator
+    /// 4. `[writable]` PDA address of the user - creator
+    /// 5. `[writable]` PDA address of the user-conversation - creator
+    /// 6. `[]` Rent sysvar
+    /// 7. `[]` System program
+    CreateCommunityAccount {
+        community_index: u32,
+    },
+
+    /// Create a group or channel inside the community - only admins can create them
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` Funder account (must be a system account)
+    /// 1. `[signer]` Creator account (must be a system account)
+    /// 2. `[writable]` PDA address of the community
+    /// 3. `[]` PDA address of the group-member of the community - creator
+    /// 4. `[writable]` PDA address of the group
+    /// 5. `[]` Rent sysvar
+    /// 6. `[]` System program
+    CreateCommunityGroupAccount {
+        kind: u8,
+    },
+
+    /// Create or update the conversation-metadata account - only participants can set it
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` Funder account (must be a system account)
+    /// 1. `[signer]` Sender account (must be a system account)
+    /// 2. `[writable]` PDA address of the conversation-metadata
+    /// 3. `[]` PDA address of the user - sender
+    /// 4. `[]` PDA address of the user - receiver
+    /// 5. `[]` PDA address of the conversation
     /// 6. `[]` Rent sysvar
     /// 7. `[]` Clock sysvar
     /// 8. `[]` System program
-    SendMessage {
+    SetConversationMetadata {
+        title: String,
+        description: String,
+        avatar_uri: String,
+    },
+
+    /// Create or update the conversation-metadata account of the group or community - requires edit-metadata permission
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` Funder account (must be a system account)
+    /// 1. `[signer]` Editor account (must be a system account)
+    /// 2. `[writable]` PDA address of the conversation-metadata
+    /// 3. `[]` PDA address of the group or community
+    /// 4. `[]` PDA address of the group-member - editor, derived from the community for community groups
+    /// 5. `[]` Rent sysvar
+    /// 6. `[]` Clock sysvar
+    /// 7. `[]` System program
+    SetGroupMetadata {
+        title: String,
+        description: String,
+        avatar_uri: String,
+    },
+
+    /// Pin the message of the conversation - only participants can pin messages
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` Funder account (must be a system account)
+    /// 1. `[signer]` Sender account (must be a system account)
+    /// 2. `[writable]` PDA address of the pinned-messages
+    /// 3. `[]` PDA address of the user - sender
+    /// 4. `[]` PDA address of the user - receiver
+    /// 5. `[]` PDA address of the conversation
+    /// 6. `[]` PDA address of the message
+    /// 7. `[]` Rent sysvar
+    /// 8. `[]` System program
+    PinMessage {
+        message_index: u64,
+    },
+
+    /// Unpin the message of the conversation - only participants can unpin messages
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` Sender account (must be a system account)
+    /// 1. `[writable]` PDA address of the pinned-messages
+    /// 2. `[]` PDA address of the user - sender
+    /// 3. `[]` PDA address of the user - receiver
+    /// 4. `[]` PDA address of the conversation
+    UnpinMessage {
+        message_index: u64,
+    },
+
+    /// Pin the message of the group - requires pin permission
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` Funder account (must be a system account)
+    /// 1. `[signer]` Member account (must be a system account)
+    /// 2. `[writable]` PDA address of the pinned-messages
+    /// 3. `[]` PDA address of the group
+    /// 4. `[]` PDA address of the group-member - member, derived from the community for community groups
+    /// 5. `[]` PDA address of the message
+    /// 6. `[]` Rent sysvar
+    /// 7. `[]` System program
+    PinGroupMessage {
+        message_index: u64,
+    },
+
+    /// Unpin the message of the group - requires pin permission
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` Member account (must be a system account)
+    /// 1. `[writable]` PDA address of the pinned-messages
+    /// 2. `[]` PDA address of the group
+    /// 3. `[]` PDA address of the group-member - member, derived from the community for community groups
+    UnpinGroupMessage {
+        message_index: u64,
+    },
+
+    /// Update per-user flags of the user-conversation - archive, mute and folder
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` User wallet account (must be a system account)
+    /// 1. `[]` PDA address of the user
+    /// 2. `[writable]` PDA address of the user-conversation
+    UpdateUserConversation {
+        conversation_index: u64,
+        archived: bool,
+        muted_until: UnixTimestamp,
+        folder_id: u32,
+    },
+
+    /// Hide the conversation from the own user-conversation list or show it again - the other participant keeps it
+    ///
+    /// The user-conversation account stays as a tombstone and lamports above its rent-exempt minimum go back
+    /// to the user wallet. Legacy accounts have no room for the flag, so hiding closes them and refunds the rent.
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer, writable]` User wallet account (must be a system account)
+    /// 1. `[]` PDA address of the user
+    /// 2. `[]` PDA address of the user - other participant
+    /// 3. `[]` PDA address of the conversation
+    /// 4. `[writable]` PDA address of the user-conversation
+    /// 5. `[]` Rent sysvar
+    HideConversation {
+        conversation_index: u64,
+        hidden: bool,
+    },
+
+    /// Send the first message - creates the missing user, conversation and user-conversation accounts
+    /// in the same instruction, existing accounts are reused
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` Funder account (must be a system account)
+    /// 1. `[signer]` Sender account (must be a system account)
+    /// 2. `[]` Receiver account (must be a system account)
+    /// 3. `[writable]` PDA address of the user - sender
+    /// 4. `[writable]` PDA address of the user - receiver
+    /// 5. `[writable]` PDA address of the conversation
+    /// 6. `[writable]` PDA address of the user-conversation - sender, at the current conversation counter
+    /// 7. `[writable]` PDA address of the user-conversation - receiver, at the current conversation counter
+    /// 8. `[writable]` PDA address of the message
+    /// 9. `[writable]` PDA address of the message-id (ignored without message ID)
+    /// 10. `[]` Rent sysvar
+    /// 11. `[]` Clock sysvar
+    /// 12. `[]` System program
+    /// 13. `[]` PDA address of the conversation-padding-policy
+    /// 14. `[]` PDA address of the account-upgrade, derived from the user address - sender
+    /// 15. `[]` PDA address of the account-upgrade, derived from the user address - receiver
+    /// 16. `[]` PDA address of the account-upgrade, derived from the conversation address
+    StartConversation {
         message_type: u8,
         content: Vec<u8>,
+        expected_index: u64,
+        message_id: [u8; 16],
     },
-}
 
-/// Creates CreateUserAccount instruction
-pub fn create_user_account(
-    funder_address: &Pubkey,
-    wallet_address: &Pubkey,
-) -> Instruction {
-    let user_pda_address = User::find_pda_address(
-        &wallet_address,
+    /// Send message in the own sequence of the sender - the conversation account is not written,
+    /// so participants sending at the same time do not contend for it
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` Funder account (must be a system account)
+    /// 1. `[signer]` Sender account (must be a system account)
+    /// 2. `[]` PDA address of the user - sender
+    /// 3. `[]` PDA address of the user - receiver
+    /// 4. `[]` PDA address of the conversation
+    /// 5. `[writable]` PDA address of the sender-sequence
+    /// 6. `[writable]` PDA address of the sequenced message
+    /// 7. `[]` Rent sysvar
+    /// 8. `[]` Clock sysvar
+    /// 9. `[]` System program
+    /// 10. `[]` PDA address of the conversation-padding-policy
+    SendSequencedMessage {
+        message_type: u8,
+        content: Vec<u8>,
+    },
+
+    /// Send messages of `(message_type, content, message_id)` in one instruction - the messages are stored
+    /// at consecutive indexes starting at `expected_index`. Messages whose ID the sender already sent are
+    /// skipped and take no index, the message accounts left over at the end stay untouched.
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` Funder account (must be a system account)
+    /// 1. `[signer]` Sender account (must be a system account)
+    /// 2. `[]` PDA address of the user - sender
+    /// 3. `[]` PDA address of the user - receiver
+    /// 4. `[writable]` PDA address of the conversation
+    /// 5. `[]` Rent sysvar
+    /// 6. `[]` Clock sysvar
+    /// 7. `[]` System program
+    /// 8. `[]` PDA address of the conversation-padding-policy
+    /// 9. ..9+2N `[writable]` PDA addresses of the messages at consecutive indexes, one for each message,
+    ///    followed by PDA addresses of the message-ids, one for each message
+    ///
+    /// Fails with `MessageIndexConflict` if `expected_index` is not the current message counter of the conversation
+    SendMessages {
+        messages: Vec<(u8, Vec<u8>, [u8; 16])>,
+        expected_index: u64,
+    },
+
+    /// Append message to the last message-page of the conversation - a new page is created
+    /// when the message does not fit into the last one
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` Funder account (must be a system account)
+    /// 1. `[signer]` Sender account (must be a system account)
+    /// 2. `[]` PDA address of the user - sender
+    /// 3. `[]` PDA address of the user - receiver
+    /// 4. `[]` PDA address of the conversation
+    /// 5. `[writable]` PDA address of the paged-conversation
+    /// 6. `[writable]` PDA address of the last message-page (ignored without pages)
+    /// 7. `[writable]` PDA address of the next message-page, at the current page counter
+    /// 8. `[]` Rent sysvar
+    /// 9. `[]` Clock sysvar
+    /// 10. `[]` System program
+    /// 11. `[]` PDA address of the conversation-padding-policy
+    SendPagedMessage {
+        message_type: u8,
+        content: Vec<u8>,
+    },
+
+    /// Move the user-conversation account from the v1 address to the v2 address - the account is stored
+    /// in the current layout, lamports of the v1 account are returned to the user wallet
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer, writable]` Funder account (must be a system account)
+    /// 1. `[signer, writable]` User wallet account (must be a system account)
+    /// 2. `[]` PDA address of the user
+    /// 3. `[writable]` PDA address of the user-conversation - v1 seeds
+    /// 4. `[writable]` PDA address of the user-conversation - v2 seeds
+    /// 5. `[]` Rent sysvar
+    /// 6. `[]` System program
+    MigrateUserConversationAccount {
+        conversation_index: u32,
+    },
+
+    /// Move the message account from the v1 address to the v2 address - the account is stored
+    /// in the current layout, lamports of the v1 account are returned to the message sender.
+    /// Messages of conversations are migrated by either participant, messages of groups and blinded
+    /// conversations by their sender
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer, writable]` Funder account (must be a system account)
+    /// 1. `[signer]` Participant wallet account - the sender of the message or the other participant of the conversation
+    /// 2. `[]` PDA address of the conversation, group or blinded conversation
+    /// 3. `[writable]` PDA address of the message - v1 seeds
+    /// 4. `[writable]` PDA address of the message - v2 seeds
+    /// 5. `[]` Rent sysvar
+    /// 6. `[]` System program
+    /// 7. `[writable]` Sender wallet account of the message
+    /// 8. `[]` PDA address of the user - participant (checked when the participant is not the sender)
+    /// 9. `[]` PDA address of the user - sender (checked when the participant is not the sender)
+    MigrateMessageAccount {
+        message_index: u32,
+    },
+
+    /// Create a new conversation-handshake account of the group - only members can start an epoch
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` Funder account (must be a system account)
+    /// 1. `[signer]` Sender account (must be a system account)
+    /// 2. `[writable]` PDA address of the conversation-handshake
+    /// 3. `[]` PDA address of the conversation-handshake of the previous epoch (ignored for epoch 0)
+    /// 4. `[]` PDA address of the group
+    /// 5. `[]` PDA address of the group-member - sender, derived from the community for community groups
+    /// 6. `[]` Rent sysvar
+    /// 7. `[]` Clock sysvar
+    /// 8. `[]` System program
+    CreateGroupHandshakeAccount {
+        epoch: u64,
+        commit: Vec<u8>,
+        welcome: Vec<u8>,
+    },
+
+    /// Create the conversation-padding-policy account of a blinded conversation - a policy without padding
+    /// can be replaced, any other padding scheme is immutable once set
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` Funder account (must be a system account)
+    /// 1. `[signer]` Authority account - derived from the shared secret
+    /// 2. `[writable]` PDA address of the conversation-padding-policy
+    /// 3. `[]` PDA address of the blinded-conversation
+    /// 4. `[]` Rent sysvar
+    /// 5. `[]` System program
+    SetBlindedConversationPaddingPolicy {
+        scheme: u8,
+    },
+
+    /// Revoke an invite to the group - only admins can revoke invites, the rent goes back to the invite creator
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` Admin account (must be a system account)
+    /// 1. `[]` PDA address of the group or community
+    /// 2. `[]` PDA address of the group-member - admin
+    /// 3. `[writable]` PDA address of the invite
+    /// 4. `[writable]` Wallet address of the invite creator
+    RevokeInvite {
+        code_hash: Hash,
+    },
+
+    /// Add a group of the community to the user-conversation list of a community member
+    ///
+    /// Members join the community once, so groups created later are not listed for them until they add them.
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` Funder account (must be a system account)
+    /// 1. `[signer]` Member account (must be a system account)
+    /// 2. `[]` PDA address of the community
+    /// 3. `[]` PDA address of the group-member - member, derived from the community
+    /// 4. `[]` PDA address of the group
+    /// 5. `[writable]` PDA address of the user - member
+    /// 6. `[writable]` PDA address of the user-conversation - member
+    /// 7. `[]` Rent sysvar
+    /// 8. `[]` System program
+    CreateCommunityGroupUserConversation,
+
+    /// Recreate a legacy user account in the current layout, storing its bump seed
+    ///
+    /// Accounts cannot grow in place, so the upgrade takes two transactions - the first call keeps the
+    /// counter in the account-upgrade account and closes the user account, the second call recreates it
+    /// once the closed account is purged. Both calls must be funded by the same funder, who gets the lamports
+    /// of both closed accounts. The user account cannot be created by anyone else between the calls, and
+    /// instructions using it fail until the second call - send it in the next transaction.
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer, writable]` Funder account (must be a system account)
+    /// 1. `[signer]` User wallet account (must be a system account)
+    /// 2. `[writable]` PDA address of the user
+    /// 3. `[writable]` PDA address of the account-upgrade, derived from the user address
+    /// 4. `[]` Rent sysvar
+    /// 5. `[]` System program
+    UpgradeUserAccount,
+
+    /// Recreate a legacy conversation account in the current layout, storing its bump seed - see `UpgradeUserAccount`
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer, writable]` Funder account (must be a system account)
+    /// 1. `[signer]` Participant wallet account (must be a system account)
+    /// 2. `[]` PDA address of the user - participant
+    /// 3. `[]` PDA address of the user - other participant
+    /// 4. `[writable]` PDA address of the conversation
+    /// 5. `[writable]` PDA address of the account-upgrade, derived from the conversation address
+    /// 6. `[]` Rent sysvar
+    /// 7. `[]` System program
+    UpgradeConversationAccount,
+
+    /// Create the conversation-padding-policy account of a group - a policy without padding can be replaced,
+    /// any other padding scheme is immutable once set
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` Funder account (must be a system account)
+    /// 1. `[signer]` Editor account (must be a system account)
+    /// 2. `[writable]` PDA address of the conversation-padding-policy
+    /// 3. `[]` PDA address of the group
+    /// 4. `[]` PDA address of the group-member - editor, derived from the community for community groups
+    /// 5. `[]` Rent sysvar
+    /// 6. `[]` System program
+    SetGroupPaddingPolicy {
+        scheme: u8,
+    },
+}
+
+/// Get v2 address of the user-conversation at the index of the user conversation counter
+pub fn user_conversation_pda_address(user_pda_address: &Pubkey, conversation_index: u64) -> Pubkey {
+    UserConversation::find_pda_address_v2(user_pda_address, conversation_index, &id())
+}
+
+/// Get v2 address of the message at the index of the conversation message counter
+pub fn message_pda_address(conversation_address: &Pubkey, message_index: u64) -> Pubkey {
+    Message::find_pda_address_v2(conversation_address, message_index, &id())
+}
+
+/// Creates CreateUserAccount instruction
+pub fn create_user_account(
+    funder_address: &Pubkey,
+    wallet_address: &Pubkey,
+) -> Instruction {
+    let user_pda_address = User::find_pda_address(
+        wallet_address,
+        &id(),
+    );
+
+    Instruction::new_with_borsh(
+        id(),
+        &InstantMessagingInstruction::CreateUserAccount {},
+        vec![
+            AccountMeta::new(*funder_address, true),
+            AccountMeta::new(user_pda_address, false),
+            AccountMeta::new(*wallet_address, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(AccountUpgrade::find_pda_address(&user_pda_address, &id()), false),
+        ],
+    )
+}
+
+/// Creates CreateConversationAccount instruction
+pub fn create_conversation_account(
+    funder_address: &Pubkey,
+    sender_pda_address: &Pubkey,
+    receiver_pda_address: &Pubkey,
+    sender_user_conversation_index: u64,
+    receiver_user_conversation_index: u64,
+) -> Instruction {
+    let conversation_pda_address = Conversation::find_pda_address(
+        sender_pda_address,
+        receiver_pda_address,
+        &id(),
+    );
+
+    let sender_user_conversation_pda_address = user_conversation_pda_address(sender_pda_address, sender_user_conversation_index);
+
+    let receiver_user_conversation_pda_address = user_conversation_pda_address(receiver_pda_address, receiver_user_conversation_index);
+
+    Instruction::new_with_borsh(
+        id(),
+        &InstantMessagingInstruction::CreateConversationAccount {},
+        vec![
+            AccountMeta::new(*funder_address, true),
+            AccountMeta::new(conversation_pda_address, false),
+            AccountMeta::new(*sender_pda_address, false),
+            AccountMeta::new(*receiver_pda_address, false),
+            AccountMeta::new(sender_user_conversation_pda_address, false),
+            AccountMeta::new(receiver_user_conversation_pda_address, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(AccountUpgrade::find_pda_address(&conversation_pda_address, &id()), false),
+        ],
+    )
+}
+
+/// Creates CreateUserConversationAccount instruction
+pub fn create_user_conversation_account(
+    funder_address: &Pubkey,
+    user_pda_address: &Pubkey,
+    conversation_index: u64,
+) -> Instruction {
+    let user_conversation_pda_address = user_conversation_pda_address(user_pda_address, conversation_index);
+
+    Instruction::new_with_borsh(
+        id(),
+        &InstantMessagingInstruction::CreateUserConversationAccount {
+            conversation_index,
+        },
+        vec![
+            AccountMeta::new(*funder_address, true),
+            AccountMeta::new(user_conversation_pda_address, false),
+            AccountMeta::new(*user_pda_address, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+/// Creates CreateMessageAccount instruction
+pub fn create_message_account(
+    funder_address: &Pubkey,
+    sender_wallet_address: &Pubkey,
+    conversation_pda_address: &Pubkey,
+    conversation_index: u64,
+    message_index: u64,
+    message_type: u8,
+    content: Vec<u8>,
+) -> Instruction {
+    let sender_user_pda_address = User::find_pda_address(
+        sender_wallet_address,
+        &id(),
+    );
+
+    let sender_user_conversation_pda_address = user_conversation_pda_address(&sender_user_pda_address, conversation_index);
+
+    let message_pda_address = message_pda_address(conversation_pda_address, message_index);
+
+    let padding_policy_pda_address = ConversationPaddingPolicy::find_pda_address(
+        conversation_pda_address,
+        &id(),
+    );
+
+    Instruction::new_with_borsh(
+        id(),
+        &InstantMessagingInstruction::CreateMessageAccount {
+            conversation_index,
+            message_type,
+            content,
+        },
+        vec![
+            AccountMeta::new(*funder_address, true),
+            AccountMeta::new(*sender_wallet_address, true),
+            AccountMeta::new(sender_user_pda_address, false),
+            AccountMeta::new(sender_user_conversation_pda_address, false),
+            AccountMeta::new(*conversation_pda_address, false),
+            AccountMeta::new(message_pda_address, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(padding_policy_pda_address, false),
+        ],
+    )
+}
+
+/// Creates CreateConversationEncryptionInfoAccount instruction
+pub fn create_conversation_encryption_info_account(
+    funder_address: &Pubkey,
+    sender_wallet_address: &Pubkey,
+    receiver_wallet_address: &Pubkey,
+    data: Vec<u8>,
+) -> Instruction {
+    let sender_user_pda_address = User::find_pda_address(
+        sender_wallet_address,
+        &id(),
+    );
+
+    let receiver_user_pda_address = User::find_pda_address(
+        receiver_wallet_address,
+        &id(),
+    );
+
+    let conversation_pda_address = Conversation::find_pda_address(
+        &sender_user_pda_address,
+        &receiver_user_pda_address,
+        &id(),
+    );
+
+    let conversation_encryption_info_pda_address = ConversationEncryptionInfo::find_pda_address(
+        &conversation_pda_address,
+        &id(),
+    );
+
+    Instruction::new_with_borsh(
+        id(),
+        &InstantMessagingInstruction::CreateConversationEncryptionInfoAccount {
+            data,
+        },
+        vec![
+            AccountMeta::new(*funder_address, true),
+            AccountMeta::new(*sender_wallet_address, true),
+            AccountMeta::new(conversation_encryption_info_pda_address, false),
+            AccountMeta::new(sender_user_pda_address, false),
+            AccountMeta::new(receiver_user_pda_address, false),
+            AccountMeta::new(conversation_pda_address, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+/// Creates SendMessage instruction - `message_id` is reused when the same message is sent again
+#[allow(clippy::too_many_arguments)]
+pub fn send_message(
+    funder_address: &Pubkey,
+    sender_wallet_address: &Pubkey,
+    receiver_wallet_address: &Pubkey,
+    message_index: u64,
+    message_type: u8,
+    content: Vec<u8>,
+    message_id: [u8; 16],
+) -> Instruction {
+    let sender_user_pda_address = User::find_pda_address(
+        sender_wallet_address,
+        &id(),
+    );
+
+    let receiver_user_pda_address = User::find_pda_address(
+        receiver_wallet_address,
+        &id(),
+    );
+
+    let conversation_pda_address = Conversation::find_pda_address(
+        &sender_user_pda_address,
+        &receiver_user_pda_address,
+        &id(),
+    );
+
+    let message_pda_address = message_pda_address(&conversation_pda_address, message_index);
+
+    let message_id_pda_address = MessageIdRecord::find_pda_address(
+        &conversation_pda_address,
+        sender_wallet_address,
+        &message_id,
+        &id(),
+    );
+
+    let padding_policy_pda_address = ConversationPaddingPolicy::find_pda_address(
+        &conversation_pda_address,
+        &id(),
+    );
+
+    Instruction::new_with_borsh(
+        id(),
+        &InstantMessagingInstruction::SendMessage {
+            message_type,
+            content,
+            expected_index: message_index,
+            message_id,
+        },
+        vec![
+            AccountMeta::new(*funder_address, true),
+            AccountMeta::new(*sender_wallet_address, true),
+            AccountMeta::new(sender_user_pda_address, false),
+            AccountMeta::new(receiver_user_pda_address, false),
+            AccountMeta::new(conversation_pda_address, false),
+            AccountMeta::new(message_pda_address, false),
+            AccountMeta::new(message_id_pda_address, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(padding_policy_pda_address, false),
+        ],
+    )
+}
+
+/// Creates CreateConversationHandshakeAccount instruction
+pub fn create_conversation_handshake_account(
+    funder_address: &Pubkey,
+    sender_wallet_address: &Pubkey,
+    receiver_wallet_address: &Pubkey,
+    epoch: u64,
+    commit: Vec<u8>,
+    welcome: Vec<u8>,
+) -> Instruction {
+    let sender_user_pda_address = User::find_pda_address(
+        sender_wallet_address,
+        &id(),
+    );
+
+    let receiver_user_pda_address = User::find_pda_address(
+        receiver_wallet_address,
+        &id(),
+    );
+
+    let conversation_pda_address = Conversation::find_pda_address(
+        &sender_user_pda_address,
+        &receiver_user_pda_address,
+        &id(),
+    );
+
+    let handshake_pda_address = ConversationHandshake::find_pda_address(
+        &conversation_pda_address,
+        epoch,
+        &id(),
+    );
+
+    let previous_handshake_pda_address = ConversationHandshake::find_pda_address(
+        &conversation_pda_address,
+        epoch.saturating_sub(1),
+        &id(),
+    );
+
+    Instruction::new_with_borsh(
+        id(),
+        &InstantMessagingInstruction::CreateConversationHandshakeAccount {
+            epoch,
+            commit,
+            welcome,
+        },
+        vec![
+            AccountMeta::new(*funder_address, true),
+            AccountMeta::new(*sender_wallet_address, true),
+            AccountMeta::new(handshake_pda_address, false),
+            AccountMeta::new_readonly(previous_handshake_pda_address, false),
+            AccountMeta::new_readonly(sender_user_pda_address, false),
+            AccountMeta::new_readonly(receiver_user_pda_address, false),
+            AccountMeta::new_readonly(conversation_pda_address, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+/// Creates SetSealedSenderCredential instruction
+pub fn set_sealed_sender_credential(
+    funder_address: &Pubkey,
+    sender_wallet_address: &Pubkey,
+    receiver_wallet_address: &Pubkey,
+    authority_address: &Pubkey,
+    chain_hash: Hash,
+    current_authority_address: Option<&Pubkey>,
+) -> Instruction {
+    let sender_user_pda_address = User::find_pda_address(
+        sender_wallet_address,
+        &id(),
+    );
+
+    let receiver_user_pda_address = User::find_pda_address(
+        receiver_wallet_address,
+        &id(),
+    );
+
+    let conversation_pda_address = Conversation::find_pda_address(
+        &sender_user_pda_address,
+        &receiver_user_pda_address,
+        &id(),
+    );
+
+    let credential_pda_address = SealedSenderCredential::find_pda_address(
+        &conversation_pda_address,
+        sender_wallet_address,
+        &id(),
+    );
+
+    let mut accounts = vec![
+        AccountMeta::new(*funder_address, true),
+        AccountMeta::new_readonly(*sender_wallet_address, true),
+        AccountMeta::new(credential_pda_address, false),
+        AccountMeta::new_readonly(sender_user_pda_address, false),
+        AccountMeta::new_readonly(receiver_user_pda_address, false),
+        AccountMeta::new_readonly(conversation_pda_address, false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    if let Some(current_authority_address) = current_authority_address {
+        accounts.push(AccountMeta::new_readonly(*current_authority_address, true));
+    }
+
+    Instruction::new_with_borsh(
+        id(),
+        &InstantMessagingInstruction::SetSealedSenderCredential {
+            authority: *authority_address,
+            chain_hash,
+        },
+        accounts,
+    )
+}
+
+/// Creates SendSealedMessage instruction
+///
+/// The credential address is passed as is - deriving it on-chain would reveal the wallet of the sender.
+#[allow(clippy::too_many_arguments)]
+pub fn send_sealed_message(
+    funder_address: &Pubkey,
+    authority_address: &Pubkey,
+    conversation_pda_address: &Pubkey,
+    credential_pda_address: &Pubkey,
+    message_index: u64,
+    next_authority_address: &Pubkey,
+    next_chain_hash: Hash,
+    content: Vec<u8>,
+) -> Instruction {
+    let message_pda_address = message_pda_address(conversation_pda_address, message_index);
+
+    let padding_policy_pda_address = ConversationPaddingPolicy::find_pda_address(
+        conversation_pda_address,
+        &id(),
+    );
+
+    Instruction::new_with_borsh(
+        id(),
+        &InstantMessagingInstruction::SendSealedMessage {
+            next_authority: *next_authority_address,
+            next_chain_hash,
+            content,
+        },
+        vec![
+            AccountMeta::new(*funder_address, true),
+            AccountMeta::new_readonly(*authority_address, true),
+            AccountMeta::new(*conversation_pda_address, false),
+            AccountMeta::new(*credential_pda_address, false),
+            AccountMeta::new(message_pda_address, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(padding_policy_pda_address, false),
+        ],
+    )
+}
+
+/// Creates CreateBlindedConversationAccount instruction
+pub fn create_blinded_conversation_account(
+    funder_address: &Pubkey,
+    authority_address: &Pubkey,
+    blinded_id: [u8; 32],
+) -> Instruction {
+    let blinded_conversation_pda_address = BlindedConversation::find_pda_address(
+        &blinded_id,
+        authority_address,
+        &id(),
+    );
+
+    Instruction::new_with_borsh(
+        id(),
+        &InstantMessagingInstruction::CreateBlindedConversationAccount {
+            blinded_id,
+        },
+        vec![
+            AccountMeta::new(*funder_address, true),
+            AccountMeta::new_readonly(*authority_address, true),
+            AccountMeta::new(blinded_conversation_pda_address, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+/// Creates SendBlindedMessage instruction
+pub fn send_blinded_message(
+    funder_address: &Pubkey,
+    authority_address: &Pubkey,
+    blinded_id: &[u8; 32],
+    message_index: u64,
+    message_type: u8,
+    content: Vec<u8>,
+) -> Instruction {
+    let blinded_conversation_pda_address = BlindedConversation::find_pda_address(
+        blinded_id,
+        authority_address,
+        &id(),
+    );
+
+    let message_pda_address = message_pda_address(&blinded_conversation_pda_address, message_index);
+
+    let padding_policy_pda_address = ConversationPaddingPolicy::find_pda_address(
+        &blinded_conversation_pda_address,
+        &id(),
+    );
+
+    Instruction::new_with_borsh(
+        id(),
+        &InstantMessagingInstruction::SendBlindedMessage {
+            message_type,
+            content,
+        },
+        vec![
+            AccountMeta::new(*funder_address, true),
+            AccountMeta::new_readonly(*authority_address, true),
+            AccountMeta::new(blinded_conversation_pda_address, false),
+            AccountMeta::new(message_pda_address, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(padding_policy_pda_address, false),
+        ],
+    )
+}
+
+/// Creates SetConversationPaddingPolicy instruction
+pub fn set_conversation_padding_policy(
+    funder_address: &Pubkey,
+    sender_wallet_address: &Pubkey,
+    receiver_wallet_address: &Pubkey,
+    scheme: u8,
+) -> Instruction {
+    let sender_user_pda_address = User::find_pda_address(
+        sender_wallet_address,
+        &id(),
+    );
+
+    let receiver_user_pda_address = User::find_pda_address(
+        receiver_wallet_address,
+        &id(),
+    );
+
+    let conversation_pda_address = Conversation::find_pda_address(
+        &sender_user_pda_address,
+        &receiver_user_pda_address,
+        &id(),
+    );
+
+    let padding_policy_pda_address = ConversationPaddingPolicy::find_pda_address(
+        &conversation_pda_address,
+        &id(),
+    );
+
+    Instruction::new_with_borsh(
+        id(),
+        &InstantMessagingInstruction::SetConversationPaddingPolicy {
+            scheme,
+        },
+        vec![
+            AccountMeta::new(*funder_address, true),
+            AccountMeta::new_readonly(*sender_wallet_address, true),
+            AccountMeta::new(padding_policy_pda_address, false),
+            AccountMeta::new_readonly(sender_user_pda_address, false),
+            AccountMeta::new_readonly(receiver_user_pda_address, false),
+            AccountMeta::new_readonly(conversation_pda_address, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+/// Creates CreateGroupAccount instruction
+pub fn create_group_account(
+    funder_address: &Pubkey,
+    creator_wallet_address: &Pubkey,
+    group_index: u64,
+    kind: u8,
+    creator_conversation_index: u64,
+) -> Instruction {
+    let group_pda_address = Group::find_pda_address(
+        creator_wallet_address,
+        group_index,
+        &id(),
+    );
+
+    let creator_member_pda_address = GroupMember::find_pda_address(
+        &group_pda_address,
+        creator_wallet_address,
+        &id(),
+    );
+
+    let creator_user_pda_address = User::find_pda_address(
+        creator_wallet_address,
+        &id(),
+    );
+
+    let creator_user_conversation_pda_address = user_conversation_pda_address(&creator_user_pda_address, creator_conversation_index);
+
+    Instruction::new_with_borsh(
+        id(),
+        &InstantMessagingInstruction::CreateGroupAccount {
+            group_index,
+            kind,
+        },
+        vec![
+            AccountMeta::new(*funder_address, true),
+            AccountMeta::new_readonly(*creator_wallet_address, true),
+            AccountMeta::new(group_pda_address, false),
+            AccountMeta::new(creator_member_pda_address, false),
+            AccountMeta::new(creator_user_pda_address, false),
+            AccountMeta::new(creator_user_conversation_pda_address, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+/// Creates AddGroupMember instruction
+pub fn add_group_member(
+    funder_address: &Pubkey,
+    inviter_wallet_address: &Pubkey,
+    group_pda_address: &Pubkey,
+    member_wallet_address: &Pubkey,
+    member_conversation_index: u64,
+    role: u8,
+    permissions: u8,
+) -> Instruction {
+    let inviter_member_pda_address = GroupMember::find_pda_address(
+        group_pda_address,
+        inviter_wallet_address,
+        &id(),
+    );
+
+    let member_pda_address = GroupMember::find_pda_address(
+        group_pda_address,
+        member_wallet_address,
+        &id(),
+    );
+
+    let member_ban_pda_address = GroupBan::find_pda_address(
+        group_pda_address,
+        member_wallet_address,
+        &id(),
+    );
+
+    let member_user_pda_address = User::find_pda_address(
+        member_wallet_address,
+        &id(),
+    );
+
+    let member_user_conversation_pda_address = user_conversation_pda_address(&member_user_pda_address, member_conversation_index);
+
+    Instruction::new_with_borsh(
+        id(),
+        &InstantMessagingInstruction::AddGroupMember {
+            role,
+            permissions,
+        },
+        vec![
+            AccountMeta::new(*funder_address, true),
+            AccountMeta::new_readonly(*inviter_wallet_address, true),
+            AccountMeta::new_readonly(*group_pda_address, false),
+            AccountMeta::new_readonly(inviter_member_pda_address, false),
+            AccountMeta::new_readonly(*member_wallet_address, true),
+            AccountMeta::new(member_pda_address, false),
+            AccountMeta::new_readonly(member_ban_pda_address, false),
+            AccountMeta::new(member_user_pda_address, false),
+            AccountMeta::new(member_user_conversation_pda_address, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+/// Creates SendGroupMessage instruction
+pub fn send_group_message(
+    funder_address: &Pubkey,
+    sender_wallet_address: &Pubkey,
+    group_pda_address: &Pubkey,
+    message_index: u64,
+    message_type: u8,
+    content: Vec<u8>,
+) -> Instruction {
+    let sender_member_pda_address = GroupMember::find_pda_address(
+        group_pda_address,
+        sender_wallet_address,
+        &id(),
+    );
+
+    let message_pda_address = message_pda_address(group_pda_address, message_index);
+
+    let padding_policy_pda_address = ConversationPaddingPolicy::find_pda_address(
+        group_pda_address,
+        &id(),
+    );
+
+    Instruction::new_with_borsh(
+        id(),
+        &InstantMessagingInstruction::SendGroupMessage {
+            message_type,
+            content,
+        },
+        vec![
+            AccountMeta::new(*funder_address, true),
+            AccountMeta::new_readonly(*sender_wallet_address, true),
+            AccountMeta::new(*group_pda_address, false),
+            AccountMeta::new_readonly(sender_member_pda_address, false),
+            AccountMeta::new(message_pda_address, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(padding_policy_pda_address, false),
+        ],
+    )
+}
+
+/// Creates SetMemberRole instruction
+pub fn set_member_role(
+    actor_wallet_address: &Pubkey,
+    group_pda_address: &Pubkey,
+    member_wallet_address: &Pubkey,
+    role: u8,
+    permissions: u8,
+) -> Instruction {
+    let actor_member_pda_address = GroupMember::find_pda_address(
+        group_pda_address,
+        actor_wallet_address,
+        &id(),
+    );
+
+    let member_pda_address = GroupMember::find_pda_address(
+        group_pda_address,
+        member_wallet_address,
+        &id(),
+    );
+
+    Instruction::new_with_borsh(
+        id(),
+        &InstantMessagingInstruction::SetMemberRole {
+            role,
+            permissions,
+        },
+        vec![
+            AccountMeta::new_readonly(*actor_wallet_address, true),
+            AccountMeta::new_readonly(*group_pda_address, false),
+            AccountMeta::new_readonly(actor_member_pda_address, false),
+            AccountMeta::new_readonly(*member_wallet_address, false),
+            AccountMeta::new(member_pda_address, false),
+        ],
+    )
+}
+
+/// Creates TransferGroupOwnership instruction
+pub fn transfer_group_ownership(
+    owner_wallet_address: &Pubkey,
+    group_pda_address: &Pubkey,
+    new_owner_wallet_address: &Pubkey,
+) -> Instruction {
+    let owner_member_pda_address = GroupMember::find_pda_address(
+        group_pda_address,
+        owner_wallet_address,
+        &id(),
+    );
+
+    let new_owner_member_pda_address = GroupMember::find_pda_address(
+        group_pda_address,
+        new_owner_wallet_address,
+        &id(),
+    );
+
+    Instruction::new_with_borsh(
+        id(),
+        &InstantMessagingInstruction::TransferGroupOwnership,
+        vec![
+            AccountMeta::new_readonly(*owner_wallet_address, true),
+            AccountMeta::new(*group_pda_address, false),
+            AccountMeta::new(owner_member_pda_address, false),
+            AccountMeta::new_readonly(*new_owner_wallet_address, false),
+            AccountMeta::new(new_owner_member_pda_address, false),
+        ],
+    )
+}
+
+/// Creates CreateInviteAccount instruction
+pub fn create_invite_account(
+    funder_address: &Pubkey,
+    creator_wallet_address: &Pubkey,
+    group_pda_address: &Pubkey,
+    code: [u8; 16],
+    expires_at: UnixTimestamp,
+    max_uses: u32,
+    role: Option<u8>,
+) -> Instruction {
+    let creator_member_pda_address = GroupMember::find_pda_address(
+        group_pda_address,
+        creator_wallet_address,
+        &id(),
+    );
+
+    let code_hash = Invite::hash_code(&code);
+
+    let invite_pda_address = Invite::find_pda_address(
+        group_pda_address,
+        &code_hash,
+        &id(),
+    );
+
+    Instruction::new_with_borsh(
+        id(),
+        &InstantMessagingInstruction::CreateInviteAccount {
+            code_hash,
+            expires_at,
+            max_uses,
+            role,
+        },
+        vec![
+            AccountMeta::new(*funder_address, true),
+            AccountMeta::new_readonly(*creator_wallet_address, true),
+            AccountMeta::new_readonly(*group_pda_address, false),
+            AccountMeta::new_readonly(creator_member_pda_address, false),
+            AccountMeta::new(invite_pda_address, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+/// Creates JoinWithInvite instruction
+pub fn join_with_invite(
+    funder_address: &Pubkey,
+    member_wallet_address: &Pubkey,
+    group_pda_address: &Pubkey,
+    code: [u8; 16],
+    member_conversation_index: u64,
+) -> Instruction {
+    let invite_pda_address = Invite::find_pda_address(
+        group_pda_address,
+        &Invite::hash_code(&code),
+        &id(),
+    );
+
+    let member_pda_address = GroupMember::find_pda_address(
+        group_pda_address,
+        member_wallet_address,
+        &id(),
+    );
+
+    let member_ban_pda_address = GroupBan::find_pda_address(
+        group_pda_address,
+        member_wallet_address,
+        &id(),
+    );
+
+    let member_user_pda_address = User::find_pda_address(
+        member_wallet_address,
+        &id(),
+    );
+
+    let member_user_conversation_pda_address = user_conversation_pda_address(&member_user_pda_address, member_conversation_index);
+
+    Instruction::new_with_borsh(
+        id(),
+        &InstantMessagingInstruction::JoinWithInvite {
+            code,
+        },
+        vec![
+            AccountMeta::new(*funder_address, true),
+            AccountMeta::new_readonly(*member_wallet_address, true),
+            AccountMeta::new_readonly(*group_pda_address, false),
+            AccountMeta::new(invite_pda_address, false),
+            AccountMeta::new(member_pda_address, false),
+            AccountMeta::new_readonly(member_ban_pda_address, false),
+            AccountMeta::new(member_user_pda_address, false),
+            AccountMeta::new(member_user_conversation_pda_address, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+/// Creates RequestToJoin instruction
+pub fn request_to_join(
+    requester_wallet_address: &Pubkey,
+    group_pda_address: &Pubkey,
+) -> Instruction {
+    let join_request_pda_address = JoinRequest::find_pda_address(
+        group_pda_address,
+        requester_wallet_address,
+        &id(),
+    );
+
+    let requester_member_pda_address = GroupMember::find_pda_address(
+        group_pda_address,
+        requester_wallet_address,
+        &id(),
+    );
+
+    let requester_ban_pda_address = GroupBan::find_pda_address(
+        group_pda_address,
+        requester_wallet_address,
+        &id(),
+    );
+
+    Instruction::new_with_borsh(
+        id(),
+        &InstantMessagingInstruction::RequestToJoin,
+        vec![
+            AccountMeta::new(*requester_wallet_address, true),
+            AccountMeta::new_readonly(*group_pda_address, false),
+            AccountMeta::new(join_request_pda_address, false),
+            AccountMeta::new_readonly(requester_member_pda_address, false),
+            AccountMeta::new_readonly(requester_ban_pda_address, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+/// Creates ApproveJoin instruction
+pub fn approve_join(
+    funder_address: &Pubkey,
+    admin_wallet_address: &Pubkey,
+    group_pda_address: &Pubkey,
+    requester_wallet_address: &Pubkey,
+    requester_conversation_index: u64,
+) -> Instruction {
+    let admin_member_pda_address = GroupMember::find_pda_address(
+        group_pda_address,
+        admin_wallet_address,
+        &id(),
+    );
+
+    let join_request_pda_address = JoinRequest::find_pda_address(
+        group_pda_address,
+        requester_wallet_address,
+        &id(),
+    );
+
+    let requester_member_pda_address = GroupMember::find_pda_address(
+        group_pda_address,
+        requester_wallet_address,
+        &id(),
+    );
+
+    let requester_ban_pda_address = GroupBan::find_pda_address(
+        group_pda_address,
+        requester_wallet_address,
+        &id(),
+    );
+
+    let requester_user_pda_address = User::find_pda_address(
+        requester_wallet_address,
+        &id(),
+    );
+
+    let requester_user_conversation_pda_address = user_conversation_pda_address(&requester_user_pda_address, requester_conversation_index);
+
+    Instruction::new_with_borsh(
+        id(),
+        &InstantMessagingInstruction::ApproveJoin,
+        vec![
+            AccountMeta::new(*funder_address, true),
+            AccountMeta::new_readonly(*admin_wallet_address, true),
+            AccountMeta::new_readonly(*group_pda_address, false),
+            AccountMeta::new_readonly(admin_member_pda_address, false),
+            AccountMeta::new(*requester_wallet_address, false),
+            AccountMeta::new(join_request_pda_address, false),
+            AccountMeta::new(requester_member_pda_address, false),
+            AccountMeta::new_readonly(requester_ban_pda_address, false),
+            AccountMeta::new(requester_user_pda_address, false),
+            AccountMeta::new(requester_user_conversation_pda_address, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+/// Creates DenyJoin instruction
+pub fn deny_join(
+    admin_wallet_address: &Pubkey,
+    group_pda_address: &Pubkey,
+    requester_wallet_address: &Pubkey,
+) -> Instruction {
+    let admin_member_pda_address = GroupMember::find_pda_address(
+        group_pda_address,
+        admin_wallet_address,
+        &id(),
+    );
+
+    let join_request_pda_address = JoinRequest::find_pda_address(
+        group_pda_address,
+        requester_wallet_address,
+        &id(),
+    );
+
+    Instruction::new_with_borsh(
+        id(),
+        &InstantMessagingInstruction::DenyJoin,
+        vec![
+            AccountMeta::new_readonly(*admin_wallet_address, true),
+            AccountMeta::new_readonly(*group_pda_address, false),
+            AccountMeta::new_readonly(admin_member_pda_address, false),
+            AccountMeta::new(*requester_wallet_address, false),
+            AccountMeta::new(join_request_pda_address, false),
+        ],
+    )
+}
+
+/// Creates RemoveMember instruction - `conversation_index` locates the group in the list of the removed member
+pub fn remove_member(
+    actor_wallet_address: &Pubkey,
+    group_pda_address: &Pubkey,
+    member_wallet_address: &Pubkey,
+    conversation_index: u64,
+) -> Instruction {
+    let actor_member_pda_address = GroupMember::find_pda_address(
+        group_pda_address,
+        actor_wallet_address,
+        &id(),
+    );
+
+    let member_pda_address = GroupMember::find_pda_address(
+        group_pda_address,
+        member_wallet_address,
+        &id(),
+    );
+
+    let member_user_pda_address = User::find_pda_address(
+        member_wallet_address,
+        &id(),
+    );
+
+    let member_user_conversation_pda_address = user_conversation_pda_address(&member_user_pda_address, conversation_index);
+
+    Instruction::new_with_borsh(
+        id(),
+        &InstantMessagingInstruction::RemoveMember {
+            conversation_index,
+        },
+        vec![
+            AccountMeta::new_readonly(*actor_wallet_address, true),
+            AccountMeta::new_readonly(*group_pda_address, false),
+            AccountMeta::new_readonly(actor_member_pda_address, false),
+            AccountMeta::new(*member_wallet_address, false),
+            AccountMeta::new(member_pda_address, false),
+            AccountMeta::new_readonly(member_user_pda_address, false),
+            AccountMeta::new(member_user_conversation_pda_address, false),
+        ],
+    )
+}
+
+/// Creates BanMember instruction - see `remove_member` for `conversation_index`
+pub fn ban_member(
+    funder_address: &Pubkey,
+    actor_wallet_address: &Pubkey,
+    group_pda_address: &Pubkey,
+    member_wallet_address: &Pubkey,
+    conversation_index: u64,
+) -> Instruction {
+    let actor_member_pda_address = GroupMember::find_pda_address(
+        group_pda_address,
+        actor_wallet_address,
+        &id(),
+    );
+
+    let member_pda_address = GroupMember::find_pda_address(
+        group_pda_address,
+        member_wallet_address,
+        &id(),
+    );
+
+    let member_ban_pda_address = GroupBan::find_pda_address(
+        group_pda_address,
+        member_wallet_address,
+        &id(),
+    );
+
+    let member_user_pda_address = User::find_pda_address(
+        member_wallet_address,
+        &id(),
+    );
+
+    let member_user_conversation_pda_address = user_conversation_pda_address(&member_user_pda_address, conversation_index);
+
+    Instruction::new_with_borsh(
+        id(),
+        &InstantMessagingInstruction::BanMember {
+            conversation_index,
+        },
+        vec![
+            AccountMeta::new(*funder_address, true),
+            AccountMeta::new_readonly(*actor_wallet_address, true),
+            AccountMeta::new_readonly(*group_pda_address, false),
+            AccountMeta::new_readonly(actor_member_pda_address, false),
+            AccountMeta::new(*member_wallet_address, false),
+            AccountMeta::new(member_pda_address, false),
+            AccountMeta::new(member_ban_pda_address, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(member_user_pda_address, false),
+            AccountMeta::new(member_user_conversation_pda_address, false),
+        ],
+    )
+}
+
+/// Creates LeaveGroup instruction
+pub fn leave_group(
+    member_wallet_address: &Pubkey,
+    group_pda_address: &Pubkey,
+    conversation_index: u64,
+) -> Instruction {
+    let member_pda_address = GroupMember::find_pda_address(
+        group_pda_address,
+        member_wallet_address,
+        &id(),
+    );
+
+    let member_user_pda_address = User::find_pda_address(
+        member_wallet_address,
+        &id(),
+    );
+
+    let member_user_conversation_pda_address = user_conversation_pda_address(&member_user_pda_address, conversation_index);
+
+    Instruction::new_with_borsh(
+        id(),
+        &InstantMessagingInstruction::LeaveGroup {
+            conversation_index,
+        },
+        vec![
+            AccountMeta::new(*member_wallet_address, true),
+            AccountMeta::new_readonly(*group_pda_address, false),
+            AccountMeta::new(member_pda_address, false),
+            AccountMeta::new_readonly(member_user_pda_address, false),
+            AccountMeta::new(member_user_conversation_pda_address, false),
+        ],
+    )
+}
+
+/// Creates Subscribe instruction
+pub fn subscribe(
+    funder_address: &Pubkey,
+    subscriber_wallet_address: &Pubkey,
+    channel_pda_address: &Pubkey,
+    subscriber_conversation_index: u64,
+) -> Instruction {
+    let subscriber_member_pda_address = GroupMember::find_pda_address(
+        channel_pda_address,
+        subscriber_wallet_address,
+        &id(),
+    );
+
+    let subscriber_ban_pda_address = GroupBan::find_pda_address(
+        channel_pda_address,
+        subscriber_wallet_address,
+        &id(),
+    );
+
+    let subscriber_user_pda_address = User::find_pda_address(
+        subscriber_wallet_address,
+        &id(),
+    );
+
+    let subscriber_user_conversation_pda_address = user_conversation_pda_address(&subscriber_user_pda_address, subscriber_conversation_index);
+
+    Instruction::new_with_borsh(
+        id(),
+        &InstantMessagingInstruction::Subscribe,
+        vec![
+            AccountMeta::new(*funder_address, true),
+            AccountMeta::new_readonly(*subscriber_wallet_address, true),
+            AccountMeta::new_readonly(*channel_pda_address, false),
+            AccountMeta::new(subscriber_member_pda_address, false),
+            AccountMeta::new_readonly(subscriber_ban_pda_address, false),
+            AccountMeta::new(subscriber_user_pda_address, false),
+            AccountMeta::new(subscriber_user_conversation_pda_address, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+/// Creates Unsubscribe instruction
+pub fn unsubscribe(
+    subscriber_wallet_address: &Pubkey,
+    channel_pda_address: &Pubkey,
+    conversation_index: u64,
+) -> Instruction {
+    let subscriber_member_pda_address = GroupMember::find_pda_address(
+        channel_pda_address,
+        subscriber_wallet_address,
+        &id(),
+    );
+
+    let subscriber_user_pda_address = User::find_pda_address(
+        subscriber_wallet_address,
+        &id(),
+    );
+
+    let subscriber_user_conversation_pda_address = user_conversation_pda_address(&subscriber_user_pda_address, conversation_index);
+
+    Instruction::new_with_borsh(
+        id(),
+        &InstantMessagingInstruction::Unsubscribe {
+            conversation_index,
+        },
+        vec![
+            AccountMeta::new(*subscriber_wallet_address, true),
+            AccountMeta::new_readonly(*channel_pda_address, false),
+            AccountMeta::new(subscriber_member_pda_address, false),
+            AccountMeta::new_readonly(subscriber_user_pda_address, false),
+            AccountMeta::new(subscriber_user_conversation_pda_address, false),
+        ],
+    )
+}
+
+/// Creates CreateCommunityAccount instruction
+pub fn create_community_account(
+    funder_address: &Pubkey,
+    creator_wallet_address: &Pubkey,
+    community_index: u32,
+    creator_conversation_index: u64,
+) -> Instruction {
+    let community_pda_address = Community::find_pda_address(
+        creator_wallet_address,
+        community_index,
+        &id(),
+    );
+
+    let creator_member_pda_address = GroupMember::find_pda_address(
+        &community_pda_address,
+        creator_wallet_address,
+        &id(),
+    );
+
+    let creator_user_pda_address = User::find_pda_address(
+        creator_wallet_address,
+        &id(),
+    );
+
+    let creator_user_conversation_pda_address = user_conversation_pda_address(&creator_user_pda_address, creator_conversation_index);
+
+    Instruction::new_with_borsh(
+        id(),
+        &InstantMessagingInstruction::CreateCommunityAccount {
+            community_index,
+        },
+        vec![
+            AccountMeta::new(*funder_address, true),
+            AccountMeta::new_readonly(*creator_wallet_address, true),
+            AccountMeta::new(community_pda_address, false),
+            AccountMeta::new(creator_member_pda_address, false),
+            AccountMeta::new(creator_user_pda_address, false),
+            AccountMeta::new(creator_user_conversation_pda_address, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+/// Creates CreateCommunityGroupAccount instruction
+pub fn create_community_group_account(
+    funder_address: &Pubkey,
+    creator_wallet_address: &Pubkey,
+    community_pda_address: &Pubkey,
+    group_index: u64,
+    kind: u8,
+) -> Instruction {
+    let creator_member_pda_address = GroupMember::find_pda_address(
+        community_pda_address,
+        creator_wallet_address,
+        &id(),
+    );
+
+    let group_pda_address = Group::find_pda_address(
+        community_pda_address,
+        group_index,
+        &id(),
+    );
+
+    Instruction::new_with_borsh(
+        id(),
+        &InstantMessagingInstruction::CreateCommunityGroupAccount {
+            kind,
+        },
+        vec![
+            AccountMeta::new(*funder_address, true),
+            AccountMeta::new_readonly(*creator_wallet_address, true),
+            AccountMeta::new(*community_pda_address, false),
+            AccountMeta::new_readonly(creator_member_pda_address, false),
+            AccountMeta::new(group_pda_address, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+/// Creates SendGroupMessage instruction for a group of the community
+pub fn send_community_group_message(
+    funder_address: &Pubkey,
+    sender_wallet_address: &Pubkey,
+    community_pda_address: &Pubkey,
+    group_index: u64,
+    message_index: u64,
+    message_type: u8,
+    content: Vec<u8>,
+) -> Instruction {
+    let group_pda_address = Group::find_pda_address(
+        community_pda_address,
+        group_index,
+        &id(),
+    );
+
+    let sender_member_pda_address = GroupMember::find_pda_address(
+        community_pda_address,
+        sender_wallet_address,
+        &id(),
+    );
+
+    let message_pda_address = message_pda_address(&group_pda_address, message_index);
+
+    let padding_policy_pda_address = ConversationPaddingPolicy::find_pda_address(
+        &group_pda_address,
+        &id(),
+    );
+
+    Instruction::new_with_borsh(
+        id(),
+        &InstantMessagingInstruction::SendGroupMessage {
+            message_type,
+            content,
+        },
+        vec![
+            AccountMeta::new(*funder_address, true),
+            AccountMeta::new_readonly(*sender_wallet_address, true),
+            AccountMeta::new(group_pda_address, false),
+            AccountMeta::new_readonly(sender_member_pda_address, false),
+            AccountMeta::new(message_pda_address, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(padding_policy_pda_address, false),
+        ],
+    )
+}
+
+/// Creates SetConversationMetadata instruction
+pub fn set_conversation_metadata(
+    funder_address: &Pubkey,
+    sender_wallet_address: &Pubkey,
+    receiver_wallet_address: &Pubkey,
+    title: String,
+    description: String,
+    avatar_uri: String,
+) -> Instruction {
+    let sender_user_pda_address = User::find_pda_address(
+        sender_wallet_address,
+        &id(),
+    );
+
+    let receiver_user_pda_address = User::find_pda_address(
+        receiver_wallet_address,
+        &id(),
+    );
+
+    let conversation_pda_address = Conversation::find_pda_address(
+        &sender_user_pda_address,
+        &receiver_user_pda_address,
+        &id(),
+    );
+
+    let metadata_pda_address = ConversationMetadata::find_pda_address(
+        &conversation_pda_address,
+        &id(),
+    );
+
+    Instruction::new_with_borsh(
+        id(),
+        &InstantMessagingInstruction::SetConversationMetadata {
+            title,
+            description,
+            avatar_uri,
+        },
+        vec![
+            AccountMeta::new(*funder_address, true),
+            AccountMeta::new_readonly(*sender_wallet_address, true),
+            AccountMeta::new(metadata_pda_address, false),
+            AccountMeta::new_readonly(sender_user_pda_address, false),
+            AccountMeta::new_readonly(receiver_user_pda_address, false),
+            AccountMeta::new_readonly(conversation_pda_address, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+/// Creates SetGroupMetadata instruction - the group-member is derived from `scope_pda_address`,
+/// which is the community for community groups and the group itself otherwise
+pub fn set_group_metadata(
+    funder_address: &Pubkey,
+    editor_wallet_address: &Pubkey,
+    group_pda_address: &Pubkey,
+    scope_pda_address: &Pubkey,
+    title: String,
+    description: String,
+    avatar_uri: String,
+) -> Instruction {
+    let editor_member_pda_address = GroupMember::find_pda_address(
+        scope_pda_address,
+        editor_wallet_address,
+        &id(),
+    );
+
+    let metadata_pda_address = ConversationMetadata::find_pda_address(
+        group_pda_address,
+        &id(),
+    );
+
+    Instruction::new_with_borsh(
+        id(),
+        &InstantMessagingInstruction::SetGroupMetadata {
+            title,
+            description,
+            avatar_uri,
+        },
+        vec![
+            AccountMeta::new(*funder_address, true),
+            AccountMeta::new_readonly(*editor_wallet_address, true),
+            AccountMeta::new(metadata_pda_address, false),
+            AccountMeta::new_readonly(*group_pda_address, false),
+            AccountMeta::new_readonly(editor_member_pda_address, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+/// Creates PinMessage instruction
+pub fn pin_message(
+    funder_address: &Pubkey,
+    sender_wallet_address: &Pubkey,
+    receiver_wallet_address: &Pubkey,
+    message_index: u64,
+) -> Instruction {
+    let sender_user_pda_address = User::find_pda_address(
+        sender_wallet_address,
+        &id(),
+    );
+
+    let receiver_user_pda_address = User::find_pda_address(
+        receiver_wallet_address,
+        &id(),
+    );
+
+    let conversation_pda_address = Conversation::find_pda_address(
+        &sender_user_pda_address,
+        &receiver_user_pda_address,
+        &id(),
+    );
+
+    let pinned_messages_pda_address = PinnedMessages::find_pda_address(
+        &conversation_pda_address,
+        &id(),
+    );
+
+    let message_pda_address = message_pda_address(&conversation_pda_address, message_index);
+
+    Instruction::new_with_borsh(
+        id(),
+        &InstantMessagingInstruction::PinMessage {
+            message_index,
+        },
+        vec![
+            AccountMeta::new(*funder_address, true),
+            AccountMeta::new_readonly(*sender_wallet_address, true),
+            AccountMeta::new(pinned_messages_pda_address, false),
+            AccountMeta::new_readonly(sender_user_pda_address, false),
+            AccountMeta::new_readonly(receiver_user_pda_address, false),
+            AccountMeta::new_readonly(conversation_pda_address, false),
+            AccountMeta::new_readonly(message_pda_address, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+/// Creates UnpinMessage instruction
+pub fn unpin_message(
+    sender_wallet_address: &Pubkey,
+    receiver_wallet_address: &Pubkey,
+    message_index: u64,
+) -> Instruction {
+    let sender_user_pda_address = User::find_pda_address(
+        sender_wallet_address,
+        &id(),
+    );
+
+    let receiver_user_pda_address = User::find_pda_address(
+        receiver_wallet_address,
+        &id(),
+    );
+
+    let conversation_pda_address = Conversation::find_pda_address(
+        &sender_user_pda_address,
+        &receiver_user_pda_address,
+        &id(),
+    );
+
+    let pinned_messages_pda_address = PinnedMessages::find_pda_address(
+        &conversation_pda_address,
+        &id(),
+    );
+
+    Instruction::new_with_borsh(
+        id(),
+        &InstantMessagingInstruction::UnpinMessage {
+            message_index,
+        },
+        vec![
+            AccountMeta::new_readonly(*sender_wallet_address, true),
+            AccountMeta::new(pinned_messages_pda_address, false),
+            AccountMeta::new_readonly(sender_user_pda_address, false),
+            AccountMeta::new_readonly(receiver_user_pda_address, false),
+            AccountMeta::new_readonly(conversation_pda_address, false),
+        ],
+    )
+}
+
+/// Creates PinGroupMessage instruction - the group-member is derived from `scope_pda_address`,
+/// which is the community for community groups and the group itself otherwise
+pub fn pin_group_message(
+    funder_address: &Pubkey,
+    member_wallet_address: &Pubkey,
+    group_pda_address: &Pubkey,
+    scope_pda_address: &Pubkey,
+    message_index: u64,
+) -> Instruction {
+    let member_pda_address = GroupMember::find_pda_address(
+        scope_pda_address,
+        member_wallet_address,
+        &id(),
+    );
+
+    let pinned_messages_pda_address = PinnedMessages::find_pda_address(
+        group_pda_address,
+        &id(),
+    );
+
+    let message_pda_address = message_pda_address(group_pda_address, message_index);
+
+    Instruction::new_with_borsh(
+        id(),
+        &InstantMessagingInstruction::PinGroupMessage {
+            message_index,
+        },
+        vec![
+            AccountMeta::new(*funder_address, true),
+            AccountMeta::new_readonly(*member_wallet_address, true),
+            AccountMeta::new(pinned_messages_pda_address, false),
+            AccountMeta::new_readonly(*group_pda_address, false),
+            AccountMeta::new_readonly(member_pda_address, false),
+            AccountMeta::new_readonly(message_pda_address, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+/// Creates UnpinGroupMessage instruction - the group-member is derived from `scope_pda_address`,
+/// which is the community for community groups and the group itself otherwise
+pub fn unpin_group_message(
+    member_wallet_address: &Pubkey,
+    group_pda_address: &Pubkey,
+    scope_pda_address: &Pubkey,
+    message_index: u64,
+) -> Instruction {
+    let member_pda_address = GroupMember::find_pda_address(
+        scope_pda_address,
+        member_wallet_address,
+        &id(),
+    );
+
+    let pinned_messages_pda_address = PinnedMessages::find_pda_address(
+        group_pda_address,
+        &id(),
+    );
+
+    Instruction::new_with_borsh(
+        id(),
+        &InstantMessagingInstruction::UnpinGroupMessage {
+            message_index,
+        },
+        vec![
+            AccountMeta::new_readonly(*member_wallet_address, true),
+            AccountMeta::new(pinned_messages_pda_address, false),
+            AccountMeta::new_readonly(*group_pda_address, false),
+            AccountMeta::new_readonly(member_pda_address, false),
+        ],
+    )
+}
+
+/// Creates UpdateUserConversation instruction
+pub fn update_user_conversation(
+    user_wallet_address: &Pubkey,
+    conversation_index: u64,
+    archived: bool,
+    muted_until: UnixTimestamp,
+    folder_id: u32,
+) -> Instruction {
+    let user_pda_address = User::find_pda_address(
+        user_wallet_address,
+        &id(),
+    );
+
+    let user_conversation_pda_address = user_conversation_pda_address(&user_pda_address, conversation_index);
+
+    Instruction::new_with_borsh(
+        id(),
+        &InstantMessagingInstruction::UpdateUserConversation {
+            conversation_index,
+            archived,
+            muted_until,
+            folder_id,
+        },
+        vec![
+            AccountMeta::new_readonly(*user_wallet_address, true),
+            AccountMeta::new_readonly(user_pda_address, false),
+            AccountMeta::new(user_conversation_pda_address, false),
+        ],
+    )
+}
+
+/// Creates HideConversation instruction
+pub fn hide_conversation(
+    user_wallet_address: &Pubkey,
+    other_wallet_address: &Pubkey,
+    conversation_index: u64,
+    hidden: bool,
+) -> Instruction {
+    let user_pda_address = User::find_pda_address(
+        user_wallet_address,
+        &id(),
+    );
+
+    let other_user_pda_address = User::find_pda_address(
+        other_wallet_address,
+        &id(),
+    );
+
+    let conversation_pda_address = Conversation::find_pda_address(
+        &user_pda_address,
+        &other_user_pda_address,
+        &id(),
+    );
+
+    let user_conversation_pda_address = user_conversation_pda_address(&user_pda_address, conversation_index);
+
+    Instruction::new_with_borsh(
+        id(),
+        &InstantMessagingInstruction::HideConversation {
+            conversation_index,
+            hidden,
+        },
+        vec![
+            AccountMeta::new(*user_wallet_address, true),
+            AccountMeta::new_readonly(user_pda_address, false),
+            AccountMeta::new_readonly(other_user_pda_address, false),
+            AccountMeta::new_readonly(conversation_pda_address, false),
+            AccountMeta::new(user_conversation_pda_address, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+        ],
+    )
+}
+
+/// Creates StartConversation instruction - conversation indexes are the current conversation counters
+/// of the users, zero for users without an account
+#[allow(clippy::too_many_arguments)]
+pub fn start_conversation(
+    funder_address: &Pubkey,
+    sender_wallet_address: &Pubkey,
+    receiver_wallet_address: &Pubkey,
+    sender_conversation_index: u64,
+    receiver_conversation_index: u64,
+    message_index: u64,
+    message_type: u8,
+    content: Vec<u8>,
+    message_id: [u8; 16],
+) -> Instruction {
+    let sender_user_pda_address = User::find_pda_address(
+        sender_wallet_address,
+        &id(),
+    );
+
+    let receiver_user_pda_address = User::find_pda_address(
+        receiver_wallet_address,
+        &id(),
+    );
+
+    let conversation_pda_address = Conversation::find_pda_address(
+        &sender_user_pda_address,
+        &receiver_user_pda_address,
+        &id(),
+    );
+
+    let sender_user_conversation_pda_address = user_conversation_pda_address(&sender_user_pda_address, sender_conversation_index);
+
+    let receiver_user_conversation_pda_address = user_conversation_pda_address(&receiver_user_pda_address, receiver_conversation_index);
+
+    let message_pda_address = message_pda_address(&conversation_pda_address, message_index);
+
+    let message_id_pda_address = MessageIdRecord::find_pda_address(
+        &conversation_pda_address,
+        sender_wallet_address,
+        &message_id,
+        &id(),
+    );
+
+    let padding_policy_pda_address = ConversationPaddingPolicy::find_pda_address(
+        &conversation_pda_address,
+        &id(),
+    );
+
+    Instruction::new_with_borsh(
+        id(),
+        &InstantMessagingInstruction::StartConversation {
+            message_type,
+            content,
+            expected_index: message_index,
+            message_id,
+        },
+        vec![
+            AccountMeta::new(*funder_address, true),
+            AccountMeta::new_readonly(*sender_wallet_address, true),
+            AccountMeta::new_readonly(*receiver_wallet_address, false),
+            AccountMeta::new(sender_user_pda_address, false),
+            AccountMeta::new(receiver_user_pda_address, false),
+            AccountMeta::new(conversation_pda_address, false),
+            AccountMeta::new(sender_user_conversation_pda_address, false),
+            AccountMeta::new(receiver_user_conversation_pda_address, false),
+            AccountMeta::new(message_pda_address, false),
+            AccountMeta::new(message_id_pda_address, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(padding_policy_pda_address, false),
+            AccountMeta::new_readonly(AccountUpgrade::find_pda_address(&sender_user_pda_address, &id()), false),
+            AccountMeta::new_readonly(AccountUpgrade::find_pda_address(&receiver_user_pda_address, &id()), false),
+            AccountMeta::new_readonly(AccountUpgrade::find_pda_address(&conversation_pda_address, &id()), false),
+        ],
+    )
+}
+
+/// Creates SendSequencedMessage instruction - `sequence` is the current message counter of the sender-sequence
+pub fn send_sequenced_message(
+    funder_address: &Pubkey,
+    sender_wallet_address: &Pubkey,
+    receiver_wallet_address: &Pubkey,
+    sequence: u64,
+    message_type: u8,
+    content: Vec<u8>,
+) -> Instruction {
+    let sender_user_pda_address = User::find_pda_address(
+        sender_wallet_address,
+        &id(),
+    );
+
+    let receiver_user_pda_address = User::find_pda_address(
+        receiver_wallet_address,
+        &id(),
+    );
+
+    let conversation_pda_address = Conversation::find_pda_address(
+        &sender_user_pda_address,
+        &receiver_user_pda_address,
+        &id(),
+    );
+
+    let sender_sequence_pda_address = SenderSequence::find_pda_address(
+        &conversation_pda_address,
+        sender_wallet_address,
+        &id(),
+    );
+
+    let message_pda_address = Message::find_sequenced_pda_address(
+        &conversation_pda_address,
+        sender_wallet_address,
+        sequence,
+        &id(),
+    );
+
+    let padding_policy_pda_address = ConversationPaddingPolicy::find_pda_address(
+        &conversation_pda_address,
         &id(),
     );
 
     Instruction::new_with_borsh(
         id(),
-        &InstantMessagingInstruction::CreateUserAccount {},
+        &InstantMessagingInstruction::SendSequencedMessage {
+            message_type,
+            content,
+        },
         vec![
             AccountMeta::new(*funder_address, true),
-            AccountMeta::new(user_pda_address, false),
-            AccountMeta::new(*wallet_address, false),
+            AccountMeta::new_readonly(*sender_wallet_address, true),
+            AccountMeta::new_readonly(sender_user_pda_address, false),
+            AccountMeta::new_readonly(receiver_user_pda_address, false),
+            AccountMeta::new_readonly(conversation_pda_address, false),
+            AccountMeta::new(sender_sequence_pda_address, false),
+            AccountMeta::new(message_pda_address, false),
             AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
             AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(padding_policy_pda_address, false),
         ],
     )
 }
 
-/// Creates CreateConversationAccount instruction
-pub fn create_conversation_account(
+/// Creates SendMessages instruction - `message_index` is the current message counter of the conversation
+pub fn send_messages(
     funder_address: &Pubkey,
-    sender_pda_address: &Pubkey,
-    receiver_pda_address: &Pubkey,
-    sender_user_conversation_index: u32,
-    receiver_user_conversation_index: u32,
+    sender_wallet_address: &Pubkey,
+    receiver_wallet_address: &Pubkey,
+    message_index: u64,
+    messages: Vec<(u8, Vec<u8>, [u8; 16])>,
+) -> Instruction {
+    let sender_user_pda_address = User::find_pda_address(
+        sender_wallet_address,
+        &id(),
+    );
+
+    let receiver_user_pda_address = User::find_pda_address(
+        receiver_wallet_address,
+        &id(),
+    );
+
+    let conversation_pda_address = Conversation::find_pda_address(
+        &sender_user_pda_address,
+        &receiver_user_pda_address,
+        &id(),
+    );
+
+    let padding_policy_pda_address = ConversationPaddingPolicy::find_pda_address(
+        &conversation_pda_address,
+        &id(),
+    );
+
+    let mut accounts = vec![
+        AccountMeta::new(*funder_address, true),
+        AccountMeta::new_readonly(*sender_wallet_address, true),
+        AccountMeta::new_readonly(sender_user_pda_address, false),
+        AccountMeta::new_readonly(receiver_user_pda_address, false),
+        AccountMeta::new(conversation_pda_address, false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(padding_policy_pda_address, false),
+    ];
+
+    for offset in 0..messages.len() as u64 {
+        accounts.push(AccountMeta::new(
+            message_pda_address(&conversation_pda_address, message_index + offset),
+            false,
+        ));
+    }
+
+    for (_, _, message_id) in messages.iter() {
+        accounts.push(AccountMeta::new(
+            MessageIdRecord::find_pda_address(&conversation_pda_address, sender_wallet_address, message_id, &id()),
+            false,
+        ));
+    }
+
+    Instruction::new_with_borsh(
+        id(),
+        &InstantMessagingInstruction::SendMessages {
+            messages,
+            expected_index: message_index,
+        },
+        accounts,
+    )
+}
+
+/// Creates SendPagedMessage instruction - `page_counter` is the current page counter of the paged-conversation
+pub fn send_paged_message(
+    funder_address: &Pubkey,
+    sender_wallet_address: &Pubkey,
+    receiver_wallet_address: &Pubkey,
+    page_counter: u32,
+    message_type: u8,
+    content: Vec<u8>,
 ) -> Instruction {
+    let sender_user_pda_address = User::find_pda_address(
+        sender_wallet_address,
+        &id(),
+    );
+
+    let receiver_user_pda_address = User::find_pda_address(
+        receiver_wallet_address,
+        &id(),
+    );
+
     let conversation_pda_address = Conversation::find_pda_address(
-        &sender_pda_address,
-        &receiver_pda_address,
+        &sender_user_pda_address,
+        &receiver_user_pda_address,
+        &id(),
+    );
+
+    let paged_conversation_pda_address = PagedConversation::find_pda_address(
+        &conversation_pda_address,
+        &id(),
+    );
+
+    let last_page_pda_address = MessagePage::find_pda_address(
+        &conversation_pda_address,
+        page_counter.saturating_sub(1),
         &id(),
     );
 
-    let sender_user_conversation_pda_address = UserConversation::find_pda_address(
-        &sender_pda_address,
-        sender_user_conversation_index,
+    let next_page_pda_address = MessagePage::find_pda_address(
+        &conversation_pda_address,
+        page_counter,
         &id(),
     );
 
-    let receiver_user_conversation_pda_address = UserConversation::find_pda_address(
-        &receiver_pda_address,
-        receiver_user_conversation_index,
+    let padding_policy_pda_address = ConversationPaddingPolicy::find_pda_address(
+        &conversation_pda_address,
         &id(),
     );
 
     Instruction::new_with_borsh(
         id(),
-        &InstantMessagingInstruction::CreateConversationAccount {},
+        &InstantMessagingInstruction::SendPagedMessage {
+            message_type,
+            content,
+        },
         vec![
             AccountMeta::new(*funder_address, true),
-            AccountMeta::new(conversation_pda_address, false),
-            AccountMeta::new(*sender_pda_address, false),
-            AccountMeta::new(*receiver_pda_address, false),
-            AccountMeta::new(sender_user_conversation_pda_address, false),
-            AccountMeta::new(receiver_user_conversation_pda_address, false),
+            AccountMeta::new_readonly(*sender_wallet_address, true),
+            AccountMeta::new_readonly(sender_user_pda_address, false),
+            AccountMeta::new_readonly(receiver_user_pda_address, false),
+            AccountMeta::new_readonly(conversation_pda_address, false),
+            AccountMeta::new(paged_conversation_pda_address, false),
+            AccountMeta::new(last_page_pda_address, false),
+            AccountMeta::new(next_page_pda_address, false),
             AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
             AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(padding_policy_pda_address, false),
         ],
     )
 }
 
-/// Creates CreateUserConversationAccount instruction
-pub fn create_user_conversation_account(
+/// Creates MigrateUserConversationAccount instruction
+pub fn migrate_user_conversation_account(
     funder_address: &Pubkey,
-    user_pda_address: &Pubkey,
+    wallet_address: &Pubkey,
     conversation_index: u32,
 ) -> Instruction {
-    let user_conversation_pda_address = UserConversation::find_pda_address(
+    let user_pda_address = User::find_pda_address(
+        wallet_address,
+        &id(),
+    );
+
+    let v1_user_conversation_pda_address = UserConversation::find_pda_address(
         &user_pda_address,
         conversation_index,
         &id(),
     );
 
+    let v2_user_conversation_pda_address = user_conversation_pda_address(&user_pda_address, conversation_index.into());
+
     Instruction::new_with_borsh(
         id(),
-        &InstantMessagingInstruction::CreateUserConversationAccount {
+        &InstantMessagingInstruction::MigrateUserConversationAccount {
             conversation_index,
         },
         vec![
             AccountMeta::new(*funder_address, true),
-            AccountMeta::new(user_conversation_pda_address, false),
-            AccountMeta::new(*user_pda_address, false),
+            AccountMeta::new(*wallet_address, true),
+            AccountMeta::new_readonly(user_pda_address, false),
+            AccountMeta::new(v1_user_conversation_pda_address, false),
+            AccountMeta::new(v2_user_conversation_pda_address, false),
             AccountMeta::new_readonly(sysvar::rent::id(), false),
             AccountMeta::new_readonly(system_program::id(), false),
         ],
     )
 }
 
-/// Creates CreateMessageAccount instruction
-pub fn create_message_account(
+/// Creates MigrateMessageAccount instruction - `conversation_address` is the conversation, group
+/// or blinded conversation the message belongs to
+pub fn migrate_message_account(
     funder_address: &Pubkey,
+    participant_wallet_address: &Pubkey,
     sender_wallet_address: &Pubkey,
-    conversation_pda_address: &Pubkey,
-    conversation_index: u32,
+    conversation_address: &Pubkey,
     message_index: u32,
-    message_type: u8,
-    content: Vec<u8>,
 ) -> Instruction {
-    let sender_user_pda_address = User::find_pda_address(
-        &sender_wallet_address,
+    let v1_message_pda_address = Message::find_pda_address(
+        conversation_address,
+        message_index,
         &id(),
     );
 
-    let sender_user_conversation_pda_address = UserConversation::find_pda_address(
-        &sender_user_pda_address,
-        conversation_index,
+    let v2_message_pda_address = message_pda_address(conversation_address, message_index.into());
+
+    Instruction::new_with_borsh(
+        id(),
+        &InstantMessagingInstruction::MigrateMessageAccount {
+            message_index,
+        },
+        vec![
+            AccountMeta::new(*funder_address, true),
+            AccountMeta::new_readonly(*participant_wallet_address, true),
+            AccountMeta::new_readonly(*conversation_address, false),
+            AccountMeta::new(v1_message_pda_address, false),
+            AccountMeta::new(v2_message_pda_address, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(*sender_wallet_address, false),
+            AccountMeta::new_readonly(User::find_pda_address(participant_wallet_address, &id()), false),
+            AccountMeta::new_readonly(User::find_pda_address(sender_wallet_address, &id()), false),
+        ],
+    )
+}
+
+/// Creates CreateGroupHandshakeAccount instruction
+pub fn create_group_handshake_account(
+    funder_address: &Pubkey,
+    sender_wallet_address: &Pubkey,
+    group_pda_address: &Pubkey,
+    epoch: u64,
+    commit: Vec<u8>,
+    welcome: Vec<u8>,
+) -> Instruction {
+    let sender_member_pda_address = GroupMember::find_pda_address(
+        group_pda_address,
+        sender_wallet_address,
         &id(),
     );
 
-    let message_pda_address = Message::find_pda_address(
-        &conversation_pda_address,
-        message_index,
+    let handshake_pda_address = ConversationHandshake::find_pda_address(
+        group_pda_address,
+        epoch,
+        &id(),
+    );
+
+    let previous_handshake_pda_address = ConversationHandshake::find_pda_address(
+        group_pda_address,
+        epoch.saturating_sub(1),
         &id(),
     );
 
     Instruction::new_with_borsh(
         id(),
-        &InstantMessagingInstruction::CreateMessageAccount {
-            conversation_index,
-            message_type,
-            content,
+        &InstantMessagingInstruction::CreateGroupHandshakeAccount {
+            epoch,
+            commit,
+            welcome,
         },
         vec![
             AccountMeta::new(*funder_address, true),
             AccountMeta::new(*sender_wallet_address, true),
-            AccountMeta::new(sender_user_pda_address, false),
-            AccountMeta::new(sender_user_conversation_pda_address, false),
-            AccountMeta::new(*conversation_pda_address, false),
-            AccountMeta::new(message_pda_address, false),
+            AccountMeta::new(handshake_pda_address, false),
+            AccountMeta::new_readonly(previous_handshake_pda_address, false),
+            AccountMeta::new_readonly(*group_pda_address, false),
+            AccountMeta::new_readonly(sender_member_pda_address, false),
             AccountMeta::new_readonly(sysvar::rent::id(), false),
             AccountMeta::new_readonly(sysvar::clock::id(), false),
             AccountMeta::new_readonly(system_program::id(), false),
@@ -256,99 +2923,224 @@ pub fn create_message_account(
     )
 }
 
-/// Creates CreateConversationEncryptionInfoAccount instruction
-pub fn create_conversation_encryption_info_account(
+/// Creates SetBlindedConversationPaddingPolicy instruction
+pub fn set_blinded_conversation_padding_policy(
     funder_address: &Pubkey,
-    sender_wallet_address: &Pubkey,
-    receiver_wallet_address: &Pubkey,
-    data: Vec<u8>,
+    authority_address: &Pubkey,
+    blinded_id: &[u8; 32],
+    scheme: u8,
 ) -> Instruction {
-    let sender_user_pda_address = User::find_pda_address(
-        &sender_wallet_address,
+    let blinded_conversation_pda_address = BlindedConversation::find_pda_address(
+        blinded_id,
+        authority_address,
         &id(),
     );
 
-    let receiver_user_pda_address = User::find_pda_address(
-        &receiver_wallet_address,
+    let padding_policy_pda_address = ConversationPaddingPolicy::find_pda_address(
+        &blinded_conversation_pda_address,
         &id(),
     );
 
-    let conversation_pda_address = Conversation::find_pda_address(
-        &sender_user_pda_address,
-        &receiver_user_pda_address,
+    Instruction::new_with_borsh(
+        id(),
+        &InstantMessagingInstruction::SetBlindedConversationPaddingPolicy {
+            scheme,
+        },
+        vec![
+            AccountMeta::new(*funder_address, true),
+            AccountMeta::new_readonly(*authority_address, true),
+            AccountMeta::new(padding_policy_pda_address, false),
+            AccountMeta::new_readonly(blinded_conversation_pda_address, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+/// Creates RevokeInvite instruction
+pub fn revoke_invite(
+    admin_wallet_address: &Pubkey,
+    group_pda_address: &Pubkey,
+    creator_wallet_address: &Pubkey,
+    code: [u8; 16],
+) -> Instruction {
+    let admin_member_pda_address = GroupMember::find_pda_address(
+        group_pda_address,
+        admin_wallet_address,
         &id(),
     );
 
-    let conversation_encryption_info_pda_address = ConversationEncryptionInfo::find_pda_address(
-        &conversation_pda_address,
+    let code_hash = Invite::hash_code(&code);
+
+    let invite_pda_address = Invite::find_pda_address(
+        group_pda_address,
+        &code_hash,
         &id(),
     );
 
     Instruction::new_with_borsh(
         id(),
-        &InstantMessagingInstruction::CreateConversationEncryptionInfoAccount {
-            data,
+        &InstantMessagingInstruction::RevokeInvite {
+            code_hash,
         },
+        vec![
+            AccountMeta::new_readonly(*admin_wallet_address, true),
+            AccountMeta::new_readonly(*group_pda_address, false),
+            AccountMeta::new_readonly(admin_member_pda_address, false),
+            AccountMeta::new(invite_pda_address, false),
+            AccountMeta::new(*creator_wallet_address, false),
+        ],
+    )
+}
+
+/// Creates CreateCommunityGroupUserConversation instruction
+pub fn create_community_group_user_conversation(
+    funder_address: &Pubkey,
+    member_wallet_address: &Pubkey,
+    community_pda_address: &Pubkey,
+    group_index: u64,
+    member_conversation_index: u64,
+) -> Instruction {
+    let member_pda_address = GroupMember::find_pda_address(
+        community_pda_address,
+        member_wallet_address,
+        &id(),
+    );
+
+    let group_pda_address = Group::find_pda_address(
+        community_pda_address,
+        group_index,
+        &id(),
+    );
+
+    let member_user_pda_address = User::find_pda_address(
+        member_wallet_address,
+        &id(),
+    );
+
+    let member_user_conversation_pda_address = user_conversation_pda_address(&member_user_pda_address, member_conversation_index);
+
+    Instruction::new_with_borsh(
+        id(),
+        &InstantMessagingInstruction::CreateCommunityGroupUserConversation,
         vec![
             AccountMeta::new(*funder_address, true),
-            AccountMeta::new(*sender_wallet_address, true),
-            AccountMeta::new(conversation_encryption_info_pda_address, false),
-            AccountMeta::new(sender_user_pda_address, false),
-            AccountMeta::new(receiver_user_pda_address, false),
-            AccountMeta::new(conversation_pda_address, false),
+            AccountMeta::new_readonly(*member_wallet_address, true),
+            AccountMeta::new_readonly(*community_pda_address, false),
+            AccountMeta::new_readonly(member_pda_address, false),
+            AccountMeta::new_readonly(group_pda_address, false),
+            AccountMeta::new(member_user_pda_address, false),
+            AccountMeta::new(member_user_conversation_pda_address, false),
             AccountMeta::new_readonly(sysvar::rent::id(), false),
-            AccountMeta::new_readonly(sysvar::clock::id(), false),
             AccountMeta::new_readonly(system_program::id(), false),
         ],
     )
 }
 
-/// Creates SendMessage instruction
-pub fn send_message(
+/// Creates UpgradeUserAccount instruction
+pub fn upgrade_user_account(
     funder_address: &Pubkey,
-    sender_wallet_address: &Pubkey,
-    receiver_wallet_address: &Pubkey,
-    message_index: u32,
-    message_type: u8,
-    content: Vec<u8>,
+    user_wallet_address: &Pubkey,
 ) -> Instruction {
-    let sender_user_pda_address = User::find_pda_address(
-        &sender_wallet_address,
+    let user_pda_address = User::find_pda_address(
+        user_wallet_address,
         &id(),
     );
 
-    let receiver_user_pda_address = User::find_pda_address(
-        &receiver_wallet_address,
+    let upgrade_pda_address = AccountUpgrade::find_pda_address(
+        &user_pda_address,
+        &id(),
+    );
+
+    Instruction::new_with_borsh(
+        id(),
+        &InstantMessagingInstruction::UpgradeUserAccount,
+        vec![
+            AccountMeta::new(*funder_address, true),
+            AccountMeta::new_readonly(*user_wallet_address, true),
+            AccountMeta::new(user_pda_address, false),
+            AccountMeta::new(upgrade_pda_address, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+/// Creates UpgradeConversationAccount instruction
+pub fn upgrade_conversation_account(
+    funder_address: &Pubkey,
+    participant_wallet_address: &Pubkey,
+    other_participant_wallet_address: &Pubkey,
+) -> Instruction {
+    let participant_user_pda_address = User::find_pda_address(
+        participant_wallet_address,
+        &id(),
+    );
+
+    let other_participant_user_pda_address = User::find_pda_address(
+        other_participant_wallet_address,
         &id(),
     );
 
     let conversation_pda_address = Conversation::find_pda_address(
-        &sender_user_pda_address,
-        &receiver_user_pda_address,
+        &participant_user_pda_address,
+        &other_participant_user_pda_address,
         &id(),
     );
 
-    let message_pda_address = Message::find_pda_address(
+    let upgrade_pda_address = AccountUpgrade::find_pda_address(
         &conversation_pda_address,
-        message_index,
         &id(),
     );
 
     Instruction::new_with_borsh(
         id(),
-        &InstantMessagingInstruction::SendMessage {
-            message_type,
-            content,
-        },
+        &InstantMessagingInstruction::UpgradeConversationAccount,
         vec![
             AccountMeta::new(*funder_address, true),
-            AccountMeta::new(*sender_wallet_address, true),
-            AccountMeta::new(sender_user_pda_address, false),
-            AccountMeta::new(receiver_user_pda_address, false),
+            AccountMeta::new_readonly(*participant_wallet_address, true),
+            AccountMeta::new_readonly(participant_user_pda_address, false),
+            AccountMeta::new_readonly(other_participant_user_pda_address, false),
             AccountMeta::new(conversation_pda_address, false),
-            AccountMeta::new(message_pda_address, false),
+            AccountMeta::new(upgrade_pda_address, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+/// Creates SetGroupPaddingPolicy instruction - the group-member is derived from `scope_pda_address`,
+/// which is the community for community groups and the group itself otherwise
+pub fn set_group_padding_policy(
+    funder_address: &Pubkey,
+    editor_wallet_address: &Pubkey,
+    group_pda_address: &Pubkey,
+    scope_pda_address: &Pubkey,
+    scheme: u8,
+) -> Instruction {
+    let editor_member_pda_address = GroupMember::find_pda_address(
+        scope_pda_address,
+        editor_wallet_address,
+        &id(),
+    );
+
+    let padding_policy_pda_address = ConversationPaddingPolicy::find_pda_address(
+        group_pda_address,
+        &id(),
+    );
+
+    Instruction::new_with_borsh(
+        id(),
+        &InstantMessagingInstruction::SetGroupPaddingPolicy {
+            scheme,
+        },
+        vec![
+            AccountMeta::new(*funder_address, true),
+            AccountMeta::new_readonly(*editor_wallet_address, true),
+            AccountMeta::new(padding_policy_pda_address, false),
+            AccountMeta::new_readonly(*group_pda_address, false),
+            AccountMeta::new_readonly(editor_member_pda_address, false),
             AccountMeta::new_readonly(sysvar::rent::id(), false),
-            AccountMeta::new_readonly(sysvar::clock::id(), false),
             AccountMeta::new_readonly(system_program::id(), false),
         ],
     )
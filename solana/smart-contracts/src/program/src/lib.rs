@@ -1,5 +1,16 @@
+#[cfg(not(target_arch = "bpf"))]
+pub mod blinded;
+#[cfg(not(target_arch = "bpf"))]
+pub mod client;
+#[cfg(not(target_arch = "bpf"))]
+pub mod encryption;
+pub mod error;
 pub mod instruction;
+#[cfg(not(target_arch = "bpf"))]
+pub mod padding;
 pub mod processor;
+#[cfg(not(target_arch = "bpf"))]
+pub mod sealed_sender;
 pub mod state;
 pub mod utils;
 
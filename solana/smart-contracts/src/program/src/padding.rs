@@ -0,0 +1,49 @@
+//! Off-chain padding of encrypted content
+//!
+//! Content is padded to the size required by the padding policy of the conversation before it is sent.
+
+use crate::{
+    encryption::CodecError,
+    state::PaddingScheme,
+};
+
+/// Marker byte that starts the padding - ISO/IEC 7816-4, followed by zeros
+const PADDING_MARKER: u8 = 0x80;
+
+/// Pad content to the size required by the conversation padding policy
+pub fn pad_content(scheme: u8, content: &[u8]) -> Result<Vec<u8>, CodecError> {
+    if !PaddingScheme::is_valid(scheme) {
+        return Err(CodecError::UnsupportedPaddingScheme(scheme));
+    }
+
+    if scheme == PaddingScheme::NONE {
+        return Ok(content.to_vec());
+    }
+
+    let mut padded = Vec::with_capacity(PaddingScheme::padded_size(scheme, content.len()));
+    padded.extend_from_slice(content);
+    padded.push(PADDING_MARKER);
+    padded.resize(PaddingScheme::padded_size(scheme, content.len()), 0);
+
+    Ok(padded)
+}
+
+/// Strip padding added by `pad_content`
+pub fn unpad_content(scheme: u8, content: &[u8]) -> Result<Vec<u8>, CodecError> {
+    if !PaddingScheme::is_valid(scheme) {
+        return Err(CodecError::UnsupportedPaddingScheme(scheme));
+    }
+
+    if scheme == PaddingScheme::NONE {
+        return Ok(content.to_vec());
+    }
+
+    if !PaddingScheme::is_padded(scheme, content.len()) {
+        return Err(CodecError::InvalidPadding);
+    }
+
+    match content.iter().rposition(|byte| *byte != 0) {
+        Some(position) if content[position] == PADDING_MARKER => Ok(content[..position].to_vec()),
+        _ => Err(CodecError::InvalidPadding),
+    }
+}
@@ -9,7 +9,11 @@ use {
             UserConversation,
             sort_addresses_asc,
         },
-        utils::create_pda_account,
+        utils::{
+            close_pda_account,
+            create_pda_account,
+            refund_excess_lamports,
+        },
     },
     borsh::{BorshDeserialize, BorshSerialize},
     solana_program::{
@@ -17,8 +21,12 @@ use {
             next_account_info,
             AccountInfo,
         },
-        clock::Clock,
+        clock::{
+            Clock,
+            UnixTimestamp,
+        },
         entrypoint::ProgramResult,
+        hash::Hash,
         msg,
         program_error::ProgramError,
         pubkey::Pubkey,
@@ -29,8 +37,10 @@ use {
             Sysvar,
         },
     },
+    std::convert::TryFrom,
 };
-use crate::state::{ConversationEncryptionInfo, Message};
+use crate::error::InstantMessagingError;
+use crate::state::{AccountKind, AccountUpgrade, BlindedConversation, Community, ConversationEncryptionInfo, ConversationHandshake, ConversationMetadata, ConversationPaddingPolicy, ConversationView, Group, GroupMember, GroupBan, GroupKind, GroupPermission, GroupRole, Invite, JoinRequest, Message, MessageIdRecord, MessagePage, MessagePageView, MessageType, MessageView, PagedConversation, PaddingScheme, PinnedMessages, SealedSenderCredential, SenderSequence, UserView};
 
 pub fn process_instruction(
     program_id: &Pubkey,
@@ -40,8 +50,8 @@ pub fn process_instruction(
     let instruction = InstantMessagingInstruction::try_from_slice(instruction_data)?;
 
     match instruction {
-        InstantMessagingInstruction::CreateUserAccount {} => create_user_account(program_id, accounts),
-        InstantMessagingInstruction::CreateConversationAccount {} => create_conversation_account(program_id, accounts),
+        InstantMessagingInstruction::CreateUserAccount => create_user_account(program_id, accounts),
+        InstantMessagingInstruction::CreateConversationAccount => create_conversation_account(program_id, accounts),
         InstantMessagingInstruction::CreateUserConversationAccount {
             conversation_index,
         } => create_user_conversation_account(program_id, accounts, conversation_index),
@@ -66,11 +76,325 @@ pub fn process_instruction(
         InstantMessagingInstruction::SendMessage {
             message_type,
             content,
+            expected_index,
+            message_id,
         } => send_message(
             program_id,
             accounts,
             message_type,
             content,
+            expected_index,
+            message_id,
+        ),
+        InstantMessagingInstruction::CreateConversationHandshakeAccount {
+            epoch,
+            commit,
+            welcome,
+        } => create_conversation_handshake_account(
+            program_id,
+            accounts,
+            epoch,
+            commit,
+            welcome,
+        ),
+        InstantMessagingInstruction::SetSealedSenderCredential {
+            authority,
+            chain_hash,
+        } => set_sealed_sender_credential(
+            program_id,
+            accounts,
+            authority,
+            chain_hash,
+        ),
+        InstantMessagingInstruction::SendSealedMessage {
+            next_authority,
+            next_chain_hash,
+            content,
+        } => send_sealed_message(
+            program_id,
+            accounts,
+            next_authority,
+            next_chain_hash,
+            content,
+        ),
+        InstantMessagingInstruction::CreateBlindedConversationAccount {
+            blinded_id,
+        } => create_blinded_conversation_account(
+            program_id,
+            accounts,
+            blinded_id,
+        ),
+        InstantMessagingInstruction::SendBlindedMessage {
+            message_type,
+            content,
+        } => send_blinded_message(
+            program_id,
+            accounts,
+            message_type,
+            content,
+        ),
+        InstantMessagingInstruction::SetConversationPaddingPolicy {
+            scheme,
+        } => set_conversation_padding_policy(
+            program_id,
+            accounts,
+            scheme,
+        ),
+        InstantMessagingInstruction::CreateGroupAccount {
+            group_index,
+            kind,
+        } => create_group_account(
+            program_id,
+            accounts,
+            group_index,
+            kind,
+        ),
+        InstantMessagingInstruction::AddGroupMember {
+            role,
+            permissions,
+        } => add_group_member(
+            program_id,
+            accounts,
+            role,
+            permissions,
+        ),
+        InstantMessagingInstruction::SendGroupMessage {
+            message_type,
+            content,
+        } => send_group_message(
+            program_id,
+            accounts,
+            message_type,
+            content,
+        ),
+        InstantMessagingInstruction::SetMemberRole {
+            role,
+            permissions,
+        } => set_member_role(
+            program_id,
+            accounts,
+            role,
+            permissions,
+        ),
+        InstantMessagingInstruction::TransferGroupOwnership => transfer_group_ownership(program_id, accounts),
+        InstantMessagingInstruction::CreateInviteAccount {
+            code_hash,
+            expires_at,
+            max_uses,
+            role,
+        } => create_invite_account(
+            program_id,
+            accounts,
+            code_hash,
+            expires_at,
+            max_uses,
+            role.unwrap_or(GroupRole::MEMBER),
+        ),
+        InstantMessagingInstruction::JoinWithInvite {
+            code,
+        } => join_with_invite(
+            program_id,
+            accounts,
+            code,
+        ),
+        InstantMessagingInstruction::RequestToJoin => request_to_join(program_id, accounts),
+        InstantMessagingInstruction::ApproveJoin => approve_join(program_id, accounts),
+        InstantMessagingInstruction::DenyJoin => deny_join(program_id, accounts),
+        InstantMessagingInstruction::RemoveMember {
+            conversation_index,
+        } => remove_member(
+            program_id,
+            accounts,
+            conversation_index,
+        ),
+        InstantMessagingInstruction::BanMember {
+            conversation_index,
+        } => ban_member(
+            program_id,
+            accounts,
+            conversation_index,
+        ),
+        InstantMessagingInstruction::LeaveGroup {
+            conversation_index,
+        } => leave_group(
+            program_id,
+            accounts,
+            conversation_index,
+        ),
+        InstantMessagingInstruction::Subscribe => subscribe(program_id, accounts),
+        InstantMessagingInstruction::CreateCommunityAccount {
+            community_index,
+        } => create_community_account(
+            program_id,
+            accounts,
+            community_index,
+        ),
+        InstantMessagingInstruction::CreateCommunityGroupAccount {
+            kind,
+        } => create_community_group_account(
+            program_id,
+            accounts,
+            kind,
+        ),
+        InstantMessagingInstruction::Unsubscribe {
+            conversation_index,
+        } => unsubscribe(
+            program_id,
+            accounts,
+            conversation_index,
+        ),
+        InstantMessagingInstruction::SetConversationMetadata {
+            title,
+            description,
+            avatar_uri,
+        } => set_conversation_metadata(
+            program_id,
+            accounts,
+            title,
+            description,
+            avatar_uri,
+        ),
+        InstantMessagingInstruction::SetGroupMetadata {
+            title,
+            description,
+            avatar_uri,
+        } => set_group_metadata(
+            program_id,
+            accounts,
+            title,
+            description,
+            avatar_uri,
+        ),
+        InstantMessagingInstruction::PinMessage {
+            message_index,
+        } => pin_message(
+            program_id,
+            accounts,
+            message_index,
+        ),
+        InstantMessagingInstruction::UnpinMessage {
+            message_index,
+        } => unpin_message(
+            program_id,
+            accounts,
+            message_index,
+        ),
+        InstantMessagingInstruction::PinGroupMessage {
+            message_index,
+        } => pin_group_message(
+            program_id,
+            accounts,
+            message_index,
+        ),
+        InstantMessagingInstruction::UnpinGroupMessage {
+            message_index,
+        } => unpin_group_message(
+            program_id,
+            accounts,
+            message_index,
+        ),
+        InstantMessagingInstruction::UpdateUserConversation {
+            conversation_index,
+            archived,
+            muted_until,
+            folder_id,
+        } => update_user_conversation(
+            program_id,
+            accounts,
+            conversation_index,
+            archived,
+            muted_until,
+            folder_id,
+        ),
+        InstantMessagingInstruction::HideConversation {
+            conversation_index,
+            hidden,
+        } => hide_conversation(
+            program_id,
+            accounts,
+            conversation_index,
+            hidden,
+        ),
+        InstantMessagingInstruction::StartConversation {
+            message_type,
+            content,
+            expected_index,
+            message_id,
+        } => start_conversation(
+            program_id,
+            accounts,
+            message_type,
+            content,
+            expected_index,
+            message_id,
+        ),
+        InstantMessagingInstruction::SendSequencedMessage {
+            message_type,
+            content,
+        } => send_sequenced_message(
+            program_id,
+            accounts,
+            message_type,
+            content,
+        ),
+        InstantMessagingInstruction::SendMessages {
+            messages,
+            expected_index,
+        } => send_messages(
+            program_id,
+            accounts,
+            messages,
+            expected_index,
+        ),
+        InstantMessagingInstruction::SendPagedMessage {
+            message_type,
+            content,
+        } => send_paged_message(
+            program_id,
+            accounts,
+            message_type,
+            content,
+        ),
+        InstantMessagingInstruction::MigrateUserConversationAccount {
+            conversation_index,
+        } => migrate_user_conversation_account(program_id, accounts, conversation_index),
+        InstantMessagingInstruction::MigrateMessageAccount {
+            message_index,
+        } => migrate_message_account(program_id, accounts, message_index),
+        InstantMessagingInstruction::CreateGroupHandshakeAccount {
+            epoch,
+            commit,
+            welcome,
+        } => create_group_handshake_account(
+            program_id,
+            accounts,
+            epoch,
+            commit,
+            welcome,
+        ),
+        InstantMessagingInstruction::SetBlindedConversationPaddingPolicy {
+            scheme,
+        } => set_blinded_conversation_padding_policy(
+            program_id,
+            accounts,
+            scheme,
+        ),
+        InstantMessagingInstruction::RevokeInvite {
+            code_hash,
+        } => revoke_invite(
+            program_id,
+            accounts,
+            code_hash,
+        ),
+        InstantMessagingInstruction::CreateCommunityGroupUserConversation => create_community_group_user_conversation(program_id, accounts),
+        InstantMessagingInstruction::UpgradeUserAccount => upgrade_user_account(program_id, accounts),
+        InstantMessagingInstruction::UpgradeConversationAccount => upgrade_conversation_account(program_id, accounts),
+        InstantMessagingInstruction::SetGroupPaddingPolicy {
+            scheme,
+        } => set_group_padding_policy(
+            program_id,
+            accounts,
+            scheme,
         ),
     }
 }
@@ -86,6 +410,7 @@ fn create_user_account(
     let user_wallet_account_info = next_account_info(account_info_iter)?;
     let rent_info = next_account_info(account_info_iter)?;
     let system_program_info = next_account_info(account_info_iter)?;
+    let upgrade_account_info = next_account_info(account_info_iter)?;
 
     let rent = &Rent::from_account_info(rent_info)?;
 
@@ -105,15 +430,18 @@ fn create_user_account(
         return Err(ProgramError::AccountAlreadyInitialized);
     }
 
+    // Check if the legacy user account is not being upgraded
+    check_account_not_upgrading(program_id, user_account_info, upgrade_account_info)?;
+
     let user_account_signer_seeds: &[&[_]] = &[
         &user_wallet_account_info.key.to_bytes(),
-        &User::ACCOUNT_ADDRESS_SEED.as_bytes(),
+        User::ACCOUNT_ADDRESS_SEED.as_bytes(),
         &[user_bump_seed],
     ];
 
     create_pda_account(
         funder_info,
-        &rent,
+        rent,
         true,
         User::retrieve_size(),
         program_id,
@@ -122,6 +450,12 @@ fn create_user_account(
         user_account_signer_seeds,
     )?;
 
+    let user = User {
+        conversation_counter: 0,
+        bump_seed: user_bump_seed,
+    };
+    user.serialize(&mut &mut user_account_info.data.borrow_mut()[..])?;
+
     Ok(())
 }
 
@@ -139,6 +473,7 @@ fn create_conversation_account(
     let receiver_user_conversation_account_info = next_account_info(account_info_iter)?;
     let rent_info = next_account_info(account_info_iter)?;
     let system_program_info = next_account_info(account_info_iter)?;
+    let upgrade_account_info = next_account_info(account_info_iter)?;
 
     let rent = &Rent::from_account_info(rent_info)?;
 
@@ -159,6 +494,10 @@ fn create_conversation_account(
         return Err(ProgramError::AccountAlreadyInitialized);
     }
 
+    // Check if the legacy conversation account is not being upgraded - recreating it would also add
+    // user-conversation entries for a conversation the users already have
+    check_account_not_upgrading(program_id, conversation_account_info, upgrade_account_info)?;
+
     // Sort first & second address so that they are always in the same order
     let [address_one, address_two] = sort_addresses_asc(
         first_user_pda_account_info.key,
@@ -168,13 +507,13 @@ fn create_conversation_account(
     let conversation_account_signer_seeds: &[&[_]] = &[
         &address_one.to_bytes(),
         &address_two.to_bytes(),
-        &Conversation::ACCOUNT_ADDRESS_SEED.as_bytes(),
+        Conversation::ACCOUNT_ADDRESS_SEED.as_bytes(),
         &[conversation_bump_seed],
     ];
 
     create_pda_account(
         funder_info,
-        &rent,
+        rent,
         true,
         Conversation::retrieve_size(),
         program_id,
@@ -183,8 +522,14 @@ fn create_conversation_account(
         conversation_account_signer_seeds,
     )?;
 
+    let conversation = Conversation {
+        message_counter: 0,
+        bump_seed: conversation_bump_seed,
+    };
+    conversation.serialize(&mut &mut conversation_account_info.data.borrow_mut()[..])?;
+
     // TODO: Create sender's user-conversation account if not exist
-    let mut sender_user = User::try_from_slice(&first_user_pda_account_info.data.borrow())?;
+    let sender_user_conversation_counter = UserView::new(&first_user_pda_account_info.data.borrow()[..])?.conversation_counter();
 
     if sender_user_conversation_account_info.data.borrow().len() == 0 {
         create_user_conversation_account(
@@ -196,21 +541,21 @@ fn create_conversation_account(
                 rent_info.clone(),
                 system_program_info.clone(),
             ],
-            sender_user.conversation_counter,
+            sender_user_conversation_counter,
         )?;
 
         // Assign conversation address to sender user-conversation account
-        let mut sender_user_conversation = UserConversation::try_from_slice(&sender_user_conversation_account_info.data.borrow())?;
+        let mut sender_user_conversation = UserConversation::unpack(&sender_user_conversation_account_info.data.borrow())?;
         sender_user_conversation.conversation_address = *conversation_account_info.key;
-        sender_user_conversation.serialize(&mut &mut sender_user_conversation_account_info.data.borrow_mut()[..])?;
+        sender_user_conversation.pack(&mut sender_user_conversation_account_info.data.borrow_mut())?;
 
         // Increment and store the number of conversations the sender user account has
-        sender_user.conversation_counter += 1;
-        sender_user.serialize(&mut &mut first_user_pda_account_info.data.borrow_mut()[..])?;
+        let next_sender_user_conversation_counter = sender_user_conversation_counter.checked_add(1).ok_or(InstantMessagingError::CounterOverflow)?;
+        UserView::new(&mut first_user_pda_account_info.data.borrow_mut()[..])?.set_conversation_counter(next_sender_user_conversation_counter)?;
     }
 
     // TODO: Create receiver's user-conversation account if not exist
-    let mut receiver_user = User::try_from_slice(&second_user_pda_account_info.data.borrow())?;
+    let receiver_user_conversation_counter = UserView::new(&second_user_pda_account_info.data.borrow()[..])?.conversation_counter();
 
     if receiver_user_conversation_account_info.data.borrow().len() == 0 {
         create_user_conversation_account(
@@ -222,17 +567,17 @@ fn create_conversation_account(
                 rent_info.clone(),
                 system_program_info.clone(),
             ],
-            receiver_user.conversation_counter,
+            receiver_user_conversation_counter,
         )?;
 
         // Assign conversation address to receiver user-conversation account
-        let mut receiver_user_conversation = UserConversation::try_from_slice(&receiver_user_conversation_account_info.data.borrow())?;
+        let mut receiver_user_conversation = UserConversation::unpack(&receiver_user_conversation_account_info.data.borrow())?;
         receiver_user_conversation.conversation_address = *conversation_account_info.key;
-        receiver_user_conversation.serialize(&mut &mut receiver_user_conversation_account_info.data.borrow_mut()[..])?;
+        receiver_user_conversation.pack(&mut receiver_user_conversation_account_info.data.borrow_mut())?;
 
         // Increment and store the number of conversations the receiver user account has
-        receiver_user.conversation_counter += 1;
-        receiver_user.serialize(&mut &mut second_user_pda_account_info.data.borrow_mut()[..])?;
+        let next_receiver_user_conversation_counter = receiver_user_conversation_counter.checked_add(1).ok_or(InstantMessagingError::CounterOverflow)?;
+        UserView::new(&mut second_user_pda_account_info.data.borrow_mut()[..])?.set_conversation_counter(next_receiver_user_conversation_counter)?;
     }
 
     Ok(())
@@ -241,7 +586,7 @@ fn create_conversation_account(
 fn create_user_conversation_account(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
-    conversation_index: u32,
+    conversation_index: u64,
 ) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
 
@@ -254,7 +599,7 @@ fn create_user_conversation_account(
     let rent = &Rent::from_account_info(rent_info)?;
 
     let (user_conversation_address, user_conversation_bump_seed) =
-        UserConversation::find_pda_address_with_bump_seed(
+        UserConversation::find_pda_address_v2_with_bump_seed(
             user_pda_account_info.key,
             conversation_index,
             program_id,
@@ -270,17 +615,17 @@ fn create_user_conversation_account(
         return Err(ProgramError::AccountAlreadyInitialized);
     }
 
-    let account_seed = conversation_index.to_string() + UserConversation::ACCOUNT_ADDRESS_SEED;
-
+    let conversation_index_bytes = conversation_index.to_le_bytes();
     let user_conversation_account_signer_seeds: &[&[_]] = &[
         &user_pda_account_info.key.to_bytes(),
-        &account_seed.as_bytes(),
+        &conversation_index_bytes,
+        UserConversation::ACCOUNT_ADDRESS_SEED_V2.as_bytes(),
         &[user_conversation_bump_seed],
     ];
 
     create_pda_account(
         funder_info,
-        &rent,
+        rent,
         true,
         UserConversation::retrieve_size(),
         program_id,
@@ -289,13 +634,151 @@ fn create_user_conversation_account(
         user_conversation_account_signer_seeds,
     )?;
 
+    let user_conversation = UserConversation {
+        bump_seed: user_conversation_bump_seed,
+        ..UserConversation::default()
+    };
+    user_conversation.serialize(&mut &mut user_conversation_account_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+fn update_user_conversation(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    conversation_index: u64,
+    archived: bool,
+    muted_until: UnixTimestamp,
+    folder_id: u32,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let user_wallet_info = next_account_info(account_info_iter)?;
+    let user_account_info = next_account_info(account_info_iter)?;
+    let user_conversation_account_info = next_account_info(account_info_iter)?;
+
+    // Check user wallet signature
+    if !user_wallet_info.is_signer {
+        msg!("Error: User wallet signature missing");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Check if user-conversation belongs to the user wallet
+    if *user_account_info.key != user_pda_address(program_id, user_account_info, user_wallet_info.key)? {
+        msg!("Error: User address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    if !is_user_conversation_address(
+        program_id,
+        user_conversation_account_info,
+        user_account_info.key,
+        conversation_index,
+    )? {
+        msg!("Error: UserConversation address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    if user_conversation_account_info.data.borrow().len() == 0 {
+        msg!("Error: UserConversation account is not initialized");
+        return Err(ProgramError::UninitializedAccount);
+    }
+
+    if user_conversation_account_info.owner != program_id {
+        msg!("Error: UserConversation account does not have the correct program id");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    // Legacy accounts have no room for the flags
+    if user_conversation_account_info.data.borrow().len() == UserConversation::LEGACY_SIZE {
+        msg!("Error: UserConversation account was created before per-user flags were supported");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let mut user_conversation = UserConversation::unpack(&user_conversation_account_info.data.borrow())?;
+    user_conversation.archived = archived;
+    user_conversation.muted_until = muted_until;
+    user_conversation.folder_id = folder_id;
+    user_conversation.pack(&mut user_conversation_account_info.data.borrow_mut())?;
+
     Ok(())
 }
 
+fn hide_conversation(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    conversation_index: u64,
+    hidden: bool,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let user_wallet_info = next_account_info(account_info_iter)?;
+    let user_account_info = next_account_info(account_info_iter)?;
+    let other_user_account_info = next_account_info(account_info_iter)?;
+    let conversation_account_info = next_account_info(account_info_iter)?;
+    let user_conversation_account_info = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+
+    let rent = &Rent::from_account_info(rent_info)?;
+
+    // Check user wallet signature
+    if !user_wallet_info.is_signer {
+        msg!("Error: User wallet signature missing");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Check if user is a participant of the conversation
+    check_conversation_participant(
+        program_id,
+        user_wallet_info,
+        user_account_info,
+        other_user_account_info,
+        conversation_account_info,
+    )?;
+
+    // Check seed derivations for user-conversation account
+    if !is_user_conversation_address(
+        program_id,
+        user_conversation_account_info,
+        user_account_info.key,
+        conversation_index,
+    )? {
+        msg!("Error: UserConversation address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    if user_conversation_account_info.data.borrow().len() == 0 || user_conversation_account_info.owner != program_id {
+        msg!("Error: UserConversation account is not initialized");
+        return Err(ProgramError::UninitializedAccount);
+    }
+
+    // Check if user-conversation account points to the conversation
+    let mut user_conversation = UserConversation::unpack(&user_conversation_account_info.data.borrow())?;
+    if user_conversation.conversation_address != *conversation_account_info.key {
+        msg!("Error: UserConversation account does not belong to the conversation");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // Legacy accounts have no room for the flag - they are closed and cannot be shown again
+    if user_conversation_account_info.data.borrow().len() == UserConversation::LEGACY_SIZE {
+        if hidden {
+            close_pda_account(user_conversation_account_info, user_wallet_info)?;
+        }
+
+        return Ok(());
+    }
+
+    // The account is kept as a tombstone, so the conversation can be shown again
+    user_conversation.hidden = hidden;
+    user_conversation.pack(&mut user_conversation_account_info.data.borrow_mut())?;
+
+    refund_excess_lamports(user_conversation_account_info, user_wallet_info, rent)
+}
+
 fn create_message_account(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
-    conversation_index: u32,
+    conversation_index: u64,
     message_type: u8,
     content: Vec<u8>,
 ) -> ProgramResult {
@@ -310,6 +793,7 @@ fn create_message_account(
     let rent_info = next_account_info(account_info_iter)?;
     let clock_info = next_account_info(account_info_iter)?;
     let system_program_info = next_account_info(account_info_iter)?;
+    let padding_policy_account_info = next_account_info(account_info_iter)?;
 
     let rent = &Rent::from_account_info(rent_info)?;
 
@@ -338,18 +822,19 @@ fn create_message_account(
     }
 
     // Check seed derivations for user conversation account
-    if *sender_user_conversation_account_info.key != UserConversation::find_pda_address(
+    if !is_user_conversation_address(
+        program_id,
+        sender_user_conversation_account_info,
         sender_user_account_info.key,
         conversation_index,
-        program_id,
-    ) {
+    )? {
         msg!("Error: UserConversation address does not match seed derivation");
         return Err(ProgramError::InvalidSeeds);
     }
 
     // Check if user has access to conversation
     // -> Find UserConversation PDA address
-    let user_conversation = UserConversation::try_from_slice(&sender_user_conversation_account_info.data.borrow())?;
+    let user_conversation = UserConversation::unpack(&sender_user_conversation_account_info.data.borrow())?;
     if *conversation_account_info.key != user_conversation.conversation_address {
         msg!("Error: Sender is not connected with this conversation.");
         return Err(ProgramError::IllegalOwner);
@@ -368,13 +853,20 @@ fn create_message_account(
         return Err(ProgramError::InvalidAccountData);
     }
 
+    // Check content against the padding policy of the conversation
+    check_padding_policy(
+        program_id,
+        conversation_account_info,
+        padding_policy_account_info,
+        content.len(),
+    )?;
+
     // Get conversation message counter
-    let mut conversation: Conversation = Conversation::try_from_slice(&conversation_account_info.data.borrow())?;
-    let message_counter = conversation.message_counter;
+    let message_counter = ConversationView::new(&conversation_account_info.data.borrow()[..])?.message_counter();
 
     // Check seed derivations for message account
-    let (message_pda_address, message_bump_seed) = Message::find_pda_address_with_bump_seed(
-        &conversation_account_info.key,
+    let (message_pda_address, message_bump_seed) = Message::find_pda_address_v2_with_bump_seed(
+        conversation_account_info.key,
         message_counter,
         program_id,
     );
@@ -385,17 +877,17 @@ fn create_message_account(
     }
 
     // Create message PDA account
-    let account_seed = message_counter.to_string() + Message::ACCOUNT_ADDRESS_SEED;
-
+    let message_index_bytes = message_counter.to_le_bytes();
     let message_account_signer_seeds: &[&[_]] = &[
         &conversation_account_info.key.to_bytes(),
-        &account_seed.as_bytes(),
+        &message_index_bytes,
+        Message::ACCOUNT_ADDRESS_SEED_V2.as_bytes(),
         &[message_bump_seed],
     ];
 
     create_pda_account(
         funder_info,
-        &rent,
+        rent,
         false,
         Message::retrieve_size(content.len()),
         program_id,
@@ -405,16 +897,17 @@ fn create_message_account(
     )?;
 
     // Assign data to message
-    let mut message = Message::new(content.len());
-    message.sender = *sender_info.key;
-    message.message_type = message_type;
-    message.content = content;
-    message.timestamp = Clock::from_account_info(clock_info)?.unix_timestamp;
-    message.serialize(&mut &mut message_account_info.data.borrow_mut()[..])?;
+    let mut message_data = message_account_info.data.borrow_mut();
+    let mut message = MessageView::new(&mut message_data[..])?;
+    message.set_sender(sender_info.key);
+    message.set_message_type(message_type);
+    message.set_content(&content)?;
+    message.set_timestamp(Clock::from_account_info(clock_info)?.unix_timestamp);
+    message.set_message_id(Message::NO_MESSAGE_ID)?;
 
     // Increment and store the number of messages the conversation account has
-    conversation.message_counter += 1;
-    conversation.serialize(&mut &mut conversation_account_info.data.borrow_mut()[..])?;
+    let next_message_counter = message_counter.checked_add(1).ok_or(InstantMessagingError::CounterOverflow)?;
+    ConversationView::new(&mut conversation_account_info.data.borrow_mut()[..])?.set_message_counter(next_message_counter)?;
 
     Ok(())
 }
@@ -480,11 +973,12 @@ fn create_conversation_encryption_info_account(
     }
 
     // Check seed derivations for conversation account
-    if *conversation_account_info.key != Conversation::find_pda_address(
+    if *conversation_account_info.key != conversation_pda_address(
+        program_id,
+        conversation_account_info,
         sender_user_account_info.key,
         receiver_user_account_info.key,
-        program_id,
-    ) {
+    )? {
         msg!("Error: Conversation address does not match seed derivation");
         return Err(ProgramError::InvalidSeeds);
     }
@@ -504,13 +998,13 @@ fn create_conversation_encryption_info_account(
     // Create conversation-encryption-info PDA account
     let encryption_account_signer_seeds: &[&[_]] = &[
         &conversation_account_info.key.to_bytes(),
-        &ConversationEncryptionInfo::ACCOUNT_ADDRESS_SEED.as_bytes(),
+        ConversationEncryptionInfo::ACCOUNT_ADDRESS_SEED.as_bytes(),
         &[conversation_encryption_info_bump_seed],
     ];
 
     create_pda_account(
         funder_info,
-        &rent,
+        rent,
         true,
         ConversationEncryptionInfo::retrieve_size(data.len()),
         program_id,
@@ -532,6 +1026,8 @@ fn send_message(
     accounts: &[AccountInfo],
     message_type: u8,
     content: Vec<u8>,
+    expected_index: u64,
+    message_id: [u8; 16],
 ) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
 
@@ -541,9 +1037,11 @@ fn send_message(
     let receiver_user_account_info = next_account_info(account_info_iter)?;
     let conversation_account_info = next_account_info(account_info_iter)?;
     let message_account_info = next_account_info(account_info_iter)?;
+    let message_id_account_info = next_account_info(account_info_iter)?;
     let rent_info = next_account_info(account_info_iter)?;
     let clock_info = next_account_info(account_info_iter)?;
     let system_program_info = next_account_info(account_info_iter)?;
+    let padding_policy_account_info = next_account_info(account_info_iter)?;
 
     let rent = &Rent::from_account_info(rent_info)?;
 
@@ -590,11 +1088,12 @@ fn send_message(
     }
 
     // Check seed derivations for conversation account
-    if *conversation_account_info.key != Conversation::find_pda_address(
+    if *conversation_account_info.key != conversation_pda_address(
+        program_id,
+        conversation_account_info,
         sender_user_account_info.key,
         receiver_user_account_info.key,
-        program_id,
-    ) {
+    )? {
         msg!("Error: Conversation address does not match seed derivation");
         return Err(ProgramError::InvalidSeeds);
     }
@@ -613,34 +1112,321 @@ fn send_message(
 
     // TODO: ?
 
-    // Get conversation message counter
-    let mut conversation: Conversation = Conversation::try_from_slice(&conversation_account_info.data.borrow())?;
-    let message_counter = conversation.message_counter;
+    // Check if the sender already sent the message - a retried send is a no-op
+    let message_id_bump_seed = if message_id == Message::NO_MESSAGE_ID {
+        None
+    } else {
+        let (message_id_pda_address, message_id_bump_seed) = MessageIdRecord::find_pda_address_with_bump_seed(
+            conversation_account_info.key,
+            sender_info.key,
+            &message_id,
+            program_id,
+        );
 
-    // Check seed derivations for message account
-    let (message_pda_address, message_bump_seed) = Message::find_pda_address_with_bump_seed(
-        &conversation_account_info.key,
-        message_counter,
-        program_id,
-    );
+        if message_id_pda_address != *message_id_account_info.key {
+            msg!("Error: Message-id address does not match seed derivation");
+            return Err(ProgramError::InvalidSeeds);
+        }
 
-    if message_pda_address != *message_account_info.key {
-        msg!("Error: Message address does not match seed derivation");
-        return Err(ProgramError::InvalidSeeds);
-    }
+        if message_id_account_info.data.borrow().len() != 0 && message_id_account_info.owner == program_id {
+            msg!("Message was already sent to the conversation by the sender");
+            return Ok(());
+        }
+
+        Some(message_id_bump_seed)
+    };
+
+    // Check content against the padding policy of the conversation
+    check_padding_policy(
+        program_id,
+        conversation_account_info,
+        padding_policy_account_info,
+        content.len(),
+    )?;
+
+    // Get conversation message counter
+    let message_counter = ConversationView::new(&conversation_account_info.data.borrow()[..])?.message_counter();
+
+    // Check if another message landed first - the current counter is logged for the client to retry with
+    if expected_index != message_counter {
+        msg!("Error: Message index conflict, expected {} but current message counter is {}", expected_index, message_counter);
+        return Err(InstantMessagingError::MessageIndexConflict.into());
+    }
+
+    // Check seed derivations for message account
+    let (message_pda_address, message_bump_seed) = Message::find_pda_address_v2_with_bump_seed(
+        conversation_account_info.key,
+        message_counter,
+        program_id,
+    );
+
+    if message_pda_address != *message_account_info.key {
+        msg!("Error: Message address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
 
     // Create message PDA account
-    let account_seed = message_counter.to_string() + Message::ACCOUNT_ADDRESS_SEED;
+    let message_index_bytes = message_counter.to_le_bytes();
+    let message_account_signer_seeds: &[&[_]] = &[
+        &conversation_account_info.key.to_bytes(),
+        &message_index_bytes,
+        Message::ACCOUNT_ADDRESS_SEED_V2.as_bytes(),
+        &[message_bump_seed],
+    ];
+
+    create_pda_account(
+        funder_info,
+        rent,
+        false,
+        Message::retrieve_size(content.len()),
+        program_id,
+        system_program_info,
+        message_account_info,
+        message_account_signer_seeds,
+    )?;
+
+    // Assign data to message
+    let mut message_data = message_account_info.data.borrow_mut();
+    let mut message = MessageView::new(&mut message_data[..])?;
+    message.set_sender(sender_info.key);
+    message.set_message_type(message_type);
+    message.set_content(&content)?;
+    message.set_timestamp(Clock::from_account_info(clock_info)?.unix_timestamp);
+    message.set_message_id(message_id)?;
+
+    // Record the message ID for retried sends
+    if let Some(message_id_bump_seed) = message_id_bump_seed {
+        let message_id_account_signer_seeds: &[&[_]] = &[
+            &conversation_account_info.key.to_bytes(),
+            &sender_info.key.to_bytes(),
+            &message_id,
+            MessageIdRecord::ACCOUNT_ADDRESS_SEED.as_bytes(),
+            &[message_id_bump_seed],
+        ];
+
+        create_pda_account(
+            funder_info,
+            rent,
+            false,
+            MessageIdRecord::retrieve_size(),
+            program_id,
+            system_program_info,
+            message_id_account_info,
+            message_id_account_signer_seeds,
+        )?;
+
+        let record = MessageIdRecord {
+            message_index: message_counter,
+        };
+        record.serialize(&mut &mut message_id_account_info.data.borrow_mut()[..])?;
+    }
+
+    // Increment and store the number of messages the conversation account has
+    let next_message_counter = message_counter.checked_add(1).ok_or(InstantMessagingError::CounterOverflow)?;
+    ConversationView::new(&mut conversation_account_info.data.borrow_mut()[..])?.set_message_counter(next_message_counter)?;
+
+    Ok(())
+}
+
+fn start_conversation(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    message_type: u8,
+    content: Vec<u8>,
+    expected_index: u64,
+    message_id: [u8; 16],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let funder_info = next_account_info(account_info_iter)?;
+    let sender_info = next_account_info(account_info_iter)?;
+    let receiver_info = next_account_info(account_info_iter)?;
+    let sender_user_account_info = next_account_info(account_info_iter)?;
+    let receiver_user_account_info = next_account_info(account_info_iter)?;
+    let conversation_account_info = next_account_info(account_info_iter)?;
+    let sender_user_conversation_account_info = next_account_info(account_info_iter)?;
+    let receiver_user_conversation_account_info = next_account_info(account_info_iter)?;
+    let message_account_info = next_account_info(account_info_iter)?;
+    let message_id_account_info = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let clock_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    let padding_policy_account_info = next_account_info(account_info_iter)?;
+    let sender_user_upgrade_account_info = next_account_info(account_info_iter)?;
+    let receiver_user_upgrade_account_info = next_account_info(account_info_iter)?;
+    let conversation_upgrade_account_info = next_account_info(account_info_iter)?;
+
+    // Check sender signature
+    if !sender_info.is_signer {
+        msg!("Error: Sender signature missing");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Create user PDA accounts if not exist
+    for (user_account_info, user_wallet_info, upgrade_account_info) in [
+        (sender_user_account_info, sender_info, sender_user_upgrade_account_info),
+        (receiver_user_account_info, receiver_info, receiver_user_upgrade_account_info),
+    ].iter() {
+        if user_account_info.data.borrow().len() == 0 {
+            create_user_account(
+                program_id,
+                &[
+                    funder_info.clone(),
+                    (*user_account_info).clone(),
+                    (*user_wallet_info).clone(),
+                    rent_info.clone(),
+                    system_program_info.clone(),
+                    (*upgrade_account_info).clone(),
+                ],
+            )?;
+        }
+    }
+
+    // Create conversation PDA account with both user-conversation accounts if not exist
+    if conversation_account_info.data.borrow().len() == 0 {
+        create_conversation_account(
+            program_id,
+            &[
+                funder_info.clone(),
+                conversation_account_info.clone(),
+                sender_user_account_info.clone(),
+                receiver_user_account_info.clone(),
+                sender_user_conversation_account_info.clone(),
+                receiver_user_conversation_account_info.clone(),
+                rent_info.clone(),
+                system_program_info.clone(),
+                conversation_upgrade_account_info.clone(),
+            ],
+        )?;
+    }
+
+    send_message(
+        program_id,
+        &[
+            funder_info.clone(),
+            sender_info.clone(),
+            sender_user_account_info.clone(),
+            receiver_user_account_info.clone(),
+            conversation_account_info.clone(),
+            message_account_info.clone(),
+            message_id_account_info.clone(),
+            rent_info.clone(),
+            clock_info.clone(),
+            system_program_info.clone(),
+            padding_policy_account_info.clone(),
+        ],
+        message_type,
+        content,
+        expected_index,
+        message_id,
+    )
+}
+
+fn send_sequenced_message(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    message_type: u8,
+    content: Vec<u8>,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let funder_info = next_account_info(account_info_iter)?;
+    let sender_info = next_account_info(account_info_iter)?;
+    let sender_user_account_info = next_account_info(account_info_iter)?;
+    let receiver_user_account_info = next_account_info(account_info_iter)?;
+    let conversation_account_info = next_account_info(account_info_iter)?;
+    let sender_sequence_account_info = next_account_info(account_info_iter)?;
+    let message_account_info = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let clock_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    let padding_policy_account_info = next_account_info(account_info_iter)?;
+
+    let rent = &Rent::from_account_info(rent_info)?;
+
+    // Check sender signature
+    if !sender_info.is_signer {
+        msg!("Error: Sender signature missing");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    check_conversation_participant(
+        program_id,
+        sender_info,
+        sender_user_account_info,
+        receiver_user_account_info,
+        conversation_account_info,
+    )?;
+
+    // Check content against the padding policy of the conversation
+    check_padding_policy(
+        program_id,
+        conversation_account_info,
+        padding_policy_account_info,
+        content.len(),
+    )?;
+
+    // Check seed derivations for sender-sequence account
+    let (sender_sequence_address, sender_sequence_bump_seed) = SenderSequence::find_pda_address_with_bump_seed(
+        conversation_account_info.key,
+        sender_info.key,
+        program_id,
+    );
+
+    if sender_sequence_address != *sender_sequence_account_info.key {
+        msg!("Error: SenderSequence address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
 
+    // Create sender-sequence PDA account on the first message of the sender
+    if sender_sequence_account_info.data.borrow().len() == 0 {
+        let sender_sequence_account_signer_seeds: &[&[_]] = &[
+            &conversation_account_info.key.to_bytes(),
+            &sender_info.key.to_bytes(),
+            SenderSequence::ACCOUNT_ADDRESS_SEED.as_bytes(),
+            &[sender_sequence_bump_seed],
+        ];
+
+        create_pda_account(
+            funder_info,
+            rent,
+            true,
+            SenderSequence::retrieve_size(),
+            program_id,
+            system_program_info,
+            sender_sequence_account_info,
+            sender_sequence_account_signer_seeds,
+        )?;
+    }
+
+    let mut sender_sequence = SenderSequence::try_from_slice(&sender_sequence_account_info.data.borrow())?;
+    let sequence = sender_sequence.message_counter;
+
+    // Check seed derivations for message account
+    let (message_pda_address, message_bump_seed) = Message::find_sequenced_pda_address_with_bump_seed(
+        conversation_account_info.key,
+        sender_info.key,
+        sequence,
+        program_id,
+    );
+
+    if message_pda_address != *message_account_info.key {
+        msg!("Error: Message address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    // Create message PDA account
     let message_account_signer_seeds: &[&[_]] = &[
         &conversation_account_info.key.to_bytes(),
-        &account_seed.as_bytes(),
+        &sender_info.key.to_bytes(),
+        &sequence.to_le_bytes(),
+        Message::SEQUENCED_ACCOUNT_ADDRESS_SEED.as_bytes(),
         &[message_bump_seed],
     ];
 
     create_pda_account(
         funder_info,
-        &rent,
+        rent,
         false,
         Message::retrieve_size(content.len()),
         program_id,
@@ -650,16 +1436,4053 @@ fn send_message(
     )?;
 
     // Assign data to message
-    let mut message = Message::new(content.len());
-    message.sender = *sender_info.key;
-    message.message_type = message_type;
-    message.content = content;
-    message.timestamp = Clock::from_account_info(clock_info)?.unix_timestamp;
+    let message = Message {
+        sender: *sender_info.key,
+        message_type,
+        content,
+        timestamp: Clock::from_account_info(clock_info)?.unix_timestamp,
+        message_id: Message::NO_MESSAGE_ID,
+    };
     message.serialize(&mut &mut message_account_info.data.borrow_mut()[..])?;
 
-    // Increment and store the number of messages the conversation account has
-    conversation.message_counter += 1;
-    conversation.serialize(&mut &mut conversation_account_info.data.borrow_mut()[..])?;
+    // Increment and store the number of messages the sender has sent
+    sender_sequence.message_counter = sender_sequence.message_counter.checked_add(1).ok_or(InstantMessagingError::CounterOverflow)?;
+    sender_sequence.serialize(&mut &mut sender_sequence_account_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+fn send_messages(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    messages: Vec<(u8, Vec<u8>, [u8; 16])>,
+    expected_index: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let funder_info = next_account_info(account_info_iter)?;
+    let sender_info = next_account_info(account_info_iter)?;
+    let sender_user_account_info = next_account_info(account_info_iter)?;
+    let receiver_user_account_info = next_account_info(account_info_iter)?;
+    let conversation_account_info = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let clock_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    let padding_policy_account_info = next_account_info(account_info_iter)?;
+    let remaining_account_infos = account_info_iter.as_slice();
+
+    let rent = &Rent::from_account_info(rent_info)?;
+    let timestamp = Clock::from_account_info(clock_info)?.unix_timestamp;
+
+    // Check sender signature
+    if !sender_info.is_signer {
+        msg!("Error: Sender signature missing");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Check if there is a message account and a message-id account for each message
+    if messages.is_empty() || 2 * messages.len() != remaining_account_infos.len() {
+        msg!("Error: Expected a message account and a message-id account for each of the {} messages", messages.len());
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let (message_account_infos, message_id_account_infos) = remaining_account_infos.split_at(messages.len());
+
+    check_conversation_participant(
+        program_id,
+        sender_info,
+        sender_user_account_info,
+        receiver_user_account_info,
+        conversation_account_info,
+    )?;
+
+    // Check content against the padding policy of the conversation
+    for (_, content, _) in messages.iter() {
+        check_padding_policy(
+            program_id,
+            conversation_account_info,
+            padding_policy_account_info,
+            content.len(),
+        )?;
+    }
+
+    // Get conversation message counter
+    let message_counter = ConversationView::new(&conversation_account_info.data.borrow()[..])?.message_counter();
+
+    // Check if another message landed first - the current counter is logged for the client to retry with
+    if expected_index != message_counter {
+        msg!("Error: Message index conflict, expected {} but current message counter is {}", expected_index, message_counter);
+        return Err(InstantMessagingError::MessageIndexConflict.into());
+    }
+
+    // Messages are stored at consecutive indexes - skipped messages do not take a message account
+    let mut message_account_info_iter = message_account_infos.iter();
+    let mut next_message_counter = message_counter;
+
+    for ((message_type, content, message_id), message_id_account_info) in messages.into_iter().zip(message_id_account_infos.iter()) {
+        // Check if the sender already sent the message - the message is skipped
+        let message_id_bump_seed = if message_id == Message::NO_MESSAGE_ID {
+            None
+        } else {
+            let (message_id_pda_address, message_id_bump_seed) = MessageIdRecord::find_pda_address_with_bump_seed(
+                conversation_account_info.key,
+                sender_info.key,
+                &message_id,
+                program_id,
+            );
+
+            if message_id_pda_address != *message_id_account_info.key {
+                msg!("Error: Message-id address does not match seed derivation");
+                return Err(ProgramError::InvalidSeeds);
+            }
+
+            if message_id_account_info.data.borrow().len() != 0 && message_id_account_info.owner == program_id {
+                msg!("Message was already sent to the conversation by the sender");
+                continue;
+            }
+
+            Some(message_id_bump_seed)
+        };
+
+        let message_index = next_message_counter;
+        let message_account_info = next_account_info(&mut message_account_info_iter)?;
+
+        // Check seed derivations for message account
+        let (message_pda_address, message_bump_seed) = Message::find_pda_address_v2_with_bump_seed(
+            conversation_account_info.key,
+            message_index,
+            program_id,
+        );
+
+        if message_pda_address != *message_account_info.key {
+            msg!("Error: Message address does not match seed derivation");
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        // Create message PDA account
+        let message_index_bytes = message_index.to_le_bytes();
+        let message_account_signer_seeds: &[&[_]] = &[
+            &conversation_account_info.key.to_bytes(),
+            &message_index_bytes,
+            Message::ACCOUNT_ADDRESS_SEED_V2.as_bytes(),
+            &[message_bump_seed],
+        ];
+
+        create_pda_account(
+            funder_info,
+            rent,
+            false,
+            Message::retrieve_size(content.len()),
+            program_id,
+            system_program_info,
+            message_account_info,
+            message_account_signer_seeds,
+        )?;
+
+        // Assign data to message
+        let message = Message {
+            sender: *sender_info.key,
+            message_type,
+            content,
+            timestamp,
+            message_id,
+        };
+        message.serialize(&mut &mut message_account_info.data.borrow_mut()[..])?;
+
+        // Record the message ID for retried sends
+        if let Some(message_id_bump_seed) = message_id_bump_seed {
+            let message_id_account_signer_seeds: &[&[_]] = &[
+                &conversation_account_info.key.to_bytes(),
+                &sender_info.key.to_bytes(),
+                &message_id,
+                MessageIdRecord::ACCOUNT_ADDRESS_SEED.as_bytes(),
+                &[message_id_bump_seed],
+            ];
+
+            create_pda_account(
+                funder_info,
+                rent,
+                false,
+                MessageIdRecord::retrieve_size(),
+                program_id,
+                system_program_info,
+                message_id_account_info,
+                message_id_account_signer_seeds,
+            )?;
+
+            let record = MessageIdRecord {
+                message_index,
+            };
+            record.serialize(&mut &mut message_id_account_info.data.borrow_mut()[..])?;
+        }
+
+        next_message_counter = message_index.checked_add(1).ok_or(InstantMessagingError::CounterOverflow)?;
+    }
+
+    // Store the number of messages the conversation account has - once for the whole batch
+    ConversationView::new(&mut conversation_account_info.data.borrow_mut()[..])?.set_message_counter(next_message_counter)?;
+
+    Ok(())
+}
+
+fn send_paged_message(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    message_type: u8,
+    content: Vec<u8>,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let funder_info = next_account_info(account_info_iter)?;
+    let sender_info = next_account_info(account_info_iter)?;
+    let sender_user_account_info = next_account_info(account_info_iter)?;
+    let receiver_user_account_info = next_account_info(account_info_iter)?;
+    let conversation_account_info = next_account_info(account_info_iter)?;
+    let paged_conversation_account_info = next_account_info(account_info_iter)?;
+    let last_page_account_info = next_account_info(account_info_iter)?;
+    let next_page_account_info = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let clock_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    let padding_policy_account_info = next_account_info(account_info_iter)?;
+
+    let rent = &Rent::from_account_info(rent_info)?;
+
+    // Check sender signature
+    if !sender_info.is_signer {
+        msg!("Error: Sender signature missing");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Check if the message fits into an empty page
+    if !MessagePage::default().fits(content.len()) {
+        msg!("Error: Message does not fit into a message page");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    check_conversation_participant(
+        program_id,
+        sender_info,
+        sender_user_account_info,
+        receiver_user_account_info,
+        conversation_account_info,
+    )?;
+
+    // Check content against the padding policy of the conversation
+    check_padding_policy(
+        program_id,
+        conversation_account_info,
+        padding_policy_account_info,
+        content.len(),
+    )?;
+
+    // Check seed derivations for paged-conversation account
+    let (paged_conversation_address, paged_conversation_bump_seed) = PagedConversation::find_pda_address_with_bump_seed(
+        conversation_account_info.key,
+        program_id,
+    );
+
+    if paged_conversation_address != *paged_conversation_account_info.key {
+        msg!("Error: PagedConversation address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    // Create paged-conversation PDA account on the first paged message of the conversation
+    if paged_conversation_account_info.data.borrow().len() == 0 {
+        let paged_conversation_account_signer_seeds: &[&[_]] = &[
+            &conversation_account_info.key.to_bytes(),
+            PagedConversation::ACCOUNT_ADDRESS_SEED.as_bytes(),
+            &[paged_conversation_bump_seed],
+        ];
+
+        create_pda_account(
+            funder_info,
+            rent,
+            true,
+            PagedConversation::retrieve_size(),
+            program_id,
+            system_program_info,
+            paged_conversation_account_info,
+            paged_conversation_account_signer_seeds,
+        )?;
+    }
+
+    let mut paged_conversation = PagedConversation::try_from_slice(&paged_conversation_account_info.data.borrow())?;
+
+    let timestamp = Clock::from_account_info(clock_info)?.unix_timestamp;
+
+    // Append message to the last page if it fits
+    if paged_conversation.page_counter > 0 {
+        if *last_page_account_info.key != MessagePage::find_pda_address(
+            conversation_account_info.key,
+            paged_conversation.page_counter - 1,
+            program_id,
+        ) {
+            msg!("Error: MessagePage address does not match seed derivation");
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        let mut last_page_data = last_page_account_info.data.borrow_mut();
+        let mut last_page = MessagePageView::new(&mut last_page_data[..])?;
+        let last_page_size = paged_conversation.last_page_size as usize;
+
+        if last_page.fits(last_page_size, content.len()) {
+            let used_size = last_page.append_message(last_page_size, sender_info.key, message_type, &content, timestamp)?;
+
+            paged_conversation.last_page_size = used_size as u32;
+            paged_conversation.message_counter = paged_conversation.message_counter.checked_add(1).ok_or(InstantMessagingError::CounterOverflow)?;
+            paged_conversation.serialize(&mut &mut paged_conversation_account_info.data.borrow_mut()[..])?;
+
+            return Ok(());
+        }
+    }
+
+    // Roll over to a new page
+    let (next_page_address, next_page_bump_seed) = MessagePage::find_pda_address_with_bump_seed(
+        conversation_account_info.key,
+        paged_conversation.page_counter,
+        program_id,
+    );
+
+    if next_page_address != *next_page_account_info.key {
+        msg!("Error: MessagePage address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    let next_page_account_signer_seeds: &[&[_]] = &[
+        &conversation_account_info.key.to_bytes(),
+        &paged_conversation.page_counter.to_le_bytes(),
+        MessagePage::ACCOUNT_ADDRESS_SEED.as_bytes(),
+        &[next_page_bump_seed],
+    ];
+
+    // Pages stay readable for good - the page is rent-exempt, unlike single message accounts
+    create_pda_account(
+        funder_info,
+        rent,
+        true,
+        MessagePage::retrieve_size(),
+        program_id,
+        system_program_info,
+        next_page_account_info,
+        next_page_account_signer_seeds,
+    )?;
+
+    let used_size = MessagePageView::new(&mut next_page_account_info.data.borrow_mut()[..])?
+        .append_message(MessagePage::EMPTY_SIZE, sender_info.key, message_type, &content, timestamp)?;
+
+    // Increment and store the page and message counters
+    paged_conversation.page_counter = paged_conversation.page_counter.checked_add(1).ok_or(InstantMessagingError::CounterOverflow)?;
+    paged_conversation.message_counter = paged_conversation.message_counter.checked_add(1).ok_or(InstantMessagingError::CounterOverflow)?;
+    paged_conversation.last_page_size = used_size as u32;
+    paged_conversation.serialize(&mut &mut paged_conversation_account_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+fn migrate_user_conversation_account(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    conversation_index: u32,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let funder_info = next_account_info(account_info_iter)?;
+    let user_wallet_account_info = next_account_info(account_info_iter)?;
+    let user_account_info = next_account_info(account_info_iter)?;
+    let v1_user_conversation_account_info = next_account_info(account_info_iter)?;
+    let v2_user_conversation_account_info = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    let rent = &Rent::from_account_info(rent_info)?;
+
+    // Check user signature
+    if !user_wallet_account_info.is_signer {
+        msg!("Error: User signature missing");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Check seed derivations for the user account
+    if *user_account_info.key != user_pda_address(program_id, user_account_info, user_wallet_account_info.key)? {
+        msg!("Error: User address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    // Check seed derivations for the v1 user-conversation account
+    if *v1_user_conversation_account_info.key != UserConversation::find_pda_address(
+        user_account_info.key,
+        conversation_index,
+        program_id,
+    ) {
+        msg!("Error: UserConversation address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    if is_closed_account_data(&v1_user_conversation_account_info.data.borrow()) || v1_user_conversation_account_info.owner != program_id {
+        msg!("Error: UserConversation account is not initialized");
+        return Err(ProgramError::UninitializedAccount);
+    }
+
+    // Check seed derivations for the v2 user-conversation account
+    let (v2_user_conversation_address, v2_user_conversation_bump_seed) =
+        UserConversation::find_pda_address_v2_with_bump_seed(
+            user_account_info.key,
+            conversation_index.into(),
+            program_id,
+        );
+
+    if v2_user_conversation_address != *v2_user_conversation_account_info.key {
+        msg!("Error: UserConversation address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    if v2_user_conversation_account_info.data.borrow().len() > 0 {
+        msg!("Error: UserConversation account is already initialized");
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    let conversation_index_bytes = u64::from(conversation_index).to_le_bytes();
+    let user_conversation_account_signer_seeds: &[&[_]] = &[
+        &user_account_info.key.to_bytes(),
+        &conversation_index_bytes,
+        UserConversation::ACCOUNT_ADDRESS_SEED_V2.as_bytes(),
+        &[v2_user_conversation_bump_seed],
+    ];
+
+    create_pda_account(
+        funder_info,
+        rent,
+        true,
+        UserConversation::retrieve_size(),
+        program_id,
+        system_program_info,
+        v2_user_conversation_account_info,
+        user_conversation_account_signer_seeds,
+    )?;
+
+    // Legacy layouts are upgraded on the way
+    let mut user_conversation = UserConversation::unpack(&v1_user_conversation_account_info.data.borrow())?;
+    user_conversation.bump_seed = v2_user_conversation_bump_seed;
+    user_conversation.serialize(&mut &mut v2_user_conversation_account_info.data.borrow_mut()[..])?;
+
+    close_pda_account(v1_user_conversation_account_info, user_wallet_account_info)
+}
+
+fn migrate_message_account(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    message_index: u32,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let funder_info = next_account_info(account_info_iter)?;
+    let participant_info = next_account_info(account_info_iter)?;
+    let conversation_account_info = next_account_info(account_info_iter)?;
+    let v1_message_account_info = next_account_info(account_info_iter)?;
+    let v2_message_account_info = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    let sender_info = next_account_info(account_info_iter)?;
+    let participant_user_account_info = next_account_info(account_info_iter)?;
+    let sender_user_account_info = next_account_info(account_info_iter)?;
+
+    let rent = &Rent::from_account_info(rent_info)?;
+
+    // Check participant signature
+    if !participant_info.is_signer {
+        msg!("Error: Participant signature missing");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Check seed derivations for the v1 message account
+    if *v1_message_account_info.key != Message::find_pda_address(
+        conversation_account_info.key,
+        message_index,
+        program_id,
+    ) {
+        msg!("Error: Message address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    if is_closed_account_data(&v1_message_account_info.data.borrow()) || v1_message_account_info.owner != program_id {
+        msg!("Error: Message account is not initialized");
+        return Err(ProgramError::UninitializedAccount);
+    }
+
+    // Check seed derivations for the v2 message account
+    let (v2_message_address, v2_message_bump_seed) = Message::find_pda_address_v2_with_bump_seed(
+        conversation_account_info.key,
+        message_index.into(),
+        program_id,
+    );
+
+    if v2_message_address != *v2_message_account_info.key {
+        msg!("Error: Message address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    if v2_message_account_info.data.borrow().len() > 0 {
+        msg!("Error: Message account is already initialized");
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    let message = Message::unpack(&v1_message_account_info.data.borrow())?;
+
+    // Lamports of the v1 account go back to the sender of the message
+    if message.sender != *sender_info.key {
+        msg!("Error: Sender account does not match the sender of the message");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // Check if the participant is the sender or the other participant of the conversation - the other
+    // participant cannot be told apart for group and blinded conversations, only their senders migrate there
+    if *participant_info.key != message.sender {
+        check_conversation_participant(
+            program_id,
+            participant_info,
+            participant_user_account_info,
+            sender_user_account_info,
+            conversation_account_info,
+        )?;
+    }
+
+    let message_index_bytes = u64::from(message_index).to_le_bytes();
+    let message_account_signer_seeds: &[&[_]] = &[
+        &conversation_account_info.key.to_bytes(),
+        &message_index_bytes,
+        Message::ACCOUNT_ADDRESS_SEED_V2.as_bytes(),
+        &[v2_message_bump_seed],
+    ];
+
+    create_pda_account(
+        funder_info,
+        rent,
+        false,
+        Message::retrieve_size(message.content.len()),
+        program_id,
+        system_program_info,
+        v2_message_account_info,
+        message_account_signer_seeds,
+    )?;
+
+    // Legacy messages get the current layout without a message ID
+    message.serialize(&mut &mut v2_message_account_info.data.borrow_mut()[..])?;
+
+    close_pda_account(v1_message_account_info, sender_info)
+}
+
+fn upgrade_user_account(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let funder_info = next_account_info(account_info_iter)?;
+    let user_wallet_account_info = next_account_info(account_info_iter)?;
+    let user_account_info = next_account_info(account_info_iter)?;
+    let upgrade_account_info = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    let rent = &Rent::from_account_info(rent_info)?;
+
+    // Check user signature
+    if !user_wallet_account_info.is_signer {
+        msg!("Error: User signature missing");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Check seed derivations for the user account
+    let (user_address, user_bump_seed) = User::find_pda_address_with_bump_seed(
+        user_wallet_account_info.key,
+        program_id,
+    );
+
+    if user_address != *user_account_info.key {
+        msg!("Error: User address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    let user_account_signer_seeds: &[&[_]] = &[
+        &user_wallet_account_info.key.to_bytes(),
+        User::ACCOUNT_ADDRESS_SEED.as_bytes(),
+        &[user_bump_seed],
+    ];
+
+    upgrade_account(
+        program_id,
+        funder_info,
+        user_account_info,
+        upgrade_account_info,
+        rent,
+        system_program_info,
+        User::retrieve_size(),
+        user_bump_seed,
+        user_account_signer_seeds,
+    )
+}
+
+fn upgrade_conversation_account(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let funder_info = next_account_info(account_info_iter)?;
+    let participant_info = next_account_info(account_info_iter)?;
+    let participant_user_account_info = next_account_info(account_info_iter)?;
+    let other_participant_user_account_info = next_account_info(account_info_iter)?;
+    let conversation_account_info = next_account_info(account_info_iter)?;
+    let upgrade_account_info = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    let rent = &Rent::from_account_info(rent_info)?;
+
+    // Check participant signature
+    if !participant_info.is_signer {
+        msg!("Error: Participant signature missing");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Check if participant is one of the two users of the conversation
+    if *participant_user_account_info.key != user_pda_address(program_id, participant_user_account_info, participant_info.key)? {
+        msg!("Error: Participant's user address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    // Check seed derivations for the conversation account
+    let (conversation_address, conversation_bump_seed) = Conversation::find_pda_address_with_bump_seed(
+        participant_user_account_info.key,
+        other_participant_user_account_info.key,
+        program_id,
+    );
+
+    if conversation_address != *conversation_account_info.key {
+        msg!("Error: Conversation address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    // Sort first & second address so that they are always in the same order
+    let [address_one, address_two] = sort_addresses_asc(
+        participant_user_account_info.key,
+        other_participant_user_account_info.key,
+    );
+
+    let conversation_account_signer_seeds: &[&[_]] = &[
+        &address_one.to_bytes(),
+        &address_two.to_bytes(),
+        Conversation::ACCOUNT_ADDRESS_SEED.as_bytes(),
+        &[conversation_bump_seed],
+    ];
+
+    upgrade_account(
+        program_id,
+        funder_info,
+        conversation_account_info,
+        upgrade_account_info,
+        rent,
+        system_program_info,
+        Conversation::retrieve_size(),
+        conversation_bump_seed,
+        conversation_account_signer_seeds,
+    )
+}
+
+/// Upgrade a legacy user or conversation account - both accounts start with the counter, followed by the bump seed.
+/// The first call moves the counter to the account-upgrade account and closes the legacy account into it, the second
+/// call recreates the account in the current layout and closes the account-upgrade account. Both calls are funded
+/// by the same funder, who gets the lamports of the closed accounts
+#[allow(clippy::too_many_arguments)]
+fn upgrade_account<'a>(
+    program_id: &Pubkey,
+    funder_info: &AccountInfo<'a>,
+    account_info: &AccountInfo<'a>,
+    upgrade_account_info: &AccountInfo<'a>,
+    rent: &Rent,
+    system_program_info: &AccountInfo<'a>,
+    size: usize,
+    bump_seed: u8,
+    account_signer_seeds: &[&[u8]],
+) -> ProgramResult {
+    // Check seed derivations for the account-upgrade account
+    let (upgrade_address, upgrade_bump_seed) = AccountUpgrade::find_pda_address_with_bump_seed(
+        account_info.key,
+        program_id,
+    );
+
+    if upgrade_address != *upgrade_account_info.key {
+        msg!("Error: AccountUpgrade address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    let account_len = account_info.data.borrow().len();
+
+    // First call - keep the counter and close the legacy account
+    if upgrade_account_info.data.borrow().len() == 0 {
+        if account_len == 0 || account_info.owner != program_id {
+            msg!("Error: Account is not initialized");
+            return Err(ProgramError::UninitializedAccount);
+        }
+
+        if account_len == size {
+            msg!("Error: Account is already upgraded");
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
+        let counter = AccountUpgrade::read_counter(&account_info.data.borrow())?;
+
+        let upgrade_account_signer_seeds: &[&[_]] = &[
+            &account_info.key.to_bytes(),
+            AccountUpgrade::ACCOUNT_ADDRESS_SEED.as_bytes(),
+            &[upgrade_bump_seed],
+        ];
+
+        create_pda_account(
+            funder_info,
+            rent,
+            true,
+            AccountUpgrade::retrieve_size(),
+            program_id,
+            system_program_info,
+            upgrade_account_info,
+            upgrade_account_signer_seeds,
+        )?;
+
+        let upgrade = AccountUpgrade {
+            counter,
+            funder: *funder_info.key,
+        };
+        upgrade.serialize(&mut &mut upgrade_account_info.data.borrow_mut()[..])?;
+
+        // Lamports of the legacy account are held until the upgrade is finished
+        return close_pda_account(account_info, upgrade_account_info);
+    }
+
+    if upgrade_account_info.owner != program_id {
+        msg!("Error: AccountUpgrade account does not have the correct program id");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let upgrade = AccountUpgrade::try_from_slice(&upgrade_account_info.data.borrow())?;
+
+    // Check if the funder started the upgrade
+    if upgrade.funder != *funder_info.key {
+        msg!("Error: Funder does not match the funder of the upgrade");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // Second call - recreate the account once the legacy account is purged. Create paths check the account-upgrade
+    // account, so the account cannot be created by anyone else in the meantime
+    if account_len > 0 {
+        msg!("Error: Legacy account is not closed yet - upgrade it in the next transaction");
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    create_pda_account(
+        funder_info,
+        rent,
+        true,
+        size,
+        program_id,
+        system_program_info,
+        account_info,
+        account_signer_seeds,
+    )?;
+
+    // User and conversation accounts hold the counter followed by the bump seed
+    (upgrade.counter, bump_seed).serialize(&mut &mut account_info.data.borrow_mut()[..])?;
+
+    close_pda_account(upgrade_account_info, funder_info)
+}
+
+/// Check that the user or conversation account is not being upgraded - the account is closed between the two
+/// upgrade calls and must not be created again by the create paths
+fn check_account_not_upgrading(
+    program_id: &Pubkey,
+    account_info: &AccountInfo,
+    upgrade_account_info: &AccountInfo,
+) -> ProgramResult {
+    if *upgrade_account_info.key != AccountUpgrade::find_pda_address(account_info.key, program_id) {
+        msg!("Error: AccountUpgrade address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    if !is_closed_account_data(&upgrade_account_info.data.borrow()) {
+        msg!("Error: Account is being upgraded - finish the upgrade first");
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    Ok(())
+}
+
+fn create_conversation_handshake_account(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    epoch: u64,
+    commit: Vec<u8>,
+    welcome: Vec<u8>,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let funder_info = next_account_info(account_info_iter)?;
+    let sender_info = next_account_info(account_info_iter)?;
+    let handshake_account_info = next_account_info(account_info_iter)?;
+    let previous_handshake_account_info = next_account_info(account_info_iter)?;
+    let sender_user_account_info = next_account_info(account_info_iter)?;
+    let receiver_user_account_info = next_account_info(account_info_iter)?;
+    let conversation_account_info = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let clock_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    // Check sender signature
+    if !sender_info.is_signer {
+        msg!("Error: Sender signature missing");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Check if conversation account exists
+    if conversation_account_info.data.borrow().len() == 0 {
+        msg!("Error: Conversation account is not initialized");
+        return Err(ProgramError::UninitializedAccount);
+    }
+
+    // Check is conversation account has correct program ids
+    if conversation_account_info.owner != program_id {
+        msg!("Error: Conversation account does not have the correct program id");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    // Check if sender is a participant of the conversation
+    if *sender_user_account_info.key != user_pda_address(program_id, sender_user_account_info, sender_info.key)? {
+        msg!("Error: Sender's user address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    if *conversation_account_info.key != conversation_pda_address(
+        program_id,
+        conversation_account_info,
+        sender_user_account_info.key,
+        receiver_user_account_info.key,
+    )? {
+        msg!("Error: Conversation address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    create_handshake_account(
+        program_id,
+        funder_info,
+        sender_info,
+        handshake_account_info,
+        previous_handshake_account_info,
+        conversation_account_info,
+        rent_info,
+        clock_info,
+        system_program_info,
+        epoch,
+        commit,
+        welcome,
+    )
+}
+
+fn create_group_handshake_account(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    epoch: u64,
+    commit: Vec<u8>,
+    welcome: Vec<u8>,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let funder_info = next_account_info(account_info_iter)?;
+    let sender_info = next_account_info(account_info_iter)?;
+    let handshake_account_info = next_account_info(account_info_iter)?;
+    let previous_handshake_account_info = next_account_info(account_info_iter)?;
+    let group_account_info = next_account_info(account_info_iter)?;
+    let sender_member_account_info = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let clock_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    // Check sender signature
+    if !sender_info.is_signer {
+        msg!("Error: Sender signature missing");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let group = load_group(program_id, group_account_info)?;
+
+    // Check if sender is a member of the group - members of a community are members of its groups
+    load_group_member(
+        program_id,
+        &group.membership_scope(group_account_info.key),
+        sender_info,
+        sender_member_account_info,
+    )?;
+
+    create_handshake_account(
+        program_id,
+        funder_info,
+        sender_info,
+        handshake_account_info,
+        previous_handshake_account_info,
+        group_account_info,
+        rent_info,
+        clock_info,
+        system_program_info,
+        epoch,
+        commit,
+        welcome,
+    )
+}
+
+/// Create the handshake of the epoch - handshakes of a conversation or group are strictly sequential
+#[allow(clippy::too_many_arguments)]
+fn create_handshake_account<'a>(
+    program_id: &Pubkey,
+    funder_info: &AccountInfo<'a>,
+    sender_info: &AccountInfo<'a>,
+    handshake_account_info: &AccountInfo<'a>,
+    previous_handshake_account_info: &AccountInfo<'a>,
+    conversation_account_info: &AccountInfo<'a>,
+    rent_info: &AccountInfo<'a>,
+    clock_info: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+    epoch: u64,
+    commit: Vec<u8>,
+    welcome: Vec<u8>,
+) -> ProgramResult {
+    let rent = &Rent::from_account_info(rent_info)?;
+
+    // Check rent system account
+    if !rent::check_id(rent_info.key) {
+        msg!("Error: Invalid rent system account");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    // Check clock system account
+    if !clock::check_id(clock_info.key) {
+        msg!("Error: Invalid clock system account");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    // Epochs are strictly sequential - the handshake of the previous epoch must exist
+    if epoch > 0 {
+        if *previous_handshake_account_info.key != ConversationHandshake::find_pda_address(
+            conversation_account_info.key,
+            epoch - 1,
+            program_id,
+        ) {
+            msg!("Error: Previous ConversationHandshake address does not match seed derivation");
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        if previous_handshake_account_info.data.borrow().len() == 0
+            || previous_handshake_account_info.owner != program_id {
+            msg!("Error: Handshake of the previous epoch does not exist");
+            return Err(ProgramError::UninitializedAccount);
+        }
+    }
+
+    // Check seed derivations for conversation-handshake account
+    let (handshake_address, handshake_bump_seed) =
+        ConversationHandshake::find_pda_address_with_bump_seed(
+            conversation_account_info.key,
+            epoch,
+            program_id,
+        );
+
+    if handshake_address != *handshake_account_info.key {
+        msg!("Error: ConversationHandshake address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    // Only the first commit for an epoch is accepted
+    if handshake_account_info.data.borrow().len() > 0 {
+        msg!("Error: ConversationHandshake account is already initialized");
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    // Create conversation-handshake PDA account
+    let handshake_account_signer_seeds: &[&[_]] = &[
+        &conversation_account_info.key.to_bytes(),
+        &epoch.to_le_bytes(),
+        ConversationHandshake::ACCOUNT_ADDRESS_SEED.as_bytes(),
+        &[handshake_bump_seed],
+    ];
+
+    create_pda_account(
+        funder_info,
+        rent,
+        true,
+        ConversationHandshake::retrieve_size(commit.len(), welcome.len()),
+        program_id,
+        system_program_info,
+        handshake_account_info,
+        handshake_account_signer_seeds,
+    )?;
+
+    // Assign data to handshake
+    let mut handshake = ConversationHandshake::new(commit.len(), welcome.len());
+    handshake.sender = *sender_info.key;
+    handshake.commit = commit;
+    handshake.welcome = welcome;
+    handshake.timestamp = Clock::from_account_info(clock_info)?.unix_timestamp;
+    handshake.serialize(&mut &mut handshake_account_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+fn set_sealed_sender_credential(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    authority: Pubkey,
+    chain_hash: Hash,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let funder_info = next_account_info(account_info_iter)?;
+    let sender_info = next_account_info(account_info_iter)?;
+    let credential_account_info = next_account_info(account_info_iter)?;
+    let sender_user_account_info = next_account_info(account_info_iter)?;
+    let receiver_user_account_info = next_account_info(account_info_iter)?;
+    let conversation_account_info = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    let current_authority_info = next_account_info(account_info_iter).ok();
+
+    let rent = &Rent::from_account_info(rent_info)?;
+
+    // Check sender signature
+    if !sender_info.is_signer {
+        msg!("Error: Sender signature missing");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Check if conversation account exists
+    if conversation_account_info.data.borrow().len() == 0 {
+        msg!("Error: Conversation account is not initialized");
+        return Err(ProgramError::UninitializedAccount);
+    }
+
+    // Check is conversation account has correct program ids
+    if conversation_account_info.owner != program_id {
+        msg!("Error: Conversation account does not have the correct program id");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    // Check if sender is a participant of the conversation
+    if *sender_user_account_info.key != user_pda_address(program_id, sender_user_account_info, sender_info.key)? {
+        msg!("Error: Sender's user address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    if *conversation_account_info.key != conversation_pda_address(
+        program_id,
+        conversation_account_info,
+        sender_user_account_info.key,
+        receiver_user_account_info.key,
+    )? {
+        msg!("Error: Conversation address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    // Check seed derivations for sealed-sender-credential account
+    let (credential_address, credential_bump_seed) =
+        SealedSenderCredential::find_pda_address_with_bump_seed(
+            conversation_account_info.key,
+            sender_info.key,
+            program_id,
+        );
+
+    if credential_address != *credential_account_info.key {
+        msg!("Error: SealedSenderCredential address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    // Create sealed-sender-credential PDA account if not exist - otherwise the credential is replaced
+    if credential_account_info.data.borrow().len() == 0 {
+        let credential_account_signer_seeds: &[&[_]] = &[
+            &conversation_account_info.key.to_bytes(),
+            &sender_info.key.to_bytes(),
+            SealedSenderCredential::ACCOUNT_ADDRESS_SEED.as_bytes(),
+            &[credential_bump_seed],
+        ];
+
+        create_pda_account(
+            funder_info,
+            rent,
+            true,
+            SealedSenderCredential::retrieve_size(),
+            program_id,
+            system_program_info,
+            credential_account_info,
+            credential_account_signer_seeds,
+        )?;
+    } else {
+        // Check that an active chain is replaced by its current authority only
+        let current_credential = SealedSenderCredential::try_from_slice(&credential_account_info.data.borrow())?;
+
+        if !current_credential.is_exhausted() {
+            match current_authority_info {
+                Some(current_authority_info)
+                    if current_authority_info.is_signer && *current_authority_info.key == current_credential.authority => {},
+                _ => {
+                    msg!("Error: Sealed sender authority signature missing");
+                    return Err(ProgramError::MissingRequiredSignature);
+                }
+            }
+        }
+    }
+
+    let credential = SealedSenderCredential {
+        conversation_address: *conversation_account_info.key,
+        authority,
+        chain_hash,
+    };
+    credential.serialize(&mut &mut credential_account_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+fn send_sealed_message(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    next_authority: Pubkey,
+    next_chain_hash: Hash,
+    content: Vec<u8>,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let funder_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let conversation_account_info = next_account_info(account_info_iter)?;
+    let credential_account_info = next_account_info(account_info_iter)?;
+    let message_account_info = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let clock_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    let padding_policy_account_info = next_account_info(account_info_iter)?;
+
+    let rent = &Rent::from_account_info(rent_info)?;
+
+    // Check if conversation account exists
+    if conversation_account_info.data.borrow().len() == 0 {
+        msg!("Error: Conversation account is not initialized");
+        return Err(ProgramError::UninitializedAccount);
+    }
+
+    // Check is conversation account has correct program ids
+    if conversation_account_info.owner != program_id {
+        msg!("Error: Conversation account does not have the correct program id");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    // Check if sealed-sender-credential account exists - its seeds name the sender, so they are not checked here
+    if credential_account_info.data.borrow().len() != SealedSenderCredential::retrieve_size()
+        || credential_account_info.owner != program_id {
+        msg!("Error: SealedSenderCredential account is not initialized");
+        return Err(ProgramError::UninitializedAccount);
+    }
+
+    // Check rent system account
+    if !rent::check_id(rent_info.key) {
+        msg!("Error: Invalid rent system account");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    // Check clock system account
+    if !clock::check_id(clock_info.key) {
+        msg!("Error: Invalid clock system account");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    // Check authority signature against the credential and advance to the next keypair of the chain
+    let mut credential = SealedSenderCredential::try_from_slice(&credential_account_info.data.borrow())?;
+
+    // Check credential belongs to the conversation - only set_sealed_sender_credential writes these accounts
+    if credential.conversation_address != *conversation_account_info.key {
+        msg!("Error: SealedSenderCredential does not belong to the conversation");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if !authority_info.is_signer || *authority_info.key != credential.authority {
+        msg!("Error: Sealed sender authority signature missing");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Check next keypair is the one the chain committed to
+    if SealedSenderCredential::chain_link(&next_authority, &next_chain_hash) != credential.chain_hash {
+        msg!("Error: Next sealed sender authority does not match the chain");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    credential.authority = next_authority;
+    credential.chain_hash = next_chain_hash;
+    credential.serialize(&mut &mut credential_account_info.data.borrow_mut()[..])?;
+
+    // Check content against the padding policy of the conversation
+    check_padding_policy(
+        program_id,
+        conversation_account_info,
+        padding_policy_account_info,
+        content.len(),
+    )?;
+
+    // Get conversation message counter
+    let message_counter = ConversationView::new(&conversation_account_info.data.borrow()[..])?.message_counter();
+
+    // Check seed derivations for message account
+    let (message_pda_address, message_bump_seed) = Message::find_pda_address_v2_with_bump_seed(
+        conversation_account_info.key,
+        message_counter,
+        program_id,
+    );
+
+    if message_pda_address != *message_account_info.key {
+        msg!("Error: Message address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    // Create message PDA account
+    let message_index_bytes = message_counter.to_le_bytes();
+    let message_account_signer_seeds: &[&[_]] = &[
+        &conversation_account_info.key.to_bytes(),
+        &message_index_bytes,
+        Message::ACCOUNT_ADDRESS_SEED_V2.as_bytes(),
+        &[message_bump_seed],
+    ];
+
+    create_pda_account(
+        funder_info,
+        rent,
+        false,
+        Message::retrieve_size(content.len()),
+        program_id,
+        system_program_info,
+        message_account_info,
+        message_account_signer_seeds,
+    )?;
+
+    // Assign data to message - the sender is part of the encrypted content
+    let mut message = Message::new(content.len());
+    message.message_type = MessageType::SEALED_SENDER;
+    message.content = content;
+    message.timestamp = Clock::from_account_info(clock_info)?.unix_timestamp;
+    message.serialize(&mut &mut message_account_info.data.borrow_mut()[..])?;
+
+    // Increment and store the number of messages the conversation account has
+    let next_message_counter = message_counter.checked_add(1).ok_or(InstantMessagingError::CounterOverflow)?;
+    ConversationView::new(&mut conversation_account_info.data.borrow_mut()[..])?.set_message_counter(next_message_counter)?;
+
+    Ok(())
+}
+
+fn create_blinded_conversation_account(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    blinded_id: [u8; 32],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let funder_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let blinded_conversation_account_info = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    let rent = &Rent::from_account_info(rent_info)?;
+
+    // Check authority signature
+    if !authority_info.is_signer {
+        msg!("Error: Authority signature missing");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let (blinded_conversation_address, blinded_conversation_bump_seed) =
+        BlindedConversation::find_pda_address_with_bump_seed(
+            &blinded_id,
+            authority_info.key,
+            program_id,
+        );
+
+    if blinded_conversation_address != *blinded_conversation_account_info.key {
+        msg!("Error: BlindedConversation address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    if blinded_conversation_account_info.data.borrow().len() > 0 {
+        msg!("Error: BlindedConversation account is already initialized");
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    let blinded_conversation_account_signer_seeds: &[&[_]] = &[
+        &blinded_id,
+        &authority_info.key.to_bytes(),
+        BlindedConversation::ACCOUNT_ADDRESS_SEED.as_bytes(),
+        &[blinded_conversation_bump_seed],
+    ];
+
+    create_pda_account(
+        funder_info,
+        rent,
+        true,
+        BlindedConversation::retrieve_size(),
+        program_id,
+        system_program_info,
+        blinded_conversation_account_info,
+        blinded_conversation_account_signer_seeds,
+    )?;
+
+    let blinded_conversation = BlindedConversation {
+        message_counter: 0,
+        authority: *authority_info.key,
+        blinded_id,
+        bump_seed: blinded_conversation_bump_seed,
+    };
+    blinded_conversation.serialize(&mut &mut blinded_conversation_account_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+fn send_blinded_message(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    message_type: u8,
+    content: Vec<u8>,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let funder_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let blinded_conversation_account_info = next_account_info(account_info_iter)?;
+    let message_account_info = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let clock_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    let padding_policy_account_info = next_account_info(account_info_iter)?;
+
+    let rent = &Rent::from_account_info(rent_info)?;
+
+    // Check authority signature
+    if !authority_info.is_signer {
+        msg!("Error: Authority signature missing");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Check if blinded conversation account exists
+    if blinded_conversation_account_info.data.borrow().len() == 0 {
+        msg!("Error: BlindedConversation account is not initialized");
+        return Err(ProgramError::UninitializedAccount);
+    }
+
+    // Check is blinded conversation account has correct program ids
+    if blinded_conversation_account_info.owner != program_id {
+        msg!("Error: BlindedConversation account does not have the correct program id");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    // Check rent system account
+    if !rent::check_id(rent_info.key) {
+        msg!("Error: Invalid rent system account");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    // Check clock system account
+    if !clock::check_id(clock_info.key) {
+        msg!("Error: Invalid clock system account");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    // Membership is proven by the signature of the conversation authority
+    let mut blinded_conversation = BlindedConversation::try_from_slice(&blinded_conversation_account_info.data.borrow())?;
+
+    if blinded_conversation.authority != *authority_info.key {
+        msg!("Error: Signer is not the authority of this conversation");
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    // Check seed derivations for blinded conversation account
+    if *blinded_conversation_account_info.key != BlindedConversation::create_pda_address(
+        &blinded_conversation.blinded_id,
+        &blinded_conversation.authority,
+        blinded_conversation.bump_seed,
+        program_id,
+    )? {
+        msg!("Error: BlindedConversation address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    // Check content against the padding policy of the conversation
+    check_padding_policy(
+        program_id,
+        blinded_conversation_account_info,
+        padding_policy_account_info,
+        content.len(),
+    )?;
+
+    let message_counter = blinded_conversation.message_counter;
+
+    // Check seed derivations for message account
+    let (message_pda_address, message_bump_seed) = Message::find_pda_address_v2_with_bump_seed(
+        blinded_conversation_account_info.key,
+        message_counter,
+        program_id,
+    );
+
+    if message_pda_address != *message_account_info.key {
+        msg!("Error: Message address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    // Create message PDA account
+    let message_index_bytes = message_counter.to_le_bytes();
+    let message_account_signer_seeds: &[&[_]] = &[
+        &blinded_conversation_account_info.key.to_bytes(),
+        &message_index_bytes,
+        Message::ACCOUNT_ADDRESS_SEED_V2.as_bytes(),
+        &[message_bump_seed],
+    ];
+
+    create_pda_account(
+        funder_info,
+        rent,
+        false,
+        Message::retrieve_size(content.len()),
+        program_id,
+        system_program_info,
+        message_account_info,
+        message_account_signer_seeds,
+    )?;
+
+    // Assign data to message - the authority is shared, so it does not tell the participants apart
+    let mut message = Message::new(content.len());
+    message.sender = *authority_info.key;
+    message.message_type = message_type;
+    message.content = content;
+    message.timestamp = Clock::from_account_info(clock_info)?.unix_timestamp;
+    message.serialize(&mut &mut message_account_info.data.borrow_mut()[..])?;
+
+    // Increment and store the number of messages the conversation account has
+    blinded_conversation.message_counter = blinded_conversation.message_counter.checked_add(1).ok_or(InstantMessagingError::CounterOverflow)?;
+    blinded_conversation.serialize(&mut &mut blinded_conversation_account_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+fn set_conversation_padding_policy(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    scheme: u8,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let funder_info = next_account_info(account_info_iter)?;
+    let sender_info = next_account_info(account_info_iter)?;
+    let padding_policy_account_info = next_account_info(account_info_iter)?;
+    let sender_user_account_info = next_account_info(account_info_iter)?;
+    let receiver_user_account_info = next_account_info(account_info_iter)?;
+    let conversation_account_info = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    // Check sender signature
+    if !sender_info.is_signer {
+        msg!("Error: Sender signature missing");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Check if conversation account exists
+    if conversation_account_info.data.borrow().len() == 0 {
+        msg!("Error: Conversation account is not initialized");
+        return Err(ProgramError::UninitializedAccount);
+    }
+
+    // Check is conversation account has correct program ids
+    if conversation_account_info.owner != program_id {
+        msg!("Error: Conversation account does not have the correct program id");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    // Check if sender is a participant of the conversation
+    if *sender_user_account_info.key != user_pda_address(program_id, sender_user_account_info, sender_info.key)? {
+        msg!("Error: Sender's user address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    if *conversation_account_info.key != conversation_pda_address(
+        program_id,
+        conversation_account_info,
+        sender_user_account_info.key,
+        receiver_user_account_info.key,
+    )? {
+        msg!("Error: Conversation address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    write_padding_policy(
+        program_id,
+        funder_info,
+        padding_policy_account_info,
+        conversation_account_info,
+        rent_info,
+        system_program_info,
+        scheme,
+    )
+}
+
+fn set_blinded_conversation_padding_policy(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    scheme: u8,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let funder_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let padding_policy_account_info = next_account_info(account_info_iter)?;
+    let blinded_conversation_account_info = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    // Check authority signature
+    if !authority_info.is_signer {
+        msg!("Error: Authority signature missing");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Check if blinded conversation account exists
+    if blinded_conversation_account_info.data.borrow().len() == 0 {
+        msg!("Error: BlindedConversation account is not initialized");
+        return Err(ProgramError::UninitializedAccount);
+    }
+
+    // Check is blinded conversation account has correct program ids
+    if blinded_conversation_account_info.owner != program_id {
+        msg!("Error: BlindedConversation account does not have the correct program id");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let blinded_conversation = BlindedConversation::try_from_slice(&blinded_conversation_account_info.data.borrow())?;
+
+    if blinded_conversation.authority != *authority_info.key {
+        msg!("Error: Signer is not the authority of this conversation");
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    // Check seed derivations for blinded conversation account
+    if *blinded_conversation_account_info.key != BlindedConversation::create_pda_address(
+        &blinded_conversation.blinded_id,
+        &blinded_conversation.authority,
+        blinded_conversation.bump_seed,
+        program_id,
+    )? {
+        msg!("Error: BlindedConversation address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    write_padding_policy(
+        program_id,
+        funder_info,
+        padding_policy_account_info,
+        blinded_conversation_account_info,
+        rent_info,
+        system_program_info,
+        scheme,
+    )
+}
+
+fn set_group_padding_policy(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    scheme: u8,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let funder_info = next_account_info(account_info_iter)?;
+    let editor_info = next_account_info(account_info_iter)?;
+    let padding_policy_account_info = next_account_info(account_info_iter)?;
+    let group_account_info = next_account_info(account_info_iter)?;
+    let editor_member_account_info = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    // Check editor signature
+    if !editor_info.is_signer {
+        msg!("Error: Editor signature missing");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let group = load_group(program_id, group_account_info)?;
+
+    // Check if editor is allowed to set the policy - members of a community are members of its groups
+    check_member_permission(
+        program_id,
+        &group.membership_scope(group_account_info.key),
+        editor_info,
+        editor_member_account_info,
+        GroupPermission::EDIT_METADATA,
+    )?;
+
+    write_padding_policy(
+        program_id,
+        funder_info,
+        padding_policy_account_info,
+        group_account_info,
+        rent_info,
+        system_program_info,
+        scheme,
+    )
+}
+
+/// Create the padding policy of a conversation - a policy without padding can be replaced, any other
+/// scheme is immutable, so neither participant can silently weaken what the other relies on. The
+/// schemes do not order by strength, so there is no tightening from one padding scheme to another
+fn write_padding_policy<'a>(
+    program_id: &Pubkey,
+    funder_info: &AccountInfo<'a>,
+    padding_policy_account_info: &AccountInfo<'a>,
+    conversation_account_info: &AccountInfo<'a>,
+    rent_info: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+    scheme: u8,
+) -> ProgramResult {
+    let rent = &Rent::from_account_info(rent_info)?;
+
+    // Check padding scheme
+    if !PaddingScheme::is_valid(scheme) {
+        msg!("Error: Unknown padding scheme");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // Check seed derivations for conversation-padding-policy account
+    let (padding_policy_address, padding_policy_bump_seed) =
+        ConversationPaddingPolicy::find_pda_address_with_bump_seed(
+            conversation_account_info.key,
+            program_id,
+        );
+
+    if padding_policy_address != *padding_policy_account_info.key {
+        msg!("Error: ConversationPaddingPolicy address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    // Create conversation-padding-policy PDA account if not exist - otherwise only a policy without padding is replaced
+    if padding_policy_account_info.data.borrow().len() > 0 {
+        let padding_policy = ConversationPaddingPolicy::try_from_slice(&padding_policy_account_info.data.borrow())?;
+
+        if padding_policy.scheme != PaddingScheme::NONE && padding_policy.scheme != scheme {
+            msg!("Error: Padding scheme of the conversation is immutable once set");
+            return Err(ProgramError::InvalidArgument);
+        }
+    } else {
+        let padding_policy_account_signer_seeds: &[&[_]] = &[
+            &conversation_account_info.key.to_bytes(),
+            ConversationPaddingPolicy::ACCOUNT_ADDRESS_SEED.as_bytes(),
+            &[padding_policy_bump_seed],
+        ];
+
+        create_pda_account(
+            funder_info,
+            rent,
+            true,
+            ConversationPaddingPolicy::retrieve_size(),
+            program_id,
+            system_program_info,
+            padding_policy_account_info,
+            padding_policy_account_signer_seeds,
+        )?;
+    }
+
+    let padding_policy = ConversationPaddingPolicy {
+        scheme,
+    };
+    padding_policy.serialize(&mut &mut padding_policy_account_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+/// Check size of the content against the conversation-padding-policy account - no account means no padding
+///
+/// The policy applies to every message type, so the sender cannot opt out by choosing another type
+fn check_padding_policy(
+    program_id: &Pubkey,
+    conversation_account_info: &AccountInfo,
+    padding_policy_account_info: &AccountInfo,
+    content_size: usize,
+) -> ProgramResult {
+    // Check seed derivations for conversation-padding-policy account
+    if *padding_policy_account_info.key != ConversationPaddingPolicy::find_pda_address(
+        conversation_account_info.key,
+        program_id,
+    ) {
+        msg!("Error: ConversationPaddingPolicy address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    if padding_policy_account_info.data.borrow().len() == 0
+        || padding_policy_account_info.owner != program_id
+    {
+        return Ok(());
+    }
+
+    let padding_policy = ConversationPaddingPolicy::try_from_slice(&padding_policy_account_info.data.borrow())?;
+
+    if !PaddingScheme::is_padded(padding_policy.scheme, content_size) {
+        msg!("Error: Content is not padded according to the conversation policy");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    Ok(())
+}
+
+fn create_group_account(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    group_index: u64,
+    kind: u8,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let funder_info = next_account_info(account_info_iter)?;
+    let creator_info = next_account_info(account_info_iter)?;
+    let group_account_info = next_account_info(account_info_iter)?;
+    let creator_member_account_info = next_account_info(account_info_iter)?;
+    let creator_user_account_info = next_account_info(account_info_iter)?;
+    let creator_user_conversation_account_info = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    let rent = &Rent::from_account_info(rent_info)?;
+
+    // Check creator signature
+    if !creator_info.is_signer {
+        msg!("Error: Creator signature missing");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Check group kind
+    if !GroupKind::is_valid(kind) {
+        msg!("Error: Unknown group kind");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // Check seed derivations for group account
+    let (group_address, group_bump_seed) = Group::find_pda_address_with_bump_seed(
+        creator_info.key,
+        group_index,
+        program_id,
+    );
+
+    if group_address != *group_account_info.key {
+        msg!("Error: Group address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    if group_account_info.data.borrow().len() > 0 {
+        msg!("Error: Group account is already initialized");
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    // Create group PDA account
+    let group_account_signer_seeds: &[&[_]] = &[
+        &creator_info.key.to_bytes(),
+        &group_index.to_le_bytes(),
+        Group::ACCOUNT_ADDRESS_SEED.as_bytes(),
+        &[group_bump_seed],
+    ];
+
+    create_pda_account(
+        funder_info,
+        rent,
+        true,
+        Group::retrieve_size(),
+        program_id,
+        system_program_info,
+        group_account_info,
+        group_account_signer_seeds,
+    )?;
+
+    let group = Group {
+        account_kind: AccountKind::GROUP,
+        creator: *creator_info.key,
+        index: group_index,
+        owner: *creator_info.key,
+        message_counter: 0,
+        kind,
+        community: Pubkey::default(),
+    };
+    group.serialize(&mut &mut group_account_info.data.borrow_mut()[..])?;
+
+    // Creator joins as the owner
+    create_group_member_account(
+        program_id,
+        funder_info,
+        group_account_info,
+        creator_info,
+        creator_member_account_info,
+        creator_user_account_info,
+        creator_user_conversation_account_info,
+        rent_info,
+        system_program_info,
+        &GroupMember {
+            role: GroupRole::OWNER,
+            permissions: GroupPermission::ALL,
+        },
+    )
+}
+
+fn add_group_member(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    role: u8,
+    permissions: u8,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let funder_info = next_account_info(account_info_iter)?;
+    let inviter_info = next_account_info(account_info_iter)?;
+    let group_account_info = next_account_info(account_info_iter)?;
+    let inviter_member_account_info = next_account_info(account_info_iter)?;
+    let member_info = next_account_info(account_info_iter)?;
+    let member_account_info = next_account_info(account_info_iter)?;
+    let member_ban_account_info = next_account_info(account_info_iter)?;
+    let member_user_account_info = next_account_info(account_info_iter)?;
+    let member_user_conversation_account_info = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    // Check inviter signature
+    if !inviter_info.is_signer {
+        msg!("Error: Inviter signature missing");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Check new member signature - nobody is added to a group without consent
+    if !member_info.is_signer {
+        msg!("Error: New member signature missing");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    load_membership_scope(program_id, group_account_info)?;
+
+    // Check if inviter may add a member with the given role and permissions
+    let inviter_member = load_group_member(
+        program_id,
+        group_account_info.key,
+        inviter_info,
+        inviter_member_account_info,
+    )?;
+
+    if !inviter_member.can_add(role, permissions) {
+        msg!("Error: Inviter is not allowed to add a member with the given role");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    check_not_banned(program_id, group_account_info, member_info, member_ban_account_info)?;
+
+    create_group_member_account(
+        program_id,
+        funder_info,
+        group_account_info,
+        member_info,
+        member_account_info,
+        member_user_account_info,
+        member_user_conversation_account_info,
+        rent_info,
+        system_program_info,
+        &GroupMember {
+            role,
+            permissions,
+        },
+    )
+}
+
+fn send_group_message(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    message_type: u8,
+    content: Vec<u8>,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let funder_info = next_account_info(account_info_iter)?;
+    let sender_info = next_account_info(account_info_iter)?;
+    let group_account_info = next_account_info(account_info_iter)?;
+    let sender_member_account_info = next_account_info(account_info_iter)?;
+    let message_account_info = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let clock_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    let padding_policy_account_info = next_account_info(account_info_iter)?;
+
+    let rent = &Rent::from_account_info(rent_info)?;
+
+    // Check sender signature
+    if !sender_info.is_signer {
+        msg!("Error: Sender signature missing");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut group = load_group(program_id, group_account_info)?;
+
+    // Check if sender may send messages to the group - members of a community are members of its groups
+    let sender_member = load_group_member(
+        program_id,
+        &group.membership_scope(group_account_info.key),
+        sender_info,
+        sender_member_account_info,
+    )?;
+
+    if !sender_member.has_permission(GroupPermission::SEND) {
+        msg!("Error: Sender is not allowed to send messages to the group");
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    // Check content against the padding policy of the group
+    check_padding_policy(
+        program_id,
+        group_account_info,
+        padding_policy_account_info,
+        content.len(),
+    )?;
+
+    // Check clock system account
+    if !clock::check_id(clock_info.key) {
+        msg!("Error: Invalid clock system account");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    // Check seed derivations for message account
+    let message_counter = group.message_counter;
+
+    let (message_pda_address, message_bump_seed) = Message::find_pda_address_v2_with_bump_seed(
+        group_account_info.key,
+        message_counter,
+        program_id,
+    );
+
+    if message_pda_address != *message_account_info.key {
+        msg!("Error: Message address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    // Create message PDA account
+    let message_index_bytes = message_counter.to_le_bytes();
+    let message_account_signer_seeds: &[&[_]] = &[
+        &group_account_info.key.to_bytes(),
+        &message_index_bytes,
+        Message::ACCOUNT_ADDRESS_SEED_V2.as_bytes(),
+        &[message_bump_seed],
+    ];
+
+    create_pda_account(
+        funder_info,
+        rent,
+        false,
+        Message::retrieve_size(content.len()),
+        program_id,
+        system_program_info,
+        message_account_info,
+        message_account_signer_seeds,
+    )?;
+
+    // Assign data to message
+    let mut message = Message::new(content.len());
+    message.sender = *sender_info.key;
+    message.message_type = message_type;
+    message.content = content;
+    message.timestamp = Clock::from_account_info(clock_info)?.unix_timestamp;
+    message.serialize(&mut &mut message_account_info.data.borrow_mut()[..])?;
+
+    // Increment and store the number of messages the group account has
+    group.message_counter = group.message_counter.checked_add(1).ok_or(InstantMessagingError::CounterOverflow)?;
+    group.serialize(&mut &mut group_account_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+fn set_member_role(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    role: u8,
+    permissions: u8,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let actor_info = next_account_info(account_info_iter)?;
+    let group_account_info = next_account_info(account_info_iter)?;
+    let actor_member_account_info = next_account_info(account_info_iter)?;
+    let member_info = next_account_info(account_info_iter)?;
+    let member_account_info = next_account_info(account_info_iter)?;
+
+    // Check actor signature
+    if !actor_info.is_signer {
+        msg!("Error: Acting member signature missing");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Members cannot change their own role
+    if actor_info.key == member_info.key {
+        msg!("Error: Members cannot change their own role");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    load_membership_scope(program_id, group_account_info)?;
+
+    let actor_member = load_group_member(
+        program_id,
+        group_account_info.key,
+        actor_info,
+        actor_member_account_info,
+    )?;
+
+    let mut member = load_group_member(
+        program_id,
+        group_account_info.key,
+        member_info,
+        member_account_info,
+    )?;
+
+    // Check if actor may give the role and permissions to the member
+    if !actor_member.can_assign(&member, role, permissions) {
+        msg!("Error: Acting member is not allowed to assign the given role");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    member.role = role;
+    member.permissions = permissions;
+    member.serialize(&mut &mut member_account_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+fn transfer_group_ownership(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let owner_info = next_account_info(account_info_iter)?;
+    let group_account_info = next_account_info(account_info_iter)?;
+    let owner_member_account_info = next_account_info(account_info_iter)?;
+    let new_owner_info = next_account_info(account_info_iter)?;
+    let new_owner_member_account_info = next_account_info(account_info_iter)?;
+
+    // Check owner signature
+    if !owner_info.is_signer {
+        msg!("Error: Owner signature missing");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut scope = load_membership_scope(program_id, group_account_info)?;
+
+    let mut owner_member = load_group_member(
+        program_id,
+        group_account_info.key,
+        owner_info,
+        owner_member_account_info,
+    )?;
+
+    if scope.owner() != *owner_info.key || owner_member.role != GroupRole::OWNER {
+        msg!("Error: Only the owner can transfer the group");
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    if owner_info.key == new_owner_info.key {
+        msg!("Error: New owner must be another member");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // New owner has to be a member already
+    let mut new_owner_member = load_group_member(
+        program_id,
+        group_account_info.key,
+        new_owner_info,
+        new_owner_member_account_info,
+    )?;
+
+    new_owner_member.role = GroupRole::OWNER;
+    new_owner_member.permissions = GroupPermission::ALL;
+    new_owner_member.serialize(&mut &mut new_owner_member_account_info.data.borrow_mut()[..])?;
+
+    owner_member.role = GroupRole::ADMIN;
+    owner_member.permissions = GroupRole::default_permissions(GroupRole::ADMIN);
+    owner_member.serialize(&mut &mut owner_member_account_info.data.borrow_mut()[..])?;
+
+    scope.set_owner(*new_owner_info.key);
+    scope.serialize(&mut &mut group_account_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+fn create_invite_account(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    code_hash: Hash,
+    expires_at: UnixTimestamp,
+    max_uses: u32,
+    role: u8,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let funder_info = next_account_info(account_info_iter)?;
+    let creator_info = next_account_info(account_info_iter)?;
+    let group_account_info = next_account_info(account_info_iter)?;
+    let creator_member_account_info = next_account_info(account_info_iter)?;
+    let invite_account_info = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let clock_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    let rent = &Rent::from_account_info(rent_info)?;
+
+    // Check creator signature
+    if !creator_info.is_signer {
+        msg!("Error: Creator signature missing");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    load_membership_scope(program_id, group_account_info)?;
+
+    // Check if creator is an admin allowed to add members with the role
+    let creator_member = load_group_member(
+        program_id,
+        group_account_info.key,
+        creator_info,
+        creator_member_account_info,
+    )?;
+
+    if creator_member.role != GroupRole::OWNER && creator_member.role != GroupRole::ADMIN {
+        msg!("Error: Only admins can create invites");
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    if !creator_member.can_add(role, GroupRole::default_permissions(role)) {
+        msg!("Error: Creator is not allowed to add a member with the given role");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // Check clock system account
+    if !clock::check_id(clock_info.key) {
+        msg!("Error: Invalid clock system account");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    // Check that the invite can be used at all
+    if max_uses == 0 || expires_at <= Clock::from_account_info(clock_info)?.unix_timestamp {
+        msg!("Error: Invite must expire in the future and allow at least one use");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // Check seed derivations for invite account
+    let (invite_address, invite_bump_seed) = Invite::find_pda_address_with_bump_seed(
+        group_account_info.key,
+        &code_hash,
+        program_id,
+    );
+
+    if invite_address != *invite_account_info.key {
+        msg!("Error: Invite address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    if invite_account_info.data.borrow().len() > 0 {
+        msg!("Error: Invite account is already initialized");
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    // Create invite PDA account
+    let invite_account_signer_seeds: &[&[_]] = &[
+        &group_account_info.key.to_bytes(),
+        code_hash.as_ref(),
+        Invite::ACCOUNT_ADDRESS_SEED.as_bytes(),
+        &[invite_bump_seed],
+    ];
+
+    create_pda_account(
+        funder_info,
+        rent,
+        true,
+        Invite::retrieve_size(),
+        program_id,
+        system_program_info,
+        invite_account_info,
+        invite_account_signer_seeds,
+    )?;
+
+    let invite = Invite {
+        creator: *creator_info.key,
+        expires_at,
+        max_uses,
+        uses: 0,
+        role,
+    };
+    invite.serialize(&mut &mut invite_account_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+fn join_with_invite(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    code: [u8; 16],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let funder_info = next_account_info(account_info_iter)?;
+    let member_info = next_account_info(account_info_iter)?;
+    let group_account_info = next_account_info(account_info_iter)?;
+    let invite_account_info = next_account_info(account_info_iter)?;
+    let member_account_info = next_account_info(account_info_iter)?;
+    let member_ban_account_info = next_account_info(account_info_iter)?;
+    let member_user_account_info = next_account_info(account_info_iter)?;
+    let member_user_conversation_account_info = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let clock_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    // Check joining member signature
+    if !member_info.is_signer {
+        msg!("Error: Joining member signature missing");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    load_membership_scope(program_id, group_account_info)?;
+
+    check_not_banned(program_id, group_account_info, member_info, member_ban_account_info)?;
+
+    // Check seed derivations for invite account - the code must be the preimage of the invite hash
+    if *invite_account_info.key != Invite::find_pda_address(
+        group_account_info.key,
+        &Invite::hash_code(&code),
+        program_id,
+    ) {
+        msg!("Error: Invite address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    // Check if invite account exists
+    if invite_account_info.data.borrow().len() == 0 || invite_account_info.owner != program_id {
+        msg!("Error: Invite account is not initialized");
+        return Err(ProgramError::UninitializedAccount);
+    }
+
+    // Check clock system account
+    if !clock::check_id(clock_info.key) {
+        msg!("Error: Invalid clock system account");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    // Check expiry and usage limit of the invite
+    let mut invite = Invite::try_from_slice(&invite_account_info.data.borrow())?;
+
+    if !invite.is_usable(Clock::from_account_info(clock_info)?.unix_timestamp) {
+        msg!("Error: Invite is expired or used up");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    invite.uses += 1;
+    invite.serialize(&mut &mut invite_account_info.data.borrow_mut()[..])?;
+
+    create_group_member_account(
+        program_id,
+        funder_info,
+        group_account_info,
+        member_info,
+        member_account_info,
+        member_user_account_info,
+        member_user_conversation_account_info,
+        rent_info,
+        system_program_info,
+        &GroupMember {
+            role: invite.role,
+            permissions: GroupRole::default_permissions(invite.role),
+        },
+    )
+}
+
+fn revoke_invite(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    code_hash: Hash,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let admin_info = next_account_info(account_info_iter)?;
+    let group_account_info = next_account_info(account_info_iter)?;
+    let admin_member_account_info = next_account_info(account_info_iter)?;
+    let invite_account_info = next_account_info(account_info_iter)?;
+    let creator_info = next_account_info(account_info_iter)?;
+
+    // Check admin signature
+    if !admin_info.is_signer {
+        msg!("Error: Admin signature missing");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    load_membership_scope(program_id, group_account_info)?;
+
+    // Check if signer is an admin of the group
+    let admin_member = load_group_member(
+        program_id,
+        group_account_info.key,
+        admin_info,
+        admin_member_account_info,
+    )?;
+
+    if admin_member.role != GroupRole::OWNER && admin_member.role != GroupRole::ADMIN {
+        msg!("Error: Only admins can revoke invites");
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    // Check seed derivations for invite account
+    if *invite_account_info.key != Invite::find_pda_address(
+        group_account_info.key,
+        &code_hash,
+        program_id,
+    ) {
+        msg!("Error: Invite address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    // Check if invite account exists
+    if invite_account_info.data.borrow().len() == 0 || invite_account_info.owner != program_id {
+        msg!("Error: Invite account is not initialized");
+        return Err(ProgramError::UninitializedAccount);
+    }
+
+    // Rent of the invite goes back to its creator
+    let invite = Invite::try_from_slice(&invite_account_info.data.borrow())?;
+
+    if invite.creator != *creator_info.key {
+        msg!("Error: Creator account does not match the invite");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    close_pda_account(invite_account_info, creator_info)
+}
+
+fn request_to_join(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let requester_info = next_account_info(account_info_iter)?;
+    let group_account_info = next_account_info(account_info_iter)?;
+    let join_request_account_info = next_account_info(account_info_iter)?;
+    let requester_member_account_info = next_account_info(account_info_iter)?;
+    let requester_ban_account_info = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let clock_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    let rent = &Rent::from_account_info(rent_info)?;
+
+    // Check requester signature
+    if !requester_info.is_signer {
+        msg!("Error: Requester signature missing");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    load_membership_scope(program_id, group_account_info)?;
+
+    // Check if requester is a member already
+    if *requester_member_account_info.key != GroupMember::find_pda_address(
+        group_account_info.key,
+        requester_info.key,
+        program_id,
+    ) {
+        msg!("Error: GroupMember address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    if requester_member_account_info.data.borrow().len() > 0 {
+        msg!("Error: Requester is already a member of the group");
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    check_not_banned(program_id, group_account_info, requester_info, requester_ban_account_info)?;
+
+    // Check clock system account
+    if !clock::check_id(clock_info.key) {
+        msg!("Error: Invalid clock system account");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    // Check seed derivations for join-request account
+    let (join_request_address, join_request_bump_seed) = JoinRequest::find_pda_address_with_bump_seed(
+        group_account_info.key,
+        requester_info.key,
+        program_id,
+    );
+
+    if join_request_address != *join_request_account_info.key {
+        msg!("Error: JoinRequest address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    if join_request_account_info.data.borrow().len() > 0 {
+        msg!("Error: JoinRequest account is already initialized");
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    // Create join-request PDA account - paid by the requester, so it can be refunded
+    let join_request_account_signer_seeds: &[&[_]] = &[
+        &group_account_info.key.to_bytes(),
+        &requester_info.key.to_bytes(),
+        JoinRequest::ACCOUNT_ADDRESS_SEED.as_bytes(),
+        &[join_request_bump_seed],
+    ];
+
+    create_pda_account(
+        requester_info,
+        rent,
+        true,
+        JoinRequest::retrieve_size(),
+        program_id,
+        system_program_info,
+        join_request_account_info,
+        join_request_account_signer_seeds,
+    )?;
+
+    let join_request = JoinRequest {
+        timestamp: Clock::from_account_info(clock_info)?.unix_timestamp,
+    };
+    join_request.serialize(&mut &mut join_request_account_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+fn approve_join(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let funder_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let group_account_info = next_account_info(account_info_iter)?;
+    let admin_member_account_info = next_account_info(account_info_iter)?;
+    let requester_info = next_account_info(account_info_iter)?;
+    let join_request_account_info = next_account_info(account_info_iter)?;
+    let requester_member_account_info = next_account_info(account_info_iter)?;
+    let requester_ban_account_info = next_account_info(account_info_iter)?;
+    let requester_user_account_info = next_account_info(account_info_iter)?;
+    let requester_user_conversation_account_info = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    check_join_request_admin(
+        program_id,
+        admin_info,
+        group_account_info,
+        admin_member_account_info,
+        requester_info,
+        join_request_account_info,
+    )?;
+
+    check_not_banned(program_id, group_account_info, requester_info, requester_ban_account_info)?;
+
+    close_pda_account(join_request_account_info, requester_info)?;
+
+    create_group_member_account(
+        program_id,
+        funder_info,
+        group_account_info,
+        requester_info,
+        requester_member_account_info,
+        requester_user_account_info,
+        requester_user_conversation_account_info,
+        rent_info,
+        system_program_info,
+        &GroupMember {
+            role: GroupRole::MEMBER,
+            permissions: GroupRole::default_permissions(GroupRole::MEMBER),
+        },
+    )
+}
+
+fn deny_join(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let admin_info = next_account_info(account_info_iter)?;
+    let group_account_info = next_account_info(account_info_iter)?;
+    let admin_member_account_info = next_account_info(account_info_iter)?;
+    let requester_info = next_account_info(account_info_iter)?;
+    let join_request_account_info = next_account_info(account_info_iter)?;
+
+    check_join_request_admin(
+        program_id,
+        admin_info,
+        group_account_info,
+        admin_member_account_info,
+        requester_info,
+        join_request_account_info,
+    )?;
+
+    close_pda_account(join_request_account_info, requester_info)
+}
+
+/// Check that the join request exists and the signer is an admin of the group
+fn check_join_request_admin(
+    program_id: &Pubkey,
+    admin_info: &AccountInfo,
+    group_account_info: &AccountInfo,
+    admin_member_account_info: &AccountInfo,
+    requester_info: &AccountInfo,
+    join_request_account_info: &AccountInfo,
+) -> ProgramResult {
+    // Check admin signature
+    if !admin_info.is_signer {
+        msg!("Error: Admin signature missing");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    load_membership_scope(program_id, group_account_info)?;
+
+    let admin_member = load_group_member(
+        program_id,
+        group_account_info.key,
+        admin_info,
+        admin_member_account_info,
+    )?;
+
+    if admin_member.role != GroupRole::OWNER && admin_member.role != GroupRole::ADMIN {
+        msg!("Error: Only admins can handle join requests");
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    // Check seed derivations for join-request account
+    if *join_request_account_info.key != JoinRequest::find_pda_address(
+        group_account_info.key,
+        requester_info.key,
+        program_id,
+    ) {
+        msg!("Error: JoinRequest address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    // Check if join-request account exists
+    if join_request_account_info.data.borrow().len() == 0 || join_request_account_info.owner != program_id {
+        msg!("Error: JoinRequest account is not initialized");
+        return Err(ProgramError::UninitializedAccount);
+    }
+
+    Ok(())
+}
+
+fn remove_member(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    conversation_index: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let actor_info = next_account_info(account_info_iter)?;
+    let group_account_info = next_account_info(account_info_iter)?;
+    let actor_member_account_info = next_account_info(account_info_iter)?;
+    let member_info = next_account_info(account_info_iter)?;
+    let member_account_info = next_account_info(account_info_iter)?;
+    let member_user_account_info = next_account_info(account_info_iter)?;
+    let member_user_conversation_account_info = next_account_info(account_info_iter)?;
+
+    // Check actor signature
+    if !actor_info.is_signer {
+        msg!("Error: Acting member signature missing");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    load_membership_scope(program_id, group_account_info)?;
+
+    let actor_member = load_group_member(
+        program_id,
+        group_account_info.key,
+        actor_info,
+        actor_member_account_info,
+    )?;
+
+    let member = load_group_member(
+        program_id,
+        group_account_info.key,
+        member_info,
+        member_account_info,
+    )?;
+
+    // Check if actor may remove the member
+    if !actor_member.can_remove(&member) {
+        msg!("Error: Acting member is not allowed to remove the member");
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    // Lamports of the membership go back to the removed member
+    close_pda_account(member_account_info, member_info)?;
+
+    close_group_user_conversation(
+        program_id,
+        group_account_info,
+        member_info,
+        member_user_account_info,
+        member_user_conversation_account_info,
+        conversation_index,
+    )
+}
+
+fn ban_member(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    conversation_index: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let funder_info = next_account_info(account_info_iter)?;
+    let actor_info = next_account_info(account_info_iter)?;
+    let group_account_info = next_account_info(account_info_iter)?;
+    let actor_member_account_info = next_account_info(account_info_iter)?;
+    let member_info = next_account_info(account_info_iter)?;
+    let member_account_info = next_account_info(account_info_iter)?;
+    let ban_account_info = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let clock_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    let member_user_account_info = next_account_info(account_info_iter)?;
+    let member_user_conversation_account_info = next_account_info(account_info_iter)?;
+
+    let rent = &Rent::from_account_info(rent_info)?;
+
+    // Check actor signature
+    if !actor_info.is_signer {
+        msg!("Error: Acting member signature missing");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    load_membership_scope(program_id, group_account_info)?;
+
+    let actor_member = load_group_member(
+        program_id,
+        group_account_info.key,
+        actor_info,
+        actor_member_account_info,
+    )?;
+
+    // Check seed derivations for group-member account of the banned wallet
+    if *member_account_info.key != GroupMember::find_pda_address(
+        group_account_info.key,
+        member_info.key,
+        program_id,
+    ) {
+        msg!("Error: GroupMember address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    // Wallets that are not members can be banned up front - they are treated as members for the check
+    let is_member = member_account_info.data.borrow().len() > 0
+        && member_account_info.owner == program_id
+        && member_account_info.lamports() > 0;
+
+    let member = match is_member {
+        true => GroupMember::try_from_slice(&member_account_info.data.borrow())?,
+        false => GroupMember {
+            role: GroupRole::MEMBER,
+            permissions: GroupPermission::NONE,
+        },
+    };
+
+    // Closed accounts revived within the transaction are zeroed
+    if member.role == GroupRole::NONE {
+        msg!("Error: Wallet is not a member of the group");
+        return Err(ProgramError::UninitializedAccount);
+    }
+
+    if actor_info.key == member_info.key || !actor_member.can_remove(&member) {
+        msg!("Error: Acting member is not allowed to ban the wallet");
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    // Check clock system account
+    if !clock::check_id(clock_info.key) {
+        msg!("Error: Invalid clock system account");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    // Check seed derivations for group-ban account
+    let (ban_address, ban_bump_seed) = GroupBan::find_pda_address_with_bump_seed(
+        group_account_info.key,
+        member_info.key,
+        program_id,
+    );
+
+    if ban_address != *ban_account_info.key {
+        msg!("Error: GroupBan address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    if ban_account_info.data.borrow().len() > 0 {
+        msg!("Error: Wallet is already banned from the group");
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    // Lamports of the membership go back to the banned member
+    if is_member {
+        close_pda_account(member_account_info, member_info)?;
+    }
+
+    close_group_user_conversation(
+        program_id,
+        group_account_info,
+        member_info,
+        member_user_account_info,
+        member_user_conversation_account_info,
+        conversation_index,
+    )?;
+
+    // Create group-ban PDA account
+    let ban_account_signer_seeds: &[&[_]] = &[
+        &group_account_info.key.to_bytes(),
+        &member_info.key.to_bytes(),
+        GroupBan::ACCOUNT_ADDRESS_SEED.as_bytes(),
+        &[ban_bump_seed],
+    ];
+
+    create_pda_account(
+        funder_info,
+        rent,
+        true,
+        GroupBan::retrieve_size(),
+        program_id,
+        system_program_info,
+        ban_account_info,
+        ban_account_signer_seeds,
+    )?;
+
+    let ban = GroupBan {
+        timestamp: Clock::from_account_info(clock_info)?.unix_timestamp,
+    };
+    ban.serialize(&mut &mut ban_account_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+fn leave_group(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    conversation_index: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let member_info = next_account_info(account_info_iter)?;
+    let group_account_info = next_account_info(account_info_iter)?;
+    let member_account_info = next_account_info(account_info_iter)?;
+    let member_user_account_info = next_account_info(account_info_iter)?;
+    let member_user_conversation_account_info = next_account_info(account_info_iter)?;
+
+    // Check member signature
+    if !member_info.is_signer {
+        msg!("Error: Member signature missing");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    load_membership_scope(program_id, group_account_info)?;
+
+    let member = load_group_member(
+        program_id,
+        group_account_info.key,
+        member_info,
+        member_account_info,
+    )?;
+
+    // The group cannot be left without an owner
+    if member.role == GroupRole::OWNER {
+        msg!("Error: Owner has to transfer the group before leaving");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    close_pda_account(member_account_info, member_info)?;
+
+    close_group_user_conversation(
+        program_id,
+        group_account_info,
+        member_info,
+        member_user_account_info,
+        member_user_conversation_account_info,
+        conversation_index,
+    )
+}
+
+/// Close the user-conversation entry of the member that points at the group, refunding the member
+///
+/// An entry that does not exist is skipped - it was never created for wallets banned up front, or the
+/// member closed it already.
+fn close_group_user_conversation<'a>(
+    program_id: &Pubkey,
+    group_account_info: &AccountInfo<'a>,
+    member_info: &AccountInfo<'a>,
+    member_user_account_info: &AccountInfo<'a>,
+    member_user_conversation_account_info: &AccountInfo<'a>,
+    conversation_index: u64,
+) -> ProgramResult {
+    // Check seed derivations for user-conversation account
+    if *member_user_account_info.key != user_pda_address(program_id, member_user_account_info, member_info.key)? {
+        msg!("Error: Member's user address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    if !is_user_conversation_address(
+        program_id,
+        member_user_conversation_account_info,
+        member_user_account_info.key,
+        conversation_index,
+    )? {
+        msg!("Error: UserConversation address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    if member_user_conversation_account_info.owner != program_id
+        || is_closed_account_data(&member_user_conversation_account_info.data.borrow())
+    {
+        return Ok(());
+    }
+
+    // Check if user-conversation account points to the group
+    let user_conversation = UserConversation::unpack(&member_user_conversation_account_info.data.borrow())?;
+    if user_conversation.conversation_address != *group_account_info.key {
+        msg!("Error: UserConversation account does not belong to the group");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    close_pda_account(member_user_conversation_account_info, member_info)
+}
+
+fn subscribe(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let funder_info = next_account_info(account_info_iter)?;
+    let subscriber_info = next_account_info(account_info_iter)?;
+    let channel_account_info = next_account_info(account_info_iter)?;
+    let subscriber_member_account_info = next_account_info(account_info_iter)?;
+    let subscriber_ban_account_info = next_account_info(account_info_iter)?;
+    let subscriber_user_account_info = next_account_info(account_info_iter)?;
+    let subscriber_user_conversation_account_info = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    // Check subscriber signature
+    if !subscriber_info.is_signer {
+        msg!("Error: Subscriber signature missing");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let channel = load_group(program_id, channel_account_info)?;
+
+    if channel.kind != GroupKind::CHANNEL || channel.community != Pubkey::default() {
+        msg!("Error: Only channels outside of communities can be subscribed to");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    check_not_banned(program_id, channel_account_info, subscriber_info, subscriber_ban_account_info)?;
+
+    create_group_member_account(
+        program_id,
+        funder_info,
+        channel_account_info,
+        subscriber_info,
+        subscriber_member_account_info,
+        subscriber_user_account_info,
+        subscriber_user_conversation_account_info,
+        rent_info,
+        system_program_info,
+        &GroupMember {
+            role: GroupRole::READ_ONLY,
+            permissions: GroupRole::default_permissions(GroupRole::READ_ONLY),
+        },
+    )
+}
+
+fn unsubscribe(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    conversation_index: u64,
+) -> ProgramResult {
+    let channel_account_info = accounts.get(1).ok_or(ProgramError::NotEnoughAccountKeys)?;
+
+    let channel = load_group(program_id, channel_account_info)?;
+
+    if channel.kind != GroupKind::CHANNEL {
+        msg!("Error: Only channels can be unsubscribed from");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    leave_group(program_id, accounts, conversation_index)
+}
+
+fn create_community_account(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    community_index: u32,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let funder_info = next_account_info(account_info_iter)?;
+    let creator_info = next_account_info(account_info_iter)?;
+    let community_account_info = next_account_info(account_info_iter)?;
+    let creator_member_account_info = next_account_info(account_info_iter)?;
+    let creator_user_account_info = next_account_info(account_info_iter)?;
+    let creator_user_conversation_account_info = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    let rent = &Rent::from_account_info(rent_info)?;
+
+    // Check creator signature
+    if !creator_info.is_signer {
+        msg!("Error: Creator signature missing");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Check seed derivations for community account
+    let (community_address, community_bump_seed) = Community::find_pda_address_with_bump_seed(
+        creator_info.key,
+        community_index,
+        program_id,
+    );
+
+    if community_address != *community_account_info.key {
+        msg!("Error: Community address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    if community_account_info.data.borrow().len() > 0 {
+        msg!("Error: Community account is already initialized");
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    // Create community PDA account
+    let community_account_signer_seeds: &[&[_]] = &[
+        &creator_info.key.to_bytes(),
+        &community_index.to_le_bytes(),
+        Community::ACCOUNT_ADDRESS_SEED.as_bytes(),
+        &[community_bump_seed],
+    ];
+
+    create_pda_account(
+        funder_info,
+        rent,
+        true,
+        Community::retrieve_size(),
+        program_id,
+        system_program_info,
+        community_account_info,
+        community_account_signer_seeds,
+    )?;
+
+    let community = Community {
+        account_kind: AccountKind::COMMUNITY,
+        creator: *creator_info.key,
+        index: community_index,
+        owner: *creator_info.key,
+        group_counter: 0,
+    };
+    community.serialize(&mut &mut community_account_info.data.borrow_mut()[..])?;
+
+    // Creator joins as the owner - the user-conversation entry points at the community
+    create_group_member_account(
+        program_id,
+        funder_info,
+        community_account_info,
+        creator_info,
+        creator_member_account_info,
+        creator_user_account_info,
+        creator_user_conversation_account_info,
+        rent_info,
+        system_program_info,
+        &GroupMember {
+            role: GroupRole::OWNER,
+            permissions: GroupPermission::ALL,
+        },
+    )
+}
+
+fn create_community_group_account(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    kind: u8,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let funder_info = next_account_info(account_info_iter)?;
+    let creator_info = next_account_info(account_info_iter)?;
+    let community_account_info = next_account_info(account_info_iter)?;
+    let creator_member_account_info = next_account_info(account_info_iter)?;
+    let group_account_info = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    let rent = &Rent::from_account_info(rent_info)?;
+
+    // Check creator signature
+    if !creator_info.is_signer {
+        msg!("Error: Creator signature missing");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Check group kind
+    if !GroupKind::is_valid(kind) {
+        msg!("Error: Unknown group kind");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let mut community = load_community(program_id, community_account_info)?;
+
+    // Check if creator is an admin of the community
+    let creator_member = load_group_member(
+        program_id,
+        community_account_info.key,
+        creator_info,
+        creator_member_account_info,
+    )?;
+
+    if creator_member.role != GroupRole::OWNER && creator_member.role != GroupRole::ADMIN {
+        msg!("Error: Only admins can create groups in the community");
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    // Check seed derivations for group account - groups of the community are derived from its address
+    let group_index = community.group_counter;
+
+    let (group_address, group_bump_seed) = Group::find_pda_address_with_bump_seed(
+        community_account_info.key,
+        group_index,
+        program_id,
+    );
+
+    if group_address != *group_account_info.key {
+        msg!("Error: Group address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    // Create group PDA account
+    let group_account_signer_seeds: &[&[_]] = &[
+        &community_account_info.key.to_bytes(),
+        &group_index.to_le_bytes(),
+        Group::ACCOUNT_ADDRESS_SEED.as_bytes(),
+        &[group_bump_seed],
+    ];
+
+    create_pda_account(
+        funder_info,
+        rent,
+        true,
+        Group::retrieve_size(),
+        program_id,
+        system_program_info,
+        group_account_info,
+        group_account_signer_seeds,
+    )?;
+
+    // Ownership stays with the community - the group does not keep a copy that would go stale on transfer
+    let group = Group {
+        account_kind: AccountKind::GROUP,
+        creator: *community_account_info.key,
+        index: group_index,
+        owner: Pubkey::default(),
+        message_counter: 0,
+        kind,
+        community: *community_account_info.key,
+    };
+    group.serialize(&mut &mut group_account_info.data.borrow_mut()[..])?;
+
+    // Increment and store the number of groups the community account has
+    community.group_counter = community.group_counter.checked_add(1).ok_or(InstantMessagingError::CounterOverflow)?;
+    community.serialize(&mut &mut community_account_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+fn create_community_group_user_conversation(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let funder_info = next_account_info(account_info_iter)?;
+    let member_info = next_account_info(account_info_iter)?;
+    let community_account_info = next_account_info(account_info_iter)?;
+    let member_account_info = next_account_info(account_info_iter)?;
+    let group_account_info = next_account_info(account_info_iter)?;
+    let user_account_info = next_account_info(account_info_iter)?;
+    let user_conversation_account_info = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    // Check member signature
+    if !member_info.is_signer {
+        msg!("Error: Member signature missing");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    load_community(program_id, community_account_info)?;
+
+    // Check if signer is a member of the community
+    load_group_member(
+        program_id,
+        community_account_info.key,
+        member_info,
+        member_account_info,
+    )?;
+
+    // Check if group belongs to the community
+    let group = load_group(program_id, group_account_info)?;
+
+    if group.community != *community_account_info.key {
+        msg!("Error: Group does not belong to the community");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // Check if member's user pda account exists
+    if *user_account_info.key != user_pda_address(program_id, user_account_info, member_info.key)? {
+        msg!("Error: Member's user address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    if user_account_info.data.borrow().len() == 0 || user_account_info.owner != program_id {
+        msg!("Error: Member's user account is not initialized");
+        return Err(ProgramError::UninitializedAccount);
+    }
+
+    add_user_conversation_entry(
+        program_id,
+        funder_info,
+        group_account_info.key,
+        user_account_info,
+        user_conversation_account_info,
+        rent_info,
+        system_program_info,
+    )
+}
+
+fn set_conversation_metadata(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    title: String,
+    description: String,
+    avatar_uri: String,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let funder_info = next_account_info(account_info_iter)?;
+    let sender_info = next_account_info(account_info_iter)?;
+    let metadata_account_info = next_account_info(account_info_iter)?;
+    let sender_user_account_info = next_account_info(account_info_iter)?;
+    let receiver_user_account_info = next_account_info(account_info_iter)?;
+    let conversation_account_info = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let clock_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    // Check sender signature
+    if !sender_info.is_signer {
+        msg!("Error: Sender signature missing");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    check_conversation_participant(
+        program_id,
+        sender_info,
+        sender_user_account_info,
+        receiver_user_account_info,
+        conversation_account_info,
+    )?;
+
+    write_conversation_metadata(
+        program_id,
+        funder_info,
+        sender_info,
+        conversation_account_info,
+        metadata_account_info,
+        rent_info,
+        clock_info,
+        system_program_info,
+        title,
+        description,
+        avatar_uri,
+    )
+}
+
+fn set_group_metadata(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    title: String,
+    description: String,
+    avatar_uri: String,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let funder_info = next_account_info(account_info_iter)?;
+    let editor_info = next_account_info(account_info_iter)?;
+    let metadata_account_info = next_account_info(account_info_iter)?;
+    let group_account_info = next_account_info(account_info_iter)?;
+    let editor_member_account_info = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let clock_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    // Check editor signature
+    if !editor_info.is_signer {
+        msg!("Error: Editor signature missing");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Members of community groups are stored on the community
+    let scope_address = if AccountKind::of(&group_account_info.data.borrow()) == AccountKind::COMMUNITY {
+        load_community(program_id, group_account_info)?;
+        *group_account_info.key
+    } else {
+        load_group(program_id, group_account_info)?.membership_scope(group_account_info.key)
+    };
+
+    // Check if editor is allowed to edit the metadata
+    check_member_permission(
+        program_id,
+        &scope_address,
+        editor_info,
+        editor_member_account_info,
+        GroupPermission::EDIT_METADATA,
+    )?;
+
+    write_conversation_metadata(
+        program_id,
+        funder_info,
+        editor_info,
+        group_account_info,
+        metadata_account_info,
+        rent_info,
+        clock_info,
+        system_program_info,
+        title,
+        description,
+        avatar_uri,
+    )
+}
+
+/// Create the conversation-metadata account if not exist and store the metadata - creator and creation time are kept on update
+#[allow(clippy::too_many_arguments)]
+fn write_conversation_metadata<'a>(
+    program_id: &Pubkey,
+    funder_info: &AccountInfo<'a>,
+    editor_info: &AccountInfo<'a>,
+    conversation_account_info: &AccountInfo<'a>,
+    metadata_account_info: &AccountInfo<'a>,
+    rent_info: &AccountInfo<'a>,
+    clock_info: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+    title: String,
+    description: String,
+    avatar_uri: String,
+) -> ProgramResult {
+    let rent = &Rent::from_account_info(rent_info)?;
+    let clock = Clock::from_account_info(clock_info)?;
+
+    // Check seed derivations for conversation-metadata account
+    let (metadata_address, metadata_bump_seed) = ConversationMetadata::find_pda_address_with_bump_seed(
+        conversation_account_info.key,
+        program_id,
+    );
+
+    if metadata_address != *metadata_account_info.key {
+        msg!("Error: ConversationMetadata address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    let mut metadata = ConversationMetadata {
+        creator: *editor_info.key,
+        created_at: clock.unix_timestamp,
+        title,
+        description,
+        avatar_uri,
+    };
+
+    // Check lengths of the metadata fields
+    if !metadata.is_valid() {
+        msg!("Error: Conversation metadata is too long");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if metadata_account_info.data.borrow().len() == 0 {
+        // Create conversation-metadata PDA account
+        let metadata_account_signer_seeds: &[&[_]] = &[
+            &conversation_account_info.key.to_bytes(),
+            ConversationMetadata::ACCOUNT_ADDRESS_SEED.as_bytes(),
+            &[metadata_bump_seed],
+        ];
+
+        create_pda_account(
+            funder_info,
+            rent,
+            true,
+            ConversationMetadata::retrieve_size(),
+            program_id,
+            system_program_info,
+            metadata_account_info,
+            metadata_account_signer_seeds,
+        )?;
+    } else {
+        let existing_metadata = ConversationMetadata::unpack(&metadata_account_info.data.borrow())?;
+
+        metadata.creator = existing_metadata.creator;
+        metadata.created_at = existing_metadata.created_at;
+    }
+
+    // Clear leftovers of longer previous values before storing the metadata
+    let mut data = metadata_account_info.data.borrow_mut();
+    data.fill(0);
+    metadata.serialize(&mut &mut data[..])?;
+
+    Ok(())
+}
+
+fn pin_message(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    message_index: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let funder_info = next_account_info(account_info_iter)?;
+    let sender_info = next_account_info(account_info_iter)?;
+    let pinned_messages_account_info = next_account_info(account_info_iter)?;
+    let sender_user_account_info = next_account_info(account_info_iter)?;
+    let receiver_user_account_info = next_account_info(account_info_iter)?;
+    let conversation_account_info = next_account_info(account_info_iter)?;
+    let message_account_info = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    // Check sender signature
+    if !sender_info.is_signer {
+        msg!("Error: Sender signature missing");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    check_conversation_participant(
+        program_id,
+        sender_info,
+        sender_user_account_info,
+        receiver_user_account_info,
+        conversation_account_info,
+    )?;
+
+    add_pinned_message(
+        program_id,
+        funder_info,
+        conversation_account_info,
+        pinned_messages_account_info,
+        message_account_info,
+        rent_info,
+        system_program_info,
+        message_index,
+    )
+}
+
+fn unpin_message(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    message_index: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let sender_info = next_account_info(account_info_iter)?;
+    let pinned_messages_account_info = next_account_info(account_info_iter)?;
+    let sender_user_account_info = next_account_info(account_info_iter)?;
+    let receiver_user_account_info = next_account_info(account_info_iter)?;
+    let conversation_account_info = next_account_info(account_info_iter)?;
+
+    // Check sender signature
+    if !sender_info.is_signer {
+        msg!("Error: Sender signature missing");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    check_conversation_participant(
+        program_id,
+        sender_info,
+        sender_user_account_info,
+        receiver_user_account_info,
+        conversation_account_info,
+    )?;
+
+    remove_pinned_message(
+        program_id,
+        conversation_account_info,
+        pinned_messages_account_info,
+        message_index,
+    )
+}
+
+fn pin_group_message(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    message_index: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let funder_info = next_account_info(account_info_iter)?;
+    let member_info = next_account_info(account_info_iter)?;
+    let pinned_messages_account_info = next_account_info(account_info_iter)?;
+    let group_account_info = next_account_info(account_info_iter)?;
+    let member_account_info = next_account_info(account_info_iter)?;
+    let message_account_info = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+
+    // Check member signature
+    if !member_info.is_signer {
+        msg!("Error: Member signature missing");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let group = load_group(program_id, group_account_info)?;
+
+    // Check if member is allowed to pin messages
+    check_member_permission(
+        program_id,
+        &group.membership_scope(group_account_info.key),
+        member_info,
+        member_account_info,
+        GroupPermission::PIN,
+    )?;
+
+    add_pinned_message(
+        program_id,
+        funder_info,
+        group_account_info,
+        pinned_messages_account_info,
+        message_account_info,
+        rent_info,
+        system_program_info,
+        message_index,
+    )
+}
+
+fn unpin_group_message(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    message_index: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let member_info = next_account_info(account_info_iter)?;
+    let pinned_messages_account_info = next_account_info(account_info_iter)?;
+    let group_account_info = next_account_info(account_info_iter)?;
+    let member_account_info = next_account_info(account_info_iter)?;
+
+    // Check member signature
+    if !member_info.is_signer {
+        msg!("Error: Member signature missing");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let group = load_group(program_id, group_account_info)?;
+
+    // Check if member is allowed to unpin messages
+    check_member_permission(
+        program_id,
+        &group.membership_scope(group_account_info.key),
+        member_info,
+        member_account_info,
+        GroupPermission::PIN,
+    )?;
+
+    remove_pinned_message(
+        program_id,
+        group_account_info,
+        pinned_messages_account_info,
+        message_index,
+    )
+}
+
+/// Create the pinned-messages account if not exist and add the message of the conversation to it
+#[allow(clippy::too_many_arguments)]
+fn add_pinned_message<'a>(
+    program_id: &Pubkey,
+    funder_info: &AccountInfo<'a>,
+    conversation_account_info: &AccountInfo<'a>,
+    pinned_messages_account_info: &AccountInfo<'a>,
+    message_account_info: &AccountInfo<'a>,
+    rent_info: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+    message_index: u64,
+) -> ProgramResult {
+    let rent = &Rent::from_account_info(rent_info)?;
+
+    // Check if message belongs to the conversation - messages not yet migrated keep the v1 address,
+    // which only exists for indexes in the u32 range
+    let is_v1_message = match u32::try_from(message_index) {
+        Ok(message_index) => *message_account_info.key == Message::find_pda_address(
+            conversation_account_info.key,
+            message_index,
+            program_id,
+        ),
+        Err(_) => false,
+    };
+    if *message_account_info.key != Message::find_pda_address_v2(
+        conversation_account_info.key,
+        message_index,
+        program_id,
+    ) && !is_v1_message {
+        msg!("Error: Message address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    if is_closed_account_data(&message_account_info.data.borrow()) {
+        msg!("Error: Message account is not initialized");
+        return Err(ProgramError::UninitializedAccount);
+    }
+
+    if message_account_info.owner != program_id {
+        msg!("Error: Message account does not have the correct program id");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    // Check seed derivations for pinned-messages account
+    let (pinned_messages_address, pinned_messages_bump_seed) = PinnedMessages::find_pda_address_with_bump_seed(
+        conversation_account_info.key,
+        program_id,
+    );
+
+    if pinned_messages_address != *pinned_messages_account_info.key {
+        msg!("Error: PinnedMessages address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    let mut pinned_messages = if pinned_messages_account_info.data.borrow().len() == 0 {
+        // Create pinned-messages PDA account
+        let pinned_messages_account_signer_seeds: &[&[_]] = &[
+            &conversation_account_info.key.to_bytes(),
+            PinnedMessages::ACCOUNT_ADDRESS_SEED.as_bytes(),
+            &[pinned_messages_bump_seed],
+        ];
+
+        create_pda_account(
+            funder_info,
+            rent,
+            true,
+            PinnedMessages::retrieve_size(),
+            program_id,
+            system_program_info,
+            pinned_messages_account_info,
+            pinned_messages_account_signer_seeds,
+        )?;
+
+        PinnedMessages::default()
+    } else {
+        PinnedMessages::unpack(&pinned_messages_account_info.data.borrow())?
+    };
+
+    if pinned_messages.message_indexes.contains(&message_index) {
+        msg!("Error: Message is already pinned");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if pinned_messages.is_full() {
+        msg!("Error: Conversation has too many pinned messages");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    pinned_messages.message_indexes.push(message_index);
+    pinned_messages.serialize(&mut &mut pinned_messages_account_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+/// Remove the message from the pinned-messages account of the conversation
+fn remove_pinned_message(
+    program_id: &Pubkey,
+    conversation_account_info: &AccountInfo,
+    pinned_messages_account_info: &AccountInfo,
+    message_index: u64,
+) -> ProgramResult {
+    // Check seed derivations for pinned-messages account
+    if *pinned_messages_account_info.key != PinnedMessages::find_pda_address(
+        conversation_account_info.key,
+        program_id,
+    ) {
+        msg!("Error: PinnedMessages address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    if pinned_messages_account_info.data.borrow().len() == 0 {
+        msg!("Error: PinnedMessages account is not initialized");
+        return Err(ProgramError::UninitializedAccount);
+    }
+
+    let mut pinned_messages = PinnedMessages::unpack(&pinned_messages_account_info.data.borrow())?;
+
+    let position = match pinned_messages.message_indexes.iter().position(|index| *index == message_index) {
+        Some(position) => position,
+        None => {
+            msg!("Error: Message is not pinned");
+            return Err(ProgramError::InvalidArgument);
+        }
+    };
+
+    pinned_messages.message_indexes.remove(position);
+
+    // Clear the index left behind by the shorter list
+    let mut data = pinned_messages_account_info.data.borrow_mut();
+    data.fill(0);
+    pinned_messages.serialize(&mut &mut data[..])?;
+
+    Ok(())
+}
+
+/// Check that the sender is one of the two participants of the conversation
+fn check_conversation_participant(
+    program_id: &Pubkey,
+    sender_info: &AccountInfo,
+    sender_user_account_info: &AccountInfo,
+    receiver_user_account_info: &AccountInfo,
+    conversation_account_info: &AccountInfo,
+) -> ProgramResult {
+    // Check if conversation account exists
+    if conversation_account_info.data.borrow().len() == 0 {
+        msg!("Error: Conversation account is not initialized");
+        return Err(ProgramError::UninitializedAccount);
+    }
+
+    // Check is conversation account has correct program ids
+    if conversation_account_info.owner != program_id {
+        msg!("Error: Conversation account does not have the correct program id");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    // Check if sender is a participant of the conversation
+    if *sender_user_account_info.key != user_pda_address(program_id, sender_user_account_info, sender_info.key)? {
+        msg!("Error: Sender's user address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    if *conversation_account_info.key != conversation_pda_address(
+        program_id,
+        conversation_account_info,
+        sender_user_account_info.key,
+        receiver_user_account_info.key,
+    )? {
+        msg!("Error: Conversation address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    Ok(())
+}
+
+/// Get expected address of the user account - accounts storing the bump seed are checked with
+/// `create_program_address`, the canonical bump seed is searched for the others
+fn user_pda_address(
+    program_id: &Pubkey,
+    user_account_info: &AccountInfo,
+    user_wallet_address: &Pubkey,
+) -> Result<Pubkey, ProgramError> {
+    let data = user_account_info.data.borrow();
+
+    if user_account_info.owner == program_id && data.len() == User::retrieve_size() {
+        let user = User::try_from_slice(&data)?;
+        return Ok(User::create_pda_address(user_wallet_address, user.bump_seed, program_id)?);
+    }
+
+    Ok(User::find_pda_address(user_wallet_address, program_id))
+}
+
+/// Get expected address of the conversation account - see `user_pda_address`
+fn conversation_pda_address(
+    program_id: &Pubkey,
+    conversation_account_info: &AccountInfo,
+    first_user_pda_address: &Pubkey,
+    second_user_pda_address: &Pubkey,
+) -> Result<Pubkey, ProgramError> {
+    let data = conversation_account_info.data.borrow();
+
+    if conversation_account_info.owner == program_id && data.len() == Conversation::retrieve_size() {
+        let conversation = Conversation::try_from_slice(&data)?;
+        return Ok(Conversation::create_pda_address(
+            first_user_pda_address,
+            second_user_pda_address,
+            conversation.bump_seed,
+            program_id,
+        )?);
+    }
+
+    Ok(Conversation::find_pda_address(first_user_pda_address, second_user_pda_address, program_id))
+}
+
+/// Check the address of the user-conversation account - accounts are created at the v2 address,
+/// accounts created before keep the v1 address until migrated. See `user_pda_address` for the bump seed
+fn is_user_conversation_address(
+    program_id: &Pubkey,
+    user_conversation_account_info: &AccountInfo,
+    user_pda_address: &Pubkey,
+    conversation_index: u64,
+) -> Result<bool, ProgramError> {
+    let address = user_conversation_account_info.key;
+    let data = user_conversation_account_info.data.borrow();
+
+    if user_conversation_account_info.owner == program_id && data.len() == UserConversation::retrieve_size() {
+        let user_conversation = UserConversation::try_from_slice(&data)?;
+
+        let v2_address = UserConversation::create_pda_address_v2(
+            user_pda_address,
+            conversation_index,
+            user_conversation.bump_seed,
+            program_id,
+        );
+        if matches!(v2_address, Ok(v2_address) if v2_address == *address) {
+            return Ok(true);
+        }
+
+        // v1 addresses were only derived while the conversation counters were `u32`
+        let v1_address = u32::try_from(conversation_index).map(|conversation_index| UserConversation::create_pda_address(
+            user_pda_address,
+            conversation_index,
+            user_conversation.bump_seed,
+            program_id,
+        ));
+        return Ok(matches!(v1_address, Ok(Ok(v1_address)) if v1_address == *address));
+    }
+
+    // Legacy layouts predate the v2 seeds
+    if user_conversation_account_info.owner == program_id && !is_closed_account_data(&data) {
+        return Ok(matches!(
+            u32::try_from(conversation_index),
+            Ok(conversation_index) if *address == UserConversation::find_pda_address(user_pda_address, conversation_index, program_id)
+        ));
+    }
+
+    Ok(*address == UserConversation::find_pda_address_v2(user_pda_address, conversation_index, program_id))
+}
+
+/// Check if the account data is empty or zeroed - accounts closed by `close_pda_account` keep zeroed
+/// data until the end of the transaction, and keep it for good if they are funded again in the meantime
+fn is_closed_account_data(data: &[u8]) -> bool {
+    data.iter().all(|byte| *byte == 0)
+}
+
+/// Check that the wallet is a member of the membership scope and has the permission
+fn check_member_permission(
+    program_id: &Pubkey,
+    scope_address: &Pubkey,
+    member_info: &AccountInfo,
+    member_account_info: &AccountInfo,
+    permission: u8,
+) -> ProgramResult {
+    let member = load_group_member(
+        program_id,
+        scope_address,
+        member_info,
+        member_account_info,
+    )?;
+
+    if !member.has_permission(permission) {
+        msg!("Error: Member does not have the required permission");
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    Ok(())
+}
+
+/// Check that the wallet is not banned from the group
+fn check_not_banned(
+    program_id: &Pubkey,
+    group_account_info: &AccountInfo,
+    wallet_info: &AccountInfo,
+    ban_account_info: &AccountInfo,
+) -> ProgramResult {
+    // Check seed derivations for group-ban account
+    if *ban_account_info.key != GroupBan::find_pda_address(
+        group_account_info.key,
+        wallet_info.key,
+        program_id,
+    ) {
+        msg!("Error: GroupBan address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    if ban_account_info.data.borrow().len() > 0 && ban_account_info.owner == program_id {
+        msg!("Error: Wallet is banned from the group");
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    Ok(())
+}
+
+/// Check and deserialize the group account
+fn load_group(
+    program_id: &Pubkey,
+    group_account_info: &AccountInfo,
+) -> Result<Group, ProgramError> {
+    // Check if group account exists
+    if group_account_info.data.borrow().len() == 0 {
+        msg!("Error: Group account is not initialized");
+        return Err(ProgramError::UninitializedAccount);
+    }
+
+    // Check is group account has correct program ids
+    if group_account_info.owner != program_id {
+        msg!("Error: Group account does not have the correct program id");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    // Check kind of the account
+    if AccountKind::of(&group_account_info.data.borrow()) != AccountKind::GROUP {
+        msg!("Error: Account is not a group");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let group = Group::try_from_slice(&group_account_info.data.borrow())?;
+
+    // Check seed derivations for group account
+    if *group_account_info.key != Group::find_pda_address(&group.creator, group.index, program_id) {
+        msg!("Error: Group address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    Ok(group)
+}
+
+/// Account that holds a membership set - a standalone group or a community
+enum MembershipScope {
+    Group(Group),
+    Community(Community),
+}
+
+impl MembershipScope {
+    fn owner(&self) -> Pubkey {
+        match self {
+            MembershipScope::Group(group) => group.owner,
+            MembershipScope::Community(community) => community.owner,
+        }
+    }
+
+    fn set_owner(&mut self, owner: Pubkey) {
+        match self {
+            MembershipScope::Group(group) => group.owner = owner,
+            MembershipScope::Community(community) => community.owner = owner,
+        }
+    }
+
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        match self {
+            MembershipScope::Group(group) => group.serialize(writer),
+            MembershipScope::Community(community) => community.serialize(writer),
+        }
+    }
+}
+
+/// Check and deserialize the account holding the membership set - groups of a community share its members
+fn load_membership_scope(
+    program_id: &Pubkey,
+    scope_account_info: &AccountInfo,
+) -> Result<MembershipScope, ProgramError> {
+    if AccountKind::of(&scope_account_info.data.borrow()) == AccountKind::COMMUNITY {
+        return Ok(MembershipScope::Community(load_community(program_id, scope_account_info)?));
+    }
+
+    let group = load_group(program_id, scope_account_info)?;
+
+    if group.community != Pubkey::default() {
+        msg!("Error: Members of a community group are managed by the community");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    Ok(MembershipScope::Group(group))
+}
+
+/// Check and deserialize the community account
+fn load_community(
+    program_id: &Pubkey,
+    community_account_info: &AccountInfo,
+) -> Result<Community, ProgramError> {
+    // Check if community account exists
+    if community_account_info.data.borrow().len() == 0 {
+        msg!("Error: Community account is not initialized");
+        return Err(ProgramError::UninitializedAccount);
+    }
+
+    // Check is community account has correct program ids
+    if community_account_info.owner != program_id {
+        msg!("Error: Community account does not have the correct program id");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    // Check kind of the account
+    if AccountKind::of(&community_account_info.data.borrow()) != AccountKind::COMMUNITY {
+        msg!("Error: Account is not a community");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let community = Community::try_from_slice(&community_account_info.data.borrow())?;
+
+    // Check seed derivations for community account
+    if *community_account_info.key != Community::find_pda_address(&community.creator, community.index, program_id) {
+        msg!("Error: Community address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    Ok(community)
+}
+
+/// Check and deserialize the group-member account of the given wallet within the membership scope
+fn load_group_member(
+    program_id: &Pubkey,
+    scope_address: &Pubkey,
+    member_info: &AccountInfo,
+    member_account_info: &AccountInfo,
+) -> Result<GroupMember, ProgramError> {
+    // Check seed derivations for group-member account
+    if *member_account_info.key != GroupMember::find_pda_address(
+        scope_address,
+        member_info.key,
+        program_id,
+    ) {
+        msg!("Error: GroupMember address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    // Check if wallet is a member of the group - closed accounts have no lamports left
+    if member_account_info.data.borrow().len() == 0
+        || member_account_info.owner != program_id
+        || member_account_info.lamports() == 0
+    {
+        msg!("Error: Wallet is not a member of the group");
+        return Err(ProgramError::UninitializedAccount);
+    }
+
+    let member = GroupMember::try_from_slice(&member_account_info.data.borrow())?;
+
+    // Closed accounts revived within the transaction are zeroed - role NONE grants nothing
+    if member.role == GroupRole::NONE {
+        msg!("Error: Wallet is not a member of the group");
+        return Err(ProgramError::UninitializedAccount);
+    }
+
+    Ok(member)
+}
+
+/// Create the group-member account and connect the group with the user-conversation list of the member
+#[allow(clippy::too_many_arguments)]
+fn create_group_member_account<'a>(
+    program_id: &Pubkey,
+    funder_info: &AccountInfo<'a>,
+    group_account_info: &AccountInfo<'a>,
+    member_info: &AccountInfo<'a>,
+    member_account_info: &AccountInfo<'a>,
+    user_account_info: &AccountInfo<'a>,
+    user_conversation_account_info: &AccountInfo<'a>,
+    rent_info: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+    member: &GroupMember,
+) -> ProgramResult {
+    let rent = &Rent::from_account_info(rent_info)?;
+
+    // Check seed derivations for group-member account
+    let (member_address, member_bump_seed) = GroupMember::find_pda_address_with_bump_seed(
+        group_account_info.key,
+        member_info.key,
+        program_id,
+    );
+
+    if member_address != *member_account_info.key {
+        msg!("Error: GroupMember address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    if member_account_info.data.borrow().len() > 0 {
+        msg!("Error: Wallet is already a member of the group");
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    // Check if member's user pda account exists
+    if *user_account_info.key != user_pda_address(program_id, user_account_info, member_info.key)? {
+        msg!("Error: Member's user address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    if user_account_info.data.borrow().len() == 0 || user_account_info.owner != program_id {
+        msg!("Error: Member's user account is not initialized");
+        return Err(ProgramError::UninitializedAccount);
+    }
+
+    // Create group-member PDA account
+    let member_account_signer_seeds: &[&[_]] = &[
+        &group_account_info.key.to_bytes(),
+        &member_info.key.to_bytes(),
+        GroupMember::ACCOUNT_ADDRESS_SEED.as_bytes(),
+        &[member_bump_seed],
+    ];
+
+    create_pda_account(
+        funder_info,
+        rent,
+        true,
+        GroupMember::retrieve_size(),
+        program_id,
+        system_program_info,
+        member_account_info,
+        member_account_signer_seeds,
+    )?;
+
+    member.serialize(&mut &mut member_account_info.data.borrow_mut()[..])?;
+
+    add_user_conversation_entry(
+        program_id,
+        funder_info,
+        group_account_info.key,
+        user_account_info,
+        user_conversation_account_info,
+        rent_info,
+        system_program_info,
+    )
+}
+
+/// Create the next user-conversation account of the user and point it at the conversation, group or community
+fn add_user_conversation_entry<'a>(
+    program_id: &Pubkey,
+    funder_info: &AccountInfo<'a>,
+    conversation_address: &Pubkey,
+    user_account_info: &AccountInfo<'a>,
+    user_conversation_account_info: &AccountInfo<'a>,
+    rent_info: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+) -> ProgramResult {
+    // Create user-conversation account
+    let user_conversation_counter = UserView::new(&user_account_info.data.borrow()[..])?.conversation_counter();
+
+    create_user_conversation_account(
+        program_id,
+        &[
+            funder_info.clone(),
+            user_conversation_account_info.clone(),
+            user_account_info.clone(),
+            rent_info.clone(),
+            system_program_info.clone(),
+        ],
+        user_conversation_counter,
+    )?;
+
+    // Assign conversation address to user-conversation account
+    let mut user_conversation = UserConversation::unpack(&user_conversation_account_info.data.borrow())?;
+    user_conversation.conversation_address = *conversation_address;
+    user_conversation.pack(&mut user_conversation_account_info.data.borrow_mut())?;
+
+    // Increment and store the number of conversations the user account has
+    let next_user_conversation_counter = user_conversation_counter.checked_add(1).ok_or(InstantMessagingError::CounterOverflow)?;
+    UserView::new(&mut user_account_info.data.borrow_mut()[..])?.set_conversation_counter(next_user_conversation_counter)?;
 
     Ok(())
 }
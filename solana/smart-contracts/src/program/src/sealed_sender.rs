@@ -0,0 +1,112 @@
+//! Off-chain codec of sealed sender messages
+//!
+//! The envelope carries the actual sender inside the encrypted content, the hash chain gives the
+//! ephemeral keypairs that sign the messages on-chain.
+
+use {
+    crate::{
+        encryption::{
+            ed25519_address,
+            CodecError,
+        },
+        state::SealedSenderCredential,
+    },
+    borsh::{BorshDeserialize, BorshSerialize},
+    solana_program::{
+        hash::{
+            hash,
+            Hash,
+        },
+        pubkey::Pubkey,
+    },
+};
+
+/// Plaintext of a sealed sender message - encrypted to the receiver before it is sent
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct SealedSenderEnvelope {
+    /// Wallet address of the actual sender
+    pub sender: Pubkey,
+    /// Message type of the inner content
+    pub message_type: u8,
+    /// Inner content
+    pub content: Vec<u8>,
+}
+
+impl SealedSenderEnvelope {
+    /// Encode envelope into bytes to be encrypted
+    pub fn encode(&self) -> Result<Vec<u8>, CodecError> {
+        Ok(self.try_to_vec()?)
+    }
+
+    /// Decode envelope from decrypted bytes
+    pub fn decode(data: &[u8]) -> Result<Self, CodecError> {
+        Ok(Self::try_from_slice(data)?)
+    }
+}
+
+/// Hash chain shared by the participants of a sealed sender conversation
+///
+/// Each element is the ed25519 seed of an ephemeral keypair. The address of the first keypair is stored in the
+/// `SealedSenderCredential`, every sealed message is signed by the current keypair and names the next one.
+#[derive(Clone, Debug)]
+pub struct SealedSenderChain {
+    hashes: Vec<Hash>,
+}
+
+impl SealedSenderChain {
+    /// Build a chain that authorizes `length` messages
+    pub fn new(seed: &[u8], length: usize) -> Self {
+        let mut hashes = Vec::with_capacity(length + 1);
+        hashes.push(hash(seed));
+
+        for i in 0..length {
+            let next = hash(hashes[i].as_ref());
+            hashes.push(next);
+        }
+
+        Self { hashes }
+    }
+
+    /// Get number of messages the chain authorizes
+    pub fn len(&self) -> usize {
+        self.hashes.len() - 1
+    }
+
+    /// Check if the chain authorizes no messages
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get ed25519 seed of the keypair that signs the message with the given index since the credential was set
+    pub fn authority_seed(&self, index: usize) -> Option<[u8; 32]> {
+        self.len()
+            .checked_sub(index + 1)
+            .map(|position| self.hashes[position].to_bytes())
+    }
+
+    /// Get address of the keypair that signs the message with the given index since the credential was set
+    ///
+    /// `authority(0)` is stored with `SetSealedSenderCredential`. The message after the last one of the chain
+    /// has no authority, so the last message names `Pubkey::default()` and the credential must be set again.
+    pub fn authority(&self, index: usize) -> Option<Pubkey> {
+        self.authority_seed(index).map(|seed| ed25519_address(&seed))
+    }
+
+    /// Get chain hash stored with the authority of the given index
+    ///
+    /// `chain_hash(len())` belongs to the used-up credential and is `Hash::default()`.
+    pub fn chain_hash(&self, index: usize) -> Option<Hash> {
+        if index > self.len() {
+            return None;
+        }
+
+        let mut chain_hash = Hash::default();
+
+        for next_index in (index + 1..=self.len()).rev() {
+            let next_authority = self.authority(next_index).unwrap_or_default();
+            chain_hash = SealedSenderCredential::chain_link(&next_authority, &chain_hash);
+        }
+
+        Some(chain_hash)
+    }
+}
@@ -1,27 +1,132 @@
 use {
+    crate::error::InstantMessagingError,
     borsh::{BorshDeserialize, BorshSerialize},
     solana_program::{
         clock::UnixTimestamp,
+        hash::{
+            hashv,
+            Hash,
+        },
+        program_error::ProgramError,
         pubkey::{
             Pubkey,
+            PubkeyError,
+        },
+    },
+    std::{
+        convert::{
+            TryFrom,
+            TryInto,
+        },
+        io,
+        mem,
+        ops::{
+            Deref,
+            DerefMut,
         },
     },
-    std::mem,
 };
 
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + mem::size_of::<u32>()].try_into().unwrap())
+}
+
+fn write_u32(data: &mut [u8], offset: usize, value: u32) {
+    data[offset..offset + mem::size_of::<u32>()].copy_from_slice(&value.to_le_bytes());
+}
+
+/// Size of the `u32` counter of user and conversation accounts created before the counters were widened
+const LEGACY_COUNTER_SIZE: usize = mem::size_of::<u32>();
+
+/// Read the counter of user and conversation accounts - legacy layouts hold a `u32` counter
+fn read_counter(data: &[u8]) -> u64 {
+    if data.len() == LEGACY_COUNTER_SIZE {
+        return u64::from(read_u32(data, 0));
+    }
+
+    u64::from_le_bytes(data[..mem::size_of::<u64>()].try_into().unwrap())
+}
+
+/// Write the counter of user and conversation accounts - fails if legacy layouts cannot hold the value
+fn write_counter(data: &mut [u8], value: u64) -> Result<(), ProgramError> {
+    if data.len() == LEGACY_COUNTER_SIZE {
+        let value = u32::try_from(value).map_err(|_| InstantMessagingError::CounterOverflow)?;
+        write_u32(data, 0, value);
+        return Ok(());
+    }
+
+    data[..mem::size_of::<u64>()].copy_from_slice(&value.to_le_bytes());
+    Ok(())
+}
+
+/// Read the bump seed following the counter - `None` for legacy accounts without one
+fn read_counter_bump_seed(data: &[u8]) -> Option<u8> {
+    match data.len() {
+        LEGACY_COUNTER_SIZE => None,
+        len => data.get(len - 1).copied(),
+    }
+}
+
+/// Narrow the counter for legacy layouts
+fn legacy_counter(counter: u64) -> Result<u32, io::Error> {
+    u32::try_from(counter).map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "Counter does not fit into the legacy account"))
+}
+
 /// Define a user account structure
-#[derive(BorshSerialize, BorshDeserialize, Debug)]
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default)]
 pub struct User {
     /// Number of conversations the user has
-    pub conversation_counter: u32,
+    pub conversation_counter: u64,
+    /// Canonical bump seed of the user address
+    pub bump_seed: u8,
 }
 
 impl User {
     pub const ACCOUNT_ADDRESS_SEED: &'static str = "user";
 
+    /// Size of user accounts created before the bump seed was stored
+    pub const LEGACY_SIZE: usize = LEGACY_COUNTER_SIZE;
+
     /// Get size of user account
     pub fn retrieve_size() -> usize {
-        mem::size_of::<User>()
+        Self::default().try_to_vec().unwrap().len()
+    }
+
+    /// Deserialize user - legacy accounts hold a `u32` conversation counter without the bump seed
+    pub fn unpack(data: &[u8]) -> Result<Self, std::io::Error> {
+        if data.len() == Self::LEGACY_SIZE {
+            return Ok(Self {
+                conversation_counter: u32::try_from_slice(data)?.into(),
+                ..Self::default()
+            });
+        }
+
+        Self::try_from_slice(data)
+    }
+
+    /// Serialize user - legacy accounts keep their layout and fail for counters beyond `u32`
+    pub fn pack(&self, mut data: &mut [u8]) -> Result<(), std::io::Error> {
+        if data.len() == Self::LEGACY_SIZE {
+            return legacy_counter(self.conversation_counter)?.serialize(&mut data);
+        }
+
+        self.serialize(&mut data)
+    }
+
+    /// Get program-derived account address for the user from the stored bump seed
+    pub fn create_pda_address(
+        user_wallet_address: &Pubkey,
+        bump_seed: u8,
+        program_id: &Pubkey,
+    ) -> Result<Pubkey, PubkeyError> {
+        Pubkey::create_program_address(
+            &[
+                &user_wallet_address.to_bytes(),
+                Self::ACCOUNT_ADDRESS_SEED.as_bytes(),
+                &[bump_seed],
+            ],
+            program_id,
+        )
     }
 
     /// Get program-derived account address and bump seeds for the user
@@ -47,19 +152,101 @@ impl User {
     }
 }
 
+/// Zero-copy view of the user account - fields are read and written in place, for legacy accounts too.
+/// Legacy accounts keep the `u32` counter, writing a larger value fails with `CounterOverflow`
+pub struct UserView<D> {
+    data: D,
+}
+
+impl<D: Deref<Target = [u8]>> UserView<D> {
+    /// Fails for data shorter than legacy user accounts
+    pub fn new(data: D) -> Result<Self, ProgramError> {
+        if data.len() < User::LEGACY_SIZE {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        Ok(Self { data })
+    }
+
+    pub fn conversation_counter(&self) -> u64 {
+        read_counter(&self.data)
+    }
+
+    /// Stored bump seed - `None` for legacy accounts
+    pub fn bump_seed(&self) -> Option<u8> {
+        read_counter_bump_seed(&self.data)
+    }
+}
+
+impl<D: DerefMut<Target = [u8]>> UserView<D> {
+    pub fn set_conversation_counter(&mut self, conversation_counter: u64) -> Result<(), ProgramError> {
+        write_counter(&mut self.data, conversation_counter)
+    }
+}
+
 /// Define a conversation account structure
-#[derive(BorshSerialize, BorshDeserialize, Debug)]
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default)]
 pub struct Conversation {
     /// Number of messages that conversation has
-    pub message_counter: u32,
+    pub message_counter: u64,
+    /// Canonical bump seed of the conversation address
+    pub bump_seed: u8,
 }
 
 impl Conversation {
     pub const ACCOUNT_ADDRESS_SEED: &'static str = "conversation";
 
+    /// Size of conversation accounts created before the bump seed was stored
+    pub const LEGACY_SIZE: usize = LEGACY_COUNTER_SIZE;
+
     /// Get size of conversation account
     pub fn retrieve_size() -> usize {
-        mem::size_of::<Conversation>()
+        Self::default().try_to_vec().unwrap().len()
+    }
+
+    /// Deserialize conversation - see `User::unpack`
+    pub fn unpack(data: &[u8]) -> Result<Self, std::io::Error> {
+        if data.len() == Self::LEGACY_SIZE {
+            return Ok(Self {
+                message_counter: u32::try_from_slice(data)?.into(),
+                ..Self::default()
+            });
+        }
+
+        Self::try_from_slice(data)
+    }
+
+    /// Serialize conversation - see `User::pack`
+    pub fn pack(&self, mut data: &mut [u8]) -> Result<(), std::io::Error> {
+        if data.len() == Self::LEGACY_SIZE {
+            return legacy_counter(self.message_counter)?.serialize(&mut data);
+        }
+
+        self.serialize(&mut data)
+    }
+
+    /// Get program-derived account address for the conversation from the stored bump seed
+    pub fn create_pda_address(
+        first_user_pda_address: &Pubkey,
+        second_user_pda_address: &Pubkey,
+        bump_seed: u8,
+        program_id: &Pubkey,
+    ) -> Result<Pubkey, PubkeyError> {
+        // Sort first & second address so that they are always in the same order
+        let [address_one, address_two] = sort_addresses_asc(
+            first_user_pda_address,
+            second_user_pda_address,
+        );
+
+        Pubkey::create_program_address(
+            &[
+                &address_one.to_bytes(),
+                &address_two.to_bytes(),
+                Self::ACCOUNT_ADDRESS_SEED.as_bytes(),
+                &[bump_seed],
+            ],
+            program_id,
+        )
     }
 
     /// Get program-derived account address and bump seeds for the conversation
@@ -98,22 +285,163 @@ impl Conversation {
     }
 }
 
+/// Zero-copy view of the conversation account - see `UserView`
+pub struct ConversationView<D> {
+    data: D,
+}
+
+impl<D: Deref<Target = [u8]>> ConversationView<D> {
+    /// Fails for data shorter than legacy conversation accounts
+    pub fn new(data: D) -> Result<Self, ProgramError> {
+        if data.len() < Conversation::LEGACY_SIZE {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        Ok(Self { data })
+    }
+
+    pub fn message_counter(&self) -> u64 {
+        read_counter(&self.data)
+    }
+
+    /// Stored bump seed - `None` for legacy accounts
+    pub fn bump_seed(&self) -> Option<u8> {
+        read_counter_bump_seed(&self.data)
+    }
+}
+
+impl<D: DerefMut<Target = [u8]>> ConversationView<D> {
+    pub fn set_message_counter(&mut self, message_counter: u64) -> Result<(), ProgramError> {
+        write_counter(&mut self.data, message_counter)
+    }
+}
+
+/// Define an account-upgrade structure - keeps the counter of a legacy user or conversation account
+/// while the account is recreated in the current layout. The account is only created and closed by the upgrade,
+/// user and conversation accounts cannot be created while it exists
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default)]
+pub struct AccountUpgrade {
+    /// Counter of the legacy account
+    pub counter: u64,
+    /// Funder of the upgrade - the only one who can finish it, and who gets the lamports of the closed accounts
+    pub funder: Pubkey,
+}
+
+impl AccountUpgrade {
+    pub const ACCOUNT_ADDRESS_SEED: &'static str = "account-upgrade";
+
+    /// Get size of account-upgrade account
+    pub fn retrieve_size() -> usize {
+        Self::default().try_to_vec().unwrap().len()
+    }
+
+    /// Read the counter of a user or conversation account, in the legacy or the current layout
+    pub fn read_counter(data: &[u8]) -> Result<u64, ProgramError> {
+        if data.len() < LEGACY_COUNTER_SIZE {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        Ok(read_counter(data))
+    }
+
+    /// Get program-derived account address and bump seeds for the account-upgrade
+    pub fn find_pda_address_with_bump_seed(
+        account_address: &Pubkey,
+        program_id: &Pubkey,
+    ) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[
+                &account_address.to_bytes(),
+                Self::ACCOUNT_ADDRESS_SEED.as_bytes(),
+            ],
+            program_id,
+        )
+    }
+
+    /// Get program-derived account address for the account-upgrade
+    pub fn find_pda_address(
+        account_address: &Pubkey,
+        program_id: &Pubkey,
+    ) -> Pubkey {
+        Self::find_pda_address_with_bump_seed(account_address, program_id).0
+    }
+}
+
 /// Define a user-conversation account structure
-#[derive(BorshSerialize, BorshDeserialize, Debug)]
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default, PartialEq)]
 pub struct UserConversation {
     /// Address of the conversation account
     pub conversation_address: Pubkey,
+    /// Conversation is moved to the archive of the user
+    pub archived: bool,
+    /// Conversation is hidden from the list of the user - the other participant keeps it
+    pub hidden: bool,
+    /// Time until notifications of the conversation are muted - zero when not muted
+    pub muted_until: UnixTimestamp,
+    /// Folder the user put the conversation into - zero when not in a folder
+    pub folder_id: u32,
+    /// Canonical bump seed of the user-conversation address
+    pub bump_seed: u8,
 }
 
 impl UserConversation {
     pub const ACCOUNT_ADDRESS_SEED: &'static str = "user-conversation";
 
+    /// Static seed of v2 addresses - a different seed keeps the 8-byte little-endian index from forming a v1 address
+    pub const ACCOUNT_ADDRESS_SEED_V2: &'static str = "user-conversation-v2";
+
+    /// Size of user-conversation accounts created before the per-user flags were added
+    pub const LEGACY_SIZE: usize = mem::size_of::<Pubkey>();
+
     /// Get size of user-conversation account
     pub fn retrieve_size() -> usize {
-        mem::size_of::<UserConversation>()
+        Self::default().try_to_vec().unwrap().len()
     }
 
-    /// Get program-derived account address and bump seeds for the user-conversation
+    /// Deserialize user-conversation - legacy accounts hold the conversation address only
+    pub fn unpack(data: &[u8]) -> Result<Self, std::io::Error> {
+        if data.len() == Self::LEGACY_SIZE {
+            return Ok(Self {
+                conversation_address: Pubkey::try_from_slice(data)?,
+                ..Self::default()
+            });
+        }
+
+        Self::try_from_slice(data)
+    }
+
+    /// Serialize user-conversation - legacy accounts keep their layout
+    pub fn pack(&self, mut data: &mut [u8]) -> Result<(), std::io::Error> {
+        if data.len() == Self::LEGACY_SIZE {
+            return self.conversation_address.serialize(&mut data);
+        }
+
+        self.serialize(&mut data)
+    }
+
+    /// Get program-derived account address for the user-conversation from the stored bump seed (v1 seeds)
+    pub fn create_pda_address(
+        user_pda_address: &Pubkey,
+        conversation_index: u32,
+        bump_seed: u8,
+        program_id: &Pubkey,
+    ) -> Result<Pubkey, PubkeyError> {
+        Pubkey::create_program_address(
+            &[
+                &user_pda_address.to_bytes(),
+                (conversation_index.to_string() + Self::ACCOUNT_ADDRESS_SEED).as_bytes(),
+                &[bump_seed],
+            ],
+            program_id,
+        )
+    }
+
+    /// Check if notifications of the conversation are muted at the given time
+    pub fn is_muted(&self, now: UnixTimestamp) -> bool {
+        self.muted_until > now
+    }
+
+    /// Get program-derived account address and bump seeds for the user-conversation (v1 seeds)
     pub fn find_pda_address_with_bump_seed(
         user_pda_address: &Pubkey,
         conversation_index: u32,
@@ -128,7 +456,7 @@ impl UserConversation {
         )
     }
 
-    /// Get program-derived account address for the user-conversation
+    /// Get program-derived account address for the user-conversation (v1 seeds)
     pub fn find_pda_address(
         user_pda_address: &Pubkey,
         conversation_index: u32,
@@ -136,6 +464,49 @@ impl UserConversation {
     ) -> Pubkey {
         Self::find_pda_address_with_bump_seed(user_pda_address, conversation_index, program_id).0
     }
+
+    /// Get program-derived account address for the user-conversation from the stored bump seed (v2 seeds)
+    pub fn create_pda_address_v2(
+        user_pda_address: &Pubkey,
+        conversation_index: u64,
+        bump_seed: u8,
+        program_id: &Pubkey,
+    ) -> Result<Pubkey, PubkeyError> {
+        Pubkey::create_program_address(
+            &[
+                &user_pda_address.to_bytes(),
+                &conversation_index.to_le_bytes(),
+                Self::ACCOUNT_ADDRESS_SEED_V2.as_bytes(),
+                &[bump_seed],
+            ],
+            program_id,
+        )
+    }
+
+    /// Get program-derived account address and bump seeds for the user-conversation (v2 seeds)
+    pub fn find_pda_address_v2_with_bump_seed(
+        user_pda_address: &Pubkey,
+        conversation_index: u64,
+        program_id: &Pubkey,
+    ) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[
+                &user_pda_address.to_bytes(),
+                &conversation_index.to_le_bytes(),
+                Self::ACCOUNT_ADDRESS_SEED_V2.as_bytes(),
+            ],
+            program_id,
+        )
+    }
+
+    /// Get program-derived account address for the user-conversation (v2 seeds)
+    pub fn find_pda_address_v2(
+        user_pda_address: &Pubkey,
+        conversation_index: u64,
+        program_id: &Pubkey,
+    ) -> Pubkey {
+        Self::find_pda_address_v2_with_bump_seed(user_pda_address, conversation_index, program_id).0
+    }
 }
 
 /// Define a message account structure
@@ -149,11 +520,25 @@ pub struct Message {
     pub content: Vec<u8>,
     /// Timestamp at which user sent a message
     pub timestamp: UnixTimestamp,
+    /// Client-generated identifier of the logical message - all zeros if the sender did not supply one
+    pub message_id: [u8; 16],
 }
 
 impl Message {
     pub const ACCOUNT_ADDRESS_SEED: &'static str = "message";
 
+    /// Static seed of v2 addresses - a different seed keeps the 8-byte little-endian index from forming a v1 address
+    pub const ACCOUNT_ADDRESS_SEED_V2: &'static str = "message-v2";
+
+    /// Static seed of messages in the sequence of a sender - the sequence is a fixed-width little-endian seed
+    pub const SEQUENCED_ACCOUNT_ADDRESS_SEED: &'static str = "sequenced-message";
+
+    /// Message ID of messages sent without deduplication
+    pub const NO_MESSAGE_ID: [u8; 16] = [0_u8; 16];
+
+    /// Size of the sender, message type and content length preceding the content
+    pub const HEADER_SIZE: usize = mem::size_of::<Pubkey>() + 1 + mem::size_of::<u32>();
+
     /// Create a new dummy message account
     pub fn new(content_size: usize) -> Self {
         Self {
@@ -161,15 +546,38 @@ impl Message {
             message_type: 0,
             content: vec![0_u8; content_size],
             timestamp: UnixTimestamp::default(),
+            message_id: Self::NO_MESSAGE_ID,
         }
     }
 
-    /// Get size of message account
+    /// Get size of message account - computed from the layout, so no message is built on the way
     pub fn retrieve_size(content_size: usize) -> usize {
-        Self::new(content_size).try_to_vec().unwrap().len()
+        Self::HEADER_SIZE + content_size + mem::size_of::<UnixTimestamp>() + Self::NO_MESSAGE_ID.len()
+    }
+
+    /// Deserialize message - legacy accounts end with the timestamp and have no message ID
+    pub fn unpack(mut data: &[u8]) -> Result<Self, std::io::Error> {
+        let sender = Pubkey::deserialize(&mut data)?;
+        let message_type = u8::deserialize(&mut data)?;
+        let content = Vec::<u8>::deserialize(&mut data)?;
+        let timestamp = UnixTimestamp::deserialize(&mut data)?;
+
+        let message_id = if data.is_empty() {
+            Self::NO_MESSAGE_ID
+        } else {
+            <[u8; 16]>::try_from_slice(data)?
+        };
+
+        Ok(Self {
+            sender,
+            message_type,
+            content,
+            timestamp,
+            message_id,
+        })
     }
 
-    /// Get program-derived account address and bump seeds for the conversation message
+    /// Get program-derived account address and bump seeds for the conversation message (v1 seeds)
     pub fn find_pda_address_with_bump_seed(
         conversation_address: &Pubkey,
         message_index: u32,
@@ -184,7 +592,7 @@ impl Message {
         )
     }
 
-    /// Get program-derived account address for the user-conversation
+    /// Get program-derived account address for the conversation message (v1 seeds)
     pub fn find_pda_address(
         conversation_address: &Pubkey,
         message_index: u32,
@@ -192,68 +600,1321 @@ impl Message {
     ) -> Pubkey {
         Self::find_pda_address_with_bump_seed(conversation_address, message_index, program_id).0
     }
-}
-
-/// Define message types
-#[non_exhaustive]
-#[derive(Debug)]
-pub struct MessageType;
-
-impl MessageType {
-    pub const PLAIN_TEXT: u8 = 0;
-    pub const RSA_ENCRYPTED: u8 = 1;
-    pub const ARWEAVE: u8 = 2;
-}
 
-/// Sort the addresses in ascending order
-pub fn sort_addresses_asc<'a>(address_one: &'a Pubkey, address_two: &'a Pubkey) -> [&'a Pubkey; 2] {
-    match *address_one < *address_two {
-        true => [address_one, address_two],
-        false => [address_two, address_one],
+    /// Get program-derived account address and bump seeds for the conversation message (v2 seeds)
+    pub fn find_pda_address_v2_with_bump_seed(
+        conversation_address: &Pubkey,
+        message_index: u64,
+        program_id: &Pubkey,
+    ) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[
+                &conversation_address.to_bytes(),
+                &message_index.to_le_bytes(),
+                Self::ACCOUNT_ADDRESS_SEED_V2.as_bytes(),
+            ],
+            program_id,
+        )
     }
-}
 
-/// Define a conversation-encryption-info account structure
-#[derive(BorshSerialize, BorshDeserialize, Debug)]
-pub struct ConversationEncryptionInfo {
-    /// Conversation encryption data - it holds info related to encryption
-    pub data: Vec<u8>,
-}
+    /// Get program-derived account address for the conversation message (v2 seeds)
+    pub fn find_pda_address_v2(
+        conversation_address: &Pubkey,
+        message_index: u64,
+        program_id: &Pubkey,
+    ) -> Pubkey {
+        Self::find_pda_address_v2_with_bump_seed(conversation_address, message_index, program_id).0
+    }
 
-impl ConversationEncryptionInfo {
-    pub const ACCOUNT_ADDRESS_SEED: &'static str = "conversation-encryption";
+    /// Merge messages of the sender sequences into one timeline - each sequence is expected in sending order
+    ///
+    /// Messages with the same timestamp are ordered by sender and then by their sequence number, so every
+    /// participant gets the same timeline whatever order the sequences are passed in.
+    pub fn merge_sequences(sequences: Vec<Vec<Message>>) -> Vec<Message> {
+        let mut messages: Vec<(usize, Message)> = sequences
+            .into_iter()
+            .flat_map(|sequence| sequence.into_iter().enumerate())
+            .collect();
 
-    /// Create a new dummy conversation-encryption-info account
-    pub fn new(data_size: usize) -> Self {
-        Self {
-            data: vec![0_u8; data_size],
-        }
-    }
+        messages.sort_by(|(first_sequence, first), (second_sequence, second)| {
+            (first.timestamp, first.sender, first_sequence).cmp(&(second.timestamp, second.sender, second_sequence))
+        });
 
-    /// Get size of conversation-encryption-info account
-    pub fn retrieve_size(data_size: usize) -> usize {
-        Self::new(data_size).try_to_vec().unwrap().len()
+        messages.into_iter().map(|(_, message)| message).collect()
     }
 
-    /// Get program-derived account address and bump seeds for the conversation-encryption-info
-    pub fn find_pda_address_with_bump_seed(
+    /// Get program-derived account address and bump seeds for the message in the sequence of the sender
+    pub fn find_sequenced_pda_address_with_bump_seed(
         conversation_address: &Pubkey,
+        sender_wallet_address: &Pubkey,
+        sequence: u64,
         program_id: &Pubkey,
     ) -> (Pubkey, u8) {
         Pubkey::find_program_address(
             &[
                 &conversation_address.to_bytes(),
-                Self::ACCOUNT_ADDRESS_SEED.as_bytes(),
+                &sender_wallet_address.to_bytes(),
+                &sequence.to_le_bytes(),
+                Self::SEQUENCED_ACCOUNT_ADDRESS_SEED.as_bytes(),
             ],
             program_id,
         )
     }
 
-    /// Get program-derived account address for the conversation-encryption-info
-    pub fn find_pda_address(
+    /// Get program-derived account address for the message in the sequence of the sender
+    pub fn find_sequenced_pda_address(
         conversation_address: &Pubkey,
+        sender_wallet_address: &Pubkey,
+        sequence: u64,
         program_id: &Pubkey,
     ) -> Pubkey {
-        Self::find_pda_address_with_bump_seed(conversation_address, program_id).0
+        Self::find_sequenced_pda_address_with_bump_seed(
+            conversation_address,
+            sender_wallet_address,
+            sequence,
+            program_id,
+        ).0
+    }
+}
+
+/// Zero-copy view of the message account - the header (sender, message type and content length)
+/// and the fields following the content are read and written in place, without copying the content
+pub struct MessageView<D> {
+    data: D,
+}
+
+impl<D: Deref<Target = [u8]>> MessageView<D> {
+    const MESSAGE_TYPE_OFFSET: usize = mem::size_of::<Pubkey>();
+    const CONTENT_LENGTH_OFFSET: usize = Self::MESSAGE_TYPE_OFFSET + 1;
+    const HEADER_SIZE: usize = Message::HEADER_SIZE;
+
+    /// Fails if the data does not hold the header, the content and the timestamp
+    pub fn new(data: D) -> Result<Self, ProgramError> {
+        if data.len() < Self::HEADER_SIZE
+            || data.len() < Self::HEADER_SIZE + read_u32(&data, Self::CONTENT_LENGTH_OFFSET) as usize + mem::size_of::<UnixTimestamp>()
+        {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        Ok(Self { data })
+    }
+
+    fn timestamp_offset(&self) -> usize {
+        Self::HEADER_SIZE + read_u32(&self.data, Self::CONTENT_LENGTH_OFFSET) as usize
+    }
+
+    fn message_id_offset(&self) -> usize {
+        self.timestamp_offset() + mem::size_of::<UnixTimestamp>()
+    }
+
+    pub fn sender(&self) -> Pubkey {
+        Pubkey::new(&self.data[..Self::MESSAGE_TYPE_OFFSET])
+    }
+
+    pub fn message_type(&self) -> u8 {
+        self.data[Self::MESSAGE_TYPE_OFFSET]
+    }
+
+    pub fn content(&self) -> &[u8] {
+        &self.data[Self::HEADER_SIZE..self.timestamp_offset()]
     }
-}
\ No newline at end of file
+
+    pub fn timestamp(&self) -> UnixTimestamp {
+        let offset = self.timestamp_offset();
+        UnixTimestamp::from_le_bytes(self.data[offset..offset + mem::size_of::<UnixTimestamp>()].try_into().unwrap())
+    }
+
+    /// Message ID - `NO_MESSAGE_ID` for legacy messages
+    pub fn message_id(&self) -> [u8; 16] {
+        let offset = self.message_id_offset();
+        match self.data.get(offset..offset + Message::NO_MESSAGE_ID.len()) {
+            Some(message_id) => message_id.try_into().unwrap(),
+            None => Message::NO_MESSAGE_ID,
+        }
+    }
+}
+
+impl<D: DerefMut<Target = [u8]>> MessageView<D> {
+    pub fn set_sender(&mut self, sender: &Pubkey) {
+        self.data[..Self::MESSAGE_TYPE_OFFSET].copy_from_slice(sender.as_ref());
+    }
+
+    pub fn set_message_type(&mut self, message_type: u8) {
+        self.data[Self::MESSAGE_TYPE_OFFSET] = message_type;
+    }
+
+    /// Write the content and its length - moves the fields following the content, so the timestamp
+    /// and the message ID are set afterwards. Fails if the content does not fit into the account
+    pub fn set_content(&mut self, content: &[u8]) -> Result<(), ProgramError> {
+        if Self::HEADER_SIZE + content.len() + mem::size_of::<UnixTimestamp>() > self.data.len() {
+            return Err(ProgramError::AccountDataTooSmall);
+        }
+
+        write_u32(&mut self.data, Self::CONTENT_LENGTH_OFFSET, content.len() as u32);
+        self.data[Self::HEADER_SIZE..Self::HEADER_SIZE + content.len()].copy_from_slice(content);
+
+        Ok(())
+    }
+
+    pub fn set_timestamp(&mut self, timestamp: UnixTimestamp) {
+        let offset = self.timestamp_offset();
+        self.data[offset..offset + mem::size_of::<UnixTimestamp>()].copy_from_slice(&timestamp.to_le_bytes());
+    }
+
+    /// Fails for legacy messages, which have no room for the message ID
+    pub fn set_message_id(&mut self, message_id: [u8; 16]) -> Result<(), ProgramError> {
+        let offset = self.message_id_offset();
+        match self.data.get_mut(offset..offset + message_id.len()) {
+            Some(data) => {
+                data.copy_from_slice(&message_id);
+                Ok(())
+            }
+            None => Err(ProgramError::AccountDataTooSmall),
+        }
+    }
+}
+
+/// Define a message-id account structure - marks a message ID as already sent to the conversation by the sender
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default)]
+pub struct MessageIdRecord {
+    /// Index of the message sent with the ID
+    pub message_index: u64,
+}
+
+impl MessageIdRecord {
+    pub const ACCOUNT_ADDRESS_SEED: &'static str = "message-id";
+
+    /// Get size of message-id account
+    pub fn retrieve_size() -> usize {
+        mem::size_of::<MessageIdRecord>()
+    }
+
+    /// Get program-derived account address and bump seeds for the message-id - message IDs of different
+    /// senders of the conversation do not collide
+    pub fn find_pda_address_with_bump_seed(
+        conversation_address: &Pubkey,
+        sender_wallet_address: &Pubkey,
+        message_id: &[u8; 16],
+        program_id: &Pubkey,
+    ) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[
+                &conversation_address.to_bytes(),
+                &sender_wallet_address.to_bytes(),
+                message_id,
+                Self::ACCOUNT_ADDRESS_SEED.as_bytes(),
+            ],
+            program_id,
+        )
+    }
+
+    /// Get program-derived account address for the message-id
+    pub fn find_pda_address(
+        conversation_address: &Pubkey,
+        sender_wallet_address: &Pubkey,
+        message_id: &[u8; 16],
+        program_id: &Pubkey,
+    ) -> Pubkey {
+        Self::find_pda_address_with_bump_seed(conversation_address, sender_wallet_address, message_id, program_id).0
+    }
+}
+
+/// Define a sender-sequence account structure - messages of one sender in the conversation
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default)]
+pub struct SenderSequence {
+    /// Number of messages the sender has sent to the conversation
+    pub message_counter: u64,
+}
+
+impl SenderSequence {
+    pub const ACCOUNT_ADDRESS_SEED: &'static str = "sender-sequence";
+
+    /// Get size of sender-sequence account
+    pub fn retrieve_size() -> usize {
+        mem::size_of::<SenderSequence>()
+    }
+
+    /// Get program-derived account address and bump seeds for the sender-sequence
+    pub fn find_pda_address_with_bump_seed(
+        conversation_address: &Pubkey,
+        sender_wallet_address: &Pubkey,
+        program_id: &Pubkey,
+    ) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[
+                &conversation_address.to_bytes(),
+                &sender_wallet_address.to_bytes(),
+                Self::ACCOUNT_ADDRESS_SEED.as_bytes(),
+            ],
+            program_id,
+        )
+    }
+
+    /// Get program-derived account address for the sender-sequence
+    pub fn find_pda_address(
+        conversation_address: &Pubkey,
+        sender_wallet_address: &Pubkey,
+        program_id: &Pubkey,
+    ) -> Pubkey {
+        Self::find_pda_address_with_bump_seed(conversation_address, sender_wallet_address, program_id).0
+    }
+}
+
+/// Define a paged-conversation account structure - counters of the paged message storage of the conversation
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default)]
+pub struct PagedConversation {
+    /// Number of message pages the conversation has - messages are appended to the last one
+    pub page_counter: u32,
+    /// Number of messages stored in the pages
+    pub message_counter: u64,
+    /// Used size of the last page - the next message is written at this offset
+    pub last_page_size: u32,
+}
+
+impl PagedConversation {
+    pub const ACCOUNT_ADDRESS_SEED: &'static str = "paged-conversation";
+
+    /// Get size of paged-conversation account
+    pub fn retrieve_size() -> usize {
+        Self::default().try_to_vec().unwrap().len()
+    }
+
+    /// Get program-derived account address and bump seeds for the paged-conversation
+    pub fn find_pda_address_with_bump_seed(
+        conversation_address: &Pubkey,
+        program_id: &Pubkey,
+    ) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[
+                &conversation_address.to_bytes(),
+                Self::ACCOUNT_ADDRESS_SEED.as_bytes(),
+            ],
+            program_id,
+        )
+    }
+
+    /// Get program-derived account address for the paged-conversation
+    pub fn find_pda_address(
+        conversation_address: &Pubkey,
+        program_id: &Pubkey,
+    ) -> Pubkey {
+        Self::find_pda_address_with_bump_seed(conversation_address, program_id).0
+    }
+}
+
+/// Define a message-page account structure - messages appended in sending order
+///
+/// Pages are rent-exempt, so they are never collected. With the default rent a page costs 57_907_200 lamports,
+/// which is less than rent-exemption of the message accounts a full page replaces - 64 messages with
+/// 64-byte content take 112_696_320 lamports in separate accounts (see `test_message_page_rent`).
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default)]
+pub struct MessagePage {
+    pub messages: Vec<Message>,
+}
+
+impl MessagePage {
+    /// Static seed of message-pages - the page index is a fixed-width little-endian seed
+    pub const ACCOUNT_ADDRESS_SEED: &'static str = "message-page";
+
+    /// Maximum number of messages in one page
+    pub const MAX_MESSAGES: usize = 64;
+
+    /// Used size of an empty page - the length of the message vector
+    pub const EMPTY_SIZE: usize = mem::size_of::<u32>();
+
+    /// Size of message-page account - the unused rest of the page is zeroed
+    pub const SIZE: usize = 8192;
+
+    /// Get size of message-page account
+    pub fn retrieve_size() -> usize {
+        Self::SIZE
+    }
+
+    /// Deserialize message-page, ignoring the unused rest of the page
+    pub fn unpack(mut data: &[u8]) -> Result<Self, std::io::Error> {
+        Self::deserialize(&mut data)
+    }
+
+    /// Get size of the messages stored in the page
+    pub fn used_size(&self) -> usize {
+        Self::EMPTY_SIZE + self.messages.iter()
+            .map(|message| Message::retrieve_size(message.content.len()))
+            .sum::<usize>()
+    }
+
+    /// Check if a message with the content size can be appended to the page
+    pub fn fits(&self, content_size: usize) -> bool {
+        self.messages.len() < Self::MAX_MESSAGES
+            && self.used_size() + Message::retrieve_size(content_size) <= Self::SIZE
+    }
+
+    /// Get program-derived account address and bump seeds for the message-page
+    pub fn find_pda_address_with_bump_seed(
+        conversation_address: &Pubkey,
+        page_index: u32,
+        program_id: &Pubkey,
+    ) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[
+                &conversation_address.to_bytes(),
+                &page_index.to_le_bytes(),
+                Self::ACCOUNT_ADDRESS_SEED.as_bytes(),
+            ],
+            program_id,
+        )
+    }
+
+    /// Get program-derived account address for the message-page
+    pub fn find_pda_address(
+        conversation_address: &Pubkey,
+        page_index: u32,
+        program_id: &Pubkey,
+    ) -> Pubkey {
+        Self::find_pda_address_with_bump_seed(conversation_address, page_index, program_id).0
+    }
+}
+
+/// Zero-copy view of the message-page account - messages are appended in place at the used size
+/// kept by the paged-conversation, without deserializing the messages already stored
+pub struct MessagePageView<D> {
+    data: D,
+}
+
+impl<D: Deref<Target = [u8]>> MessagePageView<D> {
+    /// Fails for data of other size than message-page accounts
+    pub fn new(data: D) -> Result<Self, ProgramError> {
+        if data.len() != MessagePage::SIZE {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        Ok(Self { data })
+    }
+
+    pub fn message_count(&self) -> u32 {
+        read_u32(&self.data, 0)
+    }
+
+    /// Check if a message with the content size can be appended to the page with the used size
+    pub fn fits(&self, used_size: usize, content_size: usize) -> bool {
+        (self.message_count() as usize) < MessagePage::MAX_MESSAGES
+            && used_size + Message::retrieve_size(content_size) <= MessagePage::SIZE
+    }
+}
+
+impl<D: DerefMut<Target = [u8]>> MessagePageView<D> {
+    /// Write the message at the used size and increment the message count - returns the new used size.
+    /// Fails if the message does not fit into the page
+    pub fn append_message(
+        &mut self,
+        used_size: usize,
+        sender: &Pubkey,
+        message_type: u8,
+        content: &[u8],
+        timestamp: UnixTimestamp,
+    ) -> Result<usize, ProgramError> {
+        if used_size < MessagePage::EMPTY_SIZE || !self.fits(used_size, content.len()) {
+            return Err(ProgramError::AccountDataTooSmall);
+        }
+
+        let next_used_size = used_size + Message::retrieve_size(content.len());
+
+        let mut message = MessageView::new(&mut self.data[used_size..next_used_size])?;
+        message.set_sender(sender);
+        message.set_message_type(message_type);
+        message.set_content(content)?;
+        message.set_timestamp(timestamp);
+        message.set_message_id(Message::NO_MESSAGE_ID)?;
+
+        let message_count = self.message_count() + 1;
+        write_u32(&mut self.data, 0, message_count);
+
+        Ok(next_used_size)
+    }
+}
+
+/// Define message types
+#[non_exhaustive]
+#[derive(Debug)]
+pub struct MessageType;
+
+impl MessageType {
+    pub const PLAIN_TEXT: u8 = 0;
+    pub const RSA_ENCRYPTED: u8 = 1;
+    pub const ARWEAVE: u8 = 2;
+    pub const SEALED_SENDER: u8 = 3;
+}
+
+/// Define padding schemes for message content
+#[non_exhaustive]
+#[derive(Debug)]
+pub struct PaddingScheme;
+
+impl PaddingScheme {
+    pub const NONE: u8 = 0;
+    pub const POWER_OF_TWO: u8 = 1;
+    pub const BLOCK_256: u8 = 2;
+
+    /// Check if the scheme is known
+    pub fn is_valid(scheme: u8) -> bool {
+        scheme <= Self::BLOCK_256
+    }
+
+    /// Get size content of the given size is padded to - padding always adds at least one byte
+    pub fn padded_size(scheme: u8, content_size: usize) -> usize {
+        match scheme {
+            Self::POWER_OF_TWO => (content_size + 1).next_power_of_two(),
+            Self::BLOCK_256 => (content_size / 256 + 1) * 256,
+            _ => content_size,
+        }
+    }
+
+    /// Check if content of the given size is padded according to the scheme
+    pub fn is_padded(scheme: u8, content_size: usize) -> bool {
+        match scheme {
+            Self::POWER_OF_TWO => content_size.is_power_of_two(),
+            Self::BLOCK_256 => content_size > 0 && content_size & 0xff == 0,
+            _ => true,
+        }
+    }
+}
+
+/// Sort the addresses in ascending order
+pub fn sort_addresses_asc<'a>(address_one: &'a Pubkey, address_two: &'a Pubkey) -> [&'a Pubkey; 2] {
+    match *address_one < *address_two {
+        true => [address_one, address_two],
+        false => [address_two, address_one],
+    }
+}
+
+/// Define a conversation-encryption-info account structure
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct ConversationEncryptionInfo {
+    /// Conversation encryption data - it holds info related to encryption
+    pub data: Vec<u8>,
+}
+
+impl ConversationEncryptionInfo {
+    pub const ACCOUNT_ADDRESS_SEED: &'static str = "conversation-encryption";
+
+    /// Create a new dummy conversation-encryption-info account
+    pub fn new(data_size: usize) -> Self {
+        Self {
+            data: vec![0_u8; data_size],
+        }
+    }
+
+    /// Get size of conversation-encryption-info account
+    pub fn retrieve_size(data_size: usize) -> usize {
+        Self::new(data_size).try_to_vec().unwrap().len()
+    }
+
+    /// Get program-derived account address and bump seeds for the conversation-encryption-info
+    pub fn find_pda_address_with_bump_seed(
+        conversation_address: &Pubkey,
+        program_id: &Pubkey,
+    ) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[
+                &conversation_address.to_bytes(),
+                Self::ACCOUNT_ADDRESS_SEED.as_bytes(),
+            ],
+            program_id,
+        )
+    }
+
+    /// Get program-derived account address for the conversation-encryption-info
+    pub fn find_pda_address(
+        conversation_address: &Pubkey,
+        program_id: &Pubkey,
+    ) -> Pubkey {
+        Self::find_pda_address_with_bump_seed(conversation_address, program_id).0
+    }
+}
+
+/// Define a conversation-handshake account structure - MLS handshake that starts an epoch of a conversation or group
+///
+/// Only the handshakes are ordered on-chain. Deriving the epoch keys from them needs an MLS implementation
+/// on the client and is not part of this crate.
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct ConversationHandshake {
+    /// The sender of a handshake
+    pub sender: Pubkey,
+    /// MLS commit that moves the group into this epoch
+    pub commit: Vec<u8>,
+    /// MLS welcome for members added by the commit - empty if nobody was added
+    pub welcome: Vec<u8>,
+    /// Timestamp at which user sent a handshake
+    pub timestamp: UnixTimestamp,
+}
+
+impl ConversationHandshake {
+    pub const ACCOUNT_ADDRESS_SEED: &'static str = "conversation-handshake";
+
+    /// Create a new dummy conversation-handshake account
+    pub fn new(commit_size: usize, welcome_size: usize) -> Self {
+        Self {
+            sender: Pubkey::default(),
+            commit: vec![0_u8; commit_size],
+            welcome: vec![0_u8; welcome_size],
+            timestamp: UnixTimestamp::default(),
+        }
+    }
+
+    /// Get size of conversation-handshake account
+    pub fn retrieve_size(commit_size: usize, welcome_size: usize) -> usize {
+        Self::new(commit_size, welcome_size).try_to_vec().unwrap().len()
+    }
+
+    /// Get program-derived account address and bump seeds for the conversation-handshake of the epoch
+    pub fn find_pda_address_with_bump_seed(
+        conversation_address: &Pubkey,
+        epoch: u64,
+        program_id: &Pubkey,
+    ) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[
+                &conversation_address.to_bytes(),
+                &epoch.to_le_bytes(),
+                Self::ACCOUNT_ADDRESS_SEED.as_bytes(),
+            ],
+            program_id,
+        )
+    }
+
+    /// Get program-derived account address for the conversation-handshake of the epoch
+    pub fn find_pda_address(
+        conversation_address: &Pubkey,
+        epoch: u64,
+        program_id: &Pubkey,
+    ) -> Pubkey {
+        Self::find_pda_address_with_bump_seed(conversation_address, epoch, program_id).0
+    }
+}
+
+/// Define a sealed-sender-credential account structure
+///
+/// Every participant of a conversation has a credential of its own, so one side cannot reset the chain
+/// of the other. The participant derives an ephemeral keypair from each element of a hash chain. A
+/// sealed message is signed by the keypair the credential names, and the signature covers the content.
+/// `chain_hash` commits to the keypair of the next message, so a leaked keypair cannot take the chain over.
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct SealedSenderCredential {
+    /// Conversation the credential authorizes sealed messages for
+    pub conversation_address: Pubkey,
+    /// Address of the ephemeral keypair that signs the next sealed message
+    pub authority: Pubkey,
+    /// Commitment to the keypair after `authority` and to the chain hash stored with it
+    pub chain_hash: Hash,
+}
+
+impl SealedSenderCredential {
+    pub const ACCOUNT_ADDRESS_SEED: &'static str = "sealed-sender-credential";
+
+    /// Get size of sealed-sender-credential account
+    pub fn retrieve_size() -> usize {
+        mem::size_of::<SealedSenderCredential>()
+    }
+
+    /// Get chain hash that commits to the given authority and the chain hash stored with it
+    pub fn chain_link(
+        next_authority: &Pubkey,
+        next_chain_hash: &Hash,
+    ) -> Hash {
+        hashv(&[next_authority.as_ref(), next_chain_hash.as_ref()])
+    }
+
+    /// Check if the chain is used up - the credential can then be set again without a chain step
+    pub fn is_exhausted(&self) -> bool {
+        self.authority == Pubkey::default()
+    }
+
+    /// Get program-derived account address and bump seeds for the sealed-sender-credential of a participant
+    pub fn find_pda_address_with_bump_seed(
+        conversation_address: &Pubkey,
+        participant_wallet_address: &Pubkey,
+        program_id: &Pubkey,
+    ) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[
+                &conversation_address.to_bytes(),
+                &participant_wallet_address.to_bytes(),
+                Self::ACCOUNT_ADDRESS_SEED.as_bytes(),
+            ],
+            program_id,
+        )
+    }
+
+    /// Get program-derived account address for the sealed-sender-credential of a participant
+    pub fn find_pda_address(
+        conversation_address: &Pubkey,
+        participant_wallet_address: &Pubkey,
+        program_id: &Pubkey,
+    ) -> Pubkey {
+        Self::find_pda_address_with_bump_seed(conversation_address, participant_wallet_address, program_id).0
+    }
+}
+
+/// Define a blinded-conversation account structure
+///
+/// The address is derived from a secret shared by the participants, so it cannot be linked to their
+/// wallets. Membership is proven by a signature of the authority derived from the same secret. The
+/// authority is part of the address, so nobody else can take the address of a blinded identifier.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default)]
+pub struct BlindedConversation {
+    /// Number of messages that conversation has
+    pub message_counter: u64,
+    /// Key derived from the shared secret - signs on behalf of the participants
+    pub authority: Pubkey,
+    /// Blinded identifier the address is derived from
+    pub blinded_id: [u8; 32],
+    /// Canonical bump seed of the blinded-conversation address
+    pub bump_seed: u8,
+}
+
+impl BlindedConversation {
+    pub const ACCOUNT_ADDRESS_SEED: &'static str = "blinded-conversation";
+    pub const AUTHORITY_SEED: &'static str = "blinded-conversation-authority";
+
+    /// Get size of blinded-conversation account
+    pub fn retrieve_size() -> usize {
+        Self::default().try_to_vec().unwrap().len()
+    }
+
+    /// Get blinded identifier of the conversation from the shared secret
+    pub fn derive_blinded_id(shared_secret: &[u8]) -> [u8; 32] {
+        hashv(&[Self::ACCOUNT_ADDRESS_SEED.as_bytes(), shared_secret]).to_bytes()
+    }
+
+    /// Get ed25519 seed of the conversation authority from the shared secret
+    pub fn derive_authority_seed(shared_secret: &[u8]) -> [u8; 32] {
+        hashv(&[Self::AUTHORITY_SEED.as_bytes(), shared_secret]).to_bytes()
+    }
+
+    /// Get program-derived account address for the blinded-conversation from the stored bump seed
+    pub fn create_pda_address(
+        blinded_id: &[u8; 32],
+        authority: &Pubkey,
+        bump_seed: u8,
+        program_id: &Pubkey,
+    ) -> Result<Pubkey, PubkeyError> {
+        Pubkey::create_program_address(
+            &[
+                blinded_id,
+                &authority.to_bytes(),
+                Self::ACCOUNT_ADDRESS_SEED.as_bytes(),
+                &[bump_seed],
+            ],
+            program_id,
+        )
+    }
+
+    /// Get program-derived account address and bump seeds for the blinded-conversation
+    pub fn find_pda_address_with_bump_seed(
+        blinded_id: &[u8; 32],
+        authority: &Pubkey,
+        program_id: &Pubkey,
+    ) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[
+                blinded_id,
+                &authority.to_bytes(),
+                Self::ACCOUNT_ADDRESS_SEED.as_bytes(),
+            ],
+            program_id,
+        )
+    }
+
+    /// Get program-derived account address for the blinded-conversation
+    pub fn find_pda_address(
+        blinded_id: &[u8; 32],
+        authority: &Pubkey,
+        program_id: &Pubkey,
+    ) -> Pubkey {
+        Self::find_pda_address_with_bump_seed(blinded_id, authority, program_id).0
+    }
+}
+
+/// Define a conversation-padding-policy account structure
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct ConversationPaddingPolicy {
+    /// Padding scheme the content of every message must follow - see `PaddingScheme`
+    pub scheme: u8,
+}
+
+impl ConversationPaddingPolicy {
+    pub const ACCOUNT_ADDRESS_SEED: &'static str = "conversation-padding-policy";
+
+    /// Get size of conversation-padding-policy account
+    pub fn retrieve_size() -> usize {
+        mem::size_of::<ConversationPaddingPolicy>()
+    }
+
+    /// Get program-derived account address and bump seeds for the conversation-padding-policy
+    pub fn find_pda_address_with_bump_seed(
+        conversation_address: &Pubkey,
+        program_id: &Pubkey,
+    ) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[
+                &conversation_address.to_bytes(),
+                Self::ACCOUNT_ADDRESS_SEED.as_bytes(),
+            ],
+            program_id,
+        )
+    }
+
+    /// Get program-derived account address for the conversation-padding-policy
+    pub fn find_pda_address(
+        conversation_address: &Pubkey,
+        program_id: &Pubkey,
+    ) -> Pubkey {
+        Self::find_pda_address_with_bump_seed(conversation_address, program_id).0
+    }
+}
+
+/// Define a conversation-metadata account structure
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default, PartialEq)]
+pub struct ConversationMetadata {
+    /// Wallet that set the metadata first
+    pub creator: Pubkey,
+    /// Time the metadata was set first
+    pub created_at: UnixTimestamp,
+    /// Title of the conversation
+    pub title: String,
+    /// Description of the conversation
+    pub description: String,
+    /// URI of the avatar image
+    pub avatar_uri: String,
+}
+
+impl ConversationMetadata {
+    pub const ACCOUNT_ADDRESS_SEED: &'static str = "conversation-metadata";
+
+    pub const MAX_TITLE_LENGTH: usize = 64;
+    pub const MAX_DESCRIPTION_LENGTH: usize = 256;
+    pub const MAX_AVATAR_URI_LENGTH: usize = 200;
+
+    /// Get size of conversation-metadata account - the account is allocated for the longest fields
+    /// so that the metadata can be updated in place, trailing bytes are left zeroed
+    pub fn retrieve_size() -> usize {
+        mem::size_of::<Pubkey>()
+            + mem::size_of::<UnixTimestamp>()
+            + mem::size_of::<u32>() + Self::MAX_TITLE_LENGTH
+            + mem::size_of::<u32>() + Self::MAX_DESCRIPTION_LENGTH
+            + mem::size_of::<u32>() + Self::MAX_AVATAR_URI_LENGTH
+    }
+
+    /// Check lengths of the fields against the limits
+    pub fn is_valid(&self) -> bool {
+        self.title.len() <= Self::MAX_TITLE_LENGTH
+            && self.description.len() <= Self::MAX_DESCRIPTION_LENGTH
+            && self.avatar_uri.len() <= Self::MAX_AVATAR_URI_LENGTH
+    }
+
+    /// Deserialize conversation-metadata ignoring the unused trailing bytes of the account
+    pub fn unpack(mut data: &[u8]) -> Result<Self, std::io::Error> {
+        Self::deserialize(&mut data)
+    }
+
+    /// Get program-derived account address and bump seeds for the conversation-metadata
+    pub fn find_pda_address_with_bump_seed(
+        conversation_address: &Pubkey,
+        program_id: &Pubkey,
+    ) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[
+                &conversation_address.to_bytes(),
+                Self::ACCOUNT_ADDRESS_SEED.as_bytes(),
+            ],
+            program_id,
+        )
+    }
+
+    /// Get program-derived account address for the conversation-metadata
+    pub fn find_pda_address(
+        conversation_address: &Pubkey,
+        program_id: &Pubkey,
+    ) -> Pubkey {
+        Self::find_pda_address_with_bump_seed(conversation_address, program_id).0
+    }
+}
+
+/// Define a pinned-messages account structure
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default, PartialEq)]
+pub struct PinnedMessages {
+    /// Indexes of the pinned messages of the conversation in the order they were pinned
+    pub message_indexes: Vec<u64>,
+}
+
+impl PinnedMessages {
+    pub const ACCOUNT_ADDRESS_SEED: &'static str = "pinned-messages";
+
+    pub const MAX_PINNED_MESSAGES: usize = 16;
+
+    /// Get size of pinned-messages account - the account is allocated for the longest list,
+    /// trailing bytes are left zeroed
+    pub fn retrieve_size() -> usize {
+        mem::size_of::<u32>() + mem::size_of::<u64>() * Self::MAX_PINNED_MESSAGES
+    }
+
+    /// Check if no more messages can be pinned
+    pub fn is_full(&self) -> bool {
+        self.message_indexes.len() >= Self::MAX_PINNED_MESSAGES
+    }
+
+    /// Deserialize pinned-messages ignoring the unused trailing bytes of the account
+    pub fn unpack(mut data: &[u8]) -> Result<Self, std::io::Error> {
+        Self::deserialize(&mut data)
+    }
+
+    /// Get program-derived account address and bump seeds for the pinned-messages
+    pub fn find_pda_address_with_bump_seed(
+        conversation_address: &Pubkey,
+        program_id: &Pubkey,
+    ) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[
+                &conversation_address.to_bytes(),
+                Self::ACCOUNT_ADDRESS_SEED.as_bytes(),
+            ],
+            program_id,
+        )
+    }
+
+    /// Get program-derived account address for the pinned-messages
+    pub fn find_pda_address(
+        conversation_address: &Pubkey,
+        program_id: &Pubkey,
+    ) -> Pubkey {
+        Self::find_pda_address_with_bump_seed(conversation_address, program_id).0
+    }
+}
+
+/// Define a group account structure
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default)]
+pub struct Group {
+    /// Kind of the account - `AccountKind::GROUP` once initialized
+    pub account_kind: u8,
+    /// Wallet address of the creator - part of the address seed
+    pub creator: Pubkey,
+    /// Index chosen by the creator - part of the address seed
+    pub index: u64,
+    /// Wallet address of the current owner - default address for community groups, owned by the community owner
+    pub owner: Pubkey,
+    /// Number of messages that group has
+    pub message_counter: u64,
+    /// Kind of the group - see `GroupKind`
+    pub kind: u8,
+    /// Address of the community the group belongs to - default address for standalone groups
+    pub community: Pubkey,
+}
+
+impl Group {
+    pub const ACCOUNT_ADDRESS_SEED: &'static str = "group";
+
+    /// Get size of group account - the layout is not padded, so the size is taken from the serialized form
+    pub fn retrieve_size() -> usize {
+        Self::default().try_to_vec().unwrap().len()
+    }
+
+    /// Get program-derived account address and bump seeds for the group
+    pub fn find_pda_address_with_bump_seed(
+        creator_wallet_address: &Pubkey,
+        group_index: u64,
+        program_id: &Pubkey,
+    ) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[
+                &creator_wallet_address.to_bytes(),
+                &group_index.to_le_bytes(),
+                Self::ACCOUNT_ADDRESS_SEED.as_bytes(),
+            ],
+            program_id,
+        )
+    }
+
+    /// Get program-derived account address for the group
+    pub fn find_pda_address(
+        creator_wallet_address: &Pubkey,
+        group_index: u64,
+        program_id: &Pubkey,
+    ) -> Pubkey {
+        Self::find_pda_address_with_bump_seed(creator_wallet_address, group_index, program_id).0
+    }
+
+    /// Get address group-member accounts of the group are derived from
+    pub fn membership_scope(&self, group_address: &Pubkey) -> Pubkey {
+        match self.community == Pubkey::default() {
+            true => *group_address,
+            false => self.community,
+        }
+    }
+}
+
+/// Define a community account structure - groups of a community share its members and their roles
+///
+/// Groups of the community are derived from the community address in place of the creator wallet.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default)]
+pub struct Community {
+    /// Kind of the account - `AccountKind::COMMUNITY` once initialized
+    pub account_kind: u8,
+    /// Wallet address of the creator - part of the address seed
+    pub creator: Pubkey,
+    /// Index chosen by the creator - part of the address seed
+    pub index: u32,
+    /// Wallet address of the current owner
+    pub owner: Pubkey,
+    /// Number of groups that community has
+    pub group_counter: u64,
+}
+
+impl Community {
+    pub const ACCOUNT_ADDRESS_SEED: &'static str = "community";
+
+    /// Get size of community account - the layout is not padded, so the size is taken from the serialized form
+    pub fn retrieve_size() -> usize {
+        Self::default().try_to_vec().unwrap().len()
+    }
+
+    /// Get program-derived account address and bump seeds for the community
+    pub fn find_pda_address_with_bump_seed(
+        creator_wallet_address: &Pubkey,
+        community_index: u32,
+        program_id: &Pubkey,
+    ) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[
+                &creator_wallet_address.to_bytes(),
+                &community_index.to_le_bytes(),
+                Self::ACCOUNT_ADDRESS_SEED.as_bytes(),
+            ],
+            program_id,
+        )
+    }
+
+    /// Get program-derived account address for the community
+    pub fn find_pda_address(
+        creator_wallet_address: &Pubkey,
+        community_index: u32,
+        program_id: &Pubkey,
+    ) -> Pubkey {
+        Self::find_pda_address_with_bump_seed(creator_wallet_address, community_index, program_id).0
+    }
+}
+
+/// Define kinds of accounts that hold a membership set
+///
+/// Stored as the first byte, so groups and communities are told apart without looking at the account size.
+#[non_exhaustive]
+#[derive(Debug)]
+pub struct AccountKind;
+
+impl AccountKind {
+    /// Kind of zeroed data - a closed account is neither a group nor a community
+    pub const UNINITIALIZED: u8 = 0;
+    pub const GROUP: u8 = 1;
+    pub const COMMUNITY: u8 = 2;
+
+    /// Get kind of the account from its data
+    pub fn of(data: &[u8]) -> u8 {
+        data.first().copied().unwrap_or(Self::UNINITIALIZED)
+    }
+}
+
+/// Define group kinds
+#[non_exhaustive]
+#[derive(Debug)]
+pub struct GroupKind;
+
+impl GroupKind {
+    /// Every member with the send permission can post
+    pub const GROUP: u8 = 0;
+    /// Publishers post, subscribers join as read-only members
+    pub const CHANNEL: u8 = 1;
+
+    /// Check if the kind is known
+    pub fn is_valid(kind: u8) -> bool {
+        kind <= Self::CHANNEL
+    }
+}
+
+/// Define group member roles
+#[non_exhaustive]
+#[derive(Debug)]
+pub struct GroupRole;
+
+impl GroupRole {
+    /// Role of zeroed data - a closed group-member account grants nothing
+    pub const NONE: u8 = 0;
+    pub const OWNER: u8 = 1;
+    pub const ADMIN: u8 = 2;
+    pub const MEMBER: u8 = 3;
+    pub const READ_ONLY: u8 = 4;
+
+    /// Check if the role is known and held by a member
+    pub fn is_valid(role: u8) -> bool {
+        role != Self::NONE && role <= Self::READ_ONLY
+    }
+
+    /// Get permissions a member with the role may hold at most
+    pub fn max_permissions(role: u8) -> u8 {
+        match role {
+            Self::OWNER | Self::ADMIN => GroupPermission::ALL,
+            Self::MEMBER => GroupPermission::SEND | GroupPermission::INVITE | GroupPermission::PIN,
+            _ => GroupPermission::NONE,
+        }
+    }
+
+    /// Get permissions a member with the role gets by default
+    pub fn default_permissions(role: u8) -> u8 {
+        match role {
+            Self::OWNER | Self::ADMIN => GroupPermission::ALL,
+            Self::MEMBER => GroupPermission::SEND,
+            _ => GroupPermission::NONE,
+        }
+    }
+}
+
+/// Define group member permissions - bits of `GroupMember::permissions`
+#[non_exhaustive]
+#[derive(Debug)]
+pub struct GroupPermission;
+
+impl GroupPermission {
+    pub const NONE: u8 = 0;
+    pub const SEND: u8 = 1;
+    pub const INVITE: u8 = 1 << 1;
+    pub const REMOVE: u8 = 1 << 2;
+    pub const EDIT_METADATA: u8 = 1 << 3;
+    pub const PIN: u8 = 1 << 4;
+    pub const ALL: u8 = Self::SEND | Self::INVITE | Self::REMOVE | Self::EDIT_METADATA | Self::PIN;
+}
+
+/// Define a group-member account structure
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct GroupMember {
+    /// Role of the member - see `GroupRole`
+    pub role: u8,
+    /// Permission bitmask of the member - see `GroupPermission`
+    pub permissions: u8,
+}
+
+impl GroupMember {
+    pub const ACCOUNT_ADDRESS_SEED: &'static str = "group-member";
+
+    /// Get size of group-member account
+    pub fn retrieve_size() -> usize {
+        mem::size_of::<GroupMember>()
+    }
+
+    /// Get program-derived account address and bump seeds for the group-member
+    pub fn find_pda_address_with_bump_seed(
+        group_address: &Pubkey,
+        member_wallet_address: &Pubkey,
+        program_id: &Pubkey,
+    ) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[
+                &group_address.to_bytes(),
+                &member_wallet_address.to_bytes(),
+                Self::ACCOUNT_ADDRESS_SEED.as_bytes(),
+            ],
+            program_id,
+        )
+    }
+
+    /// Get program-derived account address for the group-member
+    pub fn find_pda_address(
+        group_address: &Pubkey,
+        member_wallet_address: &Pubkey,
+        program_id: &Pubkey,
+    ) -> Pubkey {
+        Self::find_pda_address_with_bump_seed(group_address, member_wallet_address, program_id).0
+    }
+
+    /// Check if the member holds all the given permissions
+    pub fn has_permission(&self, permission: u8) -> bool {
+        self.permissions & permission == permission
+    }
+
+    /// Check if the member may add a new member with the given role and permissions
+    pub fn can_add(&self, role: u8, permissions: u8) -> bool {
+        self.has_permission(GroupPermission::INVITE)
+            && self.can_grant(role, permissions)
+            && (role != GroupRole::ADMIN || self.role == GroupRole::OWNER)
+    }
+
+    /// Check if the member may give the given role and permissions to an existing member
+    pub fn can_assign(&self, target: &GroupMember, role: u8, permissions: u8) -> bool {
+        let can_manage_target = match self.role {
+            GroupRole::OWNER => target.role != GroupRole::OWNER,
+            GroupRole::ADMIN => target.role != GroupRole::OWNER
+                && target.role != GroupRole::ADMIN
+                && role != GroupRole::ADMIN,
+            _ => false,
+        };
+
+        can_manage_target && self.can_grant(role, permissions)
+    }
+
+    /// Check if the member may remove or ban the target member
+    pub fn can_remove(&self, target: &GroupMember) -> bool {
+        self.has_permission(GroupPermission::REMOVE) && match self.role {
+            GroupRole::OWNER => target.role != GroupRole::OWNER,
+            GroupRole::ADMIN => target.role == GroupRole::MEMBER || target.role == GroupRole::READ_ONLY,
+            _ => false,
+        }
+    }
+
+    /// Only the ownership transfer creates an owner, and nobody grants permissions they do not hold
+    fn can_grant(&self, role: u8, permissions: u8) -> bool {
+        GroupRole::is_valid(role)
+            && role != GroupRole::OWNER
+            && permissions & !GroupRole::max_permissions(role) == 0
+            && self.has_permission(permissions)
+    }
+}
+
+/// Define an invite account structure
+///
+/// The address is derived from the hash of the invite code, so creating an invite does not reveal the
+/// code - joining proves knowledge of the code by revealing it.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default)]
+pub struct Invite {
+    /// Wallet address of the group member that created the invite
+    pub creator: Pubkey,
+    /// Time after which the invite cannot be used
+    pub expires_at: UnixTimestamp,
+    /// Number of times the invite can be used
+    pub max_uses: u32,
+    /// Number of times the invite has been used
+    pub uses: u32,
+    /// Role given to the members joining with the invite - see `GroupRole`
+    pub role: u8,
+}
+
+impl Invite {
+    pub const ACCOUNT_ADDRESS_SEED: &'static str = "invite";
+
+    /// Get size of invite account
+    pub fn retrieve_size() -> usize {
+        Self::default().try_to_vec().unwrap().len()
+    }
+
+    /// Get hash of the invite code the address is derived from
+    pub fn hash_code(code: &[u8; 16]) -> Hash {
+        hashv(&[Self::ACCOUNT_ADDRESS_SEED.as_bytes(), code])
+    }
+
+    /// Check if the invite can be used at the given time
+    pub fn is_usable(&self, now: UnixTimestamp) -> bool {
+        now < self.expires_at && self.uses < self.max_uses
+    }
+
+    /// Get program-derived account address and bump seeds for the invite
+    pub fn find_pda_address_with_bump_seed(
+        group_address: &Pubkey,
+        code_hash: &Hash,
+        program_id: &Pubkey,
+    ) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[
+                &group_address.to_bytes(),
+                code_hash.as_ref(),
+                Self::ACCOUNT_ADDRESS_SEED.as_bytes(),
+            ],
+            program_id,
+        )
+    }
+
+    /// Get program-derived account address for the invite
+    pub fn find_pda_address(
+        group_address: &Pubkey,
+        code_hash: &Hash,
+        program_id: &Pubkey,
+    ) -> Pubkey {
+        Self::find_pda_address_with_bump_seed(group_address, code_hash, program_id).0
+    }
+}
+
+/// Define a join-request account structure
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default)]
+pub struct JoinRequest {
+    /// Time the request was made
+    pub timestamp: UnixTimestamp,
+}
+
+impl JoinRequest {
+    pub const ACCOUNT_ADDRESS_SEED: &'static str = "join-request";
+
+    /// Get size of join-request account
+    pub fn retrieve_size() -> usize {
+        Self::default().try_to_vec().unwrap().len()
+    }
+
+    /// Get program-derived account address and bump seeds for the join-request
+    pub fn find_pda_address_with_bump_seed(
+        group_address: &Pubkey,
+        requester_wallet_address: &Pubkey,
+        program_id: &Pubkey,
+    ) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[
+                &group_address.to_bytes(),
+                &requester_wallet_address.to_bytes(),
+                Self::ACCOUNT_ADDRESS_SEED.as_bytes(),
+            ],
+            program_id,
+        )
+    }
+
+    /// Get program-derived account address for the join-request
+    pub fn find_pda_address(
+        group_address: &Pubkey,
+        requester_wallet_address: &Pubkey,
+        program_id: &Pubkey,
+    ) -> Pubkey {
+        Self::find_pda_address_with_bump_seed(group_address, requester_wallet_address, program_id).0
+    }
+}
+
+/// Define a group-ban account structure
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default)]
+pub struct GroupBan {
+    /// Time the wallet was banned
+    pub timestamp: UnixTimestamp,
+}
+
+impl GroupBan {
+    pub const ACCOUNT_ADDRESS_SEED: &'static str = "group-ban";
+
+    /// Get size of group-ban account
+    pub fn retrieve_size() -> usize {
+        Self::default().try_to_vec().unwrap().len()
+    }
+
+    /// Get program-derived account address and bump seeds for the group-ban
+    pub fn find_pda_address_with_bump_seed(
+        group_address: &Pubkey,
+        wallet_address: &Pubkey,
+        program_id: &Pubkey,
+    ) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[
+                &group_address.to_bytes(),
+                &wallet_address.to_bytes(),
+                Self::ACCOUNT_ADDRESS_SEED.as_bytes(),
+            ],
+            program_id,
+        )
+    }
+
+    /// Get program-derived account address for the group-ban
+    pub fn find_pda_address(
+        group_address: &Pubkey,
+        wallet_address: &Pubkey,
+        program_id: &Pubkey,
+    ) -> Pubkey {
+        Self::find_pda_address_with_bump_seed(group_address, wallet_address, program_id).0
+    }
+}
@@ -4,12 +4,14 @@ use solana_program::{
     account_info::AccountInfo,
     entrypoint::ProgramResult,
     program::{invoke_signed},
+    program_error::ProgramError,
     pubkey::Pubkey,
     rent::Rent,
     system_instruction,
 };
 
 /// Creates Program Derived Address for the given seeds
+#[allow(clippy::too_many_arguments)]
 pub fn create_pda_account<'a>(
     payer: &AccountInfo<'a>,
     rent: &Rent,
@@ -23,7 +25,7 @@ pub fn create_pda_account<'a>(
     let mut rent_balance = rent.minimum_balance(space);
 
     if !is_rent_exempt {
-        rent_balance = (rent_balance / (rent.exemption_threshold * 365 as f64) as u64) * 7;
+        rent_balance = (rent_balance / (rent.exemption_threshold * 365_f64) as u64) * 7;
     }
 
     invoke_signed(
@@ -42,3 +44,41 @@ pub fn create_pda_account<'a>(
         &[new_pda_signer_seeds],
     )
 }
+
+/// Closes Program Derived Address and transfers its lamports to the receiver
+pub fn close_pda_account<'a>(
+    pda_account: &AccountInfo<'a>,
+    receiver: &AccountInfo<'a>,
+) -> ProgramResult {
+    let receiver_lamports = receiver
+        .lamports()
+        .checked_add(pda_account.lamports())
+        .ok_or(ProgramError::InvalidAccountData)?;
+
+    **receiver.lamports.borrow_mut() = receiver_lamports;
+    **pda_account.lamports.borrow_mut() = 0;
+    pda_account.data.borrow_mut().fill(0);
+
+    Ok(())
+}
+
+/// Transfers lamports above the rent-exempt minimum of the Program Derived Address to the receiver
+pub fn refund_excess_lamports<'a>(
+    pda_account: &AccountInfo<'a>,
+    receiver: &AccountInfo<'a>,
+    rent: &Rent,
+) -> ProgramResult {
+    let excess_lamports = pda_account
+        .lamports()
+        .saturating_sub(rent.minimum_balance(pda_account.data_len()));
+
+    let receiver_lamports = receiver
+        .lamports()
+        .checked_add(excess_lamports)
+        .ok_or(ProgramError::InvalidAccountData)?;
+
+    **receiver.lamports.borrow_mut() = receiver_lamports;
+    **pda_account.lamports.borrow_mut() -= excess_lamports;
+
+    Ok(())
+}
@@ -0,0 +1,71 @@
+// Vectors in `fixtures/address_vectors.json` are produced by `fixtures/generate_address_vectors.ts`,
+// which derives addresses with the seeds of the TypeScript client
+
+use {
+    instant_messaging::state::{
+        Message,
+        MessagePage,
+        UserConversation,
+    },
+    serde_json::Value,
+    solana_program::pubkey::Pubkey,
+    std::str::FromStr,
+};
+
+const VECTORS: &str = include_str!("fixtures/address_vectors.json");
+
+fn vectors() -> Value {
+    serde_json::from_str(VECTORS).unwrap()
+}
+
+fn address(address: &Value) -> Pubkey {
+    Pubkey::from_str(address.as_str().unwrap()).unwrap()
+}
+
+#[test]
+fn test_message_page_addresses() {
+    let vectors = vectors();
+    let program_id = address(&vectors["program_id"]);
+    let conversation = address(&vectors["conversation"]);
+
+    for vector in vectors["message_pages"].as_array().unwrap() {
+        let page_index = vector["page_index"].as_u64().unwrap() as u32;
+
+        assert_eq!(
+            MessagePage::find_pda_address(&conversation, page_index, &program_id),
+            address(&vector["address"]),
+            "page {}", page_index,
+        );
+    }
+}
+
+#[test]
+fn test_v2_index_addresses() {
+    let vectors = vectors();
+    let program_id = address(&vectors["program_id"]);
+    let conversation = address(&vectors["conversation"]);
+    let user = address(&vectors["user"]);
+
+    // Indexes are strings, JSON numbers do not hold every u64
+    let index = |index: &Value| -> u64 { index.as_str().unwrap().parse().unwrap() };
+
+    for vector in vectors["user_conversations"].as_array().unwrap() {
+        let conversation_index = index(&vector["conversation_index"]);
+
+        assert_eq!(
+            UserConversation::find_pda_address_v2(&user, conversation_index, &program_id),
+            address(&vector["address"]),
+            "conversation {}", conversation_index,
+        );
+    }
+
+    for vector in vectors["messages"].as_array().unwrap() {
+        let message_index = index(&vector["message_index"]);
+
+        assert_eq!(
+            Message::find_pda_address_v2(&conversation, message_index, &program_id),
+            address(&vector["address"]),
+            "message {}", message_index,
+        );
+    }
+}
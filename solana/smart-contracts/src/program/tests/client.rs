@@ -0,0 +1,140 @@
+use {
+    std::collections::HashMap,
+    borsh::BorshDeserialize,
+    instant_messaging::{
+        client::{
+            is_message_index_conflict,
+            send_message_with_retry,
+            ConversationClient,
+        },
+        error::InstantMessagingError,
+        instruction::InstantMessagingInstruction,
+    },
+    solana_program::{
+        instruction::{
+            Instruction,
+            InstructionError,
+        },
+        pubkey::Pubkey,
+    },
+};
+
+/// Error the runtime reports for the program error
+fn program_error(error: InstantMessagingError) -> InstructionError {
+    InstructionError::Custom(error as u32)
+}
+
+/// Conversation where other participants keep sending between reading the counter and sending
+struct BusyConversation {
+    message_counter: u64,
+    /// Messages of other participants landing right before each of the next sends
+    competing_messages: Vec<u64>,
+    /// Message indexes stored in the message-id accounts
+    message_ids: HashMap<Pubkey, u64>,
+    attempts: u32,
+}
+
+impl BusyConversation {
+    fn new(message_counter: u64, competing_messages: Vec<u64>) -> Self {
+        Self {
+            message_counter,
+            competing_messages,
+            message_ids: HashMap::new(),
+            attempts: 0,
+        }
+    }
+}
+
+impl ConversationClient for BusyConversation {
+    type Error = InstructionError;
+
+    fn message_counter(&mut self, _conversation_address: &Pubkey) -> Result<u64, Self::Error> {
+        Ok(self.message_counter)
+    }
+
+    fn message_id_index(&mut self, message_id_address: &Pubkey) -> Result<Option<u64>, Self::Error> {
+        Ok(self.message_ids.get(message_id_address).copied())
+    }
+
+    fn process_instruction(&mut self, instruction: Instruction) -> Result<(), Self::Error> {
+        self.attempts += 1;
+
+        if !self.competing_messages.is_empty() {
+            self.message_counter += self.competing_messages.remove(0);
+        }
+
+        // Message-id account of SendMessage
+        let message_id_address = instruction.accounts[6].pubkey;
+
+        match InstantMessagingInstruction::try_from_slice(&instruction.data).unwrap() {
+            InstantMessagingInstruction::SendMessage { .. } if self.message_ids.contains_key(&message_id_address) => Ok(()),
+            InstantMessagingInstruction::SendMessage { expected_index, .. } if expected_index == self.message_counter => {
+                self.message_ids.insert(message_id_address, self.message_counter);
+                self.message_counter += 1;
+                Ok(())
+            }
+            InstantMessagingInstruction::SendMessage { .. } => {
+                Err(program_error(InstantMessagingError::MessageIndexConflict))
+            }
+            _ => Err(InstructionError::InvalidInstructionData),
+        }
+    }
+
+    fn instruction_error(error: &Self::Error) -> Option<&InstructionError> {
+        Some(error)
+    }
+}
+
+fn send(conversation: &mut BusyConversation, max_attempts: u32) -> Result<u64, InstructionError> {
+    let sender = Pubkey::new_from_array([1_u8; 32]);
+
+    send_message_with_retry(
+        conversation,
+        &sender,
+        &sender,
+        &Pubkey::new_from_array([2_u8; 32]),
+        0,
+        b"Hello",
+        [1_u8; 16],
+        max_attempts,
+    )
+}
+
+#[test]
+fn test_message_index_conflict_error() {
+    assert!(is_message_index_conflict(&program_error(InstantMessagingError::MessageIndexConflict)));
+    assert!(!is_message_index_conflict(&program_error(InstantMessagingError::AddressDerivationMismatch)));
+    assert!(!is_message_index_conflict(&InstructionError::InvalidSeeds));
+}
+
+#[test]
+fn test_send_message_with_retry() {
+    let mut conversation = BusyConversation::new(3, vec![1, 2]);
+
+    // Two sends lose against other participants, the third lands after their messages
+    assert_eq!(send(&mut conversation, 3), Ok(6));
+    assert_eq!(conversation.attempts, 3);
+    assert_eq!(conversation.message_counter, 7);
+}
+
+#[test]
+fn test_send_message_with_retry_gives_up() {
+    let mut conversation = BusyConversation::new(0, vec![1, 1, 1]);
+
+    assert_eq!(send(&mut conversation, 2), Err(program_error(InstantMessagingError::MessageIndexConflict)));
+    assert_eq!(conversation.attempts, 2);
+}
+
+#[test]
+fn test_send_message_with_retry_after_lost_response() {
+    let mut conversation = BusyConversation::new(0, vec![]);
+
+    assert_eq!(send(&mut conversation, 1), Ok(0));
+
+    // The response was lost and the message is sent again after another message landed -
+    // the no-op send reports the index of the first send
+    conversation.message_counter += 1;
+
+    assert_eq!(send(&mut conversation, 1), Ok(0));
+    assert_eq!(conversation.message_counter, 2);
+}
@@ -0,0 +1,530 @@
+use {
+    borsh::BorshSerialize,
+    instant_messaging::{
+        error::InstantMessagingError,
+        instruction::{
+            message_pda_address,
+            send_messages,
+            send_sequenced_message,
+        },
+        state::{
+            Conversation,
+            ConversationHandshake,
+            ConversationMetadata,
+            ConversationView,
+            Message,
+            MessageIdRecord,
+            MessagePage,
+            MessagePageView,
+            MessageType,
+            MessageView,
+            PinnedMessages,
+            User,
+            UserConversation,
+            UserView,
+        },
+    },
+    solana_program::{
+        pubkey::Pubkey,
+        rent::Rent,
+    },
+};
+
+fn metadata(title_length: usize, description_length: usize, avatar_uri_length: usize) -> ConversationMetadata {
+    ConversationMetadata {
+        creator: Pubkey::new_unique(),
+        created_at: 1_640_995_200,
+        title: "t".repeat(title_length),
+        description: "d".repeat(description_length),
+        avatar_uri: "a".repeat(avatar_uri_length),
+    }
+}
+
+#[test]
+fn test_conversation_metadata_size() {
+    let longest = metadata(
+        ConversationMetadata::MAX_TITLE_LENGTH,
+        ConversationMetadata::MAX_DESCRIPTION_LENGTH,
+        ConversationMetadata::MAX_AVATAR_URI_LENGTH,
+    );
+
+    assert!(longest.is_valid());
+    assert_eq!(longest.try_to_vec().unwrap().len(), ConversationMetadata::retrieve_size());
+
+    assert!(!metadata(ConversationMetadata::MAX_TITLE_LENGTH + 1, 0, 0).is_valid());
+    assert!(!metadata(0, ConversationMetadata::MAX_DESCRIPTION_LENGTH + 1, 0).is_valid());
+    assert!(!metadata(0, 0, ConversationMetadata::MAX_AVATAR_URI_LENGTH + 1).is_valid());
+}
+
+#[test]
+fn test_unpack_conversation_metadata() {
+    let short = metadata(8, 16, 0);
+
+    // Shorter metadata leaves the rest of the account zeroed
+    let mut data = vec![0_u8; ConversationMetadata::retrieve_size()];
+    short.serialize(&mut &mut data[..]).unwrap();
+
+    assert_eq!(ConversationMetadata::unpack(&data).unwrap(), short);
+}
+
+#[test]
+fn test_pinned_messages_size() {
+    let mut pinned_messages = PinnedMessages::default();
+
+    while !pinned_messages.is_full() {
+        pinned_messages.message_indexes.push(pinned_messages.message_indexes.len() as u64);
+    }
+
+    assert_eq!(pinned_messages.message_indexes.len(), PinnedMessages::MAX_PINNED_MESSAGES);
+    assert_eq!(pinned_messages.try_to_vec().unwrap().len(), PinnedMessages::retrieve_size());
+
+    // Freshly created account holds an empty list
+    let data = vec![0_u8; PinnedMessages::retrieve_size()];
+
+    assert_eq!(PinnedMessages::unpack(&data).unwrap(), PinnedMessages::default());
+}
+
+#[test]
+fn test_unpack_user_conversation() {
+    let conversation_address = Pubkey::new_unique();
+
+    // Accounts created before the per-user flags hold the conversation address only
+    let legacy = UserConversation::unpack(&conversation_address.to_bytes()).unwrap();
+
+    assert_eq!(legacy, UserConversation {
+        conversation_address,
+        ..UserConversation::default()
+    });
+
+    let user_conversation = UserConversation {
+        conversation_address,
+        archived: true,
+        hidden: true,
+        muted_until: 1_640_995_200,
+        folder_id: 2,
+        bump_seed: 254,
+    };
+    let data = user_conversation.try_to_vec().unwrap();
+
+    assert_eq!(data.len(), UserConversation::retrieve_size());
+    assert_eq!(UserConversation::unpack(&data).unwrap(), user_conversation);
+
+    assert!(user_conversation.is_muted(1_640_995_199));
+    assert!(!user_conversation.is_muted(1_640_995_200));
+}
+
+#[test]
+fn test_unpack_message() {
+    let message = Message {
+        sender: Pubkey::new_unique(),
+        message_type: 0,
+        content: b"Hello".to_vec(),
+        timestamp: 1_640_995_200,
+        message_id: [7_u8; 16],
+    };
+    let data = message.try_to_vec().unwrap();
+
+    assert_eq!(data.len(), Message::retrieve_size(message.content.len()));
+    assert_eq!(Message::unpack(&data).unwrap().message_id, message.message_id);
+
+    // Messages sent before message IDs end with the timestamp
+    let legacy = Message::unpack(&data[..data.len() - message.message_id.len()]).unwrap();
+
+    assert_eq!(legacy.content, message.content);
+    assert_eq!(legacy.timestamp, message.timestamp);
+    assert_eq!(legacy.message_id, Message::NO_MESSAGE_ID);
+}
+
+#[test]
+fn test_message_view() {
+    let message = Message {
+        sender: Pubkey::new_unique(),
+        message_type: 2,
+        content: b"Hello".to_vec(),
+        timestamp: 1_640_995_200,
+        message_id: [7_u8; 16],
+    };
+    let data = message.try_to_vec().unwrap();
+
+    // Fields written in place match the Borsh layout
+    let mut written = vec![0_u8; Message::retrieve_size(message.content.len())];
+    let mut view = MessageView::new(&mut written[..]).unwrap();
+    view.set_sender(&message.sender);
+    view.set_message_type(message.message_type);
+    view.set_content(&message.content).unwrap();
+    view.set_timestamp(message.timestamp);
+    view.set_message_id(message.message_id).unwrap();
+
+    assert_eq!(written, data);
+    assert!(MessageView::new(&mut written[..]).unwrap().set_content(&[0_u8; 64]).is_err());
+
+    let view = MessageView::new(&data[..]).unwrap();
+
+    assert_eq!(view.sender(), message.sender);
+    assert_eq!(view.message_type(), message.message_type);
+    assert_eq!(view.content(), &message.content[..]);
+    assert_eq!(view.timestamp(), message.timestamp);
+    assert_eq!(view.message_id(), message.message_id);
+
+    // Messages sent before message IDs end with the timestamp
+    let legacy = MessageView::new(&data[..data.len() - message.message_id.len()]).unwrap();
+
+    assert_eq!(legacy.timestamp(), message.timestamp);
+    assert_eq!(legacy.message_id(), Message::NO_MESSAGE_ID);
+    assert!(MessageView::new(&data[..Message::HEADER_SIZE + 2]).is_err());
+}
+
+#[test]
+fn test_counter_views() {
+    let user = User {
+        conversation_counter: 7,
+        bump_seed: 254,
+    };
+    let mut data = user.try_to_vec().unwrap();
+
+    let mut view = UserView::new(&mut data[..]).unwrap();
+    assert_eq!(view.conversation_counter(), 7);
+    assert_eq!(view.bump_seed(), Some(254));
+
+    view.set_conversation_counter(8).unwrap();
+    assert_eq!(User::unpack(&data).unwrap().conversation_counter, 8);
+    assert_eq!(User::unpack(&data).unwrap().bump_seed, 254);
+
+    // Legacy accounts are read and written in place as well
+    let mut data = vec![0_u8; Conversation::LEGACY_SIZE];
+    let mut view = ConversationView::new(&mut data[..]).unwrap();
+    assert_eq!(view.bump_seed(), None);
+
+    view.set_message_counter(9).unwrap();
+    assert_eq!(Conversation::unpack(&data).unwrap().message_counter, 9);
+    assert!(ConversationView::new(&data[..2]).is_err());
+}
+
+#[test]
+fn test_counter_overflow() {
+    let conversation = Conversation {
+        message_counter: u64::from(u32::MAX),
+        bump_seed: 254,
+    };
+
+    // Legacy accounts keep the `u32` counter
+    let mut data = vec![0_u8; Conversation::LEGACY_SIZE];
+    conversation.pack(&mut data).unwrap();
+
+    let mut view = ConversationView::new(&mut data[..]).unwrap();
+    assert_eq!(view.message_counter(), u64::from(u32::MAX));
+    assert_eq!(view.bump_seed(), None);
+    assert_eq!(
+        view.set_message_counter(u64::from(u32::MAX) + 1),
+        Err(InstantMessagingError::CounterOverflow.into()),
+    );
+    assert_eq!(Conversation::unpack(&data).unwrap().message_counter, u64::from(u32::MAX));
+
+    // Current accounts hold `u64` counters
+    let mut data = conversation.try_to_vec().unwrap();
+
+    let mut view = ConversationView::new(&mut data[..]).unwrap();
+    view.set_message_counter(u64::from(u32::MAX) + 1).unwrap();
+    assert_eq!(view.bump_seed(), Some(254));
+    assert_eq!(Conversation::unpack(&data).unwrap().message_counter, u64::from(u32::MAX) + 1);
+
+    let mut data = vec![0_u8; User::LEGACY_SIZE];
+    assert!(UserView::new(&mut data[..]).unwrap().set_conversation_counter(u64::MAX).is_err());
+}
+
+#[test]
+fn test_v2_index_seed() {
+    let conversation_address = Pubkey::new_unique();
+
+    // Every index takes the 8-byte seed, small indexes too
+    assert_eq!(
+        Message::find_pda_address_v2(&conversation_address, 7, &instant_messaging::id()),
+        Pubkey::find_program_address(
+            &[&conversation_address.to_bytes(), &7_u64.to_le_bytes(), Message::ACCOUNT_ADDRESS_SEED_V2.as_bytes()],
+            &instant_messaging::id(),
+        ).0,
+    );
+
+    let message_index = u64::from(u32::MAX) + 1;
+
+    assert_eq!(
+        message_pda_address(&conversation_address, message_index),
+        Message::find_pda_address_v2(&conversation_address, message_index, &instant_messaging::id()),
+    );
+    assert_ne!(
+        message_pda_address(&conversation_address, message_index),
+        message_pda_address(&conversation_address, 0),
+    );
+}
+
+#[test]
+fn test_sequenced_sends_do_not_contend() {
+    let first = Pubkey::new_unique();
+    let second = Pubkey::new_unique();
+
+    let first_instruction = send_sequenced_message(&first, &first, &second, 0, 0, vec![1]);
+    let second_instruction = send_sequenced_message(&second, &second, &first, 0, 0, vec![2]);
+
+    let writable = |instruction: &solana_program::instruction::Instruction| -> Vec<Pubkey> {
+        instruction.accounts.iter()
+            .filter(|account| account.is_writable && !account.is_signer)
+            .map(|account| account.pubkey)
+            .collect()
+    };
+
+    // Both participants address the same conversation, but write different accounts
+    assert_eq!(first_instruction.accounts[4].pubkey, second_instruction.accounts[4].pubkey);
+    assert!(!first_instruction.accounts[4].is_writable);
+
+    let first_writable = writable(&first_instruction);
+    assert!(writable(&second_instruction).iter().all(|address| !first_writable.contains(address)));
+}
+
+#[test]
+fn test_merge_sequences() {
+    let first = Pubkey::new_unique();
+    let second = Pubkey::new_unique();
+
+    let message = |sender: Pubkey, timestamp, content: u8| Message {
+        sender,
+        message_type: 0,
+        content: vec![content],
+        timestamp,
+        message_id: Message::NO_MESSAGE_ID,
+    };
+
+    let first_sequence = || vec![message(first, 10, 1), message(first, 20, 2), message(first, 20, 3)];
+    let second_sequence = || vec![message(second, 15, 4), message(second, 20, 5)];
+
+    let contents = |merged: Vec<Message>| -> Vec<u8> {
+        merged.iter().map(|message| message.content[0]).collect()
+    };
+
+    // Same timestamps are ordered by sender, then by sequence - the order of the sequences does not matter
+    assert_eq!(contents(Message::merge_sequences(vec![first_sequence(), second_sequence()])), vec![1, 4, 2, 3, 5]);
+    assert_eq!(contents(Message::merge_sequences(vec![second_sequence(), first_sequence()])), vec![1, 4, 2, 3, 5]);
+}
+
+#[test]
+fn test_send_messages_accounts() {
+    let sender = Pubkey::new_unique();
+    let receiver = Pubkey::new_unique();
+
+    let message_ids = [[1_u8; 16], [2_u8; 16], [3_u8; 16]];
+    let instruction = send_messages(&sender, &sender, &receiver, 5, vec![
+        (0, vec![1], message_ids[0]),
+        (0, vec![2], message_ids[1]),
+        (0, vec![3], message_ids[2]),
+    ]);
+    let conversation_address = instruction.accounts[4].pubkey;
+
+    // One conversation write for the batch, followed by the consecutive message accounts
+    let message_addresses: Vec<Pubkey> = instruction.accounts[9..12].iter().map(|account| account.pubkey).collect();
+
+    assert_eq!(message_addresses, vec![
+        Message::find_pda_address_v2(&conversation_address, 5, &instant_messaging::id()),
+        Message::find_pda_address_v2(&conversation_address, 6, &instant_messaging::id()),
+        Message::find_pda_address_v2(&conversation_address, 7, &instant_messaging::id()),
+    ]);
+
+    // Message-id accounts follow in the order of the messages
+    let message_id_addresses: Vec<Pubkey> = instruction.accounts[12..].iter().map(|account| account.pubkey).collect();
+
+    assert_eq!(message_id_addresses, message_ids.iter()
+        .map(|message_id| MessageIdRecord::find_pda_address(&conversation_address, &sender, message_id, &instant_messaging::id()))
+        .collect::<Vec<Pubkey>>());
+}
+
+#[test]
+fn test_message_page_rent() {
+    let rent = Rent::default();
+
+    // A page full of messages with 64-byte content holds the message limit
+    let content_size = 64;
+    assert!(MessagePage::MAX_MESSAGES * Message::retrieve_size(content_size) + 4 <= MessagePage::SIZE);
+
+    let page_rent = rent.minimum_balance(MessagePage::SIZE);
+    let message_accounts_rent = MessagePage::MAX_MESSAGES as u64 * rent.minimum_balance(Message::retrieve_size(content_size));
+
+    assert_eq!(page_rent, 57_907_200);
+    assert_eq!(message_accounts_rent, 112_696_320);
+
+    // Full pages of short messages cost less than rent-exempt accounts of their messages - a page holding
+    // a few long messages wastes its unused rest
+    for content_size in [0, 16, 128, 512].iter() {
+        let messages_per_page = MessagePage::MAX_MESSAGES.min((MessagePage::SIZE - 4) / Message::retrieve_size(*content_size));
+
+        assert!(page_rent < messages_per_page as u64 * rent.minimum_balance(Message::retrieve_size(*content_size)));
+    }
+}
+
+#[test]
+fn test_message_page_rollover() {
+    let message = |content_size: usize| Message {
+        sender: Pubkey::new_unique(),
+        message_type: 0,
+        content: vec![1_u8; content_size],
+        timestamp: 1_640_995_200,
+        message_id: Message::NO_MESSAGE_ID,
+    };
+
+    // Short messages fill the page up to the message limit
+    let mut page = MessagePage::default();
+
+    while page.fits(8) {
+        page.messages.push(message(8));
+    }
+
+    assert_eq!(page.messages.len(), MessagePage::MAX_MESSAGES);
+
+    // Long messages fill the page up to its size
+    let mut page = MessagePage::default();
+
+    while page.fits(1000) {
+        page.messages.push(message(1000));
+    }
+
+    let data = page.try_to_vec().unwrap();
+
+    assert!(page.messages.len() < MessagePage::MAX_MESSAGES);
+    assert_eq!(data.len(), page.used_size());
+    assert!(data.len() <= MessagePage::SIZE);
+    assert!(data.len() + Message::retrieve_size(1000) > MessagePage::SIZE);
+
+    // Page account holds the messages followed by zeroes
+    let mut account = vec![0_u8; MessagePage::retrieve_size()];
+    page.serialize(&mut &mut account[..]).unwrap();
+
+    assert_eq!(MessagePage::unpack(&account).unwrap().messages.len(), page.messages.len());
+    assert!(MessagePage::unpack(&vec![0_u8; MessagePage::retrieve_size()]).unwrap().messages.is_empty());
+
+    assert!(!MessagePage::default().fits(MessagePage::SIZE));
+}
+
+#[test]
+fn test_message_page_append_in_place() {
+    let sender = Pubkey::new_unique();
+    let mut account = vec![0_u8; MessagePage::retrieve_size()];
+    let mut used_size = MessagePage::EMPTY_SIZE;
+    let mut page = MessagePage::default();
+
+    // Messages appended in place are laid out as the serialized page
+    for content_size in [0, 8, 1000].iter() {
+        let content = vec![*content_size as u8; *content_size];
+
+        used_size = MessagePageView::new(&mut account[..]).unwrap()
+            .append_message(used_size, &sender, MessageType::PLAIN_TEXT, &content, 1_640_995_200)
+            .unwrap();
+
+        page.messages.push(Message {
+            sender,
+            message_type: MessageType::PLAIN_TEXT,
+            content,
+            timestamp: 1_640_995_200,
+            message_id: Message::NO_MESSAGE_ID,
+        });
+    }
+
+    let data = page.try_to_vec().unwrap();
+
+    assert_eq!(used_size, page.used_size());
+    assert_eq!(&account[..used_size], &data[..]);
+    assert!(account[used_size..].iter().all(|byte| *byte == 0));
+    assert_eq!(MessagePage::unpack(&account).unwrap().messages.len(), 3);
+
+    // Messages past the end of the page are rejected
+    let mut view = MessagePageView::new(&mut account[..]).unwrap();
+
+    assert!(!view.fits(used_size, MessagePage::SIZE));
+    assert!(view.append_message(used_size, &sender, MessageType::PLAIN_TEXT, &[0_u8; MessagePage::SIZE], 0).is_err());
+    assert!(MessagePageView::new(&mut vec![0_u8; MessagePage::SIZE - 1][..]).is_err());
+}
+
+#[test]
+fn test_stored_bump_seeds() {
+    let program_id = instant_messaging::id();
+    let first = Pubkey::new_unique();
+    let second = Pubkey::new_unique();
+
+    let (user_address, user_bump_seed) = User::find_pda_address_with_bump_seed(&first, &program_id);
+    assert_eq!(User::create_pda_address(&first, user_bump_seed, &program_id).unwrap(), user_address);
+
+    // Conversation address does not depend on the order of the users
+    let (conversation_address, conversation_bump_seed) = Conversation::find_pda_address_with_bump_seed(&first, &second, &program_id);
+    assert_eq!(Conversation::create_pda_address(&second, &first, conversation_bump_seed, &program_id).unwrap(), conversation_address);
+
+    let (user_conversation_address, user_conversation_bump_seed) = UserConversation::find_pda_address_with_bump_seed(&user_address, 3, &program_id);
+    assert_eq!(UserConversation::create_pda_address(&user_address, 3, user_conversation_bump_seed, &program_id).unwrap(), user_conversation_address);
+}
+
+#[test]
+fn test_handshake_epoch_seed() {
+    let program_id = instant_messaging::id();
+    let conversation_address = Pubkey::new_unique();
+
+    // Epochs of any size fit into the seed
+    assert_ne!(
+        ConversationHandshake::find_pda_address(&conversation_address, u64::MAX, &program_id),
+        ConversationHandshake::find_pda_address(&conversation_address, 10_000_000_000, &program_id),
+    );
+}
+
+#[test]
+fn test_v2_seeds() {
+    let program_id = instant_messaging::id();
+    let address = Pubkey::new_unique();
+
+    let (user_conversation_address, user_conversation_bump_seed) = UserConversation::find_pda_address_v2_with_bump_seed(&address, 3, &program_id);
+    assert_eq!(UserConversation::create_pda_address_v2(&address, 3, user_conversation_bump_seed, &program_id).unwrap(), user_conversation_address);
+    assert_ne!(user_conversation_address, UserConversation::find_pda_address(&address, 3, &program_id));
+
+    // "1234" of the v1 seed are the little-endian bytes of 875770417 - the v2 static seed keeps them apart
+    assert_eq!(u32::from_le_bytes(*b"1234"), 875_770_417);
+    assert_ne!(Message::find_pda_address_v2(&address, 875_770_417, &program_id), Message::find_pda_address(&address, 1234, &program_id));
+    assert_ne!(Message::find_pda_address_v2(&address, 1234, &program_id), Message::find_pda_address(&address, 1234, &program_id));
+}
+
+#[test]
+fn test_pack_legacy_accounts() {
+    // Legacy accounts keep their size, the bump seed is dropped
+    let user = User {
+        conversation_counter: 7,
+        bump_seed: 254,
+    };
+    let mut data = vec![0_u8; User::LEGACY_SIZE];
+    user.pack(&mut data).unwrap();
+
+    assert_eq!(User::unpack(&data).unwrap().conversation_counter, 7);
+    assert_eq!(User::unpack(&data).unwrap().bump_seed, 0);
+
+    let conversation = Conversation {
+        message_counter: 9,
+        bump_seed: 254,
+    };
+    let mut data = vec![0_u8; Conversation::LEGACY_SIZE];
+    conversation.pack(&mut data).unwrap();
+
+    assert_eq!(Conversation::unpack(&data).unwrap().message_counter, 9);
+
+    let user_conversation = UserConversation {
+        conversation_address: Pubkey::new_unique(),
+        archived: true,
+        hidden: true,
+        muted_until: 1_640_995_200,
+        folder_id: 2,
+        bump_seed: 254,
+    };
+    let mut data = vec![0_u8; UserConversation::LEGACY_SIZE];
+    user_conversation.pack(&mut data).unwrap();
+
+    assert_eq!(UserConversation::unpack(&data).unwrap(), UserConversation {
+        conversation_address: user_conversation.conversation_address,
+        ..UserConversation::default()
+    });
+
+    // Current accounts round-trip with the bump seed
+    let mut data = vec![0_u8; UserConversation::retrieve_size()];
+    user_conversation.pack(&mut data).unwrap();
+
+    assert_eq!(UserConversation::unpack(&data).unwrap(), user_conversation);
+}
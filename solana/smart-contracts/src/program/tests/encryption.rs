@@ -0,0 +1,227 @@
+// Vectors in `fixtures/encryption_vectors.json` are produced by `fixtures/generate_encryption_vectors.ts`,
+// which encodes data with the schemas of the TypeScript client
+
+use {
+    borsh::{BorshDeserialize, BorshSerialize},
+    instant_messaging::{
+        blinded::BlindedConversationKeys,
+        encryption::{
+            EncryptionInfoData,
+            EncryptionType,
+            MessageContent,
+        },
+        padding::{
+            pad_content,
+            unpad_content,
+        },
+        sealed_sender::{
+            SealedSenderChain,
+            SealedSenderEnvelope,
+        },
+        state::{
+            Message,
+            MessageType,
+            PaddingScheme,
+            SealedSenderCredential,
+        },
+    },
+    serde_json::Value,
+    solana_program::{
+        hash::{
+            hash,
+            Hash,
+        },
+        pubkey::Pubkey,
+    },
+    solana_sdk::signature::{
+        keypair_from_seed,
+        Keypair,
+        Signer,
+    },
+    std::str::FromStr,
+};
+
+const VECTORS: &str = include_str!("fixtures/encryption_vectors.json");
+
+fn vectors() -> Value {
+    serde_json::from_str(VECTORS).unwrap()
+}
+
+fn hex_to_bytes(hex: &Value) -> Vec<u8> {
+    let hex = hex.as_str().unwrap();
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+fn address(address: &Value) -> Pubkey {
+    Pubkey::from_str(address.as_str().unwrap()).unwrap()
+}
+
+#[test]
+fn test_encode_encryption_info_data() {
+    let vectors = vectors();
+    let sender = address(&vectors["sender"]);
+    let receiver = address(&vectors["receiver"]);
+
+    let encryption_info = EncryptionInfoData::rsa(&sender, &receiver);
+
+    assert_eq!(
+        encryption_info.encode().unwrap(),
+        hex_to_bytes(&vectors["encryption_info"][0]["data"]),
+    );
+}
+
+#[test]
+fn test_decode_encryption_info_data() {
+    let vectors = vectors();
+    let sender = address(&vectors["sender"]);
+    let receiver = address(&vectors["receiver"]);
+
+    let encryption_info = EncryptionInfoData::decode(
+        &hex_to_bytes(&vectors["encryption_info"][0]["data"]),
+    ).unwrap();
+
+    assert_eq!(encryption_info.encryption_type, EncryptionType::Rsa);
+    assert_eq!(encryption_info.addresses().unwrap(), vec![sender, receiver]);
+}
+
+#[test]
+fn test_encryption_info_data_without_addresses() {
+    let vectors = vectors();
+    let data = hex_to_bytes(&vectors["encryption_info"][1]["data"]);
+
+    let encryption_info = EncryptionInfoData::decode(&data).unwrap();
+
+    assert_eq!(encryption_info.data.addresses, None);
+    assert!(encryption_info.addresses().unwrap().is_empty());
+    assert_eq!(encryption_info.encode().unwrap(), data);
+}
+
+#[test]
+fn test_decode_unsupported_encryption_type() {
+    assert!(EncryptionInfoData::decode(br#"{"type":7,"data":{}}"#).is_err());
+}
+
+#[test]
+fn test_message_content() {
+    let vectors = vectors();
+    let sender = address(&vectors["sender"]);
+
+    for vector in vectors["messages"].as_array().unwrap() {
+        let message_type = vector["message_type"].as_u64().unwrap() as u8;
+        let content = hex_to_bytes(&vector["content"]);
+        let account = hex_to_bytes(&vector["account"]);
+
+        let message_content = MessageContent::decode(message_type, &content).unwrap();
+        assert_eq!(message_content.message_type(), message_type);
+        assert_eq!(message_content.encode(), content);
+
+        let message = Message::try_from_slice(&account).unwrap();
+        assert_eq!(message.sender, sender);
+        assert_eq!(message.message_type, message_type);
+        assert_eq!(message.content, content);
+        assert_eq!(message.timestamp, vector["timestamp"].as_i64().unwrap());
+        assert_eq!(message.message_id.to_vec(), hex_to_bytes(&vector["message_id"]));
+        assert_eq!(message.try_to_vec().unwrap(), account);
+    }
+}
+
+#[test]
+fn test_rsa_content_is_stored_verbatim() {
+    let ciphertext = vec![0_u8, 159, 255, 1];
+
+    let content = MessageContent::RsaEncrypted(ciphertext.clone());
+
+    assert_eq!(content.message_type(), MessageType::RSA_ENCRYPTED);
+    assert_eq!(content.encode(), ciphertext);
+    assert_eq!(MessageContent::decode(MessageType::RSA_ENCRYPTED, &ciphertext).unwrap(), content);
+    assert!(MessageContent::decode(MessageType::PLAIN_TEXT, &ciphertext).is_err());
+}
+
+#[test]
+fn test_sealed_sender_envelope() {
+    let envelope = SealedSenderEnvelope {
+        sender: Pubkey::new_unique(),
+        message_type: MessageType::PLAIN_TEXT,
+        content: String::from("First message!").into_bytes(),
+    };
+
+    let data = envelope.encode().unwrap();
+
+    assert_eq!(SealedSenderEnvelope::decode(&data).unwrap(), envelope);
+    assert!(SealedSenderEnvelope::decode(&data[1..]).is_err());
+}
+
+#[test]
+fn test_sealed_sender_chain() {
+    let chain = SealedSenderChain::new(b"shared secret", 3);
+
+    assert_eq!(chain.len(), 3);
+
+    // Every seed hashes to the seed of the previous message and signs as the announced authority
+    for index in 0..chain.len() {
+        let seed = chain.authority_seed(index).unwrap();
+        let keypair = keypair_from_seed(&seed).unwrap();
+        assert_eq!(Some(keypair.pubkey()), chain.authority(index));
+
+        if index > 0 {
+            assert_eq!(hash(&seed).to_bytes(), chain.authority_seed(index - 1).unwrap());
+        }
+
+        // Chain hash commits to the next authority and the chain hash stored with it
+        let next_authority = chain.authority(index + 1).unwrap_or_default();
+        assert_eq!(
+            chain.chain_hash(index),
+            Some(SealedSenderCredential::chain_link(&next_authority, &chain.chain_hash(index + 1).unwrap())),
+        );
+    }
+
+    assert_eq!(chain.authority(chain.len()), None);
+    assert_eq!(chain.chain_hash(chain.len()), Some(Hash::default()));
+    assert_eq!(chain.chain_hash(chain.len() + 1), None);
+}
+
+#[test]
+fn test_blinded_conversation_keys() {
+    let alice = Keypair::new();
+    let bob = Keypair::new();
+    let eve = Keypair::new();
+
+    let alice_keys = BlindedConversationKeys::derive(alice.secret().as_bytes(), &bob.pubkey()).unwrap();
+    let bob_keys = BlindedConversationKeys::derive(bob.secret().as_bytes(), &alice.pubkey()).unwrap();
+    let eve_keys = BlindedConversationKeys::derive(eve.secret().as_bytes(), &bob.pubkey()).unwrap();
+
+    // Both participants derive the same keys, anybody else does not
+    assert_eq!(alice_keys, bob_keys);
+    assert_ne!(alice_keys.blinded_id, eve_keys.blinded_id);
+    assert_ne!(alice_keys.blinded_id, alice_keys.authority_seed);
+}
+
+#[test]
+fn test_pad_content() {
+    let ciphertext = vec![7_u8; 300];
+
+    for scheme in [PaddingScheme::POWER_OF_TWO, PaddingScheme::BLOCK_256].iter() {
+        let padded = pad_content(*scheme, &ciphertext).unwrap();
+
+        assert_eq!(padded.len(), PaddingScheme::padded_size(*scheme, ciphertext.len()));
+        assert!(PaddingScheme::is_padded(*scheme, padded.len()));
+        assert_eq!(unpad_content(*scheme, &padded).unwrap(), ciphertext);
+    }
+
+    assert_eq!(pad_content(PaddingScheme::POWER_OF_TWO, &ciphertext).unwrap().len(), 512);
+    assert_eq!(pad_content(PaddingScheme::BLOCK_256, &ciphertext).unwrap().len(), 512);
+    assert_eq!(pad_content(PaddingScheme::BLOCK_256, &[0_u8; 256]).unwrap().len(), 512);
+    assert_eq!(pad_content(PaddingScheme::NONE, &ciphertext).unwrap(), ciphertext);
+}
+
+#[test]
+fn test_unpad_invalid_content() {
+    assert!(unpad_content(PaddingScheme::BLOCK_256, &[7_u8; 256]).is_err());
+    assert!(unpad_content(PaddingScheme::BLOCK_256, &[0_u8; 256]).is_err());
+    assert!(unpad_content(PaddingScheme::POWER_OF_TWO, &[0x80_u8; 3]).is_err());
+    assert!(pad_content(7, &[]).is_err());
+}
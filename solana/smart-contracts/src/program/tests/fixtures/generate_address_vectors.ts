@@ -0,0 +1,58 @@
+// Generates `address_vectors.json` with the account addresses derived by the TypeScript client.
+//
+// Usage (from solana/smart-contracts):
+// npx ts-node src/program/tests/fixtures/generate_address_vectors.ts > src/program/tests/fixtures/address_vectors.json
+
+import crypto from 'crypto';
+import {PublicKey} from '@solana/web3.js';
+import BN from 'bn.js';
+import {Message, MessagePage, UserConversation} from '../../../client/im/state';
+
+function deterministicBytes(label: string, length: number): Buffer {
+  return crypto.createHash('sha256').update(label).digest().subarray(0, length);
+}
+
+const programId = new PublicKey('Hk5f9Xw9PdaQ9GEg8TPVFusojLA9otDpUkziXw1hAVE5');
+const conversation = new PublicKey(deterministicBytes('conversation', 32));
+const user = new PublicKey(deterministicBytes('user', 32));
+
+// Indexes below and above the u32 range - v2 seeds take all 8 bytes of both
+const indexes = ['0', '7', '4294967296'];
+
+async function main() {
+  const messagePages = [];
+
+  for (const pageIndex of [0, 1, 10, 256]) {
+    messagePages.push({
+      page_index: pageIndex,
+      address: (await MessagePage.findPdaAddress(conversation, pageIndex, programId)).toBase58(),
+    });
+  }
+
+  const userConversations = [];
+  const messages = [];
+
+  for (const index of indexes) {
+    userConversations.push({
+      conversation_index: index,
+      address: (await UserConversation.findPdaAddressV2(user, new BN(index), programId)).toBase58(),
+    });
+    messages.push({
+      message_index: index,
+      address: (await Message.findPdaAddressV2(conversation, new BN(index), programId)).toBase58(),
+    });
+  }
+
+  const vectors = {
+    program_id: programId.toBase58(),
+    conversation: conversation.toBase58(),
+    user: user.toBase58(),
+    message_pages: messagePages,
+    user_conversations: userConversations,
+    messages,
+  };
+
+  console.log(JSON.stringify(vectors, null, 2));
+}
+
+main();
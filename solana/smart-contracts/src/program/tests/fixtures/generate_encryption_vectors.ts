@@ -0,0 +1,79 @@
+// Generates `encryption_vectors.json` with the encoding of the TypeScript client.
+//
+// Usage (from solana/smart-contracts):
+// npx ts-node src/program/tests/fixtures/generate_encryption_vectors.ts > src/program/tests/fixtures/encryption_vectors.json
+
+import crypto from 'crypto';
+import {PublicKey} from '@solana/web3.js';
+import BN from 'bn.js';
+import {Message} from '../../../client/im/state';
+
+function deterministicBytes(label: string, length: number): Buffer {
+  return crypto.createHash('sha256').update(label).digest().subarray(0, length);
+}
+
+// Account data of the message - encoded with `Message.schema`
+function encodeMessageAccount(sender: Buffer, messageType: number, content: Buffer, timestamp: number, messageId: Buffer): Buffer {
+  return new Message({
+    sender,
+    message_type: messageType,
+    content,
+    timestamp: new BN(timestamp),
+    message_id: messageId,
+  }).encode();
+}
+
+const sender = deterministicBytes('sender', 32);
+const receiver = deterministicBytes('receiver', 32);
+
+// Same expression as `Service.sendMessage`
+const encryptionInfoData = Buffer.from(JSON.stringify({
+  type: 0,
+  data: {
+    addresses: [
+      new PublicKey(sender).toBase58(),
+      new PublicKey(receiver).toBase58(),
+    ],
+  },
+}));
+
+const ciphertext = deterministicBytes('ciphertext', 32);
+const plainText = Buffer.from('First message!');
+const timestamp = 1634567890;
+const messageId = deterministicBytes('message-id', 16);
+const noMessageId = Buffer.alloc(16);
+
+const vectors = {
+  sender: new PublicKey(sender).toBase58(),
+  receiver: new PublicKey(receiver).toBase58(),
+  encryption_info: [
+    {
+      description: 'RSA encryption info written on the first message',
+      data: encryptionInfoData.toString('hex'),
+    },
+    {
+      description: 'RSA encryption info without addresses',
+      data: Buffer.from(JSON.stringify({type: 0, data: {}})).toString('hex'),
+    },
+  ],
+  messages: [
+    {
+      description: 'RSA encrypted message',
+      message_type: 1,
+      content: ciphertext.toString('hex'),
+      timestamp,
+      message_id: messageId.toString('hex'),
+      account: encodeMessageAccount(sender, 1, ciphertext, timestamp, messageId).toString('hex'),
+    },
+    {
+      description: 'Plain text message',
+      message_type: 0,
+      content: plainText.toString('hex'),
+      timestamp,
+      message_id: noMessageId.toString('hex'),
+      account: encodeMessageAccount(sender, 0, plainText, timestamp, noMessageId).toString('hex'),
+    },
+  ],
+};
+
+console.log(JSON.stringify(vectors, null, 2));
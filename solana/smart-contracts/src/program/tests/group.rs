@@ -0,0 +1,150 @@
+use borsh::BorshDeserialize;
+use instant_messaging::state::{
+    GroupMember,
+    Invite,
+    GroupPermission,
+    GroupRole,
+};
+
+fn member(role: u8) -> GroupMember {
+    GroupMember {
+        role,
+        permissions: GroupRole::default_permissions(role),
+    }
+}
+
+#[test]
+fn test_add_group_member() {
+    let owner = member(GroupRole::OWNER);
+    let admin = member(GroupRole::ADMIN);
+    let inviting_member = GroupMember {
+        role: GroupRole::MEMBER,
+        permissions: GroupPermission::SEND | GroupPermission::INVITE,
+    };
+
+    assert!(owner.can_add(GroupRole::ADMIN, GroupPermission::ALL));
+    assert!(admin.can_add(GroupRole::MEMBER, GroupPermission::SEND));
+    assert!(inviting_member.can_add(GroupRole::MEMBER, GroupPermission::SEND));
+
+    // Nobody adds another owner
+    assert!(!owner.can_add(GroupRole::OWNER, GroupPermission::ALL));
+    // Only the owner adds admins
+    assert!(!admin.can_add(GroupRole::ADMIN, GroupPermission::ALL));
+    // Inviting needs the permission
+    assert!(!member(GroupRole::MEMBER).can_add(GroupRole::MEMBER, GroupPermission::SEND));
+    // Permissions are limited by the role and by the inviter
+    assert!(!admin.can_add(GroupRole::MEMBER, GroupPermission::REMOVE));
+    assert!(!inviting_member.can_add(GroupRole::MEMBER, GroupPermission::SEND | GroupPermission::PIN));
+    assert!(!owner.can_add(7, GroupPermission::NONE));
+}
+
+#[test]
+fn test_assign_group_role() {
+    let owner = member(GroupRole::OWNER);
+    let admin = member(GroupRole::ADMIN);
+    let regular_member = member(GroupRole::MEMBER);
+
+    assert!(owner.can_assign(&admin, GroupRole::MEMBER, GroupPermission::SEND));
+    assert!(owner.can_assign(&regular_member, GroupRole::ADMIN, GroupPermission::ALL));
+    assert!(admin.can_assign(&regular_member, GroupRole::READ_ONLY, GroupPermission::NONE));
+
+    // Privilege escalation attempts
+    assert!(!regular_member.can_assign(&regular_member, GroupRole::ADMIN, GroupPermission::ALL));
+    assert!(!admin.can_assign(&regular_member, GroupRole::ADMIN, GroupPermission::ALL));
+    assert!(!admin.can_assign(&owner, GroupRole::READ_ONLY, GroupPermission::NONE));
+    assert!(!admin.can_assign(&admin, GroupRole::MEMBER, GroupPermission::SEND));
+    assert!(!owner.can_assign(&regular_member, GroupRole::OWNER, GroupPermission::ALL));
+    assert!(!owner.can_assign(&regular_member, GroupRole::READ_ONLY, GroupPermission::SEND));
+}
+
+#[test]
+fn test_closed_group_member() {
+    // Data of a closed account is zeroed - it must not decode to a privileged member
+    let closed = GroupMember::try_from_slice(&[0_u8; 2]).unwrap();
+
+    assert_eq!(closed.role, GroupRole::NONE);
+    assert!(!closed.has_permission(GroupPermission::SEND));
+    assert!(!closed.can_add(GroupRole::MEMBER, GroupPermission::NONE));
+    assert!(!closed.can_remove(&member(GroupRole::READ_ONLY)));
+
+    // Nobody is given the role of a closed account
+    assert!(!member(GroupRole::OWNER).can_add(GroupRole::NONE, GroupPermission::NONE));
+    assert!(!GroupRole::is_valid(GroupRole::NONE));
+}
+
+#[test]
+fn test_invite_is_usable() {
+    let invite = Invite {
+        expires_at: 1_000,
+        max_uses: 2,
+        uses: 1,
+        ..Invite::default()
+    };
+
+    assert!(invite.is_usable(999));
+    assert!(!invite.is_usable(1_000));
+
+    let used_up = Invite {
+        uses: 2,
+        ..invite
+    };
+
+    assert!(!used_up.is_usable(0));
+}
+
+#[test]
+fn test_remove_group_member() {
+    let owner = member(GroupRole::OWNER);
+    let admin = member(GroupRole::ADMIN);
+    let regular_member = member(GroupRole::MEMBER);
+
+    assert!(owner.can_remove(&admin));
+    assert!(admin.can_remove(&regular_member));
+    assert!(admin.can_remove(&member(GroupRole::READ_ONLY)));
+
+    assert!(!admin.can_remove(&owner));
+    assert!(!admin.can_remove(&admin));
+    assert!(!regular_member.can_remove(&member(GroupRole::READ_ONLY)));
+
+    // Removing needs the permission
+    let restricted_admin = GroupMember {
+        role: GroupRole::ADMIN,
+        permissions: GroupPermission::SEND,
+    };
+    assert!(!restricted_admin.can_remove(&regular_member));
+}
+
+#[test]
+fn test_group_account_size() {
+    use borsh::BorshDeserialize;
+    use instant_messaging::state::Group;
+
+    // Accounts are allocated with this size and must deserialize without trailing bytes
+    let data = vec![0_u8; Group::retrieve_size()];
+
+    assert!(Group::try_from_slice(&data).is_ok());
+}
+
+#[test]
+fn test_account_kind() {
+    use borsh::{BorshDeserialize, BorshSerialize};
+    use instant_messaging::state::{AccountKind, Community, Group};
+
+    let group = Group {
+        account_kind: AccountKind::GROUP,
+        ..Group::default()
+    };
+    let community = Community {
+        account_kind: AccountKind::COMMUNITY,
+        ..Community::default()
+    };
+
+    assert_eq!(AccountKind::of(&group.try_to_vec().unwrap()), AccountKind::GROUP);
+    assert_eq!(AccountKind::of(&community.try_to_vec().unwrap()), AccountKind::COMMUNITY);
+
+    // Closed accounts are zeroed and tell nothing about what they held
+    let data = vec![0_u8; Community::retrieve_size()];
+
+    assert_eq!(AccountKind::of(&data), AccountKind::UNINITIALIZED);
+    assert!(Community::try_from_slice(&data).is_ok());
+}
@@ -4,14 +4,63 @@
 mod program_test;
 mod utils;
 
-use solana_sdk::signer::keypair::Keypair;
+use solana_sdk::signer::keypair::{
+    keypair_from_seed,
+    Keypair,
+};
 use {
     utils::{
+        add_group_member,
+        create_group_with_members,
+        create_invite_pda_account,
+        join_with_invite,
+        revoke_invite,
+        request_to_join,
+        approve_join,
+        deny_join,
+        ban_member,
+        leave_group,
+        remove_member,
+        create_group_pda_account,
+        create_community_pda_account,
+        create_community_group_pda_account,
+        create_community_group_user_conversation,
+        send_community_group_message,
+        subscribe,
+        unsubscribe,
+        send_group_message,
+        set_member_role,
+        transfer_group_ownership,
         create_user_pda_account,
         create_conversation_pda_account,
         create_user_conversation_pda_account,
         create_conversation_encryption_info_pda_account,
+        create_conversation_handshake_pda_account,
+        create_conversation_between_wallets,
+        create_blinded_conversation_pda_account,
+        send_blinded_message,
         send_message,
+        send_message_with_id,
+        send_messages,
+        send_paged_message,
+        add_conversation_accounts,
+        add_v1_message_account,
+        add_blinded_conversation_account,
+        process_signed_instruction,
+        send_sealed_message,
+        set_blinded_conversation_padding_policy_pda_account,
+        set_conversation_padding_policy_pda_account,
+        set_conversation_metadata_pda_account,
+        set_group_metadata_pda_account,
+        pin_message,
+        unpin_message,
+        pin_group_message,
+        update_user_conversation,
+        hide_conversation,
+        start_conversation,
+        sequenced_message_transaction,
+        message_transaction,
+        set_sealed_sender_credential_pda_account,
     },
     borsh::{BorshDeserialize},
     solana_program::{
@@ -23,23 +72,53 @@ use {
         signature::{
             Signer,
         },
+        system_instruction,
+        transaction::Transaction,
+    },
+    program_test::{
+        program_test,
+        program_test_with_compute_max_units,
     },
-    program_test::program_test,
     instant_messaging::{
+        blinded::BlindedConversationKeys,
+        padding::pad_content,
+        sealed_sender::SealedSenderChain,
         id,
+        instruction,
         state::{
+            AccountKind,
+            AccountUpgrade,
+            BlindedConversation,
+            Community,
             User,
             Conversation,
             ConversationEncryptionInfo,
+            ConversationHandshake,
+            ConversationMetadata,
+            ConversationPaddingPolicy,
+            Group,
+            GroupKind,
+            GroupMember,
+            GroupPermission,
+            GroupRole,
+            Invite,
+            JoinRequest,
             Message,
+            MessageIdRecord,
+            MessagePage,
             MessageType,
+            PagedConversation,
+            PaddingScheme,
+            PinnedMessages,
+            SealedSenderCredential,
+            SenderSequence,
         },
     },
 };
 use instant_messaging::state::UserConversation;
 use crate::utils::create_message_pda_account;
 
-//#[tokio::test]
+#[tokio::test]
 async fn test_create_user_account() {
     let user_wallet_address = Pubkey::new_unique();
     let user_pda_address = User::find_pda_address(
@@ -89,10 +168,10 @@ async fn test_create_user_account() {
     let is_rent_exempt = sysvar::rent::Rent::default()
         .is_exempt(user_pda_account.lamports, user_pda_account.data.len());
 
-    assert_eq!(is_rent_exempt, true);
+    assert!(is_rent_exempt);
 }
 
-//#[tokio::test]
+#[tokio::test]
 async fn test_conversation_pda_address_generation() {
     let sender_pda_address = Pubkey::new_unique();
     let receiver_pda_address = Pubkey::new_unique();
@@ -112,10 +191,13 @@ async fn test_conversation_pda_address_generation() {
     assert_eq!(conversation_pda_address_1, conversation_pda_address_2);
 }
 
-//#[tokio::test]
+#[tokio::test]
 async fn test_create_conversation_account() {
-    let sender_pda_address = Pubkey::new_unique();
-    let receiver_pda_address = Pubkey::new_unique();
+    let sender_wallet_address = Pubkey::new_unique();
+    let receiver_wallet_address = Pubkey::new_unique();
+
+    let sender_pda_address = User::find_pda_address(&sender_wallet_address, &id());
+    let receiver_pda_address = User::find_pda_address(&receiver_wallet_address, &id());
 
     let conversation_pda_address = Conversation::find_pda_address(
         &sender_pda_address,
@@ -131,7 +213,7 @@ async fn test_create_conversation_account() {
         &payer,
         &mut banks_client,
         &recent_blockhash,
-        &sender_pda_address,
+        &sender_wallet_address,
     ).await;
 
     // Create User PDA account
@@ -139,7 +221,7 @@ async fn test_create_conversation_account() {
         &payer,
         &mut banks_client,
         &recent_blockhash,
-        &receiver_pda_address,
+        &receiver_wallet_address,
     ).await;
 
     // Conversation PDA account does not exist
@@ -187,7 +269,7 @@ async fn test_create_conversation_account() {
     let is_rent_exempt = sysvar::rent::Rent::default()
         .is_exempt(conversation_pda_account.lamports, conversation_pda_account.data.len());
 
-    assert_eq!(is_rent_exempt, true);
+    assert!(is_rent_exempt);
 
     // Sender's user PDA account should have 1 conversation
     let sender_user_pda_account = banks_client
@@ -218,7 +300,7 @@ async fn test_create_conversation_account() {
     );
 
     // Sender's user-conversation PDA account should have conversation address
-    let sender_user_conversation_pda_address = UserConversation::find_pda_address(
+    let sender_user_conversation_pda_address = UserConversation::find_pda_address_v2(
         &sender_pda_address,
         sender_user_conversation_index,
         &id(),
@@ -238,7 +320,7 @@ async fn test_create_conversation_account() {
     );
 
     // Receiver's user-conversation PDA account should have conversation address
-    let receiver_user_conversation_pda_address = UserConversation::find_pda_address(
+    let receiver_user_conversation_pda_address = UserConversation::find_pda_address_v2(
         &receiver_pda_address,
         receiver_user_conversation_index,
         &id(),
@@ -258,12 +340,12 @@ async fn test_create_conversation_account() {
     );
 }
 
-//#[tokio::test]
+#[tokio::test]
 async fn test_create_user_conversation_account() {
     let user_pda_address = Pubkey::new_unique();
     let conversation_index = 0;
 
-    let user_conversation_pda_address = UserConversation::find_pda_address(
+    let user_conversation_pda_address = UserConversation::find_pda_address_v2(
         &user_pda_address,
         conversation_index,
         &id(),
@@ -304,10 +386,10 @@ async fn test_create_user_conversation_account() {
     let is_rent_exempt = sysvar::rent::Rent::default()
         .is_exempt(user_conversation_pda_account.lamports, user_conversation_pda_account.data.len());
 
-    assert_eq!(is_rent_exempt, true);
+    assert!(is_rent_exempt);
 }
 
-// #[tokio::test]
+#[tokio::test]
 async fn create_message_account() {
     let (mut banks_client, payer, recent_blockhash) =
         program_test().start().await;
@@ -378,7 +460,7 @@ async fn create_message_account() {
         &message_content,
     ).await;
 
-    let message_pda_address = Message::find_pda_address(
+    let message_pda_address = Message::find_pda_address_v2(
         &conversation_pda_address,
         message_index,
         &id(),
@@ -413,7 +495,7 @@ async fn create_message_account() {
     );
 }
 
-//#[tokio::test]
+#[tokio::test]
 #[should_panic]
 async fn test_create_message_to_wrong_conversation() {
     let (mut banks_client, payer, recent_blockhash) =
@@ -533,7 +615,7 @@ async fn test_create_message_to_wrong_conversation() {
     ).await;
 }
 
-//#[tokio::test]
+#[tokio::test]
 async fn test_create_conversation_encryption_info_account() {
     let (mut banks_client, payer, recent_blockhash) =
         program_test().start().await;
@@ -701,7 +783,7 @@ async fn test_send_message() {
     );
 
     // Sender's user-conversation PDA account should have conversation address
-    let sender_user_conversation_pda_address = UserConversation::find_pda_address(
+    let sender_user_conversation_pda_address = UserConversation::find_pda_address_v2(
         &sender_pda_address,
         sender_user_conversation_index,
         &id(),
@@ -721,7 +803,7 @@ async fn test_send_message() {
     );
 
     // Receiver's user-conversation PDA account should have conversation address
-    let receiver_user_conversation_pda_address = UserConversation::find_pda_address(
+    let receiver_user_conversation_pda_address = UserConversation::find_pda_address_v2(
         &receiver_pda_address,
         receiver_user_conversation_index,
         &id(),
@@ -745,7 +827,7 @@ async fn test_send_message() {
     let first_message_type = MessageType::PLAIN_TEXT;
     let first_message_content = String::from("First message!").into_bytes();
 
-    let message_pda_address = Message::find_pda_address(
+    let message_pda_address = Message::find_pda_address_v2(
         &conversation_pda_address,
         first_message_index,
         &id(),
@@ -800,3 +882,4638 @@ async fn test_send_message() {
         1,
     );
 }
+
+#[tokio::test]
+async fn test_create_conversation_handshake_account() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let sender = Keypair::new();
+    let receiver = Keypair::new();
+
+    let conversation_pda_address = create_conversation_between_wallets(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender.pubkey(),
+        &receiver.pubkey(),
+    ).await;
+
+    // Sender creates the group - epoch 0 welcomes the receiver
+    let commit = vec![1_u8; 64];
+    let welcome = vec![2_u8; 128];
+
+    create_conversation_handshake_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender,
+        &receiver.pubkey(),
+        0,
+        &commit,
+        &welcome,
+    ).await;
+
+    // Receiver updates its leaf - epoch 1 has no welcome
+    let update_commit = vec![3_u8; 64];
+
+    create_conversation_handshake_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &receiver,
+        &sender.pubkey(),
+        1,
+        &update_commit,
+        &[],
+    ).await;
+
+    let first_handshake_account = banks_client
+        .get_account(ConversationHandshake::find_pda_address(&conversation_pda_address, 0, &id()))
+        .await
+        .expect("get_account")
+        .expect("first_handshake_account not found");
+
+    let first_handshake = ConversationHandshake::try_from_slice(&first_handshake_account.data)
+        .unwrap();
+
+    assert_eq!(first_handshake.sender, sender.pubkey());
+    assert_eq!(first_handshake.commit, commit);
+    assert_eq!(first_handshake.welcome, welcome);
+    assert_ne!(first_handshake.timestamp, 0);
+
+    let second_handshake_account = banks_client
+        .get_account(ConversationHandshake::find_pda_address(&conversation_pda_address, 1, &id()))
+        .await
+        .expect("get_account")
+        .expect("second_handshake_account not found");
+
+    let second_handshake = ConversationHandshake::try_from_slice(&second_handshake_account.data)
+        .unwrap();
+
+    assert_eq!(second_handshake.sender, receiver.pubkey());
+    assert_eq!(second_handshake.commit, update_commit);
+    assert!(second_handshake.welcome.is_empty());
+}
+
+#[tokio::test]
+#[should_panic]
+async fn test_create_conversation_handshake_account_skipping_epoch() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let sender = Keypair::new();
+    let receiver = Keypair::new();
+
+    create_conversation_between_wallets(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender.pubkey(),
+        &receiver.pubkey(),
+    ).await;
+
+    create_conversation_handshake_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender,
+        &receiver.pubkey(),
+        0,
+        &[1_u8; 64],
+        &[2_u8; 128],
+    ).await;
+
+    // Epoch 1 is missing
+    create_conversation_handshake_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender,
+        &receiver.pubkey(),
+        2,
+        &[3_u8; 64],
+        &[],
+    ).await;
+}
+
+#[tokio::test]
+async fn test_create_group_handshake_account() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let owner = Keypair::new();
+    let admin = Keypair::new();
+    let member = Keypair::new();
+
+    let group_pda_address = create_group_with_members(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &owner,
+        &admin,
+        &member,
+    ).await;
+
+    // Owner starts the group epoch, a member commits the next one
+    for (epoch, sender) in [&owner, &member].iter().enumerate() {
+        process_signed_instruction(
+            &mut banks_client,
+            &recent_blockhash,
+            &payer,
+            &[sender],
+            instruction::create_group_handshake_account(
+                &payer.pubkey(),
+                &sender.pubkey(),
+                &group_pda_address,
+                epoch as u64,
+                vec![epoch as u8; 64],
+                vec![],
+            ),
+        ).await;
+    }
+
+    let second_handshake_account = banks_client
+        .get_account(ConversationHandshake::find_pda_address(&group_pda_address, 1, &id()))
+        .await
+        .expect("get_account")
+        .expect("second_handshake_account not found");
+
+    let second_handshake = ConversationHandshake::try_from_slice(&second_handshake_account.data)
+        .unwrap();
+
+    assert_eq!(second_handshake.sender, member.pubkey());
+    assert_eq!(second_handshake.commit, vec![1_u8; 64]);
+}
+
+#[tokio::test]
+#[should_panic]
+async fn test_create_group_handshake_account_by_non_member() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let owner = Keypair::new();
+    let admin = Keypair::new();
+    let member = Keypair::new();
+    let outsider = Keypair::new();
+
+    let group_pda_address = create_group_with_members(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &owner,
+        &admin,
+        &member,
+    ).await;
+
+    process_signed_instruction(
+        &mut banks_client,
+        &recent_blockhash,
+        &payer,
+        &[&outsider],
+        instruction::create_group_handshake_account(
+            &payer.pubkey(),
+            &outsider.pubkey(),
+            &group_pda_address,
+            0,
+            vec![1_u8; 64],
+            vec![],
+        ),
+    ).await;
+}
+
+#[tokio::test]
+async fn test_send_sealed_message() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let sender = Keypair::new();
+    let receiver = Keypair::new();
+
+    let conversation_pda_address = create_conversation_between_wallets(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender.pubkey(),
+        &receiver.pubkey(),
+    ).await;
+
+    let credential_pda_address = SealedSenderCredential::find_pda_address(
+        &conversation_pda_address,
+        &sender.pubkey(),
+        &id(),
+    );
+
+    let chain = SealedSenderChain::new(b"shared secret", 2);
+
+    set_sealed_sender_credential_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender,
+        &receiver.pubkey(),
+        &chain.authority(0).unwrap(),
+        chain.chain_hash(0).unwrap(),
+        None,
+    ).await;
+
+    // Relayer submits both messages, each signed by the ephemeral authority only
+    let first_content = vec![7_u8; 48];
+    let second_content = vec![8_u8; 48];
+
+    send_sealed_message(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &keypair_from_seed(&chain.authority_seed(0).unwrap()).unwrap(),
+        &conversation_pda_address,
+        &credential_pda_address,
+        0,
+        &chain.authority(1).unwrap(),
+        chain.chain_hash(1).unwrap(),
+        &first_content,
+    ).await;
+
+    send_sealed_message(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &keypair_from_seed(&chain.authority_seed(1).unwrap()).unwrap(),
+        &conversation_pda_address,
+        &credential_pda_address,
+        1,
+        &Pubkey::default(),
+        chain.chain_hash(2).unwrap(),
+        &second_content,
+    ).await;
+
+    let message_pda_account = banks_client
+        .get_account(Message::find_pda_address_v2(&conversation_pda_address, 1, &id()))
+        .await
+        .expect("get_account")
+        .expect("message_pda_account not found");
+
+    let message = Message::try_from_slice(&message_pda_account.data)
+        .unwrap();
+
+    assert_eq!(message.sender, Pubkey::default());
+    assert_eq!(message.message_type, MessageType::SEALED_SENDER);
+    assert_eq!(message.content, second_content);
+
+    // Chain is used up - the credential names no keypair until it is set again
+    let credential_pda_account = banks_client
+        .get_account(credential_pda_address)
+        .await
+        .expect("get_account")
+        .expect("credential_pda_account not found");
+
+    let credential = SealedSenderCredential::try_from_slice(&credential_pda_account.data)
+        .unwrap();
+
+    assert!(credential.is_exhausted());
+
+    // Used-up credential is set again without a chain step
+    let next_chain = SealedSenderChain::new(b"next shared secret", 1);
+
+    set_sealed_sender_credential_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender,
+        &receiver.pubkey(),
+        &next_chain.authority(0).unwrap(),
+        next_chain.chain_hash(0).unwrap(),
+        None,
+    ).await;
+}
+
+#[tokio::test]
+#[should_panic]
+async fn test_send_sealed_message_with_replayed_token() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let sender = Keypair::new();
+    let receiver = Keypair::new();
+
+    let conversation_pda_address = create_conversation_between_wallets(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender.pubkey(),
+        &receiver.pubkey(),
+    ).await;
+
+    let credential_pda_address = SealedSenderCredential::find_pda_address(
+        &conversation_pda_address,
+        &receiver.pubkey(),
+        &id(),
+    );
+
+    let chain = SealedSenderChain::new(b"shared secret", 2);
+
+    set_sealed_sender_credential_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &receiver,
+        &sender.pubkey(),
+        &chain.authority(0).unwrap(),
+        chain.chain_hash(0).unwrap(),
+        None,
+    ).await;
+
+    let authority = keypair_from_seed(&chain.authority_seed(0).unwrap()).unwrap();
+
+    send_sealed_message(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &authority,
+        &conversation_pda_address,
+        &credential_pda_address,
+        0,
+        &chain.authority(1).unwrap(),
+        chain.chain_hash(1).unwrap(),
+        &[7_u8; 48],
+    ).await;
+
+    // Keypair of a sent message does not authorize another message
+    send_sealed_message(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &authority,
+        &conversation_pda_address,
+        &credential_pda_address,
+        1,
+        &chain.authority(1).unwrap(),
+        chain.chain_hash(1).unwrap(),
+        &[8_u8; 48],
+    ).await;
+}
+
+#[tokio::test]
+#[should_panic]
+async fn test_send_sealed_message_with_next_authority_outside_chain() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let sender = Keypair::new();
+    let receiver = Keypair::new();
+
+    let conversation_pda_address = create_conversation_between_wallets(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender.pubkey(),
+        &receiver.pubkey(),
+    ).await;
+
+    let chain = SealedSenderChain::new(b"shared secret", 2);
+
+    set_sealed_sender_credential_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender,
+        &receiver.pubkey(),
+        &chain.authority(0).unwrap(),
+        chain.chain_hash(0).unwrap(),
+        None,
+    ).await;
+
+    // Holder of a leaked keypair tries to hand the chain to a keypair of its own
+    send_sealed_message(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &keypair_from_seed(&chain.authority_seed(0).unwrap()).unwrap(),
+        &conversation_pda_address,
+        &SealedSenderCredential::find_pda_address(&conversation_pda_address, &sender.pubkey(), &id()),
+        0,
+        &Keypair::new().pubkey(),
+        chain.chain_hash(1).unwrap(),
+        &[7_u8; 48],
+    ).await;
+}
+
+#[tokio::test]
+async fn test_set_sealed_sender_credential_per_participant() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let sender = Keypair::new();
+    let receiver = Keypair::new();
+
+    let conversation_pda_address = create_conversation_between_wallets(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender.pubkey(),
+        &receiver.pubkey(),
+    ).await;
+
+    let sender_chain = SealedSenderChain::new(b"sender secret", 2);
+    let receiver_chain = SealedSenderChain::new(b"receiver secret", 2);
+
+    set_sealed_sender_credential_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender,
+        &receiver.pubkey(),
+        &sender_chain.authority(0).unwrap(),
+        sender_chain.chain_hash(0).unwrap(),
+        None,
+    ).await;
+
+    set_sealed_sender_credential_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &receiver,
+        &sender.pubkey(),
+        &receiver_chain.authority(0).unwrap(),
+        receiver_chain.chain_hash(0).unwrap(),
+        None,
+    ).await;
+
+    // Active chain is replaced with a signature of its current authority
+    let next_sender_chain = SealedSenderChain::new(b"next sender secret", 2);
+
+    set_sealed_sender_credential_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender,
+        &receiver.pubkey(),
+        &next_sender_chain.authority(0).unwrap(),
+        next_sender_chain.chain_hash(0).unwrap(),
+        Some(&keypair_from_seed(&sender_chain.authority_seed(0).unwrap()).unwrap()),
+    ).await;
+
+    // Credential of the receiver is not touched by the sender
+    let credential_pda_account = banks_client
+        .get_account(SealedSenderCredential::find_pda_address(&conversation_pda_address, &receiver.pubkey(), &id()))
+        .await
+        .expect("get_account")
+        .expect("credential_pda_account not found");
+
+    let credential = SealedSenderCredential::try_from_slice(&credential_pda_account.data)
+        .unwrap();
+
+    assert_eq!(credential.authority, receiver_chain.authority(0).unwrap());
+    assert_eq!(credential.chain_hash, receiver_chain.chain_hash(0).unwrap());
+}
+
+#[tokio::test]
+#[should_panic]
+async fn test_cannot_replace_active_sealed_sender_credential() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let sender = Keypair::new();
+    let receiver = Keypair::new();
+
+    create_conversation_between_wallets(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender.pubkey(),
+        &receiver.pubkey(),
+    ).await;
+
+    let chain = SealedSenderChain::new(b"shared secret", 2);
+    let next_chain = SealedSenderChain::new(b"next shared secret", 2);
+
+    set_sealed_sender_credential_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender,
+        &receiver.pubkey(),
+        &chain.authority(0).unwrap(),
+        chain.chain_hash(0).unwrap(),
+        None,
+    ).await;
+
+    set_sealed_sender_credential_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender,
+        &receiver.pubkey(),
+        &next_chain.authority(0).unwrap(),
+        next_chain.chain_hash(0).unwrap(),
+        None,
+    ).await;
+}
+
+#[tokio::test]
+async fn test_send_blinded_message() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let sender = Keypair::new();
+    let receiver = Keypair::new();
+
+    let sender_keys = BlindedConversationKeys::derive(sender.secret().as_bytes(), &receiver.pubkey())
+        .unwrap();
+    let receiver_keys = BlindedConversationKeys::derive(receiver.secret().as_bytes(), &sender.pubkey())
+        .unwrap();
+
+    let sender_authority = keypair_from_seed(&sender_keys.authority_seed).unwrap();
+    let receiver_authority = keypair_from_seed(&receiver_keys.authority_seed).unwrap();
+
+    create_blinded_conversation_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender_authority,
+        sender_keys.blinded_id,
+    ).await;
+
+    // Both participants sign with the same derived authority
+    send_blinded_message(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender_authority,
+        &sender_keys.blinded_id,
+        0,
+        MessageType::RSA_ENCRYPTED,
+        &[1_u8; 32],
+    ).await;
+
+    send_blinded_message(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &receiver_authority,
+        &receiver_keys.blinded_id,
+        1,
+        MessageType::RSA_ENCRYPTED,
+        &[2_u8; 32],
+    ).await;
+
+    let conversation_pda_address = sender_keys.conversation_address(&id());
+
+    let conversation_pda_account = banks_client
+        .get_account(conversation_pda_address)
+        .await
+        .expect("get_account")
+        .expect("conversation_pda_account not found");
+
+    let conversation = BlindedConversation::try_from_slice(&conversation_pda_account.data)
+        .unwrap();
+
+    assert_eq!(conversation.message_counter, 2);
+    assert_eq!(conversation.authority, sender_authority.pubkey());
+
+    let message_pda_account = banks_client
+        .get_account(Message::find_pda_address_v2(&conversation_pda_address, 1, &id()))
+        .await
+        .expect("get_account")
+        .expect("message_pda_account not found");
+
+    let message = Message::try_from_slice(&message_pda_account.data)
+        .unwrap();
+
+    assert_eq!(message.sender, receiver_authority.pubkey());
+    assert_eq!(message.content, vec![2_u8; 32]);
+}
+
+#[tokio::test]
+async fn test_create_blinded_conversation_with_observed_blinded_id() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let sender = Keypair::new();
+    let receiver = Keypair::new();
+    let squatter = Keypair::new();
+
+    let keys = BlindedConversationKeys::derive(sender.secret().as_bytes(), &receiver.pubkey())
+        .unwrap();
+
+    // Blinded identifier copied from a pending transaction does not take the participants' address
+    create_blinded_conversation_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &squatter,
+        keys.blinded_id,
+    ).await;
+
+    let authority = keypair_from_seed(&keys.authority_seed).unwrap();
+    assert_eq!(authority.pubkey(), keys.authority());
+
+    create_blinded_conversation_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &authority,
+        keys.blinded_id,
+    ).await;
+
+    let conversation_pda_account = banks_client
+        .get_account(keys.conversation_address(&id()))
+        .await
+        .expect("get_account")
+        .expect("conversation_pda_account not found");
+
+    let conversation = BlindedConversation::try_from_slice(&conversation_pda_account.data)
+        .unwrap();
+
+    assert_eq!(conversation.authority, authority.pubkey());
+    assert_ne!(
+        keys.conversation_address(&id()),
+        BlindedConversation::find_pda_address(&keys.blinded_id, &squatter.pubkey(), &id()),
+    );
+}
+
+#[tokio::test]
+#[should_panic]
+async fn test_send_blinded_message_without_shared_secret() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let sender = Keypair::new();
+    let receiver = Keypair::new();
+
+    let keys = BlindedConversationKeys::derive(sender.secret().as_bytes(), &receiver.pubkey())
+        .unwrap();
+
+    create_blinded_conversation_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &keypair_from_seed(&keys.authority_seed).unwrap(),
+        keys.blinded_id,
+    ).await;
+
+    // Knowing the address is not enough to post
+    send_blinded_message(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &Keypair::new(),
+        &keys.blinded_id,
+        0,
+        MessageType::PLAIN_TEXT,
+        &String::from("Hello").into_bytes(),
+    ).await;
+}
+
+#[tokio::test]
+#[should_panic]
+async fn test_send_blinded_message_to_copied_conversation() {
+    let authority = Keypair::new();
+    let blinded_id = [3_u8; 32];
+
+    let (_, bump_seed) = BlindedConversation::find_pda_address_with_bump_seed(&blinded_id, &authority.pubkey(), &id());
+
+    // Program account with the data of a blinded conversation, outside of its seed derivation
+    let copied_address = Pubkey::new_unique();
+
+    let mut program_test = program_test();
+    add_blinded_conversation_account(&mut program_test, copied_address, &BlindedConversation {
+        message_counter: 0,
+        authority: authority.pubkey(),
+        blinded_id,
+        bump_seed,
+    });
+
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    let mut send_instruction = instruction::send_blinded_message(
+        &payer.pubkey(),
+        &authority.pubkey(),
+        &blinded_id,
+        0,
+        MessageType::PLAIN_TEXT,
+        String::from("Hello").into_bytes(),
+    );
+    send_instruction.accounts[2].pubkey = copied_address;
+    send_instruction.accounts[3].pubkey = Message::find_pda_address_v2(&copied_address, 0, &id());
+    send_instruction.accounts[7].pubkey = ConversationPaddingPolicy::find_pda_address(&copied_address, &id());
+
+    process_signed_instruction(
+        &mut banks_client,
+        &recent_blockhash,
+        &payer,
+        &[&authority],
+        send_instruction,
+    ).await;
+}
+
+#[tokio::test]
+async fn test_send_padded_message() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let sender = Keypair::new();
+    let receiver = Keypair::new();
+
+    let conversation_pda_address = create_conversation_between_wallets(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender.pubkey(),
+        &receiver.pubkey(),
+    ).await;
+
+    set_conversation_padding_policy_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &receiver,
+        &sender.pubkey(),
+        PaddingScheme::BLOCK_256,
+    ).await;
+
+    let padding_policy_pda_account = banks_client
+        .get_account(ConversationPaddingPolicy::find_pda_address(&conversation_pda_address, &id()))
+        .await
+        .expect("get_account")
+        .expect("padding_policy_pda_account not found");
+
+    let padding_policy = ConversationPaddingPolicy::try_from_slice(&padding_policy_pda_account.data)
+        .unwrap();
+
+    assert_eq!(padding_policy.scheme, PaddingScheme::BLOCK_256);
+
+    let content = pad_content(PaddingScheme::BLOCK_256, &[7_u8; 100]).unwrap();
+
+    send_message(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender,
+        &receiver.pubkey(),
+        0,
+        MessageType::RSA_ENCRYPTED,
+        &content,
+    ).await;
+
+    // Plain text is padded as well
+    send_message(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender,
+        &receiver.pubkey(),
+        1,
+        MessageType::PLAIN_TEXT,
+        &pad_content(PaddingScheme::BLOCK_256, &String::from("Hello").into_bytes()).unwrap(),
+    ).await;
+
+    let message_pda_account = banks_client
+        .get_account(Message::find_pda_address_v2(&conversation_pda_address, 0, &id()))
+        .await
+        .expect("get_account")
+        .expect("message_pda_account not found");
+
+    let message = Message::try_from_slice(&message_pda_account.data)
+        .unwrap();
+
+    assert_eq!(message.content.len(), 256);
+}
+
+#[tokio::test]
+#[should_panic]
+async fn test_send_unpadded_encrypted_message() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let sender = Keypair::new();
+    let receiver = Keypair::new();
+
+    create_conversation_between_wallets(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender.pubkey(),
+        &receiver.pubkey(),
+    ).await;
+
+    set_conversation_padding_policy_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender,
+        &receiver.pubkey(),
+        PaddingScheme::POWER_OF_TWO,
+    ).await;
+
+    send_message(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender,
+        &receiver.pubkey(),
+        0,
+        MessageType::RSA_ENCRYPTED,
+        &[7_u8; 100],
+    ).await;
+}
+
+#[tokio::test]
+#[should_panic]
+async fn test_send_unpadded_message_as_plain_text() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let sender = Keypair::new();
+    let receiver = Keypair::new();
+
+    create_conversation_between_wallets(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender.pubkey(),
+        &receiver.pubkey(),
+    ).await;
+
+    set_conversation_padding_policy_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &receiver,
+        &sender.pubkey(),
+        PaddingScheme::POWER_OF_TWO,
+    ).await;
+
+    // Choosing another message type does not skip the policy
+    send_message(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender,
+        &receiver.pubkey(),
+        0,
+        MessageType::PLAIN_TEXT,
+        &[7_u8; 100],
+    ).await;
+}
+
+#[tokio::test]
+#[should_panic]
+async fn test_loosen_conversation_padding_policy() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let sender = Keypair::new();
+    let receiver = Keypair::new();
+
+    create_conversation_between_wallets(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender.pubkey(),
+        &receiver.pubkey(),
+    ).await;
+
+    set_conversation_padding_policy_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &receiver,
+        &sender.pubkey(),
+        PaddingScheme::BLOCK_256,
+    ).await;
+
+    // The other participant cannot drop the padding the receiver relies on
+    set_conversation_padding_policy_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender,
+        &receiver.pubkey(),
+        PaddingScheme::NONE,
+    ).await;
+}
+
+#[tokio::test]
+#[should_panic]
+async fn test_send_unpadded_blinded_message() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let sender = Keypair::new();
+    let receiver = Keypair::new();
+
+    let keys = BlindedConversationKeys::derive(sender.secret().as_bytes(), &receiver.pubkey())
+        .unwrap();
+    let authority = keypair_from_seed(&keys.authority_seed).unwrap();
+
+    create_blinded_conversation_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &authority,
+        keys.blinded_id,
+    ).await;
+
+    set_blinded_conversation_padding_policy_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &authority,
+        &keys.blinded_id,
+        PaddingScheme::BLOCK_256,
+    ).await;
+
+    send_blinded_message(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &authority,
+        &keys.blinded_id,
+        0,
+        MessageType::RSA_ENCRYPTED,
+        &[1_u8; 32],
+    ).await;
+}
+
+#[tokio::test]
+async fn test_send_padded_group_message() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let owner = Keypair::new();
+    let admin = Keypair::new();
+    let member = Keypair::new();
+
+    let group_pda_address = create_group_with_members(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &owner,
+        &admin,
+        &member,
+    ).await;
+
+    process_signed_instruction(
+        &mut banks_client,
+        &recent_blockhash,
+        &payer,
+        &[&admin],
+        instruction::set_group_padding_policy(
+            &payer.pubkey(),
+            &admin.pubkey(),
+            &group_pda_address,
+            &group_pda_address,
+            PaddingScheme::BLOCK_256,
+        ),
+    ).await;
+
+    send_group_message(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &member,
+        &group_pda_address,
+        0,
+        MessageType::RSA_ENCRYPTED,
+        &[1_u8; 256],
+    ).await;
+
+    let message_pda_account = banks_client
+        .get_account(Message::find_pda_address_v2(&group_pda_address, 0, &id()))
+        .await
+        .expect("get_account")
+        .expect("message_pda_account not found");
+
+    let message = Message::try_from_slice(&message_pda_account.data)
+        .unwrap();
+
+    assert_eq!(message.content.len(), 256);
+}
+
+#[tokio::test]
+#[should_panic]
+async fn test_send_unpadded_community_group_message() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let owner = Keypair::new();
+
+    create_user_pda_account(&payer, &mut banks_client, &recent_blockhash, &owner.pubkey()).await;
+
+    let community_pda_address = create_community_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &owner,
+        0,
+        0,
+    ).await;
+
+    let group_pda_address = create_community_group_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &owner,
+        &community_pda_address,
+        0,
+        GroupKind::GROUP,
+    ).await;
+
+    // Editor permission is checked against the community membership
+    process_signed_instruction(
+        &mut banks_client,
+        &recent_blockhash,
+        &payer,
+        &[&owner],
+        instruction::set_group_padding_policy(
+            &payer.pubkey(),
+            &owner.pubkey(),
+            &group_pda_address,
+            &community_pda_address,
+            PaddingScheme::BLOCK_256,
+        ),
+    ).await;
+
+    send_community_group_message(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &owner,
+        &community_pda_address,
+        0,
+        0,
+        MessageType::RSA_ENCRYPTED,
+        &[1_u8; 32],
+    ).await;
+}
+
+#[tokio::test]
+async fn test_group_roles() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let owner = Keypair::new();
+    let admin = Keypair::new();
+    let member = Keypair::new();
+
+    let group_pda_address = create_group_with_members(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &owner,
+        &admin,
+        &member,
+    ).await;
+
+    send_group_message(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &member,
+        &group_pda_address,
+        0,
+        MessageType::PLAIN_TEXT,
+        &String::from("Hello group").into_bytes(),
+    ).await;
+
+    // Admin makes the member read-only
+    set_member_role(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &admin,
+        &group_pda_address,
+        &member.pubkey(),
+        GroupRole::READ_ONLY,
+        GroupPermission::NONE,
+    ).await;
+
+    let member_pda_account = banks_client
+        .get_account(GroupMember::find_pda_address(&group_pda_address, &member.pubkey(), &id()))
+        .await
+        .expect("get_account")
+        .expect("member_pda_account not found");
+
+    assert_eq!(
+        GroupMember::try_from_slice(&member_pda_account.data).unwrap(),
+        GroupMember {
+            role: GroupRole::READ_ONLY,
+            permissions: GroupPermission::NONE,
+        },
+    );
+
+    // Group is listed among the conversations of the member
+    let member_user_conversation_pda_account = banks_client
+        .get_account(UserConversation::find_pda_address_v2(
+            &User::find_pda_address(&member.pubkey(), &id()),
+            0,
+            &id(),
+        ))
+        .await
+        .expect("get_account")
+        .expect("member_user_conversation_pda_account not found");
+
+    assert_eq!(
+        UserConversation::try_from_slice(&member_user_conversation_pda_account.data).unwrap().conversation_address,
+        group_pda_address,
+    );
+
+    transfer_group_ownership(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &owner,
+        &group_pda_address,
+        &admin.pubkey(),
+    ).await;
+
+    let group_pda_account = banks_client
+        .get_account(group_pda_address)
+        .await
+        .expect("get_account")
+        .expect("group_pda_account not found");
+
+    let group = Group::try_from_slice(&group_pda_account.data)
+        .unwrap();
+
+    assert_eq!(group.owner, admin.pubkey());
+    assert_eq!(group.creator, owner.pubkey());
+    assert_eq!(group.message_counter, 1);
+
+    let owner_pda_account = banks_client
+        .get_account(GroupMember::find_pda_address(&group_pda_address, &owner.pubkey(), &id()))
+        .await
+        .expect("get_account")
+        .expect("owner_pda_account not found");
+
+    assert_eq!(GroupMember::try_from_slice(&owner_pda_account.data).unwrap().role, GroupRole::ADMIN);
+}
+
+#[tokio::test]
+#[should_panic]
+async fn test_member_cannot_promote_self() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let owner = Keypair::new();
+    let admin = Keypair::new();
+    let member = Keypair::new();
+
+    let group_pda_address = create_group_with_members(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &owner,
+        &admin,
+        &member,
+    ).await;
+
+    set_member_role(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &member,
+        &group_pda_address,
+        &member.pubkey(),
+        GroupRole::ADMIN,
+        GroupPermission::ALL,
+    ).await;
+}
+
+#[tokio::test]
+#[should_panic]
+async fn test_admin_cannot_promote_to_admin() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let owner = Keypair::new();
+    let admin = Keypair::new();
+    let member = Keypair::new();
+
+    let group_pda_address = create_group_with_members(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &owner,
+        &admin,
+        &member,
+    ).await;
+
+    set_member_role(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &admin,
+        &group_pda_address,
+        &member.pubkey(),
+        GroupRole::ADMIN,
+        GroupPermission::ALL,
+    ).await;
+}
+
+#[tokio::test]
+#[should_panic]
+async fn test_admin_cannot_demote_owner() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let owner = Keypair::new();
+    let admin = Keypair::new();
+    let member = Keypair::new();
+
+    let group_pda_address = create_group_with_members(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &owner,
+        &admin,
+        &member,
+    ).await;
+
+    set_member_role(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &admin,
+        &group_pda_address,
+        &owner.pubkey(),
+        GroupRole::READ_ONLY,
+        GroupPermission::NONE,
+    ).await;
+}
+
+#[tokio::test]
+#[should_panic]
+async fn test_member_cannot_add_admin() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let owner = Keypair::new();
+    let admin = Keypair::new();
+    let member = Keypair::new();
+    let newcomer = Keypair::new();
+
+    let group_pda_address = create_group_with_members(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &owner,
+        &admin,
+        &member,
+    ).await;
+
+    create_user_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &newcomer.pubkey(),
+    ).await;
+
+    add_group_member(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &member,
+        &group_pda_address,
+        &newcomer,
+        0,
+        GroupRole::ADMIN,
+        GroupPermission::ALL,
+    ).await;
+}
+
+#[tokio::test]
+#[should_panic]
+async fn test_add_group_member_without_member_signature() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let owner = Keypair::new();
+    let admin = Keypair::new();
+    let member = Keypair::new();
+    let newcomer = Keypair::new();
+
+    let group_pda_address = create_group_with_members(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &owner,
+        &admin,
+        &member,
+    ).await;
+
+    create_user_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &newcomer.pubkey(),
+    ).await;
+
+    // Owner cannot pull a wallet into the group without its consent
+    let mut add_instruction = instruction::add_group_member(
+        &payer.pubkey(),
+        &owner.pubkey(),
+        &group_pda_address,
+        &newcomer.pubkey(),
+        0,
+        GroupRole::MEMBER,
+        GroupRole::default_permissions(GroupRole::MEMBER),
+    );
+    add_instruction.accounts[4].is_signer = false;
+
+    process_signed_instruction(
+        &mut banks_client,
+        &recent_blockhash,
+        &payer,
+        &[&owner],
+        add_instruction,
+    ).await;
+}
+
+#[tokio::test]
+#[should_panic]
+async fn test_read_only_member_cannot_send() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let owner = Keypair::new();
+    let admin = Keypair::new();
+    let member = Keypair::new();
+
+    let group_pda_address = create_group_with_members(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &owner,
+        &admin,
+        &member,
+    ).await;
+
+    set_member_role(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &owner,
+        &group_pda_address,
+        &member.pubkey(),
+        GroupRole::READ_ONLY,
+        GroupPermission::NONE,
+    ).await;
+
+    send_group_message(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &member,
+        &group_pda_address,
+        0,
+        MessageType::PLAIN_TEXT,
+        &String::from("Hello group").into_bytes(),
+    ).await;
+}
+
+#[tokio::test]
+#[should_panic]
+async fn test_admin_cannot_transfer_ownership() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let owner = Keypair::new();
+    let admin = Keypair::new();
+    let member = Keypair::new();
+
+    let group_pda_address = create_group_with_members(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &owner,
+        &admin,
+        &member,
+    ).await;
+
+    transfer_group_ownership(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &admin,
+        &group_pda_address,
+        &admin.pubkey(),
+    ).await;
+}
+
+#[tokio::test]
+async fn test_join_with_invite() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let owner = Keypair::new();
+    let admin = Keypair::new();
+    let member = Keypair::new();
+    let newcomer = Keypair::new();
+    let code = [3_u8; 16];
+
+    let group_pda_address = create_group_with_members(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &owner,
+        &admin,
+        &member,
+    ).await;
+
+    create_invite_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &admin,
+        &group_pda_address,
+        code,
+        i64::MAX,
+        2,
+    ).await;
+
+    join_with_invite(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &newcomer,
+        &group_pda_address,
+        code,
+    ).await;
+
+    let invite_pda_account = banks_client
+        .get_account(Invite::find_pda_address(&group_pda_address, &Invite::hash_code(&code), &id()))
+        .await
+        .expect("get_account")
+        .expect("invite_pda_account not found");
+
+    let invite = Invite::try_from_slice(&invite_pda_account.data)
+        .unwrap();
+
+    assert_eq!(invite.creator, admin.pubkey());
+    assert_eq!(invite.uses, 1);
+
+    let newcomer_pda_account = banks_client
+        .get_account(GroupMember::find_pda_address(&group_pda_address, &newcomer.pubkey(), &id()))
+        .await
+        .expect("get_account")
+        .expect("newcomer_pda_account not found");
+
+    assert_eq!(
+        GroupMember::try_from_slice(&newcomer_pda_account.data).unwrap(),
+        GroupMember {
+            role: GroupRole::MEMBER,
+            permissions: GroupPermission::SEND,
+        },
+    );
+
+    send_group_message(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &newcomer,
+        &group_pda_address,
+        0,
+        MessageType::PLAIN_TEXT,
+        &String::from("Hi all").into_bytes(),
+    ).await;
+}
+
+#[tokio::test]
+#[should_panic]
+async fn test_join_with_used_up_invite() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let owner = Keypair::new();
+    let admin = Keypair::new();
+    let member = Keypair::new();
+    let code = [3_u8; 16];
+
+    let group_pda_address = create_group_with_members(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &owner,
+        &admin,
+        &member,
+    ).await;
+
+    create_invite_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &owner,
+        &group_pda_address,
+        code,
+        i64::MAX,
+        1,
+    ).await;
+
+    for newcomer in [Keypair::new(), Keypair::new()].iter() {
+        join_with_invite(
+            &payer,
+            &mut banks_client,
+            &recent_blockhash,
+            newcomer,
+            &group_pda_address,
+            code,
+        ).await;
+    }
+}
+
+#[tokio::test]
+async fn test_revoke_invite() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let owner = Keypair::new();
+    let admin = Keypair::new();
+    let member = Keypair::new();
+    let code = [3_u8; 16];
+
+    let group_pda_address = create_group_with_members(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &owner,
+        &admin,
+        &member,
+    ).await;
+
+    create_invite_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &admin,
+        &group_pda_address,
+        code,
+        i64::MAX,
+        2,
+    ).await;
+
+    let invite_pda_address = Invite::find_pda_address(&group_pda_address, &Invite::hash_code(&code), &id());
+
+    let invite_lamports = banks_client
+        .get_balance(invite_pda_address)
+        .await
+        .unwrap();
+
+    // Owner revokes the invite of the admin, the rent goes back to the admin
+    revoke_invite(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &owner,
+        &group_pda_address,
+        &admin.pubkey(),
+        code,
+    ).await;
+
+    assert!(banks_client
+        .get_account(invite_pda_address)
+        .await
+        .expect("get_account")
+        .is_none());
+
+    assert_eq!(
+        banks_client.get_balance(admin.pubkey()).await.unwrap(),
+        invite_lamports,
+    );
+}
+
+#[tokio::test]
+#[should_panic]
+async fn test_join_with_revoked_invite() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let owner = Keypair::new();
+    let admin = Keypair::new();
+    let member = Keypair::new();
+    let code = [3_u8; 16];
+
+    let group_pda_address = create_group_with_members(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &owner,
+        &admin,
+        &member,
+    ).await;
+
+    create_invite_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &admin,
+        &group_pda_address,
+        code,
+        i64::MAX,
+        2,
+    ).await;
+
+    revoke_invite(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &admin,
+        &group_pda_address,
+        &admin.pubkey(),
+        code,
+    ).await;
+
+    join_with_invite(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &Keypair::new(),
+        &group_pda_address,
+        code,
+    ).await;
+}
+
+#[tokio::test]
+#[should_panic]
+async fn test_member_cannot_create_invite() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let owner = Keypair::new();
+    let admin = Keypair::new();
+    let member = Keypair::new();
+
+    let group_pda_address = create_group_with_members(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &owner,
+        &admin,
+        &member,
+    ).await;
+
+    create_invite_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &member,
+        &group_pda_address,
+        [3_u8; 16],
+        i64::MAX,
+        1,
+    ).await;
+}
+
+#[tokio::test]
+async fn test_approve_join_request() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let owner = Keypair::new();
+    let admin = Keypair::new();
+    let member = Keypair::new();
+    let requester = Keypair::new();
+
+    let group_pda_address = create_group_with_members(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &owner,
+        &admin,
+        &member,
+    ).await;
+
+    create_user_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &requester.pubkey(),
+    ).await;
+
+    request_to_join(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &requester,
+        &group_pda_address,
+        10_000_000,
+    ).await;
+
+    let join_request_pda_address = JoinRequest::find_pda_address(&group_pda_address, &requester.pubkey(), &id());
+
+    assert!(banks_client
+        .get_account(join_request_pda_address)
+        .await
+        .expect("get_account")
+        .is_some());
+
+    approve_join(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &admin,
+        &group_pda_address,
+        &requester.pubkey(),
+        0,
+    ).await;
+
+    // Request is closed and the requester is a member
+    assert_eq!(
+        banks_client
+            .get_account(join_request_pda_address)
+            .await
+            .expect("get_account"),
+        None,
+    );
+
+    let requester_pda_account = banks_client
+        .get_account(GroupMember::find_pda_address(&group_pda_address, &requester.pubkey(), &id()))
+        .await
+        .expect("get_account")
+        .expect("requester_pda_account not found");
+
+    assert_eq!(GroupMember::try_from_slice(&requester_pda_account.data).unwrap().role, GroupRole::MEMBER);
+}
+
+#[tokio::test]
+async fn test_deny_join_request() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let owner = Keypair::new();
+    let admin = Keypair::new();
+    let member = Keypair::new();
+    let requester = Keypair::new();
+
+    let group_pda_address = create_group_with_members(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &owner,
+        &admin,
+        &member,
+    ).await;
+
+    request_to_join(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &requester,
+        &group_pda_address,
+        10_000_000,
+    ).await;
+
+    deny_join(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &owner,
+        &group_pda_address,
+        &requester.pubkey(),
+    ).await;
+
+    // Request is closed and refunded to the requester
+    assert_eq!(
+        banks_client
+            .get_account(JoinRequest::find_pda_address(&group_pda_address, &requester.pubkey(), &id()))
+            .await
+            .expect("get_account"),
+        None,
+    );
+
+    assert_eq!(banks_client.get_balance(requester.pubkey()).await.unwrap(), 10_000_000);
+}
+
+#[tokio::test]
+#[should_panic]
+async fn test_member_cannot_approve_join_request() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let owner = Keypair::new();
+    let admin = Keypair::new();
+    let member = Keypair::new();
+    let requester = Keypair::new();
+
+    let group_pda_address = create_group_with_members(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &owner,
+        &admin,
+        &member,
+    ).await;
+
+    create_user_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &requester.pubkey(),
+    ).await;
+
+    request_to_join(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &requester,
+        &group_pda_address,
+        10_000_000,
+    ).await;
+
+    approve_join(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &member,
+        &group_pda_address,
+        &requester.pubkey(),
+        0,
+    ).await;
+}
+
+#[tokio::test]
+async fn test_remove_member_and_leave_group() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let owner = Keypair::new();
+    let admin = Keypair::new();
+    let member = Keypair::new();
+
+    let group_pda_address = create_group_with_members(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &owner,
+        &admin,
+        &member,
+    ).await;
+
+    remove_member(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &admin,
+        &group_pda_address,
+        &member.pubkey(),
+        0,
+    ).await;
+
+    assert_eq!(
+        banks_client
+            .get_account(GroupMember::find_pda_address(&group_pda_address, &member.pubkey(), &id()))
+            .await
+            .expect("get_account"),
+        None,
+    );
+
+    // Group is dropped from the list of the removed member, who gets the rent of both accounts back
+    assert_eq!(
+        banks_client
+            .get_account(UserConversation::find_pda_address_v2(
+                &User::find_pda_address(&member.pubkey(), &id()),
+                0,
+                &id(),
+            ))
+            .await
+            .expect("get_account"),
+        None,
+    );
+
+    let rent = banks_client.get_rent().await.unwrap();
+
+    assert_eq!(
+        banks_client.get_balance(member.pubkey()).await.unwrap(),
+        rent.minimum_balance(GroupMember::retrieve_size()) + rent.minimum_balance(UserConversation::retrieve_size()),
+    );
+
+    leave_group(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &admin,
+        &group_pda_address,
+        0,
+    ).await;
+
+    assert_eq!(
+        banks_client
+            .get_account(GroupMember::find_pda_address(&group_pda_address, &admin.pubkey(), &id()))
+            .await
+            .expect("get_account"),
+        None,
+    );
+
+    assert_eq!(
+        banks_client
+            .get_account(UserConversation::find_pda_address_v2(
+                &User::find_pda_address(&admin.pubkey(), &id()),
+                0,
+                &id(),
+            ))
+            .await
+            .expect("get_account"),
+        None,
+    );
+}
+
+#[tokio::test]
+#[should_panic]
+async fn test_removed_member_cannot_send() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let owner = Keypair::new();
+    let admin = Keypair::new();
+    let member = Keypair::new();
+
+    let group_pda_address = create_group_with_members(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &owner,
+        &admin,
+        &member,
+    ).await;
+
+    remove_member(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &owner,
+        &group_pda_address,
+        &member.pubkey(),
+        0,
+    ).await;
+
+    send_group_message(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &member,
+        &group_pda_address,
+        0,
+        MessageType::PLAIN_TEXT,
+        &String::from("Still here?").into_bytes(),
+    ).await;
+}
+
+#[tokio::test]
+#[should_panic]
+async fn test_revived_member_account_grants_nothing() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let owner = Keypair::new();
+    let admin = Keypair::new();
+    let member = Keypair::new();
+
+    let group_pda_address = create_group_with_members(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &owner,
+        &admin,
+        &member,
+    ).await;
+
+    // Closed account is funded again in the same transaction - its zeroed data must not act as an owner
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            instruction::leave_group(&member.pubkey(), &group_pda_address, 0),
+            system_instruction::transfer(
+                &payer.pubkey(),
+                &GroupMember::find_pda_address(&group_pda_address, &member.pubkey(), &id()),
+                10_000_000,
+            ),
+            instruction::set_member_role(
+                &member.pubkey(),
+                &group_pda_address,
+                &admin.pubkey(),
+                GroupRole::READ_ONLY,
+                GroupPermission::NONE,
+            ),
+        ],
+        Some(&payer.pubkey()),
+        &[&payer, &member],
+        recent_blockhash,
+    );
+
+    banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+#[should_panic]
+async fn test_banned_wallet_cannot_join_with_invite() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let owner = Keypair::new();
+    let admin = Keypair::new();
+    let member = Keypair::new();
+    let outsider = Keypair::new();
+    let code = [3_u8; 16];
+
+    let group_pda_address = create_group_with_members(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &owner,
+        &admin,
+        &member,
+    ).await;
+
+    // Wallets can be banned before they ever join
+    ban_member(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &admin,
+        &group_pda_address,
+        &outsider.pubkey(),
+        0,
+    ).await;
+
+    create_invite_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &admin,
+        &group_pda_address,
+        code,
+        i64::MAX,
+        10,
+    ).await;
+
+    join_with_invite(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &outsider,
+        &group_pda_address,
+        code,
+    ).await;
+}
+
+#[tokio::test]
+#[should_panic]
+async fn test_admin_cannot_remove_owner() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let owner = Keypair::new();
+    let admin = Keypair::new();
+    let member = Keypair::new();
+
+    let group_pda_address = create_group_with_members(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &owner,
+        &admin,
+        &member,
+    ).await;
+
+    remove_member(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &admin,
+        &group_pda_address,
+        &owner.pubkey(),
+        0,
+    ).await;
+}
+
+#[tokio::test]
+async fn test_channel_subscription() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let publisher = Keypair::new();
+    let subscriber = Keypair::new();
+
+    create_user_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &publisher.pubkey(),
+    ).await;
+
+    let channel_pda_address = create_group_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &publisher,
+        0,
+        GroupKind::CHANNEL,
+        0,
+    ).await;
+
+    subscribe(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &subscriber,
+        &channel_pda_address,
+    ).await;
+
+    send_group_message(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &publisher,
+        &channel_pda_address,
+        0,
+        MessageType::PLAIN_TEXT,
+        &String::from("Release notes").into_bytes(),
+    ).await;
+
+    let subscriber_user_conversation_pda_address = UserConversation::find_pda_address_v2(
+        &User::find_pda_address(&subscriber.pubkey(), &id()),
+        0,
+        &id(),
+    );
+
+    let subscriber_user_conversation_pda_account = banks_client
+        .get_account(subscriber_user_conversation_pda_address)
+        .await
+        .expect("get_account")
+        .expect("subscriber_user_conversation_pda_account not found");
+
+    assert_eq!(
+        UserConversation::try_from_slice(&subscriber_user_conversation_pda_account.data).unwrap().conversation_address,
+        channel_pda_address,
+    );
+
+    let subscriber_pda_account = banks_client
+        .get_account(GroupMember::find_pda_address(&channel_pda_address, &subscriber.pubkey(), &id()))
+        .await
+        .expect("get_account")
+        .expect("subscriber_pda_account not found");
+
+    assert_eq!(GroupMember::try_from_slice(&subscriber_pda_account.data).unwrap().role, GroupRole::READ_ONLY);
+
+    unsubscribe(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &subscriber,
+        &channel_pda_address,
+        0,
+    ).await;
+
+    assert_eq!(
+        banks_client
+            .get_account(subscriber_user_conversation_pda_address)
+            .await
+            .expect("get_account"),
+        None,
+    );
+}
+
+#[tokio::test]
+#[should_panic]
+async fn test_subscriber_cannot_post_to_channel() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let publisher = Keypair::new();
+    let subscriber = Keypair::new();
+
+    create_user_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &publisher.pubkey(),
+    ).await;
+
+    let channel_pda_address = create_group_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &publisher,
+        0,
+        GroupKind::CHANNEL,
+        0,
+    ).await;
+
+    subscribe(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &subscriber,
+        &channel_pda_address,
+    ).await;
+
+    send_group_message(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &subscriber,
+        &channel_pda_address,
+        0,
+        MessageType::PLAIN_TEXT,
+        &String::from("Hello?").into_bytes(),
+    ).await;
+}
+
+#[tokio::test]
+#[should_panic]
+async fn test_cannot_subscribe_to_group() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let owner = Keypair::new();
+    let admin = Keypair::new();
+    let member = Keypair::new();
+
+    let group_pda_address = create_group_with_members(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &owner,
+        &admin,
+        &member,
+    ).await;
+
+    subscribe(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &Keypair::new(),
+        &group_pda_address,
+    ).await;
+}
+
+#[tokio::test]
+async fn test_community_groups_share_membership() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let owner = Keypair::new();
+    let admin = Keypair::new();
+    let member = Keypair::new();
+
+    for wallet in [&owner, &admin, &member].iter() {
+        create_user_pda_account(&payer, &mut banks_client, &recent_blockhash, &wallet.pubkey()).await;
+    }
+
+    let community_pda_address = create_community_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &owner,
+        0,
+        0,
+    ).await;
+
+    add_group_member(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &owner,
+        &community_pda_address,
+        &admin,
+        0,
+        GroupRole::ADMIN,
+        GroupRole::default_permissions(GroupRole::ADMIN),
+    ).await;
+
+    add_group_member(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &admin,
+        &community_pda_address,
+        &member,
+        0,
+        GroupRole::MEMBER,
+        GroupRole::default_permissions(GroupRole::MEMBER),
+    ).await;
+
+    create_community_group_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &owner,
+        &community_pda_address,
+        0,
+        GroupKind::GROUP,
+    ).await;
+
+    let channel_pda_address = create_community_group_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &admin,
+        &community_pda_address,
+        1,
+        GroupKind::CHANNEL,
+    ).await;
+
+    // Member can send to every group of the community without joining it separately
+    send_community_group_message(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &member,
+        &community_pda_address,
+        0,
+        0,
+        MessageType::PLAIN_TEXT,
+        &String::from("Hello, community!").into_bytes(),
+    ).await;
+
+    send_community_group_message(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &admin,
+        &community_pda_address,
+        1,
+        0,
+        MessageType::PLAIN_TEXT,
+        &String::from("Announcement").into_bytes(),
+    ).await;
+
+    let community_pda_account = banks_client
+        .get_account(community_pda_address)
+        .await
+        .expect("get_account")
+        .expect("community_pda_account not found");
+
+    let community = Community::try_from_slice(&community_pda_account.data).unwrap();
+
+    assert_eq!(community.owner, owner.pubkey());
+    assert_eq!(community.group_counter, 2);
+
+    let channel_pda_account = banks_client
+        .get_account(channel_pda_address)
+        .await
+        .expect("get_account")
+        .expect("channel_pda_account not found");
+
+    let channel = Group::try_from_slice(&channel_pda_account.data).unwrap();
+
+    assert_eq!(channel.account_kind, AccountKind::GROUP);
+    assert_eq!(channel.community, community_pda_address);
+    // Community groups are owned by the community owner - the group does not keep a copy
+    assert_eq!(channel.owner, Pubkey::default());
+    assert_eq!(channel.kind, GroupKind::CHANNEL);
+    assert_eq!(channel.message_counter, 1);
+
+    // Member lists the channel next to the community entry created on joining
+    create_community_group_user_conversation(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &member,
+        &community_pda_address,
+        1,
+        1,
+    ).await;
+
+    let member_user_pda_address = User::find_pda_address(&member.pubkey(), &id());
+
+    let member_user_conversation_pda_account = banks_client
+        .get_account(UserConversation::find_pda_address_v2(&member_user_pda_address, 1, &id()))
+        .await
+        .expect("get_account")
+        .expect("member_user_conversation_pda_account not found");
+
+    let member_user_conversation = UserConversation::unpack(&member_user_conversation_pda_account.data).unwrap();
+
+    assert_eq!(member_user_conversation.conversation_address, channel_pda_address);
+}
+
+#[tokio::test]
+#[should_panic]
+async fn test_non_member_cannot_create_community_group_user_conversation() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let owner = Keypair::new();
+    let outsider = Keypair::new();
+
+    for wallet in [&owner, &outsider].iter() {
+        create_user_pda_account(&payer, &mut banks_client, &recent_blockhash, &wallet.pubkey()).await;
+    }
+
+    let community_pda_address = create_community_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &owner,
+        0,
+        0,
+    ).await;
+
+    create_community_group_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &owner,
+        &community_pda_address,
+        0,
+        GroupKind::GROUP,
+    ).await;
+
+    create_community_group_user_conversation(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &outsider,
+        &community_pda_address,
+        0,
+        0,
+    ).await;
+}
+
+#[tokio::test]
+#[should_panic]
+async fn test_member_cannot_create_community_group() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let owner = Keypair::new();
+    let member = Keypair::new();
+
+    for wallet in [&owner, &member].iter() {
+        create_user_pda_account(&payer, &mut banks_client, &recent_blockhash, &wallet.pubkey()).await;
+    }
+
+    let community_pda_address = create_community_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &owner,
+        0,
+        0,
+    ).await;
+
+    add_group_member(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &owner,
+        &community_pda_address,
+        &member,
+        0,
+        GroupRole::MEMBER,
+        GroupRole::default_permissions(GroupRole::MEMBER),
+    ).await;
+
+    create_community_group_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &member,
+        &community_pda_address,
+        0,
+        GroupKind::GROUP,
+    ).await;
+}
+
+#[tokio::test]
+#[should_panic]
+async fn test_outsider_cannot_send_to_community_group() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let owner = Keypair::new();
+
+    create_user_pda_account(&payer, &mut banks_client, &recent_blockhash, &owner.pubkey()).await;
+
+    let community_pda_address = create_community_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &owner,
+        0,
+        0,
+    ).await;
+
+    create_community_group_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &owner,
+        &community_pda_address,
+        0,
+        GroupKind::GROUP,
+    ).await;
+
+    send_community_group_message(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &Keypair::new(),
+        &community_pda_address,
+        0,
+        0,
+        MessageType::PLAIN_TEXT,
+        &String::from("Hello?").into_bytes(),
+    ).await;
+}
+
+#[tokio::test]
+async fn test_set_conversation_metadata() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let sender = Keypair::new();
+    let receiver = Keypair::new();
+
+    let conversation_pda_address = create_conversation_between_wallets(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender.pubkey(),
+        &receiver.pubkey(),
+    ).await;
+
+    set_conversation_metadata_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender,
+        &receiver.pubkey(),
+        "Customer support",
+        "Questions about the order #1024 and its delivery",
+        "https://example.com/avatar.png",
+    ).await;
+
+    // The other participant can rename the conversation - creator is kept
+    set_conversation_metadata_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &receiver,
+        &sender.pubkey(),
+        "Support",
+        "",
+        "",
+    ).await;
+
+    let metadata_pda_account = banks_client
+        .get_account(ConversationMetadata::find_pda_address(&conversation_pda_address, &id()))
+        .await
+        .expect("get_account")
+        .expect("metadata_pda_account not found");
+
+    assert_eq!(metadata_pda_account.data.len(), ConversationMetadata::retrieve_size());
+
+    let metadata = ConversationMetadata::unpack(&metadata_pda_account.data).unwrap();
+
+    assert_eq!(metadata.creator, sender.pubkey());
+    assert_eq!(metadata.title, "Support");
+    assert_eq!(metadata.description, "");
+    assert_eq!(metadata.avatar_uri, "");
+}
+
+#[tokio::test]
+#[should_panic]
+async fn test_outsider_cannot_set_conversation_metadata() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let sender = Keypair::new();
+    let receiver = Keypair::new();
+    let outsider = Keypair::new();
+
+    create_conversation_between_wallets(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender.pubkey(),
+        &receiver.pubkey(),
+    ).await;
+
+    create_user_pda_account(&payer, &mut banks_client, &recent_blockhash, &outsider.pubkey()).await;
+
+    set_conversation_metadata_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &outsider,
+        &receiver.pubkey(),
+        "Hijacked",
+        "",
+        "",
+    ).await;
+}
+
+#[tokio::test]
+async fn test_set_group_metadata() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let owner = Keypair::new();
+    let admin = Keypair::new();
+    let member = Keypair::new();
+
+    let group_pda_address = create_group_with_members(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &owner,
+        &admin,
+        &member,
+    ).await;
+
+    set_group_metadata_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &admin,
+        &group_pda_address,
+        "Release team",
+    ).await;
+
+    let metadata_pda_account = banks_client
+        .get_account(ConversationMetadata::find_pda_address(&group_pda_address, &id()))
+        .await
+        .expect("get_account")
+        .expect("metadata_pda_account not found");
+
+    let metadata = ConversationMetadata::unpack(&metadata_pda_account.data).unwrap();
+
+    assert_eq!(metadata.creator, admin.pubkey());
+    assert_eq!(metadata.title, "Release team");
+}
+
+#[tokio::test]
+#[should_panic]
+async fn test_member_cannot_set_group_metadata() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let owner = Keypair::new();
+    let admin = Keypair::new();
+    let member = Keypair::new();
+
+    let group_pda_address = create_group_with_members(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &owner,
+        &admin,
+        &member,
+    ).await;
+
+    set_group_metadata_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &member,
+        &group_pda_address,
+        "Renamed",
+    ).await;
+}
+
+#[tokio::test]
+async fn test_pin_and_unpin_messages() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let agent = Keypair::new();
+    let customer = Keypair::new();
+
+    let conversation_pda_address = create_conversation_between_wallets(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &agent.pubkey(),
+        &customer.pubkey(),
+    ).await;
+
+    for (message_index, content) in ["Read the FAQ first", "Attach your order number"].iter().enumerate() {
+        send_message(
+            &payer,
+            &mut banks_client,
+            &recent_blockhash,
+            &agent,
+            &customer.pubkey(),
+            message_index as u64,
+            MessageType::PLAIN_TEXT,
+            content.as_bytes(),
+        ).await;
+    }
+
+    pin_message(&payer, &mut banks_client, &recent_blockhash, &agent, &customer.pubkey(), 1).await;
+    pin_message(&payer, &mut banks_client, &recent_blockhash, &customer, &agent.pubkey(), 0).await;
+
+    let pinned_messages_pda_address = PinnedMessages::find_pda_address(&conversation_pda_address, &id());
+
+    let pinned_messages_pda_account = banks_client
+        .get_account(pinned_messages_pda_address)
+        .await
+        .expect("get_account")
+        .expect("pinned_messages_pda_account not found");
+
+    assert_eq!(
+        PinnedMessages::unpack(&pinned_messages_pda_account.data).unwrap().message_indexes,
+        vec![1, 0],
+    );
+
+    unpin_message(&payer, &mut banks_client, &recent_blockhash, &agent, &customer.pubkey(), 1).await;
+
+    let pinned_messages_pda_account = banks_client
+        .get_account(pinned_messages_pda_address)
+        .await
+        .expect("get_account")
+        .expect("pinned_messages_pda_account not found");
+
+    assert_eq!(
+        PinnedMessages::unpack(&pinned_messages_pda_account.data).unwrap().message_indexes,
+        vec![0],
+    );
+}
+
+#[tokio::test]
+#[should_panic]
+async fn test_cannot_pin_missing_message() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let sender = Keypair::new();
+    let receiver = Keypair::new();
+
+    create_conversation_between_wallets(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender.pubkey(),
+        &receiver.pubkey(),
+    ).await;
+
+    pin_message(&payer, &mut banks_client, &recent_blockhash, &sender, &receiver.pubkey(), 0).await;
+}
+
+#[tokio::test]
+#[should_panic]
+async fn test_read_only_member_cannot_pin_group_message() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let publisher = Keypair::new();
+    let subscriber = Keypair::new();
+
+    create_user_pda_account(&payer, &mut banks_client, &recent_blockhash, &publisher.pubkey()).await;
+
+    let channel_pda_address = create_group_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &publisher,
+        0,
+        GroupKind::CHANNEL,
+        0,
+    ).await;
+
+    subscribe(&payer, &mut banks_client, &recent_blockhash, &subscriber, &channel_pda_address).await;
+
+    send_group_message(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &publisher,
+        &channel_pda_address,
+        0,
+        MessageType::PLAIN_TEXT,
+        &String::from("Welcome").into_bytes(),
+    ).await;
+
+    // Publisher can pin, subscriber cannot
+    pin_group_message(&payer, &mut banks_client, &recent_blockhash, &publisher, &channel_pda_address, 0).await;
+    pin_group_message(&payer, &mut banks_client, &recent_blockhash, &subscriber, &channel_pda_address, 0).await;
+}
+
+#[tokio::test]
+async fn test_update_user_conversation() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let sender = Keypair::new();
+    let receiver = Keypair::new();
+
+    let conversation_pda_address = create_conversation_between_wallets(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender.pubkey(),
+        &receiver.pubkey(),
+    ).await;
+
+    update_user_conversation(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender,
+        0,
+        true,
+        1_893_456_000,
+        3,
+    ).await;
+
+    let sender_user_conversation_pda_account = banks_client
+        .get_account(UserConversation::find_pda_address_v2(&User::find_pda_address(&sender.pubkey(), &id()), 0, &id()))
+        .await
+        .expect("get_account")
+        .expect("sender_user_conversation_pda_account not found");
+
+    let sender_user_conversation = UserConversation::unpack(&sender_user_conversation_pda_account.data).unwrap();
+
+    assert_eq!(sender_user_conversation.conversation_address, conversation_pda_address);
+    assert!(sender_user_conversation.archived);
+    assert_eq!(sender_user_conversation.muted_until, 1_893_456_000);
+    assert_eq!(sender_user_conversation.folder_id, 3);
+
+    // Flags are per user - the receiver's entry is untouched
+    let receiver_user_conversation_pda_account = banks_client
+        .get_account(UserConversation::find_pda_address_v2(&User::find_pda_address(&receiver.pubkey(), &id()), 0, &id()))
+        .await
+        .expect("get_account")
+        .expect("receiver_user_conversation_pda_account not found");
+
+    let receiver_user_conversation = UserConversation::unpack(&receiver_user_conversation_pda_account.data).unwrap();
+
+    assert!(!receiver_user_conversation.archived);
+    assert_eq!(receiver_user_conversation.folder_id, 0);
+}
+
+#[tokio::test]
+#[should_panic]
+async fn test_cannot_update_missing_user_conversation() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let user = Keypair::new();
+
+    create_user_pda_account(&payer, &mut banks_client, &recent_blockhash, &user.pubkey()).await;
+
+    update_user_conversation(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &user,
+        0,
+        true,
+        0,
+        0,
+    ).await;
+}
+
+#[tokio::test]
+async fn test_hide_conversation() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let sender = Keypair::new();
+    let receiver = Keypair::new();
+
+    let conversation_pda_address = create_conversation_between_wallets(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender.pubkey(),
+        &receiver.pubkey(),
+    ).await;
+
+    let sender_user_conversation_pda_address = UserConversation::find_pda_address_v2(
+        &User::find_pda_address(&sender.pubkey(), &id()),
+        0,
+        &id(),
+    );
+
+    // Lamports above the rent-exempt minimum go back to the user wallet
+    process_signed_instruction(
+        &mut banks_client,
+        &recent_blockhash,
+        &payer,
+        &[],
+        system_instruction::transfer(&payer.pubkey(), &sender_user_conversation_pda_address, 10_000_000),
+    ).await;
+
+    hide_conversation(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender,
+        &receiver.pubkey(),
+        0,
+        true,
+    ).await;
+
+    assert_eq!(banks_client.get_balance(sender.pubkey()).await.unwrap(), 10_000_000);
+
+    let sender_user_conversation_pda_account = banks_client
+        .get_account(sender_user_conversation_pda_address)
+        .await
+        .expect("get_account")
+        .expect("sender_user_conversation_pda_account not found");
+
+    let sender_user_conversation = UserConversation::unpack(&sender_user_conversation_pda_account.data).unwrap();
+
+    assert!(sender_user_conversation.hidden);
+    assert_eq!(sender_user_conversation.conversation_address, conversation_pda_address);
+
+    // The other participant keeps the conversation
+    let receiver_user_conversation_pda_account = banks_client
+        .get_account(UserConversation::find_pda_address_v2(&User::find_pda_address(&receiver.pubkey(), &id()), 0, &id()))
+        .await
+        .expect("get_account")
+        .expect("receiver_user_conversation_pda_account not found");
+
+    assert_eq!(
+        UserConversation::unpack(&receiver_user_conversation_pda_account.data).unwrap().conversation_address,
+        conversation_pda_address,
+    );
+
+    assert!(
+        banks_client
+            .get_account(conversation_pda_address)
+            .await
+            .expect("get_account")
+            .is_some()
+    );
+
+    // Hiding is reversible
+    hide_conversation(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender,
+        &receiver.pubkey(),
+        0,
+        false,
+    ).await;
+
+    let sender_user_conversation_pda_account = banks_client
+        .get_account(sender_user_conversation_pda_address)
+        .await
+        .expect("get_account")
+        .expect("sender_user_conversation_pda_account not found");
+
+    assert!(!UserConversation::unpack(&sender_user_conversation_pda_account.data).unwrap().hidden);
+}
+
+#[tokio::test]
+#[should_panic]
+async fn test_cannot_hide_other_conversation_entry() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let sender = Keypair::new();
+    let receiver = Keypair::new();
+    let other_receiver = Keypair::new();
+
+    create_conversation_between_wallets(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender.pubkey(),
+        &receiver.pubkey(),
+    ).await;
+
+    create_user_pda_account(&payer, &mut banks_client, &recent_blockhash, &other_receiver.pubkey()).await;
+
+    create_conversation_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &User::find_pda_address(&sender.pubkey(), &id()),
+        &User::find_pda_address(&other_receiver.pubkey(), &id()),
+        1,
+        0,
+    ).await;
+
+    // Entry 1 points at the conversation with the other receiver
+    hide_conversation(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender,
+        &receiver.pubkey(),
+        1,
+        true,
+    ).await;
+}
+
+#[tokio::test]
+async fn test_hide_legacy_conversation_entry() {
+    let sender = Keypair::new();
+    let receiver = Keypair::new();
+
+    let mut program_test = program_test();
+    add_conversation_accounts(&mut program_test, &sender.pubkey(), &receiver.pubkey(), true);
+
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    let sender_user_conversation_pda_address = UserConversation::find_pda_address(
+        &User::find_pda_address(&sender.pubkey(), &id()),
+        0,
+        &id(),
+    );
+
+    // Legacy entries keep the v1 address and have no room for the flag
+    let mut hide_instruction = instruction::hide_conversation(&sender.pubkey(), &receiver.pubkey(), 0, true);
+    hide_instruction.accounts[4].pubkey = sender_user_conversation_pda_address;
+
+    process_signed_instruction(
+        &mut banks_client,
+        &recent_blockhash,
+        &payer,
+        &[&sender],
+        hide_instruction,
+    ).await;
+
+    // Entry is closed and its rent goes back to the user wallet
+    assert_eq!(
+        banks_client
+            .get_account(sender_user_conversation_pda_address)
+            .await
+            .expect("get_account"),
+        None,
+    );
+
+    let rent = banks_client.get_rent().await.unwrap();
+
+    assert_eq!(
+        banks_client.get_balance(sender.pubkey()).await.unwrap(),
+        rent.minimum_balance(UserConversation::LEGACY_SIZE),
+    );
+}
+
+#[tokio::test]
+async fn test_start_conversation() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let sender = Keypair::new();
+    let receiver = Keypair::new();
+
+    // Receiver already has an account with another conversation, sender has none
+    create_conversation_between_wallets(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &receiver.pubkey(),
+        &Pubkey::new_unique(),
+    ).await;
+
+    start_conversation(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender,
+        &receiver.pubkey(),
+        0,
+        1,
+        0,
+        &String::from("Hi!").into_bytes(),
+    ).await;
+
+    // Accounts exist now - the instruction only sends the message
+    start_conversation(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender,
+        &receiver.pubkey(),
+        0,
+        1,
+        1,
+        &String::from("Are you there?").into_bytes(),
+    ).await;
+
+    let sender_user_pda_address = User::find_pda_address(&sender.pubkey(), &id());
+    let receiver_user_pda_address = User::find_pda_address(&receiver.pubkey(), &id());
+    let conversation_pda_address = Conversation::find_pda_address(&sender_user_pda_address, &receiver_user_pda_address, &id());
+
+    for (user_pda_address, conversation_counter) in [(sender_user_pda_address, 1), (receiver_user_pda_address, 2)].iter() {
+        let user_pda_account = banks_client
+            .get_account(*user_pda_address)
+            .await
+            .expect("get_account")
+            .expect("user_pda_account not found");
+
+        assert_eq!(User::try_from_slice(&user_pda_account.data).unwrap().conversation_counter, *conversation_counter);
+
+        let user_conversation_pda_account = banks_client
+            .get_account(UserConversation::find_pda_address_v2(user_pda_address, conversation_counter - 1, &id()))
+            .await
+            .expect("get_account")
+            .expect("user_conversation_pda_account not found");
+
+        assert_eq!(
+            UserConversation::unpack(&user_conversation_pda_account.data).unwrap().conversation_address,
+            conversation_pda_address,
+        );
+    }
+
+    let conversation_pda_account = banks_client
+        .get_account(conversation_pda_address)
+        .await
+        .expect("get_account")
+        .expect("conversation_pda_account not found");
+
+    assert_eq!(Conversation::try_from_slice(&conversation_pda_account.data).unwrap().message_counter, 2);
+}
+
+#[tokio::test]
+#[should_panic]
+async fn test_start_conversation_with_wrong_conversation_index() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let sender = Keypair::new();
+    let receiver = Keypair::new();
+
+    // Users without an account start counting conversations from zero
+    start_conversation(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender,
+        &receiver.pubkey(),
+        1,
+        0,
+        0,
+        &String::from("Hi!").into_bytes(),
+    ).await;
+}
+
+#[tokio::test]
+async fn test_sequenced_sends_do_not_share_writable_accounts() {
+    const ROUNDS: u64 = 10;
+
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let first = Keypair::new();
+    let second = Keypair::new();
+
+    // Each sender pays with an own funder - a shared fee payer would serialize the batch by itself
+    let first_funder = Keypair::new();
+    let second_funder = Keypair::new();
+
+    banks_client
+        .process_transaction(Transaction::new_signed_with_payer(
+            &[
+                system_instruction::transfer(&payer.pubkey(), &first_funder.pubkey(), 1_000_000_000),
+                system_instruction::transfer(&payer.pubkey(), &second_funder.pubkey(), 1_000_000_000),
+            ],
+            Some(&payer.pubkey()),
+            &[&payer],
+            recent_blockhash,
+        ))
+        .await
+        .unwrap();
+
+    let conversation_pda_address = create_conversation_between_wallets(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &first.pubkey(),
+        &second.pubkey(),
+    ).await;
+
+    let writable_accounts = |transaction: &Transaction| -> Vec<Pubkey> {
+        transaction.message.get_account_keys_by_lock_type(true).0.into_iter().copied().collect()
+    };
+
+    // SendMessage of both participants writes the conversation counter - the sends cannot run in parallel
+    let first_message = message_transaction(&first_funder, &recent_blockhash, &first, &second.pubkey(), 0, b"First");
+    let second_message = message_transaction(&second_funder, &recent_blockhash, &second, &first.pubkey(), 0, b"Second");
+
+    assert!(writable_accounts(&first_message).contains(&conversation_pda_address));
+    assert!(writable_accounts(&second_message).contains(&conversation_pda_address));
+
+    for sequence in 0..ROUNDS {
+        let first_sequenced = sequenced_message_transaction(
+            &first_funder,
+            &recent_blockhash,
+            &first,
+            &second.pubkey(),
+            sequence,
+            format!("First #{}", sequence).as_bytes(),
+        );
+        let second_sequenced = sequenced_message_transaction(
+            &second_funder,
+            &recent_blockhash,
+            &second,
+            &first.pubkey(),
+            sequence,
+            format!("Second #{}", sequence).as_bytes(),
+        );
+
+        // Sequenced sends of both participants do not share a writable account
+        let first_writable = writable_accounts(&first_sequenced);
+        assert!(writable_accounts(&second_sequenced).iter().all(|address| !first_writable.contains(address)));
+
+        banks_client
+            .process_transactions(vec![first_sequenced, second_sequenced])
+            .await
+            .unwrap();
+    }
+
+    for sender in [&first, &second].iter() {
+        let sender_sequence_pda_account = banks_client
+            .get_account(SenderSequence::find_pda_address(&conversation_pda_address, &sender.pubkey(), &id()))
+            .await
+            .expect("get_account")
+            .expect("sender_sequence_pda_account not found");
+
+        assert_eq!(SenderSequence::try_from_slice(&sender_sequence_pda_account.data).unwrap().message_counter, ROUNDS);
+
+        let message_pda_account = banks_client
+            .get_account(Message::find_sequenced_pda_address(&conversation_pda_address, &sender.pubkey(), ROUNDS - 1, &id()))
+            .await
+            .expect("get_account")
+            .expect("message_pda_account not found");
+
+        assert_eq!(Message::try_from_slice(&message_pda_account.data).unwrap().sender, sender.pubkey());
+    }
+
+    // Shared counter is untouched by sequenced sends
+    let conversation_pda_account = banks_client
+        .get_account(conversation_pda_address)
+        .await
+        .expect("get_account")
+        .expect("conversation_pda_account not found");
+
+    assert_eq!(Conversation::try_from_slice(&conversation_pda_account.data).unwrap().message_counter, 0);
+}
+
+#[tokio::test]
+#[should_panic]
+async fn test_send_message_with_stale_index() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let sender = Keypair::new();
+    let receiver = Keypair::new();
+
+    create_conversation_between_wallets(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender.pubkey(),
+        &receiver.pubkey(),
+    ).await;
+
+    send_message(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &receiver,
+        &sender.pubkey(),
+        0,
+        MessageType::PLAIN_TEXT,
+        &String::from("First").into_bytes(),
+    ).await;
+
+    // Message 0 landed first - the send fails with a message index conflict
+    send_message(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender,
+        &receiver.pubkey(),
+        0,
+        MessageType::PLAIN_TEXT,
+        &String::from("Second").into_bytes(),
+    ).await;
+}
+
+#[tokio::test]
+async fn test_send_message_with_duplicate_id() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let sender = Keypair::new();
+    let receiver = Keypair::new();
+    let message_id = [7_u8; 16];
+
+    let conversation_pda_address = create_conversation_between_wallets(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender.pubkey(),
+        &receiver.pubkey(),
+    ).await;
+
+    send_message_with_id(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender,
+        &receiver.pubkey(),
+        0,
+        MessageType::PLAIN_TEXT,
+        &String::from("Hello").into_bytes(),
+        message_id,
+    ).await;
+
+    // Retry after a lost response - the client refreshed the counter, the message is not stored twice
+    send_message_with_id(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender,
+        &receiver.pubkey(),
+        1,
+        MessageType::PLAIN_TEXT,
+        &String::from("Hello").into_bytes(),
+        message_id,
+    ).await;
+
+    let conversation_pda_account = banks_client
+        .get_account(conversation_pda_address)
+        .await
+        .expect("get_account")
+        .expect("conversation_pda_account not found");
+
+    assert_eq!(Conversation::try_from_slice(&conversation_pda_account.data).unwrap().message_counter, 1);
+
+    let message_pda_account = banks_client
+        .get_account(Message::find_pda_address_v2(&conversation_pda_address, 0, &id()))
+        .await
+        .expect("get_account")
+        .expect("message_pda_account not found");
+
+    assert_eq!(Message::try_from_slice(&message_pda_account.data).unwrap().message_id, message_id);
+
+    let message_id_pda_account = banks_client
+        .get_account(MessageIdRecord::find_pda_address(&conversation_pda_address, &sender.pubkey(), &message_id, &id()))
+        .await
+        .expect("get_account")
+        .expect("message_id_pda_account not found");
+
+    assert_eq!(MessageIdRecord::try_from_slice(&message_id_pda_account.data).unwrap().message_index, 0);
+
+    let duplicate_pda_account = banks_client
+        .get_account(Message::find_pda_address_v2(&conversation_pda_address, 1, &id()))
+        .await
+        .expect("get_account");
+
+    assert!(duplicate_pda_account.is_none());
+
+    // Message IDs are chosen per sender - the same ID of the other participant is a different message
+    send_message_with_id(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &receiver,
+        &sender.pubkey(),
+        1,
+        MessageType::PLAIN_TEXT,
+        &String::from("Hi").into_bytes(),
+        message_id,
+    ).await;
+
+    let receiver_message_pda_account = banks_client
+        .get_account(Message::find_pda_address_v2(&conversation_pda_address, 1, &id()))
+        .await
+        .expect("get_account")
+        .expect("receiver_message_pda_account not found");
+
+    assert_eq!(Message::try_from_slice(&receiver_message_pda_account.data).unwrap().sender, receiver.pubkey());
+}
+
+#[tokio::test]
+async fn test_send_messages() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let sender = Keypair::new();
+    let receiver = Keypair::new();
+
+    let conversation_pda_address = create_conversation_between_wallets(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender.pubkey(),
+        &receiver.pubkey(),
+    ).await;
+
+    send_message(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender,
+        &receiver.pubkey(),
+        0,
+        MessageType::PLAIN_TEXT,
+        &String::from("First").into_bytes(),
+    ).await;
+
+    let contents = vec!["Second", "Third", "Fourth"];
+
+    send_messages(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender,
+        &receiver.pubkey(),
+        1,
+        contents.iter().map(|content| (MessageType::PLAIN_TEXT, content.as_bytes().to_vec(), Message::NO_MESSAGE_ID)).collect(),
+    ).await;
+
+    let conversation_pda_account = banks_client
+        .get_account(conversation_pda_address)
+        .await
+        .expect("get_account")
+        .expect("conversation_pda_account not found");
+
+    assert_eq!(Conversation::try_from_slice(&conversation_pda_account.data).unwrap().message_counter, 4);
+
+    for (message_index, content) in (1..).zip(contents.iter()) {
+        let message_pda_account = banks_client
+            .get_account(Message::find_pda_address_v2(&conversation_pda_address, message_index, &id()))
+            .await
+            .expect("get_account")
+            .expect("message_pda_account not found");
+
+        let message = Message::try_from_slice(&message_pda_account.data).unwrap();
+
+        assert_eq!(message.sender, sender.pubkey());
+        assert_eq!(message.content, content.as_bytes());
+    }
+}
+
+#[tokio::test]
+async fn test_send_messages_with_duplicate_ids() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let sender = Keypair::new();
+    let receiver = Keypair::new();
+
+    let conversation_pda_address = create_conversation_between_wallets(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender.pubkey(),
+        &receiver.pubkey(),
+    ).await;
+
+    send_messages(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender,
+        &receiver.pubkey(),
+        0,
+        vec![
+            (MessageType::PLAIN_TEXT, b"First".to_vec(), [1_u8; 16]),
+            (MessageType::PLAIN_TEXT, b"Second".to_vec(), [2_u8; 16]),
+        ],
+    ).await;
+
+    // Retried batch after a lost response - sent messages are skipped, the new ones take the next indexes
+    send_messages(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender,
+        &receiver.pubkey(),
+        2,
+        vec![
+            (MessageType::PLAIN_TEXT, b"First".to_vec(), [1_u8; 16]),
+            (MessageType::PLAIN_TEXT, b"Third".to_vec(), [3_u8; 16]),
+            (MessageType::PLAIN_TEXT, b"Second".to_vec(), [2_u8; 16]),
+            (MessageType::PLAIN_TEXT, b"Third".to_vec(), [3_u8; 16]),
+        ],
+    ).await;
+
+    let conversation_pda_account = banks_client
+        .get_account(conversation_pda_address)
+        .await
+        .expect("get_account")
+        .expect("conversation_pda_account not found");
+
+    assert_eq!(Conversation::try_from_slice(&conversation_pda_account.data).unwrap().message_counter, 3);
+
+    for (message_index, (content, message_id)) in (0..).zip([("First", [1_u8; 16]), ("Second", [2_u8; 16]), ("Third", [3_u8; 16])].iter()) {
+        let message_pda_account = banks_client
+            .get_account(Message::find_pda_address_v2(&conversation_pda_address, message_index, &id()))
+            .await
+            .expect("get_account")
+            .expect("message_pda_account not found");
+
+        let message = Message::try_from_slice(&message_pda_account.data).unwrap();
+
+        assert_eq!(message.content, content.as_bytes());
+        assert_eq!(message.message_id, *message_id);
+
+        let message_id_pda_account = banks_client
+            .get_account(MessageIdRecord::find_pda_address(&conversation_pda_address, &sender.pubkey(), message_id, &id()))
+            .await
+            .expect("get_account")
+            .expect("message_id_pda_account not found");
+
+        assert_eq!(MessageIdRecord::try_from_slice(&message_id_pda_account.data).unwrap().message_index, message_index);
+    }
+
+    // Leftover message accounts of the skipped messages stay untouched
+    assert!(
+        banks_client
+            .get_account(Message::find_pda_address_v2(&conversation_pda_address, 3, &id()))
+            .await
+            .expect("get_account")
+            .is_none()
+    );
+}
+
+#[tokio::test]
+async fn test_send_paged_messages() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let sender = Keypair::new();
+    let receiver = Keypair::new();
+
+    let conversation_pda_address = create_conversation_between_wallets(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender.pubkey(),
+        &receiver.pubkey(),
+    ).await;
+
+    let paged_conversation_pda_address = PagedConversation::find_pda_address(&conversation_pda_address, &id());
+
+    // Long messages roll over to a new page every few messages
+    let content = vec![1_u8; 500];
+    let messages_per_page = (MessagePage::SIZE - 4) / Message::retrieve_size(content.len());
+
+    for message_index in 0..(messages_per_page + 1) as u32 {
+        let page_counter = match banks_client.get_account(paged_conversation_pda_address).await.unwrap() {
+            Some(account) => PagedConversation::try_from_slice(&account.data).unwrap().page_counter,
+            None => 0,
+        };
+
+        let mut message_content = content.clone();
+        message_content[0] = message_index as u8;
+
+        send_paged_message(
+            &payer,
+            &mut banks_client,
+            &recent_blockhash,
+            &sender,
+            &receiver.pubkey(),
+            page_counter,
+            &message_content,
+        ).await;
+    }
+
+    let paged_conversation_pda_account = banks_client
+        .get_account(paged_conversation_pda_address)
+        .await
+        .expect("get_account")
+        .expect("paged_conversation_pda_account not found");
+
+    let paged_conversation = PagedConversation::try_from_slice(&paged_conversation_pda_account.data).unwrap();
+
+    assert_eq!(paged_conversation.page_counter, 2);
+    assert_eq!(paged_conversation.message_counter, messages_per_page as u64 + 1);
+
+    // Clients read the pages sequentially
+    let rent = banks_client.get_rent().await.unwrap();
+    let mut contents = Vec::new();
+
+    for page_index in 0..paged_conversation.page_counter {
+        let message_page_account = banks_client
+            .get_account(MessagePage::find_pda_address(&conversation_pda_address, page_index, &id()))
+            .await
+            .expect("get_account")
+            .expect("message_page_account not found");
+
+        assert_eq!(message_page_account.data.len(), MessagePage::SIZE);
+        assert!(rent.is_exempt(message_page_account.lamports, MessagePage::SIZE));
+
+        for message in MessagePage::unpack(&message_page_account.data).unwrap().messages {
+            assert_eq!(message.sender, sender.pubkey());
+            contents.push(message.content[0]);
+        }
+    }
+
+    assert_eq!(contents, (0..=messages_per_page as u8).collect::<Vec<u8>>());
+
+    // The last page holds the one message that did not fit into the first page
+    let last_page_account = banks_client
+        .get_account(MessagePage::find_pda_address(&conversation_pda_address, 1, &id()))
+        .await
+        .expect("get_account")
+        .expect("last_page_account not found");
+
+    let last_page = MessagePage::unpack(&last_page_account.data).unwrap();
+    assert_eq!(paged_conversation.last_page_size as usize, last_page.used_size());
+}
+
+#[tokio::test]
+async fn test_send_message_to_legacy_conversation() {
+    let sender = Keypair::new();
+    let receiver = Keypair::new();
+
+    // Accounts created before bump seeds were stored
+    let mut program_test = program_test();
+    let conversation_pda_address = add_conversation_accounts(&mut program_test, &sender.pubkey(), &receiver.pubkey(), true);
+
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    send_message(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender,
+        &receiver.pubkey(),
+        0,
+        MessageType::PLAIN_TEXT,
+        &String::from("Hello").into_bytes(),
+    ).await;
+
+    let conversation_pda_account = banks_client
+        .get_account(conversation_pda_address)
+        .await
+        .expect("get_account")
+        .expect("conversation_pda_account not found");
+
+    assert_eq!(conversation_pda_account.data.len(), Conversation::LEGACY_SIZE);
+    assert_eq!(Conversation::unpack(&conversation_pda_account.data).unwrap().message_counter, 1);
+}
+
+#[tokio::test]
+async fn test_migrate_user_conversation_account() {
+    let sender = Keypair::new();
+    let receiver = Keypair::new();
+
+    let mut program_test = program_test();
+    let conversation_pda_address = add_conversation_accounts(&mut program_test, &sender.pubkey(), &receiver.pubkey(), true);
+
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    process_signed_instruction(
+        &mut banks_client,
+        &recent_blockhash,
+        &payer,
+        &[&sender],
+        instruction::migrate_user_conversation_account(&payer.pubkey(), &sender.pubkey(), 0),
+    ).await;
+
+    let sender_user_pda_address = User::find_pda_address(&sender.pubkey(), &id());
+
+    // Lamports of the v1 account go back to the user
+    let sender_account = banks_client
+        .get_account(sender.pubkey())
+        .await
+        .expect("get_account")
+        .expect("sender_account not found");
+
+    assert!(sender_account.lamports > 0);
+
+    let v1_user_conversation_pda_account = banks_client
+        .get_account(UserConversation::find_pda_address(&sender_user_pda_address, 0, &id()))
+        .await
+        .expect("get_account");
+
+    assert!(v1_user_conversation_pda_account.is_none());
+
+    // Migrated account gets the current layout with the bump seed of the v2 address
+    let (v2_user_conversation_pda_address, v2_user_conversation_bump_seed) =
+        UserConversation::find_pda_address_v2_with_bump_seed(&sender_user_pda_address, 0, &id());
+
+    let v2_user_conversation_pda_account = banks_client
+        .get_account(v2_user_conversation_pda_address)
+        .await
+        .expect("get_account")
+        .expect("v2_user_conversation_pda_account not found");
+
+    let user_conversation = UserConversation::unpack(&v2_user_conversation_pda_account.data).unwrap();
+
+    assert_eq!(v2_user_conversation_pda_account.data.len(), UserConversation::retrieve_size());
+    assert_eq!(user_conversation.conversation_address, conversation_pda_address);
+    assert_eq!(user_conversation.bump_seed, v2_user_conversation_bump_seed);
+
+    // Instructions built for the v2 address accept the migrated account
+    update_user_conversation(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender,
+        0,
+        true,
+        0,
+        0,
+    ).await;
+}
+
+#[tokio::test]
+async fn test_migrate_message_account() {
+    let sender = Keypair::new();
+    let receiver = Keypair::new();
+
+    let mut program_test = program_test();
+    let conversation_pda_address = add_conversation_accounts(&mut program_test, &sender.pubkey(), &receiver.pubkey(), true);
+
+    let mut message = Message::new(5);
+    message.sender = sender.pubkey();
+    message.message_type = MessageType::PLAIN_TEXT;
+    message.content = String::from("Hello").into_bytes();
+    message.timestamp = 1_640_995_200;
+    add_v1_message_account(&mut program_test, &conversation_pda_address, 0, &message, true);
+
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    process_signed_instruction(
+        &mut banks_client,
+        &recent_blockhash,
+        &payer,
+        &[&sender],
+        instruction::migrate_message_account(&payer.pubkey(), &sender.pubkey(), &sender.pubkey(), &conversation_pda_address, 0),
+    ).await;
+
+    let v1_message_pda_account = banks_client
+        .get_account(Message::find_pda_address(&conversation_pda_address, 0, &id()))
+        .await
+        .expect("get_account");
+
+    assert!(v1_message_pda_account.is_none());
+
+    let v2_message_pda_account = banks_client
+        .get_account(Message::find_pda_address_v2(&conversation_pda_address, 0, &id()))
+        .await
+        .expect("get_account")
+        .expect("v2_message_pda_account not found");
+
+    let migrated_message = Message::unpack(&v2_message_pda_account.data).unwrap();
+
+    assert_eq!(v2_message_pda_account.data.len(), Message::retrieve_size(5));
+    assert_eq!(migrated_message.sender, sender.pubkey());
+    assert_eq!(migrated_message.content, message.content);
+    assert_eq!(migrated_message.timestamp, message.timestamp);
+    assert_eq!(migrated_message.message_id, Message::NO_MESSAGE_ID);
+}
+
+#[tokio::test]
+async fn test_migrate_message_account_by_receiver() {
+    let sender = Keypair::new();
+    let receiver = Keypair::new();
+
+    let mut program_test = program_test();
+    let conversation_pda_address = add_conversation_accounts(&mut program_test, &sender.pubkey(), &receiver.pubkey(), true);
+
+    let mut message = Message::new(5);
+    message.sender = sender.pubkey();
+    message.content = String::from("Hello").into_bytes();
+    add_v1_message_account(&mut program_test, &conversation_pda_address, 0, &message, true);
+
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    let v1_message_pda_account = banks_client
+        .get_account(Message::find_pda_address(&conversation_pda_address, 0, &id()))
+        .await
+        .expect("get_account")
+        .expect("v1_message_pda_account not found");
+
+    process_signed_instruction(
+        &mut banks_client,
+        &recent_blockhash,
+        &payer,
+        &[&receiver],
+        instruction::migrate_message_account(&payer.pubkey(), &receiver.pubkey(), &sender.pubkey(), &conversation_pda_address, 0),
+    ).await;
+
+    // The sender still gets the lamports of the v1 account
+    assert_eq!(banks_client.get_balance(sender.pubkey()).await.unwrap(), v1_message_pda_account.lamports);
+    assert!(banks_client
+        .get_account(Message::find_pda_address_v2(&conversation_pda_address, 0, &id()))
+        .await
+        .expect("get_account")
+        .is_some());
+}
+
+#[tokio::test]
+#[should_panic]
+async fn test_cannot_migrate_message_to_other_wallet() {
+    let sender = Keypair::new();
+    let receiver = Keypair::new();
+
+    let mut program_test = program_test();
+    let conversation_pda_address = add_conversation_accounts(&mut program_test, &sender.pubkey(), &receiver.pubkey(), true);
+
+    let mut message = Message::new(5);
+    message.sender = sender.pubkey();
+    message.content = String::from("Hello").into_bytes();
+    add_v1_message_account(&mut program_test, &conversation_pda_address, 0, &message, true);
+
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    // The receiver would collect the lamports of the sender's message
+    process_signed_instruction(
+        &mut banks_client,
+        &recent_blockhash,
+        &payer,
+        &[&receiver],
+        instruction::migrate_message_account(&payer.pubkey(), &receiver.pubkey(), &receiver.pubkey(), &conversation_pda_address, 0),
+    ).await;
+}
+
+#[tokio::test]
+#[should_panic]
+async fn test_cannot_migrate_message_by_outsider() {
+    let sender = Keypair::new();
+    let receiver = Keypair::new();
+    let outsider = Keypair::new();
+
+    let mut program_test = program_test();
+    let conversation_pda_address = add_conversation_accounts(&mut program_test, &sender.pubkey(), &receiver.pubkey(), true);
+
+    let mut message = Message::new(5);
+    message.sender = sender.pubkey();
+    message.content = String::from("Hello").into_bytes();
+    add_v1_message_account(&mut program_test, &conversation_pda_address, 0, &message, true);
+
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    create_user_pda_account(&payer, &mut banks_client, &recent_blockhash, &outsider.pubkey()).await;
+
+    process_signed_instruction(
+        &mut banks_client,
+        &recent_blockhash,
+        &payer,
+        &[&outsider],
+        instruction::migrate_message_account(&payer.pubkey(), &outsider.pubkey(), &sender.pubkey(), &conversation_pda_address, 0),
+    ).await;
+}
+
+#[tokio::test]
+#[should_panic]
+async fn test_revived_v1_message_account_cannot_be_pinned() {
+    let sender = Keypair::new();
+    let receiver = Keypair::new();
+
+    let mut program_test = program_test();
+    let conversation_pda_address = add_conversation_accounts(&mut program_test, &sender.pubkey(), &receiver.pubkey(), true);
+
+    let mut message = Message::new(5);
+    message.sender = sender.pubkey();
+    message.content = String::from("Hello").into_bytes();
+    add_v1_message_account(&mut program_test, &conversation_pda_address, 0, &message, true);
+
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    let v1_message_pda_address = Message::find_pda_address(&conversation_pda_address, 0, &id());
+
+    // The closed v1 account is funded again in the same transaction, so it keeps its zeroed data
+    let mut pin_instruction = instruction::pin_message(&payer.pubkey(), &sender.pubkey(), &receiver.pubkey(), 0);
+    pin_instruction.accounts[6].pubkey = v1_message_pda_address;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            instruction::migrate_message_account(&payer.pubkey(), &sender.pubkey(), &sender.pubkey(), &conversation_pda_address, 0),
+            system_instruction::transfer(&payer.pubkey(), &v1_message_pda_address, 10_000_000),
+            pin_instruction,
+        ],
+        Some(&payer.pubkey()),
+        &[&payer, &sender],
+        recent_blockhash,
+    );
+
+    banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_upgrade_user_account() {
+    let sender = Keypair::new();
+    let receiver = Keypair::new();
+
+    let mut program_test = program_test();
+    add_conversation_accounts(&mut program_test, &sender.pubkey(), &receiver.pubkey(), true);
+
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    let (user_pda_address, user_bump_seed) = User::find_pda_address_with_bump_seed(&sender.pubkey(), &id());
+    let upgrade_pda_address = AccountUpgrade::find_pda_address(&user_pda_address, &id());
+
+    process_signed_instruction(
+        &mut banks_client,
+        &recent_blockhash,
+        &payer,
+        &[&sender],
+        instruction::upgrade_user_account(&payer.pubkey(), &sender.pubkey()),
+    ).await;
+
+    // The legacy account is closed, its counter is kept for the second call
+    assert!(banks_client.get_account(user_pda_address).await.expect("get_account").is_none());
+
+    let upgrade_pda_account = banks_client
+        .get_account(upgrade_pda_address)
+        .await
+        .expect("get_account")
+        .expect("upgrade_pda_account not found");
+
+    let upgrade = AccountUpgrade::try_from_slice(&upgrade_pda_account.data).unwrap();
+
+    assert_eq!(upgrade.counter, 1);
+    assert_eq!(upgrade.funder, payer.pubkey());
+
+    // Fees paid by the user so that the second call is a different transaction
+    process_signed_instruction(
+        &mut banks_client,
+        &recent_blockhash,
+        &payer,
+        &[],
+        system_instruction::transfer(&payer.pubkey(), &sender.pubkey(), 1_000_000_000),
+    ).await;
+
+    let payer_balance = banks_client.get_balance(payer.pubkey()).await.unwrap();
+
+    process_signed_instruction(
+        &mut banks_client,
+        &recent_blockhash,
+        &sender,
+        &[&payer],
+        instruction::upgrade_user_account(&payer.pubkey(), &sender.pubkey()),
+    ).await;
+
+    // The funder pays for the recreated account and gets the lamports of the closed accounts
+    let rent = banks_client.get_rent().await.unwrap();
+
+    assert_eq!(
+        banks_client.get_balance(payer.pubkey()).await.unwrap(),
+        payer_balance + upgrade_pda_account.lamports - rent.minimum_balance(User::retrieve_size()),
+    );
+
+    let user_pda_account = banks_client
+        .get_account(user_pda_address)
+        .await
+        .expect("get_account")
+        .expect("user_pda_account not found");
+
+    let user = User::try_from_slice(&user_pda_account.data).unwrap();
+
+    assert_eq!(user.conversation_counter, 1);
+    assert_eq!(user.bump_seed, user_bump_seed);
+    assert!(banks_client.get_account(upgrade_pda_address).await.expect("get_account").is_none());
+}
+
+#[tokio::test]
+async fn test_upgrade_conversation_account() {
+    let sender = Keypair::new();
+    let receiver = Keypair::new();
+
+    let mut program_test = program_test();
+    let conversation_pda_address = add_conversation_accounts(&mut program_test, &sender.pubkey(), &receiver.pubkey(), true);
+
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    send_message(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender,
+        &receiver.pubkey(),
+        0,
+        MessageType::PLAIN_TEXT,
+        &String::from("Hello").into_bytes(),
+    ).await;
+
+    process_signed_instruction(
+        &mut banks_client,
+        &recent_blockhash,
+        &payer,
+        &[&receiver],
+        instruction::upgrade_conversation_account(&payer.pubkey(), &receiver.pubkey(), &sender.pubkey()),
+    ).await;
+
+    assert!(banks_client.get_account(conversation_pda_address).await.expect("get_account").is_none());
+
+    process_signed_instruction(
+        &mut banks_client,
+        &recent_blockhash,
+        &payer,
+        &[],
+        system_instruction::transfer(&payer.pubkey(), &receiver.pubkey(), 1_000_000_000),
+    ).await;
+
+    process_signed_instruction(
+        &mut banks_client,
+        &recent_blockhash,
+        &receiver,
+        &[&payer],
+        instruction::upgrade_conversation_account(&payer.pubkey(), &receiver.pubkey(), &sender.pubkey()),
+    ).await;
+
+    let conversation_pda_account = banks_client
+        .get_account(conversation_pda_address)
+        .await
+        .expect("get_account")
+        .expect("conversation_pda_account not found");
+
+    let (_, conversation_bump_seed) = Conversation::find_pda_address_with_bump_seed(
+        &User::find_pda_address(&sender.pubkey(), &id()),
+        &User::find_pda_address(&receiver.pubkey(), &id()),
+        &id(),
+    );
+    let conversation = Conversation::try_from_slice(&conversation_pda_account.data).unwrap();
+
+    assert_eq!(conversation.message_counter, 1);
+    assert_eq!(conversation.bump_seed, conversation_bump_seed);
+
+    // Messages continue at the next index
+    send_message(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender,
+        &receiver.pubkey(),
+        1,
+        MessageType::PLAIN_TEXT,
+        &String::from("Hello again").into_bytes(),
+    ).await;
+}
+
+#[tokio::test]
+#[should_panic]
+async fn test_cannot_upgrade_current_user_account() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test().start().await;
+
+    let user = Keypair::new();
+
+    create_user_pda_account(&payer, &mut banks_client, &recent_blockhash, &user.pubkey()).await;
+
+    process_signed_instruction(
+        &mut banks_client,
+        &recent_blockhash,
+        &payer,
+        &[&user],
+        instruction::upgrade_user_account(&payer.pubkey(), &user.pubkey()),
+    ).await;
+}
+
+#[tokio::test]
+#[should_panic]
+async fn test_cannot_create_user_account_during_upgrade() {
+    let sender = Keypair::new();
+    let receiver = Keypair::new();
+
+    let mut program_test = program_test();
+    add_conversation_accounts(&mut program_test, &sender.pubkey(), &receiver.pubkey(), true);
+
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    process_signed_instruction(
+        &mut banks_client,
+        &recent_blockhash,
+        &payer,
+        &[&sender],
+        instruction::upgrade_user_account(&payer.pubkey(), &sender.pubkey()),
+    ).await;
+
+    // The closed user account cannot be created with a zero counter before the upgrade is finished
+    create_user_pda_account(&payer, &mut banks_client, &recent_blockhash, &sender.pubkey()).await;
+}
+
+#[tokio::test]
+#[should_panic]
+async fn test_cannot_create_conversation_account_during_upgrade() {
+    let sender = Keypair::new();
+    let receiver = Keypair::new();
+
+    let mut program_test = program_test();
+    add_conversation_accounts(&mut program_test, &sender.pubkey(), &receiver.pubkey(), true);
+
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    process_signed_instruction(
+        &mut banks_client,
+        &recent_blockhash,
+        &payer,
+        &[&sender],
+        instruction::upgrade_conversation_account(&payer.pubkey(), &sender.pubkey(), &receiver.pubkey()),
+    ).await;
+
+    // Recreating the closed conversation account would add second user-conversation entries
+    create_conversation_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &User::find_pda_address(&sender.pubkey(), &id()),
+        &User::find_pda_address(&receiver.pubkey(), &id()),
+        1,
+        1,
+    ).await;
+}
+
+#[tokio::test]
+#[should_panic]
+async fn test_cannot_finish_upgrade_with_other_funder() {
+    let sender = Keypair::new();
+    let receiver = Keypair::new();
+
+    let mut program_test = program_test();
+    add_conversation_accounts(&mut program_test, &sender.pubkey(), &receiver.pubkey(), true);
+
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    process_signed_instruction(
+        &mut banks_client,
+        &recent_blockhash,
+        &payer,
+        &[&sender],
+        instruction::upgrade_conversation_account(&payer.pubkey(), &sender.pubkey(), &receiver.pubkey()),
+    ).await;
+
+    process_signed_instruction(
+        &mut banks_client,
+        &recent_blockhash,
+        &payer,
+        &[],
+        system_instruction::transfer(&payer.pubkey(), &receiver.pubkey(), 1_000_000_000),
+    ).await;
+
+    // The other participant cannot finish the upgrade and take the lamports of the closed accounts
+    process_signed_instruction(
+        &mut banks_client,
+        &recent_blockhash,
+        &receiver,
+        &[],
+        instruction::upgrade_conversation_account(&receiver.pubkey(), &receiver.pubkey(), &sender.pubkey()),
+    ).await;
+}
+
+// Compute unit regression tests - each instruction runs with a budget well below the default,
+// so a new find_program_address loop in the handler shows up here. Budgets only apply to the BPF
+// build (`cargo test-bpf`), the native `processor!` runs these tests without metering
+
+#[tokio::test]
+async fn test_create_user_account_compute_units() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test_with_compute_max_units(10_000).start().await;
+
+    let wallet = Keypair::new();
+
+    process_signed_instruction(
+        &mut banks_client,
+        &recent_blockhash,
+        &payer,
+        &[],
+        instruction::create_user_account(&payer.pubkey(), &wallet.pubkey()),
+    ).await;
+
+    let user_pda_account = banks_client
+        .get_account(User::find_pda_address(&wallet.pubkey(), &id()))
+        .await
+        .expect("get_account")
+        .expect("user_pda_account not found");
+
+    let (_, bump_seed) = User::find_pda_address_with_bump_seed(&wallet.pubkey(), &id());
+
+    assert_eq!(User::try_from_slice(&user_pda_account.data).unwrap().bump_seed, bump_seed);
+}
+
+#[tokio::test]
+async fn test_create_conversation_account_compute_units() {
+    let (mut banks_client, payer, recent_blockhash) =
+        program_test_with_compute_max_units(30_000).start().await;
+
+    let sender = Keypair::new();
+    let receiver = Keypair::new();
+
+    for wallet in [&sender, &receiver].iter() {
+        process_signed_instruction(
+            &mut banks_client,
+            &recent_blockhash,
+            &payer,
+            &[],
+            instruction::create_user_account(&payer.pubkey(), &wallet.pubkey()),
+        ).await;
+    }
+
+    let sender_user_pda_address = User::find_pda_address(&sender.pubkey(), &id());
+    let receiver_user_pda_address = User::find_pda_address(&receiver.pubkey(), &id());
+
+    process_signed_instruction(
+        &mut banks_client,
+        &recent_blockhash,
+        &payer,
+        &[],
+        instruction::create_conversation_account(&payer.pubkey(), &sender_user_pda_address, &receiver_user_pda_address, 0, 0),
+    ).await;
+
+    let (conversation_pda_address, bump_seed) = Conversation::find_pda_address_with_bump_seed(
+        &sender_user_pda_address,
+        &receiver_user_pda_address,
+        &id(),
+    );
+
+    let conversation_pda_account = banks_client
+        .get_account(conversation_pda_address)
+        .await
+        .expect("get_account")
+        .expect("conversation_pda_account not found");
+
+    assert_eq!(Conversation::try_from_slice(&conversation_pda_account.data).unwrap().bump_seed, bump_seed);
+}
+
+/// Compute units of a bump seed search - every round is a `create_program_address` syscall
+fn bump_seed_search_units(bump_seed: u8) -> u64 {
+    (u64::from(u8::MAX) - u64::from(bump_seed) + 1) * CREATE_PROGRAM_ADDRESS_UNITS
+}
+
+const CREATE_PROGRAM_ADDRESS_UNITS: u64 = 1_500;
+const INVOKE_UNITS: u64 = 1_000;
+
+#[tokio::test]
+async fn test_send_message_compute_units() {
+    let sender = Keypair::new();
+    let receiver = Keypair::new();
+
+    let mut program_test = program_test();
+    let conversation_pda_address = add_conversation_accounts(&mut program_test, &sender.pubkey(), &receiver.pubkey(), false);
+
+    // The budget follows the bump seed searches of these wallets, so it holds for any keys: the conversation
+    // address is checked with its stored bump seed, the padding-policy and the message addresses are searched
+    let (_, padding_policy_bump_seed) = ConversationPaddingPolicy::find_pda_address_with_bump_seed(&conversation_pda_address, &id());
+    let (_, message_bump_seed) = Message::find_pda_address_v2_with_bump_seed(&conversation_pda_address, 0, &id());
+
+    let syscall_units = CREATE_PROGRAM_ADDRESS_UNITS
+        + bump_seed_search_units(padding_policy_bump_seed)
+        + bump_seed_search_units(message_bump_seed)
+        + INVOKE_UNITS;
+
+    // The handler itself reads and writes the accounts in place - the Borsh path it replaced was not
+    // metered, so the allowance is not a measured saving
+    let handler_units = 4_500;
+
+    println!("send_message budget: {} syscall units + {} handler units", syscall_units, handler_units);
+
+    program_test.set_bpf_compute_max_units(syscall_units + handler_units);
+
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    send_message(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender,
+        &receiver.pubkey(),
+        0,
+        MessageType::PLAIN_TEXT,
+        &String::from("Hello").into_bytes(),
+    ).await;
+}
+
+#[tokio::test]
+async fn test_update_user_conversation_compute_units() {
+    let sender = Keypair::new();
+    let receiver = Keypair::new();
+
+    let mut program_test = program_test_with_compute_max_units(8_000);
+    add_conversation_accounts(&mut program_test, &sender.pubkey(), &receiver.pubkey(), false);
+
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    process_signed_instruction(
+        &mut banks_client,
+        &recent_blockhash,
+        &payer,
+        &[&sender],
+        instruction::update_user_conversation(&sender.pubkey(), 0, true, 0, 1),
+    ).await;
+}
+
+#[tokio::test]
+async fn test_hide_conversation_compute_units() {
+    let sender = Keypair::new();
+    let receiver = Keypair::new();
+
+    let mut program_test = program_test_with_compute_max_units(8_000);
+    add_conversation_accounts(&mut program_test, &sender.pubkey(), &receiver.pubkey(), false);
+
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    process_signed_instruction(
+        &mut banks_client,
+        &recent_blockhash,
+        &payer,
+        &[&sender],
+        instruction::hide_conversation(&sender.pubkey(), &receiver.pubkey(), 0, true),
+    ).await;
+}
+
+#[tokio::test]
+async fn test_create_user_conversation_account_compute_units() {
+    let sender = Keypair::new();
+    let receiver = Keypair::new();
+
+    let mut program_test = program_test_with_compute_max_units(15_000);
+    add_conversation_accounts(&mut program_test, &sender.pubkey(), &receiver.pubkey(), false);
+
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    let sender_user_pda_address = User::find_pda_address(&sender.pubkey(), &id());
+
+    create_user_conversation_pda_account(&payer, &mut banks_client, &recent_blockhash, &sender_user_pda_address, 1).await;
+}
+
+#[tokio::test]
+async fn test_create_message_account_compute_units() {
+    let sender = Keypair::new();
+    let receiver = Keypair::new();
+
+    let mut program_test = program_test_with_compute_max_units(25_000);
+    let conversation_pda_address = add_conversation_accounts(&mut program_test, &sender.pubkey(), &receiver.pubkey(), false);
+
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    create_message_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender,
+        &conversation_pda_address,
+        0,
+        0,
+        MessageType::PLAIN_TEXT,
+        &String::from("Hello").into_bytes(),
+    ).await;
+}
+
+#[tokio::test]
+async fn test_create_conversation_encryption_info_account_compute_units() {
+    let sender = Keypair::new();
+    let receiver = Keypair::new();
+
+    let mut program_test = program_test_with_compute_max_units(20_000);
+    add_conversation_accounts(&mut program_test, &sender.pubkey(), &receiver.pubkey(), false);
+
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    create_conversation_encryption_info_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender,
+        &receiver.pubkey(),
+        &[1_u8; 32],
+    ).await;
+}
+
+#[tokio::test]
+async fn test_create_conversation_handshake_account_compute_units() {
+    let sender = Keypair::new();
+    let receiver = Keypair::new();
+
+    let mut program_test = program_test_with_compute_max_units(25_000);
+    add_conversation_accounts(&mut program_test, &sender.pubkey(), &receiver.pubkey(), false);
+
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    create_conversation_handshake_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender,
+        &receiver.pubkey(),
+        0,
+        &[1_u8; 32],
+        &[2_u8; 32],
+    ).await;
+}
+
+#[tokio::test]
+async fn test_set_conversation_padding_policy_compute_units() {
+    let sender = Keypair::new();
+    let receiver = Keypair::new();
+
+    let mut program_test = program_test_with_compute_max_units(20_000);
+    add_conversation_accounts(&mut program_test, &sender.pubkey(), &receiver.pubkey(), false);
+
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    set_conversation_padding_policy_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender,
+        &receiver.pubkey(),
+        PaddingScheme::BLOCK_256,
+    ).await;
+}
+
+#[tokio::test]
+async fn test_set_conversation_metadata_compute_units() {
+    let sender = Keypair::new();
+    let receiver = Keypair::new();
+
+    let mut program_test = program_test_with_compute_max_units(20_000);
+    add_conversation_accounts(&mut program_test, &sender.pubkey(), &receiver.pubkey(), false);
+
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    set_conversation_metadata_pda_account(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender,
+        &receiver.pubkey(),
+        "Support",
+        "",
+        "",
+    ).await;
+}
+
+#[tokio::test]
+async fn test_pin_and_unpin_message_compute_units() {
+    let sender = Keypair::new();
+    let receiver = Keypair::new();
+
+    let mut program_test = program_test_with_compute_max_units(20_000);
+    add_conversation_accounts(&mut program_test, &sender.pubkey(), &receiver.pubkey(), false);
+
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    send_message(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender,
+        &receiver.pubkey(),
+        0,
+        MessageType::PLAIN_TEXT,
+        &String::from("Hello").into_bytes(),
+    ).await;
+
+    pin_message(&payer, &mut banks_client, &recent_blockhash, &sender, &receiver.pubkey(), 0).await;
+    unpin_message(&payer, &mut banks_client, &recent_blockhash, &sender, &receiver.pubkey(), 0).await;
+}
+
+#[tokio::test]
+async fn test_send_sequenced_message_compute_units() {
+    let sender = Keypair::new();
+    let receiver = Keypair::new();
+
+    let mut program_test = program_test_with_compute_max_units(20_000);
+    add_conversation_accounts(&mut program_test, &sender.pubkey(), &receiver.pubkey(), false);
+
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    banks_client
+        .process_transaction(sequenced_message_transaction(
+            &payer,
+            &recent_blockhash,
+            &sender,
+            &receiver.pubkey(),
+            0,
+            &String::from("Hello").into_bytes(),
+        ))
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_send_messages_compute_units() {
+    let sender = Keypair::new();
+    let receiver = Keypair::new();
+
+    let mut program_test = program_test_with_compute_max_units(30_000);
+    add_conversation_accounts(&mut program_test, &sender.pubkey(), &receiver.pubkey(), false);
+
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    send_messages(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender,
+        &receiver.pubkey(),
+        0,
+        vec![
+            (MessageType::PLAIN_TEXT, b"First".to_vec(), [1_u8; 16]),
+            (MessageType::PLAIN_TEXT, b"Second".to_vec(), Message::NO_MESSAGE_ID),
+        ],
+    ).await;
+}
+
+#[tokio::test]
+async fn test_send_paged_message_compute_units() {
+    let sender = Keypair::new();
+    let receiver = Keypair::new();
+
+    let mut program_test = program_test_with_compute_max_units(30_000);
+    add_conversation_accounts(&mut program_test, &sender.pubkey(), &receiver.pubkey(), false);
+
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    send_paged_message(
+        &payer,
+        &mut banks_client,
+        &recent_blockhash,
+        &sender,
+        &receiver.pubkey(),
+        0,
+        &String::from("Hello").into_bytes(),
+    ).await;
+}
+
+#[tokio::test]
+async fn test_migrate_user_conversation_account_compute_units() {
+    let sender = Keypair::new();
+    let receiver = Keypair::new();
+
+    let mut program_test = program_test_with_compute_max_units(20_000);
+    add_conversation_accounts(&mut program_test, &sender.pubkey(), &receiver.pubkey(), true);
+
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    process_signed_instruction(
+        &mut banks_client,
+        &recent_blockhash,
+        &payer,
+        &[&sender],
+        instruction::migrate_user_conversation_account(&payer.pubkey(), &sender.pubkey(), 0),
+    ).await;
+}
+
+#[tokio::test]
+async fn test_upgrade_user_account_compute_units() {
+    let sender = Keypair::new();
+    let receiver = Keypair::new();
+
+    let mut program_test = program_test_with_compute_max_units(20_000);
+    add_conversation_accounts(&mut program_test, &sender.pubkey(), &receiver.pubkey(), true);
+
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    process_signed_instruction(
+        &mut banks_client,
+        &recent_blockhash,
+        &payer,
+        &[&sender],
+        instruction::upgrade_user_account(&payer.pubkey(), &sender.pubkey()),
+    ).await;
+
+    process_signed_instruction(
+        &mut banks_client,
+        &recent_blockhash,
+        &payer,
+        &[],
+        system_instruction::transfer(&payer.pubkey(), &sender.pubkey(), 1_000_000_000),
+    ).await;
+
+    process_signed_instruction(
+        &mut banks_client,
+        &recent_blockhash,
+        &sender,
+        &[&payer],
+        instruction::upgrade_user_account(&payer.pubkey(), &sender.pubkey()),
+    ).await;
+}
+
+#[tokio::test]
+async fn test_upgrade_conversation_account_compute_units() {
+    let sender = Keypair::new();
+    let receiver = Keypair::new();
+
+    let mut program_test = program_test_with_compute_max_units(25_000);
+    add_conversation_accounts(&mut program_test, &sender.pubkey(), &receiver.pubkey(), true);
+
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    process_signed_instruction(
+        &mut banks_client,
+        &recent_blockhash,
+        &payer,
+        &[&sender],
+        instruction::upgrade_conversation_account(&payer.pubkey(), &sender.pubkey(), &receiver.pubkey()),
+    ).await;
+
+    process_signed_instruction(
+        &mut banks_client,
+        &recent_blockhash,
+        &payer,
+        &[],
+        system_instruction::transfer(&payer.pubkey(), &sender.pubkey(), 1_000_000_000),
+    ).await;
+
+    process_signed_instruction(
+        &mut banks_client,
+        &recent_blockhash,
+        &sender,
+        &[&payer],
+        instruction::upgrade_conversation_account(&payer.pubkey(), &sender.pubkey(), &receiver.pubkey()),
+    ).await;
+}
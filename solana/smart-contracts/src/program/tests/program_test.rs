@@ -10,14 +10,19 @@ use {
 };
 
 pub fn program_test() -> ProgramTest {
+    // Dial down the BPF compute budget to detect if the program gets bloated in the future
+    program_test_with_compute_max_units(50_000)
+}
+
+/// Program test with the BPF compute budget of one instruction - regression tests of single instructions
+pub fn program_test_with_compute_max_units(compute_max_units: u64) -> ProgramTest {
     let mut program_test = ProgramTest::new(
         "instant_messaging",
         id(),
         processor!(process_instruction),
     );
 
-    // Dial down the BPF compute budget to detect if the program gets bloated in the future
-    program_test.set_bpf_compute_max_units(50_000);
+    program_test.set_bpf_compute_max_units(compute_max_units);
 
     program_test
 }
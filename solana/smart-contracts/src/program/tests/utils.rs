@@ -1,16 +1,40 @@
 use {
+    borsh::BorshSerialize,
     solana_program::{
+        clock::UnixTimestamp,
         hash::Hash,
+        instruction::Instruction,
         pubkey::Pubkey,
+        rent::Rent,
+    },
+    solana_program_test::{
+        BanksClient,
+        ProgramTest,
     },
-    solana_program_test::BanksClient,
     solana_sdk::{
+        account::Account,
         signature::{
             Keypair,
             Signer,
         },
+        system_instruction,
         transaction::Transaction,
     },
+    instant_messaging::{
+        id,
+        state::{
+            BlindedConversation,
+            Community,
+            Conversation,
+            Group,
+            GroupKind,
+            GroupRole,
+            Message,
+            MessageType,
+            User,
+            UserConversation,
+        },
+    },
     instant_messaging::instruction::{
         create_user_account,
         create_conversation_account,
@@ -19,7 +43,47 @@ use {
         send_message as send_message_instruction,
     },
 };
-use instant_messaging::instruction::create_conversation_encryption_info_account;
+use instant_messaging::instruction::{
+    create_conversation_encryption_info_account,
+    create_conversation_handshake_account,
+    create_blinded_conversation_account,
+    send_blinded_message as send_blinded_message_instruction,
+    send_sealed_message as send_sealed_message_instruction,
+    set_blinded_conversation_padding_policy,
+    set_conversation_padding_policy,
+    set_conversation_metadata,
+    set_group_metadata,
+    pin_message as pin_message_instruction,
+    unpin_message as unpin_message_instruction,
+    pin_group_message as pin_group_message_instruction,
+    update_user_conversation as update_user_conversation_instruction,
+    hide_conversation as hide_conversation_instruction,
+    start_conversation as start_conversation_instruction,
+    send_sequenced_message as send_sequenced_message_instruction,
+    send_messages as send_messages_instruction,
+    send_paged_message as send_paged_message_instruction,
+    set_sealed_sender_credential,
+    add_group_member as add_group_member_instruction,
+    create_group_account,
+    create_community_account,
+    create_community_group_account,
+    create_community_group_user_conversation as create_community_group_user_conversation_instruction,
+    send_community_group_message as send_community_group_message_instruction,
+    create_invite_account,
+    approve_join as approve_join_instruction,
+    deny_join as deny_join_instruction,
+    request_to_join as request_to_join_instruction,
+    ban_member as ban_member_instruction,
+    leave_group as leave_group_instruction,
+    remove_member as remove_member_instruction,
+    subscribe as subscribe_instruction,
+    unsubscribe as unsubscribe_instruction,
+    join_with_invite as join_with_invite_instruction,
+    revoke_invite as revoke_invite_instruction,
+    send_group_message as send_group_message_instruction,
+    set_member_role as set_member_role_instruction,
+    transfer_group_ownership as transfer_group_ownership_instruction,
+};
 
 /// Creates User PDA account
 pub async fn create_user_pda_account(
@@ -31,7 +95,7 @@ pub async fn create_user_pda_account(
     let transaction = Transaction::new_signed_with_payer(
         &[create_user_account(
             &payer.pubkey(),
-            &user_wallet_address,
+            user_wallet_address,
         )],
         Some(&payer.pubkey()),
         &[payer],
@@ -51,14 +115,14 @@ pub async fn create_conversation_pda_account(
     recent_blockhash: &Hash,
     sender_pda_address: &Pubkey,
     receiver_pda_address: &Pubkey,
-    sender_user_conversation_index: u32,
-    receiver_user_conversation_index: u32,
+    sender_user_conversation_index: u64,
+    receiver_user_conversation_index: u64,
 ) {
     let transaction = Transaction::new_signed_with_payer(
         &[create_conversation_account(
             &payer.pubkey(),
-            &sender_pda_address,
-            &receiver_pda_address,
+            sender_pda_address,
+            receiver_pda_address,
             sender_user_conversation_index,
             receiver_user_conversation_index,
         )],
@@ -79,12 +143,12 @@ pub async fn create_user_conversation_pda_account(
     banks_client: &mut BanksClient,
     recent_blockhash: &Hash,
     user_pda_address: &Pubkey,
-    conversation_index: u32,
+    conversation_index: u64,
 ) {
     let transaction = Transaction::new_signed_with_payer(
         &[create_user_conversation_account(
             &payer.pubkey(),
-            &user_pda_address,
+            user_pda_address,
             conversation_index,
         )],
         Some(&payer.pubkey()),
@@ -99,26 +163,27 @@ pub async fn create_user_conversation_pda_account(
 }
 
 /// Creates Message PDA account
+#[allow(clippy::too_many_arguments)]
 pub async fn create_message_pda_account(
     payer: &Keypair,
     banks_client: &mut BanksClient,
     recent_blockhash: &Hash,
     sender: &Keypair,
     conversation_pda_address: &Pubkey,
-    conversation_index: u32,
-    message_index: u32,
+    conversation_index: u64,
+    message_index: u64,
     message_type: u8,
-    content: &Vec<u8>,
+    content: &[u8],
 ) {
     let transaction = Transaction::new_signed_with_payer(
         &[create_message_account(
             &payer.pubkey(),
             &sender.pubkey(),
-            &conversation_pda_address,
+            conversation_pda_address,
             conversation_index,
             message_index,
             message_type,
-            content.clone(),
+            content.to_vec(),
         )],
         Some(&payer.pubkey()),
         &[payer, sender],
@@ -138,14 +203,14 @@ pub async fn create_conversation_encryption_info_pda_account(
     recent_blockhash: &Hash,
     sender: &Keypair,
     receiver_wallet_address: &Pubkey,
-    data: &Vec<u8>,
+    data: &[u8],
 ) {
     let transaction = Transaction::new_signed_with_payer(
         &[create_conversation_encryption_info_account(
             &payer.pubkey(),
             &sender.pubkey(),
             receiver_wallet_address,
-            data.clone(),
+            data.to_vec(),
         )],
         Some(&payer.pubkey()),
         &[payer, sender],
@@ -159,15 +224,42 @@ pub async fn create_conversation_encryption_info_pda_account(
 }
 
 /// Send Message
+#[allow(clippy::too_many_arguments)]
 pub async fn send_message(
     payer: &Keypair,
     banks_client: &mut BanksClient,
     recent_blockhash: &Hash,
     sender: &Keypair,
     receiver_wallet_address: &Pubkey,
-    message_index: u32,
+    message_index: u64,
+    message_type: u8,
+    content: &[u8],
+) {
+    send_message_with_id(
+        payer,
+        banks_client,
+        recent_blockhash,
+        sender,
+        receiver_wallet_address,
+        message_index,
+        message_type,
+        content,
+        Message::NO_MESSAGE_ID,
+    ).await;
+}
+
+/// Send Message with a client-supplied message ID
+#[allow(clippy::too_many_arguments)]
+pub async fn send_message_with_id(
+    payer: &Keypair,
+    banks_client: &mut BanksClient,
+    recent_blockhash: &Hash,
+    sender: &Keypair,
+    receiver_wallet_address: &Pubkey,
+    message_index: u64,
     message_type: u8,
-    content: &Vec<u8>,
+    content: &[u8],
+    message_id: [u8; 16],
 ) {
     let transaction = Transaction::new_signed_with_payer(
         &[send_message_instruction(
@@ -176,7 +268,395 @@ pub async fn send_message(
             receiver_wallet_address,
             message_index,
             message_type,
-            content.clone(),
+            content.to_vec(),
+            message_id,
+        )],
+        Some(&payer.pubkey()),
+        &[payer, sender],
+        *recent_blockhash,
+    );
+
+    banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+/// Creates ConversationHandshake PDA account
+#[allow(clippy::too_many_arguments)]
+pub async fn create_conversation_handshake_pda_account(
+    payer: &Keypair,
+    banks_client: &mut BanksClient,
+    recent_blockhash: &Hash,
+    sender: &Keypair,
+    receiver_wallet_address: &Pubkey,
+    epoch: u64,
+    commit: &[u8],
+    welcome: &[u8],
+) {
+    let transaction = Transaction::new_signed_with_payer(
+        &[create_conversation_handshake_account(
+            &payer.pubkey(),
+            &sender.pubkey(),
+            receiver_wallet_address,
+            epoch,
+            commit.to_vec(),
+            welcome.to_vec(),
+        )],
+        Some(&payer.pubkey()),
+        &[payer, sender],
+        *recent_blockhash,
+    );
+
+    banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+/// Creates both users' PDA accounts and the Conversation PDA account between them
+pub async fn create_conversation_between_wallets(
+    payer: &Keypair,
+    banks_client: &mut BanksClient,
+    recent_blockhash: &Hash,
+    sender_wallet_address: &Pubkey,
+    receiver_wallet_address: &Pubkey,
+) -> Pubkey {
+    let sender_pda_address = User::find_pda_address(sender_wallet_address, &id());
+    let receiver_pda_address = User::find_pda_address(receiver_wallet_address, &id());
+
+    create_user_pda_account(payer, banks_client, recent_blockhash, sender_wallet_address).await;
+    create_user_pda_account(payer, banks_client, recent_blockhash, receiver_wallet_address).await;
+
+    create_conversation_pda_account(
+        payer,
+        banks_client,
+        recent_blockhash,
+        &sender_pda_address,
+        &receiver_pda_address,
+        0,
+        0,
+    ).await;
+
+    Conversation::find_pda_address(&sender_pda_address, &receiver_pda_address, &id())
+}
+
+/// Creates or replaces SealedSenderCredential PDA account
+#[allow(clippy::too_many_arguments)]
+pub async fn set_sealed_sender_credential_pda_account(
+    payer: &Keypair,
+    banks_client: &mut BanksClient,
+    recent_blockhash: &Hash,
+    sender: &Keypair,
+    receiver_wallet_address: &Pubkey,
+    authority_address: &Pubkey,
+    chain_hash: Hash,
+    current_authority: Option<&Keypair>,
+) {
+    let mut signers = vec![payer, sender];
+    signers.extend(current_authority);
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[set_sealed_sender_credential(
+            &payer.pubkey(),
+            &sender.pubkey(),
+            receiver_wallet_address,
+            authority_address,
+            chain_hash,
+            current_authority.map(|current_authority| current_authority.pubkey()).as_ref(),
+        )],
+        Some(&payer.pubkey()),
+        &signers,
+        *recent_blockhash,
+    );
+
+    banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+/// Creates or updates ConversationPaddingPolicy PDA account
+pub async fn set_conversation_padding_policy_pda_account(
+    payer: &Keypair,
+    banks_client: &mut BanksClient,
+    recent_blockhash: &Hash,
+    sender: &Keypair,
+    receiver_wallet_address: &Pubkey,
+    scheme: u8,
+) {
+    let transaction = Transaction::new_signed_with_payer(
+        &[set_conversation_padding_policy(
+            &payer.pubkey(),
+            &sender.pubkey(),
+            receiver_wallet_address,
+            scheme,
+        )],
+        Some(&payer.pubkey()),
+        &[payer, sender],
+        *recent_blockhash,
+    );
+
+    banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+/// Creates or updates ConversationPaddingPolicy PDA account of a blinded conversation
+pub async fn set_blinded_conversation_padding_policy_pda_account(
+    payer: &Keypair,
+    banks_client: &mut BanksClient,
+    recent_blockhash: &Hash,
+    authority: &Keypair,
+    blinded_id: &[u8; 32],
+    scheme: u8,
+) {
+    let transaction = Transaction::new_signed_with_payer(
+        &[set_blinded_conversation_padding_policy(
+            &payer.pubkey(),
+            &authority.pubkey(),
+            blinded_id,
+            scheme,
+        )],
+        Some(&payer.pubkey()),
+        &[payer, authority],
+        *recent_blockhash,
+    );
+
+    banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+/// Send sealed Message - signed by the funder and the ephemeral authority only
+#[allow(clippy::too_many_arguments)]
+pub async fn send_sealed_message(
+    payer: &Keypair,
+    banks_client: &mut BanksClient,
+    recent_blockhash: &Hash,
+    authority: &Keypair,
+    conversation_pda_address: &Pubkey,
+    credential_pda_address: &Pubkey,
+    message_index: u64,
+    next_authority_address: &Pubkey,
+    next_chain_hash: Hash,
+    content: &[u8],
+) {
+    let transaction = Transaction::new_signed_with_payer(
+        &[send_sealed_message_instruction(
+            &payer.pubkey(),
+            &authority.pubkey(),
+            conversation_pda_address,
+            credential_pda_address,
+            message_index,
+            next_authority_address,
+            next_chain_hash,
+            content.to_vec(),
+        )],
+        Some(&payer.pubkey()),
+        &[payer, authority],
+        *recent_blockhash,
+    );
+
+    banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+/// Creates BlindedConversation PDA account
+pub async fn create_blinded_conversation_pda_account(
+    payer: &Keypair,
+    banks_client: &mut BanksClient,
+    recent_blockhash: &Hash,
+    authority: &Keypair,
+    blinded_id: [u8; 32],
+) {
+    let transaction = Transaction::new_signed_with_payer(
+        &[create_blinded_conversation_account(
+            &payer.pubkey(),
+            &authority.pubkey(),
+            blinded_id,
+        )],
+        Some(&payer.pubkey()),
+        &[payer, authority],
+        *recent_blockhash,
+    );
+
+    banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+/// Send Message to a blinded conversation
+#[allow(clippy::too_many_arguments)]
+pub async fn send_blinded_message(
+    payer: &Keypair,
+    banks_client: &mut BanksClient,
+    recent_blockhash: &Hash,
+    authority: &Keypair,
+    blinded_id: &[u8; 32],
+    message_index: u64,
+    message_type: u8,
+    content: &[u8],
+) {
+    let transaction = Transaction::new_signed_with_payer(
+        &[send_blinded_message_instruction(
+            &payer.pubkey(),
+            &authority.pubkey(),
+            blinded_id,
+            message_index,
+            message_type,
+            content.to_vec(),
+        )],
+        Some(&payer.pubkey()),
+        &[payer, authority],
+        *recent_blockhash,
+    );
+
+    banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+/// Creates Group PDA account owned by the creator
+pub async fn create_group_pda_account(
+    payer: &Keypair,
+    banks_client: &mut BanksClient,
+    recent_blockhash: &Hash,
+    creator: &Keypair,
+    group_index: u64,
+    kind: u8,
+    creator_conversation_index: u64,
+) -> Pubkey {
+    let transaction = Transaction::new_signed_with_payer(
+        &[create_group_account(
+            &payer.pubkey(),
+            &creator.pubkey(),
+            group_index,
+            kind,
+            creator_conversation_index,
+        )],
+        Some(&payer.pubkey()),
+        &[payer, creator],
+        *recent_blockhash,
+    );
+
+    banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    Group::find_pda_address(&creator.pubkey(), group_index, &id())
+}
+
+/// Creates Group PDA account with an admin and a member - all wallets have no other conversations
+pub async fn create_group_with_members(
+    payer: &Keypair,
+    banks_client: &mut BanksClient,
+    recent_blockhash: &Hash,
+    owner: &Keypair,
+    admin: &Keypair,
+    member: &Keypair,
+) -> Pubkey {
+    for wallet in [owner, admin, member].iter() {
+        create_user_pda_account(payer, banks_client, recent_blockhash, &wallet.pubkey()).await;
+    }
+
+    let group_pda_address = create_group_pda_account(
+        payer,
+        banks_client,
+        recent_blockhash,
+        owner,
+        0,
+        GroupKind::GROUP,
+        0,
+    ).await;
+
+    add_group_member(
+        payer,
+        banks_client,
+        recent_blockhash,
+        owner,
+        &group_pda_address,
+        admin,
+        0,
+        GroupRole::ADMIN,
+        GroupRole::default_permissions(GroupRole::ADMIN),
+    ).await;
+
+    add_group_member(
+        payer,
+        banks_client,
+        recent_blockhash,
+        owner,
+        &group_pda_address,
+        member,
+        0,
+        GroupRole::MEMBER,
+        GroupRole::default_permissions(GroupRole::MEMBER),
+    ).await;
+
+    group_pda_address
+}
+
+/// Adds member to the group - signed by the inviter and the new member
+#[allow(clippy::too_many_arguments)]
+pub async fn add_group_member(
+    payer: &Keypair,
+    banks_client: &mut BanksClient,
+    recent_blockhash: &Hash,
+    inviter: &Keypair,
+    group_pda_address: &Pubkey,
+    member: &Keypair,
+    member_conversation_index: u64,
+    role: u8,
+    permissions: u8,
+) {
+    let transaction = Transaction::new_signed_with_payer(
+        &[add_group_member_instruction(
+            &payer.pubkey(),
+            &inviter.pubkey(),
+            group_pda_address,
+            &member.pubkey(),
+            member_conversation_index,
+            role,
+            permissions,
+        )],
+        Some(&payer.pubkey()),
+        &[payer, inviter, member],
+        *recent_blockhash,
+    );
+
+    banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+/// Send Message to the group
+#[allow(clippy::too_many_arguments)]
+pub async fn send_group_message(
+    payer: &Keypair,
+    banks_client: &mut BanksClient,
+    recent_blockhash: &Hash,
+    sender: &Keypair,
+    group_pda_address: &Pubkey,
+    message_index: u64,
+    message_type: u8,
+    content: &[u8],
+) {
+    let transaction = Transaction::new_signed_with_payer(
+        &[send_group_message_instruction(
+            &payer.pubkey(),
+            &sender.pubkey(),
+            group_pda_address,
+            message_index,
+            message_type,
+            content.to_vec(),
         )],
         Some(&payer.pubkey()),
         &[payer, sender],
@@ -187,4 +667,960 @@ pub async fn send_message(
         .process_transaction(transaction)
         .await
         .unwrap();
-}
\ No newline at end of file
+}
+
+/// Changes role and permissions of the group member
+#[allow(clippy::too_many_arguments)]
+pub async fn set_member_role(
+    payer: &Keypair,
+    banks_client: &mut BanksClient,
+    recent_blockhash: &Hash,
+    actor: &Keypair,
+    group_pda_address: &Pubkey,
+    member_wallet_address: &Pubkey,
+    role: u8,
+    permissions: u8,
+) {
+    let transaction = Transaction::new_signed_with_payer(
+        &[set_member_role_instruction(
+            &actor.pubkey(),
+            group_pda_address,
+            member_wallet_address,
+            role,
+            permissions,
+        )],
+        Some(&payer.pubkey()),
+        &[payer, actor],
+        *recent_blockhash,
+    );
+
+    banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+/// Transfers ownership of the group
+pub async fn transfer_group_ownership(
+    payer: &Keypair,
+    banks_client: &mut BanksClient,
+    recent_blockhash: &Hash,
+    owner: &Keypair,
+    group_pda_address: &Pubkey,
+    new_owner_wallet_address: &Pubkey,
+) {
+    let transaction = Transaction::new_signed_with_payer(
+        &[transfer_group_ownership_instruction(
+            &owner.pubkey(),
+            group_pda_address,
+            new_owner_wallet_address,
+        )],
+        Some(&payer.pubkey()),
+        &[payer, owner],
+        *recent_blockhash,
+    );
+
+    banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+/// Creates Invite PDA account
+#[allow(clippy::too_many_arguments)]
+pub async fn create_invite_pda_account(
+    payer: &Keypair,
+    banks_client: &mut BanksClient,
+    recent_blockhash: &Hash,
+    creator: &Keypair,
+    group_pda_address: &Pubkey,
+    code: [u8; 16],
+    expires_at: UnixTimestamp,
+    max_uses: u32,
+) {
+    let transaction = Transaction::new_signed_with_payer(
+        &[create_invite_account(
+            &payer.pubkey(),
+            &creator.pubkey(),
+            group_pda_address,
+            code,
+            expires_at,
+            max_uses,
+            None,
+        )],
+        Some(&payer.pubkey()),
+        &[payer, creator],
+        *recent_blockhash,
+    );
+
+    banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+/// Joins the group with an invite - creates the User PDA account of the member first
+pub async fn join_with_invite(
+    payer: &Keypair,
+    banks_client: &mut BanksClient,
+    recent_blockhash: &Hash,
+    member: &Keypair,
+    group_pda_address: &Pubkey,
+    code: [u8; 16],
+) {
+    create_user_pda_account(payer, banks_client, recent_blockhash, &member.pubkey()).await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[join_with_invite_instruction(
+            &payer.pubkey(),
+            &member.pubkey(),
+            group_pda_address,
+            code,
+            0,
+        )],
+        Some(&payer.pubkey()),
+        &[payer, member],
+        *recent_blockhash,
+    );
+
+    banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+/// Revokes the invite - the rent goes back to the invite creator
+pub async fn revoke_invite(
+    payer: &Keypair,
+    banks_client: &mut BanksClient,
+    recent_blockhash: &Hash,
+    admin: &Keypair,
+    group_pda_address: &Pubkey,
+    creator_wallet_address: &Pubkey,
+    code: [u8; 16],
+) {
+    let transaction = Transaction::new_signed_with_payer(
+        &[revoke_invite_instruction(
+            &admin.pubkey(),
+            group_pda_address,
+            creator_wallet_address,
+            code,
+        )],
+        Some(&payer.pubkey()),
+        &[payer, admin],
+        *recent_blockhash,
+    );
+
+    banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+/// Requests to join the group - funds the requester with the given lamports first
+pub async fn request_to_join(
+    payer: &Keypair,
+    banks_client: &mut BanksClient,
+    recent_blockhash: &Hash,
+    requester: &Keypair,
+    group_pda_address: &Pubkey,
+    lamports: u64,
+) {
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            system_instruction::transfer(&payer.pubkey(), &requester.pubkey(), lamports),
+            request_to_join_instruction(&requester.pubkey(), group_pda_address),
+        ],
+        Some(&payer.pubkey()),
+        &[payer, requester],
+        *recent_blockhash,
+    );
+
+    banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+/// Approves the join request
+pub async fn approve_join(
+    payer: &Keypair,
+    banks_client: &mut BanksClient,
+    recent_blockhash: &Hash,
+    admin: &Keypair,
+    group_pda_address: &Pubkey,
+    requester_wallet_address: &Pubkey,
+    requester_conversation_index: u64,
+) {
+    let transaction = Transaction::new_signed_with_payer(
+        &[approve_join_instruction(
+            &payer.pubkey(),
+            &admin.pubkey(),
+            group_pda_address,
+            requester_wallet_address,
+            requester_conversation_index,
+        )],
+        Some(&payer.pubkey()),
+        &[payer, admin],
+        *recent_blockhash,
+    );
+
+    banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+/// Denies the join request
+pub async fn deny_join(
+    payer: &Keypair,
+    banks_client: &mut BanksClient,
+    recent_blockhash: &Hash,
+    admin: &Keypair,
+    group_pda_address: &Pubkey,
+    requester_wallet_address: &Pubkey,
+) {
+    let transaction = Transaction::new_signed_with_payer(
+        &[deny_join_instruction(
+            &admin.pubkey(),
+            group_pda_address,
+            requester_wallet_address,
+        )],
+        Some(&payer.pubkey()),
+        &[payer, admin],
+        *recent_blockhash,
+    );
+
+    banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+/// Removes member from the group
+pub async fn remove_member(
+    payer: &Keypair,
+    banks_client: &mut BanksClient,
+    recent_blockhash: &Hash,
+    actor: &Keypair,
+    group_pda_address: &Pubkey,
+    member_wallet_address: &Pubkey,
+    conversation_index: u64,
+) {
+    let transaction = Transaction::new_signed_with_payer(
+        &[remove_member_instruction(
+            &actor.pubkey(),
+            group_pda_address,
+            member_wallet_address,
+            conversation_index,
+        )],
+        Some(&payer.pubkey()),
+        &[payer, actor],
+        *recent_blockhash,
+    );
+
+    banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+/// Bans wallet from the group
+pub async fn ban_member(
+    payer: &Keypair,
+    banks_client: &mut BanksClient,
+    recent_blockhash: &Hash,
+    actor: &Keypair,
+    group_pda_address: &Pubkey,
+    member_wallet_address: &Pubkey,
+    conversation_index: u64,
+) {
+    let transaction = Transaction::new_signed_with_payer(
+        &[ban_member_instruction(
+            &payer.pubkey(),
+            &actor.pubkey(),
+            group_pda_address,
+            member_wallet_address,
+            conversation_index,
+        )],
+        Some(&payer.pubkey()),
+        &[payer, actor],
+        *recent_blockhash,
+    );
+
+    banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+/// Leaves the group
+pub async fn leave_group(
+    payer: &Keypair,
+    banks_client: &mut BanksClient,
+    recent_blockhash: &Hash,
+    member: &Keypair,
+    group_pda_address: &Pubkey,
+    conversation_index: u64,
+) {
+    let transaction = Transaction::new_signed_with_payer(
+        &[leave_group_instruction(
+            &member.pubkey(),
+            group_pda_address,
+            conversation_index,
+        )],
+        Some(&payer.pubkey()),
+        &[payer, member],
+        *recent_blockhash,
+    );
+
+    banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+/// Subscribes to the channel - creates the User PDA account of the subscriber first
+pub async fn subscribe(
+    payer: &Keypair,
+    banks_client: &mut BanksClient,
+    recent_blockhash: &Hash,
+    subscriber: &Keypair,
+    channel_pda_address: &Pubkey,
+) {
+    create_user_pda_account(payer, banks_client, recent_blockhash, &subscriber.pubkey()).await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[subscribe_instruction(
+            &payer.pubkey(),
+            &subscriber.pubkey(),
+            channel_pda_address,
+            0,
+        )],
+        Some(&payer.pubkey()),
+        &[payer, subscriber],
+        *recent_blockhash,
+    );
+
+    banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+/// Unsubscribes from the channel
+pub async fn unsubscribe(
+    payer: &Keypair,
+    banks_client: &mut BanksClient,
+    recent_blockhash: &Hash,
+    subscriber: &Keypair,
+    channel_pda_address: &Pubkey,
+    conversation_index: u64,
+) {
+    let transaction = Transaction::new_signed_with_payer(
+        &[unsubscribe_instruction(
+            &subscriber.pubkey(),
+            channel_pda_address,
+            conversation_index,
+        )],
+        Some(&payer.pubkey()),
+        &[payer, subscriber],
+        *recent_blockhash,
+    );
+
+    banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+/// Creates Community PDA account owned by the creator
+pub async fn create_community_pda_account(
+    payer: &Keypair,
+    banks_client: &mut BanksClient,
+    recent_blockhash: &Hash,
+    creator: &Keypair,
+    community_index: u32,
+    creator_conversation_index: u64,
+) -> Pubkey {
+    let transaction = Transaction::new_signed_with_payer(
+        &[create_community_account(
+            &payer.pubkey(),
+            &creator.pubkey(),
+            community_index,
+            creator_conversation_index,
+        )],
+        Some(&payer.pubkey()),
+        &[payer, creator],
+        *recent_blockhash,
+    );
+
+    banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    Community::find_pda_address(&creator.pubkey(), community_index, &id())
+}
+
+/// Creates Group PDA account inside the community
+pub async fn create_community_group_pda_account(
+    payer: &Keypair,
+    banks_client: &mut BanksClient,
+    recent_blockhash: &Hash,
+    creator: &Keypair,
+    community_pda_address: &Pubkey,
+    group_index: u64,
+    kind: u8,
+) -> Pubkey {
+    let transaction = Transaction::new_signed_with_payer(
+        &[create_community_group_account(
+            &payer.pubkey(),
+            &creator.pubkey(),
+            community_pda_address,
+            group_index,
+            kind,
+        )],
+        Some(&payer.pubkey()),
+        &[payer, creator],
+        *recent_blockhash,
+    );
+
+    banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    Group::find_pda_address(community_pda_address, group_index, &id())
+}
+
+/// Adds the group of the community to the user-conversation list of the member
+pub async fn create_community_group_user_conversation(
+    payer: &Keypair,
+    banks_client: &mut BanksClient,
+    recent_blockhash: &Hash,
+    member: &Keypair,
+    community_pda_address: &Pubkey,
+    group_index: u64,
+    member_conversation_index: u64,
+) {
+    let transaction = Transaction::new_signed_with_payer(
+        &[create_community_group_user_conversation_instruction(
+            &payer.pubkey(),
+            &member.pubkey(),
+            community_pda_address,
+            group_index,
+            member_conversation_index,
+        )],
+        Some(&payer.pubkey()),
+        &[payer, member],
+        *recent_blockhash,
+    );
+
+    banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+/// Send Message to the group of the community
+#[allow(clippy::too_many_arguments)]
+pub async fn send_community_group_message(
+    payer: &Keypair,
+    banks_client: &mut BanksClient,
+    recent_blockhash: &Hash,
+    sender: &Keypair,
+    community_pda_address: &Pubkey,
+    group_index: u64,
+    message_index: u64,
+    message_type: u8,
+    content: &[u8],
+) {
+    let transaction = Transaction::new_signed_with_payer(
+        &[send_community_group_message_instruction(
+            &payer.pubkey(),
+            &sender.pubkey(),
+            community_pda_address,
+            group_index,
+            message_index,
+            message_type,
+            content.to_vec(),
+        )],
+        Some(&payer.pubkey()),
+        &[payer, sender],
+        *recent_blockhash,
+    );
+
+    banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+/// Creates or updates ConversationMetadata PDA account of the conversation between wallets
+#[allow(clippy::too_many_arguments)]
+pub async fn set_conversation_metadata_pda_account(
+    payer: &Keypair,
+    banks_client: &mut BanksClient,
+    recent_blockhash: &Hash,
+    sender: &Keypair,
+    receiver_wallet_address: &Pubkey,
+    title: &str,
+    description: &str,
+    avatar_uri: &str,
+) {
+    let transaction = Transaction::new_signed_with_payer(
+        &[set_conversation_metadata(
+            &payer.pubkey(),
+            &sender.pubkey(),
+            receiver_wallet_address,
+            title.to_string(),
+            description.to_string(),
+            avatar_uri.to_string(),
+        )],
+        Some(&payer.pubkey()),
+        &[payer, sender],
+        *recent_blockhash,
+    );
+
+    banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+/// Creates or updates ConversationMetadata PDA account of the group
+pub async fn set_group_metadata_pda_account(
+    payer: &Keypair,
+    banks_client: &mut BanksClient,
+    recent_blockhash: &Hash,
+    editor: &Keypair,
+    group_pda_address: &Pubkey,
+    title: &str,
+) {
+    let transaction = Transaction::new_signed_with_payer(
+        &[set_group_metadata(
+            &payer.pubkey(),
+            &editor.pubkey(),
+            group_pda_address,
+            group_pda_address,
+            title.to_string(),
+            String::new(),
+            String::new(),
+        )],
+        Some(&payer.pubkey()),
+        &[payer, editor],
+        *recent_blockhash,
+    );
+
+    banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+/// Pins Message of the conversation between wallets
+pub async fn pin_message(
+    payer: &Keypair,
+    banks_client: &mut BanksClient,
+    recent_blockhash: &Hash,
+    sender: &Keypair,
+    receiver_wallet_address: &Pubkey,
+    message_index: u64,
+) {
+    let transaction = Transaction::new_signed_with_payer(
+        &[pin_message_instruction(
+            &payer.pubkey(),
+            &sender.pubkey(),
+            receiver_wallet_address,
+            message_index,
+        )],
+        Some(&payer.pubkey()),
+        &[payer, sender],
+        *recent_blockhash,
+    );
+
+    banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+/// Unpins Message of the conversation between wallets
+pub async fn unpin_message(
+    payer: &Keypair,
+    banks_client: &mut BanksClient,
+    recent_blockhash: &Hash,
+    sender: &Keypair,
+    receiver_wallet_address: &Pubkey,
+    message_index: u64,
+) {
+    let transaction = Transaction::new_signed_with_payer(
+        &[unpin_message_instruction(
+            &sender.pubkey(),
+            receiver_wallet_address,
+            message_index,
+        )],
+        Some(&payer.pubkey()),
+        &[payer, sender],
+        *recent_blockhash,
+    );
+
+    banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+/// Pins Message of the group
+pub async fn pin_group_message(
+    payer: &Keypair,
+    banks_client: &mut BanksClient,
+    recent_blockhash: &Hash,
+    member: &Keypair,
+    group_pda_address: &Pubkey,
+    message_index: u64,
+) {
+    let transaction = Transaction::new_signed_with_payer(
+        &[pin_group_message_instruction(
+            &payer.pubkey(),
+            &member.pubkey(),
+            group_pda_address,
+            group_pda_address,
+            message_index,
+        )],
+        Some(&payer.pubkey()),
+        &[payer, member],
+        *recent_blockhash,
+    );
+
+    banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+/// Updates per-user flags of the UserConversation PDA account
+#[allow(clippy::too_many_arguments)]
+pub async fn update_user_conversation(
+    payer: &Keypair,
+    banks_client: &mut BanksClient,
+    recent_blockhash: &Hash,
+    user: &Keypair,
+    conversation_index: u64,
+    archived: bool,
+    muted_until: UnixTimestamp,
+    folder_id: u32,
+) {
+    let transaction = Transaction::new_signed_with_payer(
+        &[update_user_conversation_instruction(
+            &user.pubkey(),
+            conversation_index,
+            archived,
+            muted_until,
+            folder_id,
+        )],
+        Some(&payer.pubkey()),
+        &[payer, user],
+        *recent_blockhash,
+    );
+
+    banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+/// Hides the conversation between wallets from the user-conversation list of the user or shows it again
+pub async fn hide_conversation(
+    payer: &Keypair,
+    banks_client: &mut BanksClient,
+    recent_blockhash: &Hash,
+    user: &Keypair,
+    other_wallet_address: &Pubkey,
+    conversation_index: u64,
+    hidden: bool,
+) {
+    let transaction = Transaction::new_signed_with_payer(
+        &[hide_conversation_instruction(
+            &user.pubkey(),
+            other_wallet_address,
+            conversation_index,
+            hidden,
+        )],
+        Some(&payer.pubkey()),
+        &[payer, user],
+        *recent_blockhash,
+    );
+
+    banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+/// Sends Message with StartConversation - missing accounts are created by the instruction
+#[allow(clippy::too_many_arguments)]
+pub async fn start_conversation(
+    payer: &Keypair,
+    banks_client: &mut BanksClient,
+    recent_blockhash: &Hash,
+    sender: &Keypair,
+    receiver_wallet_address: &Pubkey,
+    sender_conversation_index: u64,
+    receiver_conversation_index: u64,
+    message_index: u64,
+    content: &[u8],
+) {
+    let transaction = Transaction::new_signed_with_payer(
+        &[start_conversation_instruction(
+            &payer.pubkey(),
+            &sender.pubkey(),
+            receiver_wallet_address,
+            sender_conversation_index,
+            receiver_conversation_index,
+            message_index,
+            0,
+            content.to_vec(),
+            Message::NO_MESSAGE_ID,
+        )],
+        Some(&payer.pubkey()),
+        &[payer, sender],
+        *recent_blockhash,
+    );
+
+    banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+/// Builds SendMessage transaction signed by the payer and the sender
+pub fn message_transaction(
+    payer: &Keypair,
+    recent_blockhash: &Hash,
+    sender: &Keypair,
+    receiver_wallet_address: &Pubkey,
+    message_index: u64,
+    content: &[u8],
+) -> Transaction {
+    Transaction::new_signed_with_payer(
+        &[send_message_instruction(
+            &payer.pubkey(),
+            &sender.pubkey(),
+            receiver_wallet_address,
+            message_index,
+            MessageType::PLAIN_TEXT,
+            content.to_vec(),
+            Message::NO_MESSAGE_ID,
+        )],
+        Some(&payer.pubkey()),
+        &[payer, sender],
+        *recent_blockhash,
+    )
+}
+
+/// Builds SendSequencedMessage transaction signed by the payer and the sender
+pub fn sequenced_message_transaction(
+    payer: &Keypair,
+    recent_blockhash: &Hash,
+    sender: &Keypair,
+    receiver_wallet_address: &Pubkey,
+    sequence: u64,
+    content: &[u8],
+) -> Transaction {
+    Transaction::new_signed_with_payer(
+        &[send_sequenced_message_instruction(
+            &payer.pubkey(),
+            &sender.pubkey(),
+            receiver_wallet_address,
+            sequence,
+            0,
+            content.to_vec(),
+        )],
+        Some(&payer.pubkey()),
+        &[payer, sender],
+        *recent_blockhash,
+    )
+}
+
+/// Send Messages in one instruction
+pub async fn send_messages(
+    payer: &Keypair,
+    banks_client: &mut BanksClient,
+    recent_blockhash: &Hash,
+    sender: &Keypair,
+    receiver_wallet_address: &Pubkey,
+    message_index: u64,
+    messages: Vec<(u8, Vec<u8>, [u8; 16])>,
+) {
+    let transaction = Transaction::new_signed_with_payer(
+        &[send_messages_instruction(
+            &payer.pubkey(),
+            &sender.pubkey(),
+            receiver_wallet_address,
+            message_index,
+            messages,
+        )],
+        Some(&payer.pubkey()),
+        &[payer, sender],
+        *recent_blockhash,
+    );
+
+    banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+/// Send Message to the last message page
+pub async fn send_paged_message(
+    payer: &Keypair,
+    banks_client: &mut BanksClient,
+    recent_blockhash: &Hash,
+    sender: &Keypair,
+    receiver_wallet_address: &Pubkey,
+    page_counter: u32,
+    content: &[u8],
+) {
+    let transaction = Transaction::new_signed_with_payer(
+        &[send_paged_message_instruction(
+            &payer.pubkey(),
+            &sender.pubkey(),
+            receiver_wallet_address,
+            page_counter,
+            0,
+            content.to_vec(),
+        )],
+        Some(&payer.pubkey()),
+        &[payer, sender],
+        *recent_blockhash,
+    );
+
+    banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+/// Adds program account with the data, trimmed to the size of legacy accounts if given
+fn add_program_account(program_test: &mut ProgramTest, address: Pubkey, mut data: Vec<u8>, legacy_size: Option<usize>) {
+    if let Some(legacy_size) = legacy_size {
+        data.truncate(legacy_size);
+    }
+
+    program_test.add_account(address, Account {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner: id(),
+        executable: false,
+        rent_epoch: 0,
+    });
+}
+
+/// Adds user, conversation and user-conversation accounts of the wallets as the program creates them,
+/// legacy accounts are added without bump seeds and at v1 addresses. Returns the conversation address
+pub fn add_conversation_accounts(
+    program_test: &mut ProgramTest,
+    sender_wallet_address: &Pubkey,
+    receiver_wallet_address: &Pubkey,
+    legacy: bool,
+) -> Pubkey {
+    let (sender_user_pda_address, sender_user_bump_seed) = User::find_pda_address_with_bump_seed(sender_wallet_address, &id());
+    let (receiver_user_pda_address, receiver_user_bump_seed) = User::find_pda_address_with_bump_seed(receiver_wallet_address, &id());
+
+    let (conversation_pda_address, conversation_bump_seed) = Conversation::find_pda_address_with_bump_seed(
+        &sender_user_pda_address,
+        &receiver_user_pda_address,
+        &id(),
+    );
+
+    for (user_pda_address, user_bump_seed) in [
+        (sender_user_pda_address, sender_user_bump_seed),
+        (receiver_user_pda_address, receiver_user_bump_seed),
+    ].iter() {
+        let user = User {
+            conversation_counter: 1,
+            bump_seed: *user_bump_seed,
+        };
+        add_program_account(program_test, *user_pda_address, user.try_to_vec().unwrap(), if legacy { Some(User::LEGACY_SIZE) } else { None });
+
+        // Legacy accounts predate the v2 seeds
+        let (user_conversation_pda_address, user_conversation_bump_seed) = if legacy {
+            UserConversation::find_pda_address_with_bump_seed(user_pda_address, 0, &id())
+        } else {
+            UserConversation::find_pda_address_v2_with_bump_seed(user_pda_address, 0, &id())
+        };
+        let user_conversation = UserConversation {
+            conversation_address: conversation_pda_address,
+            bump_seed: user_conversation_bump_seed,
+            ..UserConversation::default()
+        };
+        add_program_account(
+            program_test,
+            user_conversation_pda_address,
+            user_conversation.try_to_vec().unwrap(),
+            if legacy { Some(UserConversation::LEGACY_SIZE) } else { None },
+        );
+    }
+
+    let conversation = Conversation {
+        message_counter: 0,
+        bump_seed: conversation_bump_seed,
+    };
+    add_program_account(program_test, conversation_pda_address, conversation.try_to_vec().unwrap(), if legacy { Some(Conversation::LEGACY_SIZE) } else { None });
+
+    conversation_pda_address
+}
+
+/// Adds message account at the v1 address, legacy messages are added without the message ID
+pub fn add_v1_message_account(
+    program_test: &mut ProgramTest,
+    conversation_address: &Pubkey,
+    message_index: u32,
+    message: &Message,
+    legacy: bool,
+) {
+    let data = message.try_to_vec().unwrap();
+    let legacy_size = data.len() - message.message_id.len();
+
+    add_program_account(
+        program_test,
+        Message::find_pda_address(conversation_address, message_index, &id()),
+        data,
+        if legacy { Some(legacy_size) } else { None },
+    );
+}
+
+/// Adds blinded-conversation account at the given address, which need not follow the seed derivation
+pub fn add_blinded_conversation_account(
+    program_test: &mut ProgramTest,
+    address: Pubkey,
+    blinded_conversation: &BlindedConversation,
+) {
+    add_program_account(program_test, address, blinded_conversation.try_to_vec().unwrap(), None);
+}
+
+/// Process instruction in a transaction signed by the payer and the signers
+pub async fn process_signed_instruction(
+    banks_client: &mut BanksClient,
+    recent_blockhash: &Hash,
+    payer: &Keypair,
+    signers: &[&Keypair],
+    instruction: Instruction,
+) {
+    let mut transaction_signers = vec![payer];
+    transaction_signers.extend_from_slice(signers);
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&payer.pubkey()),
+        &transaction_signers,
+        *recent_blockhash,
+    );
+
+    banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}